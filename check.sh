@@ -3,9 +3,11 @@
 # set -eux
 
 # cargo check --quiet --workspace --all-targets
+# cargo check --quiet --workspace --no-default-features --lib
 # cargo check --quiet --workspace --all-features --lib --target wasm32-unknown-unknown
 # cargo fmt --all -- --check
 # cargo clippy --quiet --workspace --all-targets --all-features --  -D warnings -W clippy::all
 # cargo test --quiet --workspace --all-targets --all-features
 # cargo test --quiet --workspace --doc
+# cargo test --quiet --release --lib -- --ignored grid_is_faster
 # trunk build
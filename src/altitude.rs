@@ -0,0 +1,102 @@
+#[cfg(feature = "gui")]
+use egui::Ui;
+
+#[cfg(feature = "gui")]
+use rand::Rng;
+
+use crate::boid_store::BoidRef;
+
+#[cfg(feature = "gui")]
+use crate::{boid_store::BoidStore, math::Vec2};
+
+/// How far up the screen a boid is drawn per unit of altitude.
+#[cfg(feature = "gui")]
+pub const ALTITUDE_SCREEN_OFFSET: f32 = 0.3;
+
+/// A pseudo third dimension: every boid has an altitude above a flat ground.
+///
+/// Boids take off from the ground, climb to a cruising altitude and now and then come back down to rest.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct AltitudeParameters {
+    pub enabled: bool,
+    pub cruise_altitude: f32,
+    /// Altitude gained or lost per tick when climbing or descending
+    pub climb_rate: f32,
+    /// Chance per tick that a flying boid decides to land
+    pub landing_chance: f32,
+    /// Shortest rest on the ground, in ticks
+    pub min_rest: u32,
+    /// Longest rest on the ground, in ticks
+    pub max_rest: u32,
+    /// The predator walks on the ground and only threatens boids below this altitude
+    pub predator_reach: f32,
+}
+
+impl Default for AltitudeParameters {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cruise_altitude: 100.0,
+            climb_rate: 1.0,
+            landing_chance: 0.0005,
+            min_rest: 120,
+            max_rest: 600,
+            predator_reach: 30.0,
+        }
+    }
+}
+
+impl AltitudeParameters {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Enabled");
+        ui.label("Cruise Altitude");
+        ui.add(egui::DragValue::new(&mut self.cruise_altitude).range(0.0..=f32::MAX));
+        ui.label("Climb Rate");
+        ui.add(egui::DragValue::new(&mut self.climb_rate).speed(0.05));
+        ui.label("Landing Chance");
+        ui.add(egui::Slider::new(&mut self.landing_chance, 0.0..=0.01).logarithmic(true));
+        ui.label("Rest Time (ticks)");
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut self.min_rest).range(0..=self.max_rest));
+            ui.label("to");
+            ui.add(egui::DragValue::new(&mut self.max_rest).range(self.min_rest..=u32::MAX));
+        });
+        ui.label("Predator Reach");
+        ui.add(egui::DragValue::new(&mut self.predator_reach));
+    }
+
+    /// Whether the ground predator can get at `boid`.
+    pub fn within_predator_reach(&self, boid: BoidRef<'_>) -> bool {
+        !self.enabled || boid.altitude <= self.predator_reach
+    }
+}
+
+/// Climb, cruise, descend and land.
+#[cfg(feature = "gui")]
+pub fn update_altitude(boids: &mut BoidStore, params: &AltitudeParameters, rng: &mut impl Rng) {
+    for mut boid in boids {
+        if boid.resting.is_some() {
+            continue;
+        }
+
+        if boid.descending {
+            boid.altitude -= params.climb_rate;
+            if boid.altitude <= 0.0 {
+                boid.altitude = 0.0;
+                boid.descending = false;
+                *boid.velocity = Vec2::ZERO;
+                *boid.acceleration = Vec2::ZERO;
+                boid.resting =
+                    Some(rng.gen_range(params.min_rest..=params.max_rest.max(params.min_rest)));
+            }
+        } else if rng.gen_bool(params.landing_chance.clamp(0.0, 1.0) as f64) {
+            boid.descending = true;
+        } else if boid.altitude < params.cruise_altitude {
+            boid.altitude = (boid.altitude + params.climb_rate).min(params.cruise_altitude);
+        } else {
+            boid.altitude = (boid.altitude - params.climb_rate).max(params.cruise_altitude);
+        }
+    }
+}
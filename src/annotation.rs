@@ -0,0 +1,108 @@
+use egui::{Align2, Color32, FontId, Painter, Pos2, Rect, Stroke, Ui, Vec2};
+
+use crate::{boid_store::BoidStore, camera::Camera};
+
+const ANNOTATION_COLOR: Color32 = Color32::from_rgb(255, 230, 120);
+/// Where a label sits relative to what its arrow points at, in screen pixels.
+const ARROW_OFFSET: Vec2 = Vec2::new(-40.0, -40.0);
+
+/// What an annotation is attached to.
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum AnnotationTarget {
+    /// Follows a boid around, by id (in scenario files, by index into the scenario's boids)
+    Boid(u64),
+    Point(Pos2),
+    Region(Rect),
+}
+
+/// A note for a prepared demo, e.g. "the flock splits here".
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Annotation {
+    pub target: AnnotationTarget,
+    pub text: String,
+    /// Draw the label off to the side with an arrow pointing at the target
+    #[serde(default)]
+    pub arrow: bool,
+}
+
+impl Annotation {
+    /// The world position the annotation points at, `None` if its boid is gone.
+    pub fn anchor(&self, boids: &BoidStore) -> Option<Pos2> {
+        match self.target {
+            AnnotationTarget::Boid(id) => boids
+                .iter()
+                .find(|boid| boid.id == id)
+                .map(|boid| boid.position),
+            AnnotationTarget::Point(position) => Some(position),
+            AnnotationTarget::Region(region) => Some(region.center_top()),
+        }
+    }
+
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera, boids: &BoidStore) {
+        let Some(anchor) = self.anchor(boids) else {
+            return;
+        };
+        let anchor = camera.world_to_screen(rect, anchor);
+        let stroke = Stroke::new(1.5, ANNOTATION_COLOR);
+
+        if let AnnotationTarget::Region(region) = self.target {
+            let screen_region = Rect::from_two_pos(
+                camera.world_to_screen(rect, region.min),
+                camera.world_to_screen(rect, region.max),
+            );
+            painter.rect_stroke(screen_region, 4.0, stroke);
+        }
+
+        let label_pos = if self.arrow {
+            painter.arrow(anchor + ARROW_OFFSET, -ARROW_OFFSET * 0.85, stroke);
+            anchor + ARROW_OFFSET
+        } else {
+            anchor
+        };
+        let align = if self.arrow {
+            Align2::RIGHT_BOTTOM
+        } else {
+            Align2::CENTER_BOTTOM
+        };
+        painter.text(
+            label_pos,
+            align,
+            &self.text,
+            FontId::proportional(16.0),
+            ANNOTATION_COLOR,
+        );
+    }
+}
+
+/// Text and style of the next annotation placed with the annotate tool.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct AnnotationBrush {
+    pub text: String,
+    pub arrow: bool,
+}
+
+impl Default for AnnotationBrush {
+    fn default() -> Self {
+        Self {
+            text: "Look here".to_owned(),
+            arrow: true,
+        }
+    }
+}
+
+impl AnnotationBrush {
+    pub fn annotation(&self, target: AnnotationTarget) -> Annotation {
+        Annotation {
+            target,
+            text: self.text.clone(),
+            arrow: self.arrow,
+        }
+    }
+
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.label("Annotation Text");
+        ui.text_edit_singleline(&mut self.text);
+        ui.checkbox(&mut self.arrow, "Arrow");
+    }
+}
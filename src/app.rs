@@ -1,144 +1,2623 @@
-use std::time::Instant;
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    ops::RangeInclusive,
+    time::{Duration, Instant},
+};
 
-use egui::{Color32, Pos2, Rect, Stroke, Ui, Vec2, Visuals};
-use rand::Rng;
+use egui::{Color32, Id, PointerButton, Pos2, Rect, Stroke, Ui, Vec2, Visuals};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tracing::{debug, info, trace};
 
-use crate::{boid::Boid, boids_simulation::BoidsSimulationParameters};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{
+    clip_export::ClipCapture, html_export::ReplayCapture, plugin::PluginHost,
+    species::SpeciesLibrary, svg_export::SvgFrame,
+};
 
-const SIMULATION_AREA_WIDTH: f32 = 1700.0;
-const SIMULATION_AREA_HEIGHT: f32 = 950.0;
+use crate::{
+    altitude::update_altitude,
+    annotation::{Annotation, AnnotationBrush, AnnotationTarget},
+    bindings::{Action, InputBindings},
+    boid::Boid,
+    boid_store::{BoidRef, BoidStore},
+    boids_simulation::{BoidsSimulationParameters, BoundaryMode},
+    breakpoint::Breakpoints,
+    camera::Camera,
+    camera_path::CameraPath,
+    chunk::ActiveChunks,
+    current_zone::{CurrentZone, CurrentZoneBrush, ZoneArea},
+    danger::DangerMap,
+    debug_panel::{format_bytes, DebugPanel, DebugStats},
+    energy::{update_energy, Thermal},
+    experiment::{lane_formation, LaneOrderPlot},
+    flow_field::FlowField,
+    force_field::ForceField,
+    game::{GameSession, GameSettings, Leaderboard, LeaderboardEntry, TICKS_PER_SECOND},
+    gate::{FlowMeter, Gate, GateBrush, GateKind},
+    genome::Genome,
+    ghost::GhostPreview,
+    goal_point::{GoalMode, GoalPoint, WaypointPath},
+    highlight::{HighlightAction, HighlightDetector},
+    hud::{HudLayout, HudStats},
+    impulse::{GustBrush, Impulse},
+    integrator::IntegratorComparison,
+    laser_pointer::LaserPointer,
+    lfo::LfoBank,
+    log_viewer::{approximate_log_bytes, LogViewer},
+    metrics::{lane_order, FlockMetrics, RegimeThresholds, RegimeTracker},
+    migration::MigrationTarget,
+    moving_obstacle::{obstacles_at, MotionKind, MovingObstacle},
+    net::{NetSession, NetSettings},
+    observer::{FlockState, Observer},
+    obstacle::{Obstacle, ObstacleBrush, ObstacleEdit},
+    particles::ParticleSystem,
+    perch::{update_perching, PerchSite},
+    playlist::Playlist,
+    predator::{hunt_goals, Predator},
+    recording_file::{is_recording_file, recording_from_dropped_file},
+    render::RenderSettings,
+    replay::{
+        state_checksum, InputPlayer, InputRecorder, InputRecording, ReplayKeyframe, ReplaySettings,
+        ReplayState, SimulationInputs,
+    },
+    scenario::Scenario,
+    simulation::{World, SIMULATION_AREA_HEIGHT, SIMULATION_AREA_WIDTH, WORLD_BOUNDS},
+    spatial_grid::SpatialGrid,
+    split_merge::{ExperimentToggle, SplitMergeExperiment},
+    steering::{flocking_rules, SteeringRule},
+    temporary_obstacle::{wear_down, TemporaryObstacle},
+    threat::Threat,
+    timeline::{MarkerKind, SessionTimeline},
+    toasts::Toasts,
+    tools::{Tool, ToolDrag},
+    tween::{ParameterTween, TransitionSettings},
+};
 
 const LEFT: f32 = -SIMULATION_AREA_WIDTH / 2.0;
 const RIGHT: f32 = SIMULATION_AREA_WIDTH / 2.0;
 const TOP: f32 = -SIMULATION_AREA_HEIGHT / 2.0;
 const BOTTOM: f32 = SIMULATION_AREA_HEIGHT / 2.0;
+/// How many random points to try when spawning a boid inside the arena before giving up
+const SPAWN_ATTEMPTS: usize = 100;
 
-const COHESION_COLOR: Color32 = Color32::BLUE;
-const SEPARATION_COLOR: Color32 = Color32::YELLOW;
-const ALIGNMENT_COLOR: Color32 = Color32::GREEN;
-const AVOIDANCE_COLOR: Color32 = Color32::RED;
+const REMOTE_PREDATOR_COLOR: Color32 = Color32::from_rgb(255, 140, 0);
 
+/// Real time each simulation tick stands for, however often frames are drawn.
 const FRAME_TIME: f32 = 1.0 / 60.0;
+/// Most ticks run in a single frame to catch up at normal speed, so a slow frame doesn't snowball
+/// into ever more ticks and ever slower frames.
+const MAX_TICKS_PER_FRAME: u32 = 4;
+const TIME_SCALE_RANGE: RangeInclusive<f32> = 0.1..=10.0;
+
+/// How close (in screen pixels) the cursor needs to be to something to pick it.
+const PICK_DISTANCE: f32 = 15.0;
+/// How much one press of the zoom bindings zooms in or out.
+const KEY_ZOOM_STEP: f32 = 1.25;
+/// How long a press has to be held without moving to count as a long press.
+const LONG_PRESS_SECONDS: f64 = 0.6;
+/// How close (in screen pixels) the cursor needs to be to a boid to show its tooltip.
+const TOOLTIP_DISTANCE: f32 = 8.0;
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct BoidsApp {
     #[serde(skip)]
-    boids: Vec<Boid>,
+    boids: BoidStore,
+    /// Id of the next boid to hatch, handed out by the app so a restarted flock gets the same ids
+    #[serde(skip)]
+    next_boid_id: u64,
     #[serde(skip)]
     last_update_time: std::time::Instant,
+    /// Real time that has passed but not been simulated yet, in seconds
+    #[serde(skip)]
+    accumulator: f32,
+    /// Simulated time per real time, below 1 for slow motion and above it to fast forward
+    time_scale: f32,
     #[serde(skip)]
     paused: bool,
     #[serde(skip)]
     predator_pos: Option<Pos2>,
+    /// How far the local predator moved over the last tick
+    #[serde(skip)]
+    predator_velocity: Vec2,
+    #[serde(skip)]
+    last_predator_pos: Option<Pos2>,
+    #[serde(skip)]
+    camera: Camera,
+    /// Where the central panel showed the world last frame, in screen coordinates
+    #[serde(skip)]
+    view_rect: Rect,
+    /// Whether the user has pinched or panned the camera, which then stays put until reset
+    #[serde(skip)]
+    manual_camera: bool,
+    /// Where a long press left the predator, for touch screens that have no hover
+    #[serde(skip)]
+    pinned_predator: Option<Pos2>,
+    /// Whether the current press already counted as a long press
+    #[serde(skip)]
+    long_press_handled: bool,
+    #[serde(skip)]
+    migration_target: MigrationTarget,
+    /// Computer controlled predators, on top of the one following the mouse
+    #[serde(skip)]
+    predators: Vec<Predator>,
+    #[serde(default)]
+    perches: Vec<PerchSite>,
+    /// Destinations the boids head for, placed with the goal tool
+    #[serde(default)]
+    goal_points: Vec<GoalPoint>,
+    /// Waypoints the flock visits in order, also placed with the goal tool
+    #[serde(default)]
+    waypoint_path: WaypointPath,
+    /// Whether the goal tool places goal points or waypoints
+    #[serde(default)]
+    goal_mode: GoalMode,
+    /// Index of the waypoint being dragged with the goal tool
+    #[serde(skip)]
+    waypoint_drag: Option<usize>,
+    #[serde(default)]
+    current_zones: Vec<CurrentZone>,
+    #[serde(default)]
+    obstacles: Vec<Obstacle>,
+    /// Obstacles that wear out, see [`TemporaryObstacle`]
+    #[serde(default)]
+    temporary_obstacles: Vec<TemporaryObstacle>,
+    #[serde(default)]
+    moving_obstacles: Vec<MovingObstacle>,
+    /// Ticks the flock has run for since it was last started, which moving and temporary
+    /// obstacles, wind, wander noise and LFOs all go by. Kept between sessions so they carry on
+    /// where they left off, unlike [`Self::tick`] which counts the session's ticks.
+    #[serde(default)]
+    obstacle_clock: u64,
+    /// Where the flock remembers predators getting close
+    #[serde(skip)]
+    danger_map: DangerMap,
+    #[serde(default)]
+    obstacle_brush: ObstacleBrush,
+    #[serde(skip)]
+    obstacle_edit: ObstacleEdit,
+    #[serde(default)]
+    current_brush: CurrentZoneBrush,
+    #[serde(default)]
+    thermals: Vec<Thermal>,
+    /// Sources and sinks boids enter and leave through
+    #[serde(default)]
+    gates: Vec<Gate>,
+    #[serde(default)]
+    gate_brush: GateBrush,
+    #[serde(skip)]
+    flow_meter: FlowMeter,
+    /// Pushes given to the boids on the next tick
+    #[serde(skip)]
+    impulses: Vec<Impulse>,
+    /// Boids caught by predators since the app started
+    #[serde(skip)]
+    kills: u32,
+    /// Ticks left until each caught boid comes back
+    #[serde(skip)]
+    respawns: Vec<u32>,
+    #[serde(skip)]
+    integrator_comparison: IntegratorComparison,
+    #[serde(default)]
+    ghost: GhostPreview,
+    #[serde(default)]
+    gust_brush: GustBrush,
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+    #[serde(default)]
+    annotation_brush: AnnotationBrush,
+    #[serde(skip)]
+    game: Option<GameSession>,
+    #[serde(default)]
+    game_settings: GameSettings,
+    #[serde(default)]
+    leaderboard: Leaderboard,
+    /// Direction the second player is steering the shepherd in this frame
+    #[serde(skip)]
+    shepherd_input: Vec2,
+    #[serde(default)]
+    tool: Tool,
+    #[serde(default)]
+    bindings: InputBindings,
+    #[serde(skip)]
+    tool_drag_start: Option<Pos2>,
+    /// Id of the boid shown in the inspector
+    #[serde(skip)]
+    selected_boid: Option<u64>,
     #[serde(default)]
     params: BoidsSimulationParameters,
+    #[serde(default)]
+    transitions: TransitionSettings,
+    /// Blending the parameters into ones just loaded
+    #[serde(skip)]
+    tween: Option<ParameterTween>,
+    #[serde(default)]
+    lfos: LfoBank,
+    #[serde(default)]
+    playlist: Playlist,
+    #[serde(default)]
+    log_viewer: LogViewer,
+    #[serde(skip)]
+    toasts: Toasts,
+    #[serde(skip)]
+    laser_pointer: LaserPointer,
+    #[serde(default)]
+    hud: HudLayout,
+    #[serde(default)]
+    render_settings: RenderSettings,
+    #[serde(default)]
+    force_field: ForceField,
+    #[serde(default)]
+    flow_field: FlowField,
+    #[serde(skip)]
+    particles: ParticleSystem,
+    /// All randomness in the simulation comes from here so it can be seeded for replays
+    #[serde(skip)]
+    rng: StdRng,
+    /// What the simulation was last started from, the same seed and inputs give the same run
+    #[serde(default)]
+    seed: u64,
+    #[serde(skip)]
+    replay: ReplayState,
+    /// Ticks simulated since the app started
+    #[serde(skip)]
+    tick: u64,
+    /// Checksum of the state after the last tick, only kept up to date when something needs it
+    #[serde(skip)]
+    checksum: Option<u64>,
+    /// Set by an embedding application to look at the flock after every tick
+    #[serde(skip)]
+    observer: Option<Observer>,
+    #[serde(default)]
+    debug_panel: DebugPanel,
+    /// The flock's collective state after the last tick
+    #[serde(skip)]
+    metrics: FlockMetrics,
+    /// Time visual crowding took over the last tick, `None` while it's off
+    #[serde(skip)]
+    crowding_time: Option<Duration>,
+    /// Time working out the forces on the flock took over the last tick
+    #[serde(skip)]
+    force_time: Duration,
+    #[serde(skip)]
+    regime: RegimeTracker,
+    #[serde(default)]
+    regime_thresholds: RegimeThresholds,
+    #[serde(default)]
+    breakpoints: Breakpoints,
+    #[serde(skip)]
+    lane_plot: LaneOrderPlot,
+    #[serde(skip)]
+    steering_rules: Vec<Box<dyn SteeringRule>>,
+    #[serde(default)]
+    camera_path: CameraPath,
+    #[serde(skip)]
+    last_recording: Option<InputRecording>,
+    /// The tick the last recording ended on, `None` if it came from a file rather than this session
+    #[serde(skip)]
+    last_recording_end: Option<u64>,
+    #[serde(default)]
+    timeline: SessionTimeline,
+    #[serde(default)]
+    highlights: HighlightDetector,
+    #[serde(default)]
+    split_merge: SplitMergeExperiment,
+    /// Where in the last recording playback starts, in seconds
+    #[serde(skip)]
+    replay_start_seconds: f32,
+    /// Filled in while a replay plays through to be exported as a web page
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    html_capture: Option<ReplayCapture>,
+    /// Filled in while a replay plays through a highlight's clip to be exported as a GIF
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    clip_capture: Option<ClipCapture>,
+    /// Save the world view as SVG once this frame is drawn
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    svg_export_requested: bool,
+    /// Groups defined in files, applied at startup and again whenever they change
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    species: SpeciesLibrary,
+    /// Compiled behaviors and visualizers from the plugins directory
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    plugins: PluginHost,
+    #[serde(default)]
+    replay_settings: ReplaySettings,
+    /// The world wrapped before recording switched it to containment
+    #[serde(skip)]
+    wrap_after_recording: bool,
+    #[serde(skip)]
+    net: NetSession,
+    #[serde(default)]
+    net_settings: NetSettings,
+}
+
+impl Default for BoidsApp {
+    fn default() -> Self {
+        let seed = rand::random();
+        Self {
+            boids: BoidStore::default(),
+            next_boid_id: 0,
+            predator_pos: None,
+            predator_velocity: Vec2::ZERO,
+            last_predator_pos: None,
+            camera: Camera::default(),
+            view_rect: Rect::NOTHING,
+            manual_camera: false,
+            pinned_predator: None,
+            long_press_handled: false,
+            migration_target: MigrationTarget::default(),
+            predators: Vec::new(),
+            perches: Vec::new(),
+            goal_points: Vec::new(),
+            waypoint_path: WaypointPath::default(),
+            goal_mode: GoalMode::Points,
+            waypoint_drag: None,
+            current_zones: Vec::new(),
+            obstacles: Vec::new(),
+            temporary_obstacles: Vec::new(),
+            moving_obstacles: Vec::new(),
+            obstacle_clock: 0,
+            danger_map: DangerMap::default(),
+            obstacle_brush: ObstacleBrush::default(),
+            obstacle_edit: ObstacleEdit::Idle,
+            current_brush: CurrentZoneBrush::default(),
+            thermals: Vec::new(),
+            gates: Vec::new(),
+            gate_brush: GateBrush::default(),
+            impulses: Vec::new(),
+            kills: 0,
+            respawns: Vec::new(),
+            integrator_comparison: IntegratorComparison::default(),
+            ghost: GhostPreview::default(),
+            gust_brush: GustBrush::default(),
+            flow_meter: FlowMeter::default(),
+            annotations: Vec::new(),
+            annotation_brush: AnnotationBrush::default(),
+            game: None,
+            game_settings: GameSettings::default(),
+            leaderboard: Leaderboard::default(),
+            shepherd_input: Vec2::ZERO,
+            tool: Tool::default(),
+            bindings: InputBindings::default(),
+            tool_drag_start: None,
+            selected_boid: None,
+            last_update_time: Instant::now(),
+            accumulator: 0.0,
+            time_scale: 1.0,
+            paused: false,
+            params: BoidsSimulationParameters::default(),
+            transitions: TransitionSettings::default(),
+            tween: None,
+            lfos: LfoBank::default(),
+            playlist: Playlist::default(),
+            log_viewer: LogViewer::default(),
+            toasts: Toasts::default(),
+            laser_pointer: LaserPointer::default(),
+            hud: HudLayout::default(),
+            render_settings: RenderSettings::default(),
+            force_field: ForceField::default(),
+            flow_field: FlowField::default(),
+            particles: ParticleSystem::default(),
+            rng: StdRng::seed_from_u64(seed),
+            seed,
+            replay: ReplayState::default(),
+            tick: 0,
+            checksum: None,
+            observer: None,
+            debug_panel: DebugPanel::default(),
+            metrics: FlockMetrics::default(),
+            crowding_time: None,
+            force_time: Duration::ZERO,
+            regime: RegimeTracker::default(),
+            regime_thresholds: RegimeThresholds::default(),
+            breakpoints: Breakpoints::default(),
+            lane_plot: LaneOrderPlot::default(),
+            steering_rules: flocking_rules(),
+            camera_path: CameraPath::default(),
+            last_recording: None,
+            last_recording_end: None,
+            timeline: SessionTimeline::default(),
+            highlights: HighlightDetector::default(),
+            split_merge: SplitMergeExperiment::default(),
+            replay_start_seconds: 0.0,
+            #[cfg(not(target_arch = "wasm32"))]
+            html_capture: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            clip_capture: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            svg_export_requested: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            species: SpeciesLibrary::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            plugins: PluginHost::default(),
+            replay_settings: ReplaySettings::default(),
+            wrap_after_recording: false,
+            net: NetSession::default(),
+            net_settings: NetSettings::default(),
+        }
+    }
+}
+
+impl BoidsApp {
+    /// Have `observer` look at (and possibly change) the flock after every tick, for applications
+    /// embedding the simulation.
+    pub fn set_observer(&mut self, observer: impl FnMut(&mut FlockState<'_>) + 'static) {
+        self.observer = Some(Box::new(observer));
+    }
+
+    fn update_observer(&mut self) {
+        if let Some(observer) = &mut self.observer {
+            observer(&mut FlockState {
+                tick: self.tick,
+                predator: self.predator_pos,
+                boids: &mut self.boids,
+            });
+        }
+    }
+
+    pub fn update_boids(&mut self) {
+        // SIMULATION LOGIC
+        // During a game caught boids stay caught, and with sources and sinks the flow decides the
+        // flock size, so in both cases it isn't kept in sync
+        if self.game.is_none() && self.gates.is_empty() {
+            self.sync_flock_size();
+        }
+        self.update_gates();
+
+        if self.params.migration.enabled {
+            self.migration_target
+                .update(&self.params.migration, &mut self.rng);
+        }
+
+        self.update_predators();
+
+        if self.params.altitude.enabled {
+            update_altitude(&mut self.boids, &self.params.altitude, &mut self.rng);
+        }
+
+        if self.params.energy.enabled {
+            update_energy(
+                &mut self.boids,
+                &self.thermals,
+                &self.params.energy,
+                self.params.max_speed,
+            );
+        }
+
+        update_perching(
+            &mut self.boids,
+            &self.perches,
+            self.predator_pos,
+            &self.params,
+            &mut self.rng,
+        );
+
+        if self.integrator_comparison.enabled {
+            self.integrator_comparison.sync(&self.boids);
+        }
+        let active_chunks = self.active_chunks();
+        let mut boids = std::mem::take(&mut self.boids);
+        let started = Instant::now();
+        let outcome = self.world(&self.params, active_chunks).step(&mut boids);
+        let step_time = started.elapsed();
+        self.boids = boids;
+        self.force_time = outcome.force_time;
+        self.crowding_time = outcome.crowding_time;
+        if self.render_settings.effects {
+            for (position, velocity) in outcome.bounces {
+                self.particles.dust(position, velocity);
+            }
+        }
+        self.wear_obstacles(outcome.hits);
+        self.update_waypoint_path();
+        if self.params.danger.enabled {
+            self.danger_map.update(
+                &self.boids,
+                &self.threats(),
+                &self.params.danger,
+                self.params.avoidance_radius,
+            );
+        }
+        self.update_integrator_comparison(step_time);
+        // Impulses only last a tick
+        self.impulses.clear();
+        // Games keep their own score of catches
+        if self.game.is_none() {
+            self.update_catches();
+        }
+        self.update_game();
+    }
+
+    /// Wear the temporary obstacles down by this tick's `hits` and break up the ones that are gone.
+    fn wear_obstacles(&mut self, hits: Vec<usize>) {
+        if self.temporary_obstacles.is_empty() {
+            return;
+        }
+        for obstacle in wear_down(&mut self.temporary_obstacles, hits, self.obstacle_clock) {
+            debug!(tick = self.tick, "temporary obstacle broke");
+            if self.render_settings.effects {
+                self.particles.crumble(obstacle.center(), obstacle.extent());
+            }
+        }
+    }
+
+    /// Move the path on to its next waypoint once the flock or its leader gets there.
+    fn update_waypoint_path(&mut self) {
+        let arrival_radius = self.params.goal_seeking.arrival_radius;
+        let Some(reached) = self.waypoint_path.update(&self.boids, arrival_radius) else {
+            return;
+        };
+        debug!(tick = self.tick, waypoint = reached, "reached waypoint");
+        if self.waypoint_path.target().is_none() {
+            info!(tick = self.tick, "finished waypoint path");
+            self.timeline
+                .mark(self.tick, MarkerKind::Event, "Finished the waypoint path");
+        }
+    }
+
+    fn update_tween(&mut self) {
+        if let Some(entry) = self.playlist.update() {
+            self.timeline.mark(
+                self.tick,
+                MarkerKind::Preset,
+                format!("Playlist moved on to {}", entry.name),
+            );
+            // Playlists always blend, jumping between presets is what they are meant to avoid
+            self.tween = Some(ParameterTween::new(
+                self.params.clone(),
+                entry.params.clone(),
+                self.transitions.seconds,
+                self.obstacle_clock,
+            ));
+        }
+        // Anyone else changing the parameters takes over from the tween
+        if self
+            .tween
+            .as_ref()
+            .is_some_and(|tween| tween.overridden(&self.params))
+        {
+            self.tween = None;
+        }
+    }
+
+    /// Swing the parameters with the tween, or the LFOs when there's none.
+    fn modulate_params(&mut self) {
+        match &mut self.tween {
+            Some(tween) => {
+                if !tween.step(&mut self.params, self.obstacle_clock) {
+                    self.tween = None;
+                }
+            }
+            None => self.lfos.step(&mut self.params, self.obstacle_clock),
+        }
+    }
+
+    /// The parameters as set by hand, without the tween or LFOs swinging them.
+    fn unmodulated_params(&self) -> BoidsSimulationParameters {
+        match &self.tween {
+            // The tween sets every parameter itself
+            Some(tween) => tween.target().clone(),
+            None => self.lfos.unmodulated(&self.params),
+        }
+    }
+
+    /// Set up the lane formation experiment from scratch and start plotting its lane order.
+    fn start_lane_experiment(&mut self) {
+        info!("starting lane formation experiment");
+        self.stop_replay();
+        self.timeline
+            .mark(self.tick, MarkerKind::Preset, "Lane formation experiment");
+        let (params, groups) = lane_formation();
+        self.params = params;
+        self.boids.clear();
+        for index in 0..self.params.num_boids {
+            // Alternate so both directions start out evenly mixed
+            self.params.groups.spawn_group = Some(groups[index % 2]);
+            self.spawn_boid();
+        }
+        self.params.groups.spawn_group = None;
+        self.lane_plot.clear();
+        self.lane_plot.open = true;
+        self.paused = false;
+    }
+
+    /// Start the flock over from `seed`, spawning every boid at once.
+    fn restart_from_seed(&mut self, seed: u64) {
+        info!(seed, "restarting from seed");
+        self.stop_replay();
+        self.timeline.mark(
+            self.tick,
+            MarkerKind::Preset,
+            format!("Restarted with seed {seed}"),
+        );
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+        self.boids.clear();
+        self.next_boid_id = 0;
+        self.respawns.clear();
+        self.predators.clear();
+        self.impulses.clear();
+        self.tween = None;
+        // Everything that changes as the flock runs starts over, so the same seed plays out the
+        // same way however long the session has gone on
+        self.obstacle_clock = 0;
+        for obstacle in &mut self.moving_obstacles {
+            obstacle.placed_at = 0;
+        }
+        for obstacle in &mut self.temporary_obstacles {
+            obstacle.placed_at = 0;
+            obstacle.hits = 0;
+        }
+        self.danger_map = DangerMap::default();
+        self.migration_target = MigrationTarget::default();
+        for _ in 0..self.params.num_boids {
+            self.spawn_boid();
+        }
+    }
+
+    fn start_game(&mut self) {
+        info!(player = self.game_settings.player_name, "starting game");
+        self.stop_replay();
+        self.boids.clear();
+        self.params.num_boids = self.game_settings.flock_size;
+        for _ in 0..self.game_settings.flock_size {
+            self.spawn_boid();
+        }
+        self.game = Some(GameSession::new(&self.game_settings));
+        self.tool = Tool::Predator;
+        self.paused = false;
+    }
+
+    fn update_game(&mut self) {
+        let Some(game) = &mut self.game else {
+            return;
+        };
+        if game.finished {
+            return;
+        }
+
+        if let Some(shepherd) = &mut game.shepherd {
+            shepherd.update(self.shepherd_input, &self.game_settings);
+        }
+        let herded = game.herded;
+        let caught_at = game.update(&mut self.boids, self.predator_pos, &self.game_settings);
+        if self.render_settings.effects {
+            for position in caught_at {
+                self.particles.catch_burst(position);
+            }
+            if game.herded > herded {
+                self.particles.celebrate(self.game_settings.pen_center);
+            }
+        }
+        if game.finished && game.shepherd.is_some() {
+            let survivors = self.boids.len();
+            let (predator, shepherd) = (game.predator_score(), game.shepherd_score(survivors));
+            let winner = match predator.cmp(&shepherd) {
+                Ordering::Greater => "The predator wins",
+                Ordering::Less => "The shepherd wins",
+                Ordering::Equal => "It's a draw",
+            };
+            self.toasts.info(format!(
+                "Round over! {winner}, predator {predator} vs shepherd {shepherd}"
+            ));
+        } else if game.finished {
+            let entry = LeaderboardEntry {
+                name: self.game_settings.player_name.clone(),
+                score: game.score(),
+                catches: game.catches,
+                herded: game.herded,
+                average_survival_seconds: game.average_survival_seconds(self.boids.len()),
+            };
+            let message = match self.leaderboard.submit(entry) {
+                Some(rank) => format!(
+                    "Round over! Score {} is #{} on the leaderboard",
+                    game.score(),
+                    rank + 1
+                ),
+                None => format!("Round over! Score {}", game.score()),
+            };
+            self.toasts.info(message);
+        }
+    }
+
+    fn draw_game_results(&mut self, ctx: &egui::Context) {
+        let Some(game) = &self.game else {
+            return;
+        };
+        if !game.finished {
+            return;
+        }
+
+        let mut play_again = false;
+        let mut done = false;
+        egui::Window::new("Round Over")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                if game.shepherd.is_some() {
+                    ui.heading(format!(
+                        "Predator {} vs Shepherd {}",
+                        game.predator_score(),
+                        game.shepherd_score(self.boids.len())
+                    ));
+                } else {
+                    ui.heading(format!("Score {}", game.score()));
+                }
+                ui.label(format!("Catches: {}", game.catches));
+                ui.label(format!("Herded into the pen: {}", game.herded));
+                ui.label(format!("Survivors: {}", self.boids.len()));
+                ui.label(format!(
+                    "Average survival: {:.1}s",
+                    game.average_survival_seconds(self.boids.len())
+                ));
+                if game.shepherd.is_none() {
+                    ui.separator();
+                    self.leaderboard.draw(ui);
+                }
+                ui.horizontal(|ui| {
+                    play_again = ui.button("Play Again").clicked();
+                    done = ui.button("Done").clicked();
+                });
+            });
+
+        if play_again {
+            self.start_game();
+        } else if done {
+            self.game = None;
+        }
+    }
+
+    fn draw_game_panel(&mut self, ui: &mut Ui) {
+        self.game_settings.draw_panel(ui);
+        ui.horizontal(|ui| {
+            let label = if self.game.is_some() {
+                "Restart"
+            } else {
+                "Start Game"
+            };
+            if ui.button(label).clicked() {
+                self.start_game();
+            }
+            if self.game.is_some() && ui.button("Stop").clicked() {
+                self.game = None;
+            }
+        });
+        ui.separator();
+        ui.label("Leaderboard");
+        self.leaderboard.draw(ui);
+    }
+
+    /// Remove boids the predators got close enough to, and bring back ones whose respawn is due.
+    fn update_catches(&mut self) {
+        // Once a respawn is no longer pending the flock is short a boid, which gets spawned on
+        // the next tick like any other
+        self.respawns.retain_mut(|ticks| {
+            *ticks = ticks.saturating_sub(1);
+            *ticks > 0
+        });
+
+        let catching = &self.params.catching;
+        if !catching.enabled {
+            return;
+        }
+        let threats = self.threats();
+        if threats.is_empty() {
+            return;
+        }
+        let radius_sq = catching.kill_radius * catching.kill_radius;
+        let mut caught_at = Vec::new();
+        self.boids.retain(|boid| {
+            let caught = threats
+                .iter()
+                .any(|threat| threat.position.distance_sq(boid.position) <= radius_sq);
+            if caught {
+                caught_at.push(boid.position);
+            }
+            !caught
+        });
+        if caught_at.is_empty() {
+            return;
+        }
+
+        debug!(count = caught_at.len(), "boids caught");
+        if self.params.danger.enabled {
+            for position in &caught_at {
+                self.danger_map
+                    .deposit(*position, self.params.danger.catch_danger);
+            }
+        }
+        self.kills += caught_at.len() as u32;
+        if catching.respawn {
+            let ticks = catching.respawn_ticks();
+            self.respawns
+                .extend(std::iter::repeat(ticks).take(caught_at.len()));
+        } else {
+            self.params.num_boids = self.params.num_boids.saturating_sub(caught_at.len());
+        }
+        if self.render_settings.effects {
+            for position in caught_at {
+                self.particles.catch_burst(position);
+            }
+        }
+    }
+
+    fn sync_flock_size(&mut self) {
+        // Caught boids waiting to respawn still count towards the flock
+        let target = self.params.num_boids.saturating_sub(self.respawns.len());
+        match self.boids.len().cmp(&target) {
+            Ordering::Greater => {
+                // Remove some boids
+                debug!(from = self.boids.len(), to = target, "removing boids");
+                for boid in self.boids.iter().skip(target) {
+                    self.particles.vanish(boid.position);
+                }
+                self.boids.truncate(target);
+            }
+            Ordering::Less => self.spawn_boid(),
+            Ordering::Equal => {}
+        }
+    }
+
+    fn spawn_boid(&mut self) {
+        // Pick a random point in our field, which in a boundless world is wherever the camera is looking
+        let mut random_pos = || {
+            self.camera.center
+                + Vec2::new(
+                    self.rng
+                        .gen_range(-SIMULATION_AREA_WIDTH / 2.0..SIMULATION_AREA_WIDTH / 2.0),
+                    self.rng
+                        .gen_range(-SIMULATION_AREA_HEIGHT / 2.0..SIMULATION_AREA_HEIGHT / 2.0),
+                )
+        };
+        let mut pos = random_pos();
+        let bounded = self.params.boundary_mode != BoundaryMode::Boundless;
+        // Round arenas don't fill the whole area and obstacles take some of it, so keep trying
+        // until we land somewhere a boid can be
+        for _ in 0..SPAWN_ATTEMPTS {
+            if (!bounded || self.params.arena.contains(&WORLD_BOUNDS, pos))
+                && !obstacles_at(
+                    &self.obstacles,
+                    &self.temporary_obstacles,
+                    &self.moving_obstacles,
+                    self.obstacle_clock as f32,
+                )
+                .iter()
+                .any(|obstacle| obstacle.contains(pos))
+            {
+                break;
+            }
+            pos = random_pos();
+        }
+        // Set a random initial velocity
+        let rand_x_vel = self
+            .rng
+            .gen_range(-self.params.max_speed..self.params.max_speed);
+        let rand_y_vel = self
+            .rng
+            .gen_range(-self.params.max_speed..self.params.max_speed);
+        let random_velocity = Vec2::new(rand_x_vel, rand_y_vel);
+
+        self.spawn_boid_at(pos, random_velocity);
+    }
+
+    fn spawn_boid_at(&mut self, pos: Pos2, velocity: Vec2) {
+        let mut boid = Boid::with_id(self.next_boid_id, pos, velocity);
+        self.next_boid_id += 1;
+        boid.genome = Genome::random(self.params.genome_variation, &mut self.rng);
+        boid.group = self.params.groups.spawn_group;
+        boid.rank = self.rng.gen();
+
+        trace!(?pos, ?velocity, "spawning boid");
+        self.boids.push(boid);
+        self.particles.hatch(pos);
+    }
+
+    /// Remove the boids that flew into a sink and let the sources emit new ones.
+    fn update_gates(&mut self) {
+        if self.gates.is_empty() {
+            self.flow_meter.clear();
+            return;
+        }
+
+        let before = self.boids.len();
+        let gates = &self.gates;
+        let particles = &mut self.particles;
+        self.boids.retain(|boid| {
+            let sunk = gates
+                .iter()
+                .any(|gate| gate.kind == GateKind::Sink && gate.area.contains(boid.position));
+            if sunk {
+                particles.vanish(boid.position);
+            }
+            !sunk
+        });
+        let removed = before - self.boids.len();
+
+        let mut spawned = 0;
+        for gate in self.gates.clone() {
+            let GateKind::Source { velocity, .. } = gate.kind else {
+                continue;
+            };
+            for _ in 0..gate.emit_count(&mut self.rng) {
+                let pos = gate.area.random_point(&mut self.rng);
+                self.spawn_boid_at(pos, velocity);
+                spawned += 1;
+            }
+        }
+        self.flow_meter.record(spawned, removed);
+    }
+
+    /// Keep the number of predators in line with the parameters and let them hunt.
+    fn update_predators(&mut self) {
+        let count = self.params.predators.count;
+        self.predators.truncate(count);
+        while self.predators.len() < count {
+            let position = self.camera.center
+                + Vec2::new(
+                    self.rng.gen_range(LEFT..RIGHT),
+                    self.rng.gen_range(TOP..BOTTOM),
+                );
+            self.predators.push(Predator::new(position, &mut self.rng));
+        }
+
+        let goals = hunt_goals(&self.predators, &self.boids, &self.params.predators);
+        for (predator, goal) in self.predators.iter_mut().zip(goals) {
+            predator.update(goal, &self.params.predators, &mut self.rng);
+            self.params.arena.contain(
+                &mut predator.position,
+                &mut predator.velocity,
+                &WORLD_BOUNDS,
+                self.params.boundary_mode,
+            );
+        }
+    }
+
+    /// Step the comparison's copy of the flock like the flock was, but with its own integrator.
+    fn update_integrator_comparison(&mut self, step_time: Duration) {
+        if !self.integrator_comparison.enabled {
+            return;
+        }
+        let mut shadow = std::mem::take(&mut self.integrator_comparison.shadow);
+        let mut params = self.params.clone();
+        params.integration.integrator = self.integrator_comparison.integrator;
+        let started = Instant::now();
+        self.world(&params, None).step(&mut shadow);
+        let shadow_time = started.elapsed();
+        self.integrator_comparison.shadow = shadow;
+        self.integrator_comparison
+            .record(&self.boids, (step_time, shadow_time));
+    }
+
+    /// Sample the force field over the part of the world on screen, from the live flock as the
+    /// next tick will see it.
+    fn update_force_field(&mut self) {
+        let Some(area) = self
+            .visible_world_rect()
+            .filter(|_| self.force_field.enabled)
+        else {
+            return;
+        };
+        let mut force_field = std::mem::take(&mut self.force_field);
+        force_field.sample(
+            &self.world(&self.params, self.active_chunks()),
+            &self.boids,
+            area,
+        );
+        self.force_field = force_field;
+    }
+
+    /// Step the ghost previewing a hovered parameter value.
+    fn update_ghost(&mut self) {
+        let Some(params) = self.ghost.params.take() else {
+            return;
+        };
+        // The ghost only feels the rest of the world, the live flock and the RNG are left alone
+        let mut boids = std::mem::take(&mut self.ghost.boids);
+        self.world(&params, None).step(&mut boids);
+        self.ghost.boids = boids;
+        self.ghost.params = Some(params);
+        self.ghost.advance(&self.boids);
+    }
+
+    /// The chunks simulated every tick, `None` when every boid is.
+    ///
+    /// The chunks follow the flock's centroid, the migration target and the local predator rather
+    /// than the camera, so replays don't depend on where anyone was looking.
+    fn active_chunks(&self) -> Option<ActiveChunks> {
+        // Level of detail only makes sense for the huge worlds you get without edges
+        if self.params.boundary_mode != BoundaryMode::Boundless || !self.params.chunks.enabled {
+            return None;
+        }
+
+        let mut focus: Vec<Pos2> = self.predator_pos.into_iter().collect();
+        focus.extend(self.flock_centroid());
+        if self.params.migration.enabled {
+            focus.push(self.migration_target.position);
+        }
+        Some(self.params.chunks.active_chunks(&focus))
+    }
+
+    /// The part of the world the central panel showed last frame, `None` before it's been drawn.
+    fn visible_world_rect(&self) -> Option<Rect> {
+        self.view_rect
+            .is_positive()
+            .then(|| self.camera.visible_world_rect(&self.view_rect))
+    }
+
+    fn flock_centroid(&self) -> Option<Pos2> {
+        if self.boids.is_empty() {
+            return None;
+        }
+        let sum = self
+            .boids
+            .iter()
+            .fold(Vec2::ZERO, |sum, boid| sum + boid.position.to_vec2());
+        Some(Pos2::ZERO + sum / self.boids.len() as f32)
+    }
+
+    /// Advance the simulation by one fixed length tick.
+    pub(crate) fn tick_simulation(&mut self) {
+        trace!(tick = self.tick, "simulation tick");
+        for mut boid in &mut self.boids {
+            boid.previous_position = *boid.position;
+        }
+        self.update_predator_velocity();
+        // Replays bring their own tweens along
+        if !self.replay.is_playing() {
+            self.update_tween();
+        }
+        // Recordings keep the parameters from before they're swung, and swing them again on playback
+        self.update_replay();
+        self.modulate_params();
+        self.update_boids();
+        self.obstacle_clock += 1;
+        self.render_settings
+            .trails
+            .record(&self.boids, &self.params.groups);
+        self.update_force_field();
+        self.update_ghost();
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(capture) = &mut self.html_capture {
+            capture.record(&self.boids);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        self.update_clip_capture();
+        self.tick += 1;
+        self.update_observer();
+        self.update_checksum();
+        self.update_metrics();
+        #[cfg(not(target_arch = "wasm32"))]
+        if let NetSession::Hosting(host) = &mut self.net {
+            host.broadcast_state(&self.boids, self.predator_pos);
+        }
+    }
+
+    /// The flock as it should be drawn this frame, part way through the tick that's under way
+    /// so motion stays smooth when frames and ticks don't line up.
+    fn interpolated_boids(&self) -> Cow<'_, BoidStore> {
+        if !self.render_settings.interpolate || self.paused || self.net.is_client() {
+            return Cow::Borrowed(&self.boids);
+        }
+        let alpha = (self.accumulator / FRAME_TIME).clamp(0.0, 1.0);
+        let mut boids = self.boids.clone();
+        for boid in &mut boids {
+            *boid.position = boid.as_ref().interpolated_position(alpha);
+        }
+        Cow::Owned(boids)
+    }
+
+    /// Track how fast the mouse predator moves, tick to tick, so boids can tell how fast it's coming at them.
+    fn update_predator_velocity(&mut self) {
+        self.predator_velocity = match (self.predator_pos, self.last_predator_pos) {
+            (Some(position), Some(last)) => position - last,
+            _ => Vec2::ZERO,
+        };
+        self.last_predator_pos = self.predator_pos;
+    }
+
+    /// Every predator in the simulation, mouse driven or not. Remote cursors only arrive as positions, so they count as standing still.
+    fn threats(&self) -> Vec<Threat> {
+        self.predator_pos
+            .map(|position| Threat {
+                position,
+                velocity: self.predator_velocity,
+            })
+            .into_iter()
+            .chain(
+                self.net
+                    .remote_predators()
+                    .into_iter()
+                    .map(|position| Threat {
+                        position,
+                        velocity: Vec2::ZERO,
+                    }),
+            )
+            .chain(self.predators.iter().map(|predator| Threat {
+                position: predator.position,
+                velocity: predator.velocity,
+            }))
+            .collect()
+    }
+
+    /// Everything but the flock a tick of it depends on, with `params` for the parameters.
+    ///
+    /// `active_chunks` are the chunks simulated every tick, see [`Self::active_chunks`].
+    fn world<'a>(
+        &'a self,
+        params: &'a BoidsSimulationParameters,
+        active_chunks: Option<ActiveChunks>,
+    ) -> World<'a> {
+        World {
+            params,
+            rules: &self.steering_rules,
+            threats: self.threats(),
+            migration_target: self.migration_target.position,
+            goal_points: &self.goal_points,
+            waypoint_path: &self.waypoint_path,
+            danger_map: &self.danger_map,
+            perches: &self.perches,
+            thermals: &self.thermals,
+            shepherd: self.game.as_ref().and_then(|game| game.shepherd.as_ref()),
+            game_settings: &self.game_settings,
+            obstacles: &self.obstacles,
+            temporary_obstacles: &self.temporary_obstacles,
+            moving_obstacles: &self.moving_obstacles,
+            obstacle_clock: self.obstacle_clock,
+            current_zones: &self.current_zones,
+            impulses: &self.impulses,
+            experiment: &self.split_merge.forces,
+            active_chunks,
+            tick: self.obstacle_clock,
+            bounds: WORLD_BOUNDS,
+            parallel: self.debug_panel.parallel_forces(),
+        }
+    }
 }
 
-impl Default for BoidsApp {
-    fn default() -> Self {
-        Self {
-            boids: Vec::new(),
-            predator_pos: None,
-            last_update_time: Instant::now(),
-            paused: false,
-            params: BoidsSimulationParameters::default(),
+impl BoidsApp {
+    fn load_scenario(&mut self, scenario: Scenario) {
+        self.stop_replay();
+        let label = if scenario.boids.is_empty() {
+            "Loaded a preset"
+        } else {
+            "Loaded a scenario"
+        };
+        self.timeline.mark(self.tick, MarkerKind::Preset, label);
+        // Presets don't carry any boids, so keep the flock we already have
+        let has_boids = !scenario.boids.is_empty();
+        let num_boids = if !has_boids {
+            self.params.num_boids
+        } else {
+            self.boids = (0..)
+                .zip(&scenario.boids)
+                .map(|(id, boid)| boid.to_boid(id))
+                .collect();
+            self.next_boid_id = scenario.boids.len() as u64;
+            self.boids.len()
+        };
+        let mut params = scenario.params;
+        params.num_boids = num_boids;
+        match self
+            .transitions
+            .tween(&self.params, params.clone(), self.obstacle_clock)
+        {
+            Some(tween) => self.tween = Some(tween),
+            None => self.params = params,
+        }
+
+        if !has_boids && scenario.annotations.is_empty() {
+            return;
+        }
+        // Scenario annotations point at boids by index, which become ids once the boids exist
+        self.annotations = scenario
+            .annotations
+            .into_iter()
+            .filter_map(|mut annotation| {
+                if let AnnotationTarget::Boid(index) = annotation.target {
+                    // Presets have no boids to attach to
+                    let boid = self.boids.get(index as usize).filter(|_| has_boids)?;
+                    annotation.target = AnnotationTarget::Boid(boid.id);
+                }
+                Some(annotation)
+            })
+            .collect();
+    }
+
+    /// Exchange state with the other instances sharing this simulation.
+    ///
+    /// Clients don't simulate anything themselves, they show the host's flock and send it their cursor.
+    fn update_network(&mut self, ctx: &egui::Context) {
+        match &mut self.net {
+            NetSession::Offline => {}
+            #[cfg(not(target_arch = "wasm32"))]
+            NetSession::Hosting(host) => host.poll(),
+            NetSession::Connected(client) => match client.poll() {
+                Ok(()) => {
+                    self.boids = client.boids();
+                    client.send_cursor(self.predator_pos);
+                    ctx.request_repaint();
+                }
+                Err(err) => {
+                    self.toasts.report("Disconnected", &err);
+                    self.net = NetSession::Offline;
+                }
+            },
+        }
+    }
+
+    fn current_inputs(&self) -> SimulationInputs {
+        SimulationInputs {
+            predator: self.predator_pos,
+            predator_velocity: self.predator_velocity,
+            spawn_center: self.camera.center,
+            flock_size: self.params.num_boids,
+            params: self.unmodulated_params(),
+            lfos: self.lfos.lfos.clone(),
+            tween: self.tween.clone(),
+            perches: self.perches.clone(),
+            goal_points: self.goal_points.clone(),
+            waypoint_path: self.waypoint_path.clone(),
+            current_zones: self.current_zones.clone(),
+            obstacles: self.obstacles.clone(),
+            temporary_obstacles: self.temporary_obstacles.clone(),
+            moving_obstacles: self.moving_obstacles.clone(),
+            thermals: self.thermals.clone(),
+            annotations: self.annotations.clone(),
+            gates: self.gates.clone(),
+            impulses: self.impulses.clone(),
+            experiment: self.split_merge.forces,
+        }
+    }
+
+    fn apply_inputs(&mut self, inputs: &SimulationInputs) {
+        self.predator_pos = inputs.predator;
+        self.predator_velocity = inputs.predator_velocity;
+        self.camera.center = inputs.spawn_center;
+        self.params = inputs.params.clone();
+        self.params.num_boids = inputs.flock_size;
+        self.lfos.lfos = inputs.lfos.clone();
+        self.tween = inputs.tween.clone();
+        self.perches = inputs.perches.clone();
+        self.goal_points = inputs.goal_points.clone();
+        self.waypoint_path = inputs.waypoint_path.clone();
+        self.current_zones = inputs.current_zones.clone();
+        self.obstacles = inputs.obstacles.clone();
+        self.temporary_obstacles = inputs.temporary_obstacles.clone();
+        self.moving_obstacles = inputs.moving_obstacles.clone();
+        self.thermals = inputs.thermals.clone();
+        self.annotations = inputs.annotations.clone();
+        self.gates = inputs.gates.clone();
+        self.impulses = inputs.impulses.clone();
+        self.split_merge.forces = inputs.experiment;
+    }
+
+    /// Start recording the user's inputs from the current state, with a fresh seed.
+    fn start_recording(&mut self) {
+        let seed = rand::random();
+        info!(seed, "recording inputs");
+        self.rng = StdRng::seed_from_u64(seed);
+        if self.replay_settings.contain_while_recording
+            && self.params.boundary_mode == BoundaryMode::Wrap
+        {
+            self.params.boundary_mode = BoundaryMode::Contain;
+            self.wrap_after_recording = true;
+        }
+        self.replay = ReplayState::Recording(InputRecorder::new(self.keyframe(seed)));
+    }
+
+    /// The state to replay from, for the recorder to fill in the tick.
+    fn keyframe(&self, seed: u64) -> ReplayKeyframe {
+        ReplayKeyframe {
+            tick: 0,
+            seed,
+            boids: self.boids.clone(),
+            next_boid_id: self.next_boid_id,
+            migration_target: self.migration_target.clone(),
+            predators: self.predators.clone(),
+            respawns: self.respawns.clone(),
+            obstacle_clock: self.obstacle_clock,
+            danger_map: self.danger_map.clone(),
+            inputs: self.current_inputs(),
+        }
+    }
+
+    /// Rewind to the start of the last recording and play it back.
+    fn start_replay(&mut self) {
+        let Some(recording) = &self.last_recording else {
+            return;
+        };
+        let recording =
+            recording.starting_at((self.replay_start_seconds * TICKS_PER_SECOND) as u64);
+
+        let start = recording.start();
+        info!(
+            seed = start.seed,
+            ticks = recording.length_ticks,
+            "replaying inputs"
+        );
+        self.rng = StdRng::seed_from_u64(start.seed);
+        self.boids = start.boids.clone();
+        self.next_boid_id = start.next_boid_id;
+        self.migration_target = start.migration_target.clone();
+        self.predators = start.predators.clone();
+        self.respawns = start.respawns.clone();
+        self.obstacle_clock = start.obstacle_clock;
+        self.danger_map = start.danger_map.clone();
+        // The recording starts from parameters nothing has swung yet
+        self.lfos.forget_modulations();
+        self.apply_inputs(&start.inputs.clone());
+        self.replay = ReplayState::Playing(InputPlayer::new(recording));
+        self.paused = false;
+    }
+
+    /// The ticks of this session the replay buffer covers.
+    fn replayable_ticks(&self) -> Option<RangeInclusive<u64>> {
+        let (length, end) = match &self.replay {
+            ReplayState::Recording(recorder) => (recorder.length_ticks(), self.tick),
+            _ => (
+                self.last_recording.as_ref()?.length_ticks,
+                self.last_recording_end?,
+            ),
+        };
+        Some(end.saturating_sub(length)..=end)
+    }
+
+    /// Replay the recording from `tick` of this session, paused there to look at.
+    fn jump_to(&mut self, tick: u64) {
+        if self.replay_from(tick) {
+            self.paused = true;
+        }
+    }
+
+    /// Play the recording from `tick` of this session, returns whether that's in the recording.
+    fn replay_from(&mut self, tick: u64) -> bool {
+        if matches!(self.replay, ReplayState::Recording(_)) {
+            self.stop_replay();
+        }
+        let Some(range) = self.replayable_ticks() else {
+            return false;
+        };
+        let Some(recording) = &self.last_recording else {
+            return false;
+        };
+        let offset = tick.saturating_sub(*range.start());
+        // Playback can only start on a keyframe, so run the rest of the way from the one before
+        let keyframe = recording
+            .keyframes
+            .iter()
+            .rev()
+            .find(|keyframe| keyframe.tick <= offset)
+            .map_or(0, |keyframe| keyframe.tick);
+        info!(tick, offset, "jumping to a moment in the recording");
+        self.replay_start_seconds = offset as f32 / TICKS_PER_SECOND;
+        self.start_replay();
+        for _ in keyframe..offset {
+            self.tick_simulation();
+        }
+        true
+    }
+
+    /// Replay the ticks of `clip` through once, saving them as a GIF.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_clip(&mut self, clip: RangeInclusive<u64>) {
+        if !self.replay_from(*clip.start()) {
+            return;
+        }
+        match ClipCapture::new(WORLD_BOUNDS, &self.obstacles, clip.end() - clip.start()) {
+            Ok(capture) => self.clip_capture = Some(capture),
+            Err(err) => {
+                self.stop_replay();
+                self.toasts.report("Could not export clip", &err);
+            }
+        }
+    }
+
+    /// Apply the species definitions whenever their files change.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn update_species(&mut self) {
+        let result = match self.species.poll() {
+            None => return,
+            Some(result) => result,
+        };
+        match result.and_then(|species| {
+            self.params.groups.apply_species(&species)?;
+            Ok(species.len())
+        }) {
+            Ok(count) => self.toasts.info(format!("Loaded {count} species")),
+            Err(err) => self.toasts.report("Could not load species", &err),
+        }
+    }
+
+    /// Load the plugins again and put their behaviors after the built-in ones.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_plugins(&mut self) {
+        match self.plugins.load() {
+            Ok(count) => {
+                if count > 0 {
+                    self.toasts.info(format!("Loaded {count} plugins"));
+                }
+                let failures = self.plugins.failure_count();
+                if failures > 0 {
+                    self.toasts.warning(format!(
+                        "{failures} plugins could not be loaded, see Behaviors"
+                    ));
+                }
+            }
+            Err(err) => self.toasts.report("Could not load plugins", &err),
+        }
+        self.steering_rules = flocking_rules();
+        self.steering_rules.extend(self.plugins.rules());
+    }
+
+    /// Add this tick to the clip being exported, saving it once it's done.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn update_clip_capture(&mut self) {
+        let Some(capture) = &mut self.clip_capture else {
+            return;
+        };
+        match capture.record(&self.boids) {
+            Ok(false) => {}
+            Ok(true) => {
+                if let Some(capture) = self.clip_capture.take() {
+                    self.save_clip_export(capture);
+                }
+                self.stop_replay();
+            }
+            Err(err) => {
+                self.clip_capture = None;
+                self.stop_replay();
+                self.toasts.report("Could not export clip", &err);
+            }
+        }
+    }
+
+    /// Stop recording or playing back, keeping whatever was recorded so far.
+    fn stop_replay(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let html = self.html_capture.take().is_some();
+            let clip = self.clip_capture.take().is_some();
+            if html || clip {
+                self.toasts.info("Export cancelled");
+            }
+        }
+        if let ReplayState::Recording(recorder) = std::mem::take(&mut self.replay) {
+            let recording = recorder.finish();
+            info!(ticks = recording.length_ticks, "recording stopped");
+            self.last_recording = Some(recording);
+            self.last_recording_end = Some(self.tick);
+        }
+        // Unless someone picked another mode since
+        if std::mem::take(&mut self.wrap_after_recording)
+            && self.params.boundary_mode == BoundaryMode::Contain
+        {
+            self.params.boundary_mode = BoundaryMode::Wrap;
+        }
+    }
+
+    /// Record this tick's inputs, or swap them for the recorded ones when replaying.
+    fn update_replay(&mut self) {
+        match &mut self.replay {
+            ReplayState::Idle => {}
+            ReplayState::Recording(_) => {
+                let inputs = self.current_inputs();
+                let keyframe_due = matches!(
+                    &self.replay,
+                    ReplayState::Recording(recorder) if recorder.keyframe_due()
+                );
+                let keyframe = keyframe_due.then(|| {
+                    // Reseeding at every keyframe lets playback start from any of them
+                    let seed = self.rng.gen();
+                    self.rng = StdRng::seed_from_u64(seed);
+                    self.keyframe(seed)
+                });
+                let ReplayState::Recording(recorder) = &mut self.replay else {
+                    return;
+                };
+                if let Some(keyframe) = keyframe {
+                    recorder.add_keyframe(keyframe);
+                }
+                recorder.record(&inputs);
+                recorder.enforce_budget(&self.replay_settings);
+            }
+            ReplayState::Playing(player) => match player.next_step() {
+                Some(step) => {
+                    if let Some(seed) = step.reseed {
+                        self.rng = StdRng::seed_from_u64(seed);
+                    }
+                    let inputs = step.inputs.clone();
+                    self.apply_inputs(&inputs);
+                }
+                None => {
+                    self.replay = ReplayState::Idle;
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(capture) = self.html_capture.take() {
+                        self.save_html_export(&capture);
+                        return;
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(capture) = self.clip_capture.take() {
+                        self.save_clip_export(capture);
+                        return;
+                    }
+                    self.toasts.info("Replay finished");
+                }
+            },
+        }
+    }
+
+    /// Checksum the new state for the debug panel and to catch replays that diverge from their recording.
+    fn update_checksum(&mut self) {
+        let needed = self.debug_panel.show_checksum || !matches!(self.replay, ReplayState::Idle);
+        self.checksum =
+            needed.then(|| state_checksum(&self.boids, &self.migration_target, &self.predators));
+
+        match (&mut self.replay, self.checksum) {
+            (ReplayState::Recording(recorder), Some(checksum)) => {
+                recorder.record_checksum(checksum);
+            }
+            (ReplayState::Playing(player), Some(checksum)) => {
+                if player.check(checksum) {
+                    self.toasts.warning(format!(
+                        "Replay diverged from the recording at tick {}",
+                        player.diverged_at.unwrap_or_default()
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Measure the flock and pause if that trips a breakpoint.
+    fn update_metrics(&mut self) {
+        self.metrics = FlockMetrics::measure(&self.boids);
+        let regime = self.regime.current;
+        self.regime
+            .update(&self.metrics, &self.regime_thresholds, self.tick);
+        // A replay would mark the same events again
+        if self.regime.current != regime && !self.replay.is_playing() {
+            self.timeline.mark(
+                self.tick,
+                MarkerKind::Event,
+                format!("Regime changed to {}", self.regime.current.label()),
+            );
+        }
+        if self.replay.is_playing() {
+            // Replays jump around the session, and would find the same highlights again
+            self.highlights.interrupt();
+            self.split_merge.interrupt();
+        } else {
+            if let Some(kind) =
+                self.highlights
+                    .update(&self.boids, self.params.neighbor_radius, self.tick)
+            {
+                self.timeline.mark(
+                    self.tick,
+                    MarkerKind::Event,
+                    format!("Highlight: {}", kind.label()),
+                );
+            }
+            if let Some(trial) =
+                self.split_merge
+                    .update(&self.boids, self.params.neighbor_radius, self.tick)
+            {
+                self.timeline.mark(
+                    self.tick,
+                    MarkerKind::Event,
+                    format!(
+                        "Flock re-merged after {:.1}s",
+                        trial.merge_ticks as f32 / TICKS_PER_SECOND
+                    ),
+                );
+            }
+        }
+        if self.lane_plot.open {
+            // Lanes run along the corridor, which goes from left to right
+            self.lane_plot.record(lane_order(
+                &self.boids,
+                Vec2::X,
+                self.params.neighbor_radius,
+            ));
+        }
+        if let Some(reason) = self.breakpoints.check(&self.metrics) {
+            self.timeline.mark(
+                self.tick,
+                MarkerKind::Event,
+                format!("Breakpoint: {reason}"),
+            );
+            self.paused = true;
+            self.toasts
+                .info(format!("Paused at tick {}: {reason}", self.tick));
+        }
+    }
+
+    fn debug_stats(&self) -> DebugStats {
+        let divergence = match &self.replay {
+            ReplayState::Playing(player) => player.diverged_at,
+            _ => None,
+        };
+        // Sizing a long recording means walking all of it, so only do it when someone is looking
+        let memory = if self.debug_panel.open {
+            let replay_bytes = match &self.replay {
+                ReplayState::Recording(recorder) => recorder.approximate_bytes(),
+                ReplayState::Playing(player) => player.recording().approximate_bytes(),
+                ReplayState::Idle => self
+                    .last_recording
+                    .as_ref()
+                    .map_or(0, InputRecording::approximate_bytes),
+            };
+            vec![
+                ("Boids", self.boids.allocated_bytes()),
+                ("Replay Buffer", replay_bytes),
+                ("Log", approximate_log_bytes()),
+            ]
+        } else {
+            Vec::new()
+        };
+
+        DebugStats {
+            tick: self.tick,
+            boids: self.boids.len(),
+            checksum: self.checksum,
+            polarization: self.metrics.polarization,
+            milling: self.metrics.milling,
+            regime: self.regime.current,
+            throughput: (!self.gates.is_empty()).then(|| self.flow_meter.rates()),
+            crowding_time: self.crowding_time,
+            force_time: self.force_time,
+            divergence,
+            memory,
+        }
+    }
+
+    fn hud_stats(&self) -> HudStats {
+        let average_velocity = if self.boids.is_empty() {
+            Vec2::ZERO
+        } else {
+            self.boids
+                .iter()
+                .fold(Vec2::ZERO, |sum, boid| sum + boid.velocity)
+                / self.boids.len() as f32
+        };
+        HudStats {
+            boids: self.boids.len(),
+            average_velocity,
+            milling: self.metrics.milling,
+            regime: self.regime.current,
+            tick: self.tick,
+        }
+    }
+
+    /// The steering rules in the order their forces are added up, then the plugins they came from.
+    fn draw_behaviors_panel(&mut self, ui: &mut Ui) {
+        for (index, rule) in self.steering_rules.iter().enumerate() {
+            let name = format!("{}. {}", index + 1, rule.name());
+            match rule.color() {
+                Some(color) => ui.colored_label(color, name),
+                None => ui.label(name),
+            };
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            ui.separator();
+            if self.plugins.draw_panel(ui) {
+                self.load_plugins();
+            }
+        }
+    }
+
+    fn draw_flock_state_panel(&mut self, ui: &mut Ui) {
+        egui::Grid::new("flock_state").show(ui, |ui| {
+            ui.label("Regime");
+            ui.strong(self.regime.current.label());
+            ui.end_row();
+            ui.label("Polarization");
+            ui.label(format!("{:.2}", self.metrics.polarization));
+            ui.end_row();
+            ui.label("Milling");
+            ui.label(format!("{:.2}", self.metrics.milling));
+            ui.end_row();
+        });
+        ui.separator();
+        self.regime_thresholds.draw_panel(ui);
+    }
+
+    fn draw_split_merge_panel(&mut self, ui: &mut Ui) {
+        let split_hint = self.bindings.describe(Action::SplitFlock);
+        let merge_hint = self.bindings.describe(Action::ForceMerge);
+        match self.split_merge.draw_panel(ui, &split_hint, &merge_hint) {
+            Some(ExperimentToggle::Split) => self.toggle_split(),
+            Some(ExperimentToggle::Merge) => self.toggle_merge(),
+            None => {}
+        }
+    }
+
+    /// Put the dividing wall up through the middle of the flock, or take it down.
+    fn toggle_split(&mut self) {
+        if self.replay.is_playing() {
+            self.toasts
+                .info("The replay decides where the flock splits");
+            return;
+        }
+        let center = self.flock_centroid().unwrap_or(self.camera.center);
+        let text = if self.split_merge.toggle_split(center, self.tick) {
+            "Flock split"
+        } else {
+            "Split released"
+        };
+        info!(tick = self.tick, "{text}");
+        self.timeline
+            .mark(self.tick, MarkerKind::Event, text.to_owned());
+    }
+
+    /// Switch the attractor in the middle of the flock on or off.
+    fn toggle_merge(&mut self) {
+        if self.replay.is_playing() {
+            self.toasts
+                .info("The replay decides where the flock merges");
+            return;
+        }
+        let center = self.flock_centroid().unwrap_or(self.camera.center);
+        let text = if self.split_merge.toggle_merge(center) {
+            "Merge forced"
+        } else {
+            "Merge released"
+        };
+        info!(tick = self.tick, "{text}");
+        self.timeline
+            .mark(self.tick, MarkerKind::Event, text.to_owned());
+    }
+
+    fn draw_highlights_panel(&mut self, ui: &mut Ui) {
+        let replayable = self.replayable_ticks();
+        #[cfg(not(target_arch = "wasm32"))]
+        let exporting = self.clip_capture.is_some();
+        #[cfg(target_arch = "wasm32")]
+        let exporting = false;
+        match self.highlights.draw_panel(ui, replayable, exporting) {
+            Some(HighlightAction::Watch(clip)) => {
+                self.replay_from(*clip.start());
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Some(HighlightAction::Export(clip)) => self.export_clip(clip),
+            None => {}
+        }
+    }
+
+    fn draw_replay_panel(&mut self, ui: &mut Ui) {
+        match &self.replay {
+            ReplayState::Idle => {
+                let can_record = self.game.is_none() && matches!(self.net, NetSession::Offline);
+                if ui
+                    .add_enabled(can_record, egui::Button::new("Record"))
+                    .on_disabled_hover_text(
+                        "Inputs can't be recorded during a game or a network session",
+                    )
+                    .clicked()
+                {
+                    self.start_recording();
+                }
+                if let Some(recording) = &self.last_recording {
+                    let length = recording.length_seconds();
+                    let label = format!("Replay ({length:.1}s)");
+                    if ui
+                        .add_enabled(can_record, egui::Button::new(label))
+                        .clicked()
+                    {
+                        self.start_replay();
+                    }
+                    ui.add(
+                        egui::Slider::new(&mut self.replay_start_seconds, 0.0..=length)
+                            .text("Start At")
+                            .suffix("s"),
+                    )
+                    .on_hover_text("Playback starts from the last keyframe before this");
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui.button("Save Recording").clicked() {
+                        self.save_recording();
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui
+                        .add_enabled(can_record, egui::Button::new("Export Web Page"))
+                        .on_hover_text("Plays the replay through once, then saves it as a page with a player anyone can open in a browser")
+                        .clicked()
+                    {
+                        self.html_capture = Some(ReplayCapture::default());
+                        self.start_replay();
+                    }
+                }
+            }
+            ReplayState::Recording(recorder) => {
+                ui.label(format!(
+                    "Recording {:.1}s ({})",
+                    recorder.length_seconds(),
+                    format_bytes(recorder.approximate_bytes())
+                ));
+                if ui.button("Stop").clicked() {
+                    self.stop_replay();
+                }
+            }
+            ReplayState::Playing(player) => {
+                ui.label(format!(
+                    "Replaying {:.1}s / {:.1}s",
+                    player.elapsed_seconds(),
+                    player.recording().length_seconds()
+                ));
+                if ui.button("Stop").clicked() {
+                    self.stop_replay();
+                }
+            }
+        }
+        self.replay_settings.draw_panel(ui);
+    }
+
+    fn update_predator(&mut self, ctx: &egui::Context, ui: &Ui, rect: &Rect) {
+        if let Some(position) = self.long_press(ctx, rect) {
+            // Long pressing the pinned predator again takes it away
+            let tolerance = PICK_DISTANCE / self.camera.zoom;
+            self.pinned_predator = match self.pinned_predator {
+                Some(pinned) if pinned.distance(position) < tolerance => None,
+                _ => Some(position),
+            };
+        }
+        self.predator_pos = ctx
+            .input(|i| i.pointer.hover_pos())
+            .filter(|mouse_pos| rect.contains(*mouse_pos))
+            .map(|mouse_pos| self.camera.screen_to_world(rect, mouse_pos))
+            .or(self.pinned_predator);
+        self.draw_predator(ui, rect);
+    }
+
+    /// World position of a press held still in the simulation area, once per press.
+    fn long_press(&mut self, ctx: &egui::Context, rect: &Rect) -> Option<Pos2> {
+        let (down, still, held, origin) = ctx.input(|i| {
+            let pointer = &i.pointer;
+            let held = pointer
+                .press_start_time()
+                .map_or(0.0, |start| i.time - start);
+            (
+                pointer.primary_down(),
+                !pointer.is_decidedly_dragging() && i.multi_touch().is_none(),
+                held,
+                pointer.press_origin(),
+            )
+        });
+        if !down {
+            self.long_press_handled = false;
+            return None;
+        }
+        if self.long_press_handled || !still || held < LONG_PRESS_SECONDS {
+            return None;
+        }
+        let origin = origin.filter(|origin| rect.contains(*origin))?;
+        self.long_press_handled = true;
+        Some(self.camera.screen_to_world(rect, origin))
+    }
+
+    /// Pinch to zoom and two-finger drag to pan, on touch screens and trackpads alike.
+    fn handle_camera_gestures(
+        &mut self,
+        ctx: &egui::Context,
+        response: &egui::Response,
+        rect: &Rect,
+    ) {
+        if self.bindings.pressed(ctx, Action::ResetView) {
+            self.manual_camera = false;
+        }
+        let key_zoom = if self.bindings.pressed(ctx, Action::ZoomIn) {
+            KEY_ZOOM_STEP
+        } else if self.bindings.pressed(ctx, Action::ZoomOut) {
+            1.0 / KEY_ZOOM_STEP
+        } else {
+            1.0
+        };
+        if key_zoom != 1.0 {
+            self.camera.zoom_around(rect, rect.center(), key_zoom);
+            self.manual_camera = true;
+        }
+
+        if !response.contains_pointer() {
+            return;
+        }
+        let (zoom, pan, focus) = ctx.input(|i| {
+            let pan = i
+                .multi_touch()
+                .map_or(i.smooth_scroll_delta, |touch| touch.translation_delta);
+            (i.zoom_delta(), pan, i.pointer.latest_pos())
+        });
+        if zoom == 1.0 && pan == Vec2::ZERO {
+            return;
+        }
+        self.camera
+            .zoom_around(rect, focus.unwrap_or(rect.center()), zoom);
+        self.camera.pan(pan);
+        self.manual_camera = true;
+    }
+
+    fn draw_predator(&self, ui: &Ui, rect: &Rect) {
+        let Some(predator_pos) = self.predator_pos else {
+            return;
+        };
+
+        let screen_pos = self.camera.world_to_screen(rect, predator_pos);
+        let painter: egui::Painter = ui.painter_at(*rect);
+        painter.circle_filled(screen_pos, 5.0, Color32::RED);
+        painter.circle_stroke(
+            screen_pos,
+            self.params.avoidance_radius * self.camera.zoom,
+            Stroke::new(5.0, Color32::RED),
+        );
+    }
+
+    /// Follow a primary button drag in the simulation area, in world coordinates.
+    fn track_drag(&mut self, ui: &Ui, response: &egui::Response, rect: &Rect) -> Option<ToolDrag> {
+        let pointer_world = ui
+            .ctx()
+            .pointer_latest_pos()
+            .map(|pos| self.camera.screen_to_world(rect, pos));
+
+        if response.drag_started_by(PointerButton::Primary) {
+            self.tool_drag_start = pointer_world;
+        }
+
+        let (start, end) = (self.tool_drag_start?, pointer_world?);
+        if response.drag_stopped_by(PointerButton::Primary) {
+            self.tool_drag_start = None;
+            Some(ToolDrag::Finished(start, end))
+        } else {
+            Some(ToolDrag::InProgress(start, end))
+        }
+    }
+
+    /// World position of a primary or secondary click in the simulation area.
+    fn click_position(&self, response: &egui::Response, rect: &Rect) -> Option<Pos2> {
+        response
+            .interact_pointer_pos()
+            .map(|pos| self.camera.screen_to_world(rect, pos))
+    }
+
+    fn handle_goal_tool(&mut self, ui: &Ui, response: &egui::Response, rect: &Rect) {
+        if self.goal_mode == GoalMode::Path {
+            self.handle_waypoint_tool(ui, response, rect);
+            return;
+        }
+        let Some(position) = self.click_position(response, rect) else {
+            return;
+        };
+        if response.clicked() {
+            // Shift keeps the goals already placed, a plain click moves the flock somewhere new
+            if !ui.input(|i| i.modifiers.shift) {
+                self.goal_points.clear();
+            }
+            self.goal_points.push(GoalPoint { position });
+        } else if response.secondary_clicked() {
+            let tolerance = PICK_DISTANCE / self.camera.zoom;
+            let closest = self
+                .goal_points
+                .iter()
+                .enumerate()
+                .map(|(index, goal)| (index, goal.position.distance(position)))
+                .filter(|(_, distance)| *distance < tolerance)
+                .min_by(|(_, a), (_, b)| a.total_cmp(b));
+            if let Some((index, _)) = closest {
+                self.goal_points.remove(index);
+            }
+        }
+    }
+
+    /// Clicks add waypoints to the end of the path, drags move them and right clicks remove them.
+    fn handle_waypoint_tool(&mut self, ui: &Ui, response: &egui::Response, rect: &Rect) {
+        let tolerance = PICK_DISTANCE / self.camera.zoom;
+        match self.track_drag(ui, response, rect) {
+            Some(ToolDrag::InProgress(start, end)) => {
+                if self.waypoint_drag.is_none() {
+                    self.waypoint_drag = self.waypoint_path.waypoint_at(start, tolerance);
+                }
+                if let Some(waypoint) = self
+                    .waypoint_drag
+                    .and_then(|index| self.waypoint_path.waypoints.get_mut(index))
+                {
+                    *waypoint = end;
+                }
+            }
+            Some(ToolDrag::Finished(_, end)) => {
+                if let Some(waypoint) = self
+                    .waypoint_drag
+                    .take()
+                    .and_then(|index| self.waypoint_path.waypoints.get_mut(index))
+                {
+                    *waypoint = end;
+                }
+            }
+            None => {}
+        }
+
+        let Some(position) = self.click_position(response, rect) else {
+            return;
+        };
+        if response.clicked() {
+            self.waypoint_path.waypoints.push(position);
+        } else if response.secondary_clicked() {
+            if let Some(index) = self.waypoint_path.waypoint_at(position, tolerance) {
+                self.waypoint_path.remove(index);
+            }
+        }
+    }
+
+    fn handle_perch_tool(&mut self, ui: &Ui, response: &egui::Response, rect: &Rect) {
+        match self.track_drag(ui, response, rect) {
+            Some(ToolDrag::InProgress(start, end)) => {
+                // Preview the perch line while dragging
+                ui.painter_at(*rect).line_segment(
+                    [
+                        self.camera.world_to_screen(rect, start),
+                        self.camera.world_to_screen(rect, end),
+                    ],
+                    Stroke::new(2.0, Color32::GRAY),
+                );
+            }
+            Some(ToolDrag::Finished(start, end)) => self.perches.push(PerchSite { start, end }),
+            None => {}
+        }
+
+        let Some(position) = self.click_position(response, rect) else {
+            return;
+        };
+        if response.clicked() {
+            self.perches.push(PerchSite {
+                start: position,
+                end: position,
+            });
+        } else if response.secondary_clicked() {
+            // Remove the closest perch under the cursor, with a tolerance that stays constant on screen
+            let tolerance = PICK_DISTANCE / self.camera.zoom;
+            let closest = self
+                .perches
+                .iter()
+                .enumerate()
+                .map(|(index, perch)| (index, perch.closest_point(position).distance(position)))
+                .filter(|(_, distance)| *distance < tolerance)
+                .min_by(|(_, a), (_, b)| a.total_cmp(b));
+            if let Some((index, _)) = closest {
+                self.perches.remove(index);
+            }
+        }
+    }
+
+    fn handle_current_tool(&mut self, ui: &Ui, response: &egui::Response, rect: &Rect) {
+        match self.track_drag(ui, response, rect) {
+            Some(ToolDrag::InProgress(start, end)) => {
+                ZoneArea::from_drag(self.current_brush.shape, start, end).draw_outline(
+                    &ui.painter_at(*rect),
+                    rect,
+                    &self.camera,
+                    Stroke::new(2.0, Color32::GRAY),
+                );
+            }
+            Some(ToolDrag::Finished(start, end)) => {
+                if start != end {
+                    self.current_zones.push(CurrentZone {
+                        area: ZoneArea::from_drag(self.current_brush.shape, start, end),
+                        force: self.current_brush.force(),
+                        one_way: self.current_brush.one_way,
+                    });
+                }
+            }
+            None => {}
+        }
+
+        if response.secondary_clicked() {
+            if let Some(position) = self.click_position(response, rect) {
+                // Remove the most recently placed zone under the cursor
+                if let Some(index) = self
+                    .current_zones
+                    .iter()
+                    .rposition(|zone| zone.area.contains(position))
+                {
+                    self.current_zones.remove(index);
+                }
+            }
+        }
+    }
+
+    fn handle_gate_tool(&mut self, ui: &Ui, response: &egui::Response, rect: &Rect) {
+        match self.track_drag(ui, response, rect) {
+            Some(ToolDrag::InProgress(start, end)) => {
+                ZoneArea::from_drag(self.gate_brush.shape, start, end).draw_outline(
+                    &ui.painter_at(*rect),
+                    rect,
+                    &self.camera,
+                    Stroke::new(2.0, Color32::GRAY),
+                );
+            }
+            Some(ToolDrag::Finished(start, end)) => {
+                if start != end {
+                    let area = ZoneArea::from_drag(self.gate_brush.shape, start, end);
+                    self.gates.push(self.gate_brush.gate(area));
+                }
+            }
+            None => {}
+        }
+
+        if response.secondary_clicked() {
+            if let Some(position) = self.click_position(response, rect) {
+                // Remove the most recently placed gate under the cursor
+                if let Some(index) = self
+                    .gates
+                    .iter()
+                    .rposition(|gate| gate.area.contains(position))
+                {
+                    self.gates.remove(index);
+                }
+            }
         }
     }
-}
 
-impl BoidsApp {
-    pub fn update_boids(&mut self) {
-        // SIMULATION LOGIC
-        if self.boids.len() > self.params.num_boids {
-            // Remove some boids
-            for _ in [0..self.boids.len() - self.params.num_boids] {
-                self.boids.remove(self.boids.len() - 1);
+    fn handle_obstacle_tool(&mut self, ui: &Ui, response: &egui::Response, rect: &Rect) {
+        let drag = self.track_drag(ui, response, rect);
+        if let Some(ToolDrag::InProgress(start, _) | ToolDrag::Finished(start, _)) = drag {
+            if let ObstacleEdit::Idle = self.obstacle_edit {
+                // Starting on an obstacle picks it up, anywhere else draws a new one
+                self.obstacle_edit = match self
+                    .obstacles
+                    .iter()
+                    .rposition(|obstacle| obstacle.contains(start))
+                {
+                    Some(index) => ObstacleEdit::Moving {
+                        index,
+                        from: self.obstacles[index],
+                    },
+                    None => ObstacleEdit::Placing,
+                };
+            }
+        }
+
+        let done = matches!(drag, None | Some(ToolDrag::Finished(..)));
+        match (drag, self.obstacle_edit) {
+            (Some(ToolDrag::InProgress(start, end)), ObstacleEdit::Placing) => {
+                ZoneArea::from_drag(self.obstacle_brush.shape, start, end).draw_outline(
+                    &ui.painter_at(*rect),
+                    rect,
+                    &self.camera,
+                    Stroke::new(2.0, Color32::GRAY),
+                );
+            }
+            (Some(ToolDrag::Finished(start, end)), ObstacleEdit::Placing) => {
+                if start != end {
+                    self.place_obstacle(Obstacle::from_drag(self.obstacle_brush.shape, start, end));
+                }
+            }
+            (
+                Some(ToolDrag::InProgress(start, end) | ToolDrag::Finished(start, end)),
+                ObstacleEdit::Moving { index, from },
+            ) => {
+                if let Some(obstacle) = self.obstacles.get_mut(index) {
+                    *obstacle = from.translated(end - start);
+                }
             }
-        } else if self.boids.len() < self.params.num_boids {
-            // Insert some boids
+            _ => {}
+        }
+        if done {
+            self.obstacle_edit = ObstacleEdit::Idle;
+        }
 
-            let mut rng: rand::prelude::ThreadRng = rand::thread_rng();
+        let Some(position) = self.click_position(response, rect) else {
+            return;
+        };
+        if response.clicked() && ui.input(|i| i.modifiers.shift) {
+            self.obstacle_brush.motion.points.push(position);
+        } else if response.secondary_clicked() {
+            // Remove the most recently placed obstacle under the cursor, moving ones first then
+            // temporary ones, in the order they're drawn on top
+            let clock = self.obstacle_clock as f32;
+            if let Some(index) = self
+                .moving_obstacles
+                .iter()
+                .rposition(|obstacle| obstacle.at(clock).contains(position))
+            {
+                self.moving_obstacles.remove(index);
+            } else if let Some(index) = self
+                .temporary_obstacles
+                .iter()
+                .rposition(|temporary| temporary.obstacle.contains(position))
+            {
+                self.temporary_obstacles.remove(index);
+            } else if let Some(index) = self
+                .obstacles
+                .iter()
+                .rposition(|obstacle| obstacle.contains(position))
+            {
+                self.obstacles.remove(index);
+            }
+        }
+    }
 
-            // Pick a random point in our field
-            let pos = Pos2::new(
-                rng.gen_range(-SIMULATION_AREA_WIDTH / 2.0..SIMULATION_AREA_WIDTH / 2.0),
-                rng.gen_range(-SIMULATION_AREA_HEIGHT / 2.0..SIMULATION_AREA_HEIGHT / 2.0),
-            );
-            // Set a random initial velocity
-            let rand_x_vel = rng.gen_range(-self.params.max_speed..self.params.max_speed);
-            let rand_y_vel = rng.gen_range(-self.params.max_speed..self.params.max_speed);
-            let random_velocity = Vec2::new(rand_x_vel, rand_y_vel);
+    /// Add an obstacle drawn with the obstacle tool, moving along the brush's path if it has one
+    /// or otherwise wearing out if the brush gives it a lifetime or strength.
+    fn place_obstacle(&mut self, obstacle: Obstacle) {
+        let motion = &self.obstacle_brush.motion;
+        match motion.path() {
+            Some(path) => self.moving_obstacles.push(MovingObstacle {
+                obstacle,
+                path,
+                speed: motion.speed,
+                placed_at: self.obstacle_clock,
+            }),
+            None => {
+                if motion.kind != MotionKind::Still {
+                    self.toasts
+                        .info("Shift click a path first, this obstacle stands still");
+                }
+                match self
+                    .obstacle_brush
+                    .durability
+                    .apply(obstacle, self.obstacle_clock)
+                {
+                    Some(temporary) => self.temporary_obstacles.push(temporary),
+                    None => self.obstacles.push(obstacle),
+                }
+            }
+        }
+    }
+
+    fn handle_gust_tool(&mut self, ui: &Ui, response: &egui::Response, rect: &Rect) {
+        match self.track_drag(ui, response, rect) {
+            Some(ToolDrag::InProgress(start, end)) => {
+                let painter = ui.painter_at(*rect);
+                let center = self.camera.world_to_screen(rect, start);
+                let stroke = Stroke::new(2.0, Color32::GRAY);
+                painter.circle_stroke(center, self.gust_brush.radius * self.camera.zoom, stroke);
+                painter.arrow(center, (end - start) * self.camera.zoom, stroke);
+            }
+            Some(ToolDrag::Finished(start, end)) => {
+                if start != end {
+                    self.impulses.push(self.gust_brush.impulse(start, end));
+                }
+            }
+            None => {}
+        }
+    }
 
-            self.boids.push(Boid::new(pos, random_velocity));
+    fn handle_thermal_tool(&mut self, ui: &Ui, response: &egui::Response, rect: &Rect) {
+        match self.track_drag(ui, response, rect) {
+            Some(ToolDrag::InProgress(center, edge)) => {
+                ui.painter_at(*rect).circle_stroke(
+                    self.camera.world_to_screen(rect, center),
+                    center.distance(edge) * self.camera.zoom,
+                    Stroke::new(2.0, Color32::GRAY),
+                );
+            }
+            Some(ToolDrag::Finished(center, edge)) => {
+                if center != edge {
+                    self.thermals.push(Thermal {
+                        center,
+                        radius: center.distance(edge),
+                    });
+                }
+            }
+            None => {}
         }
 
-        self.update_forces();
-        self.update_boids_position();
+        if response.secondary_clicked() {
+            if let Some(position) = self.click_position(response, rect) {
+                if let Some(index) = self
+                    .thermals
+                    .iter()
+                    .rposition(|thermal| thermal.contains(position))
+                {
+                    self.thermals.remove(index);
+                }
+            }
+        }
     }
 
-    fn update_boids_position(&mut self) {
-        // Update positions from velocity/acceleration
-        for boid in &mut self.boids {
-            boid.apply_forces(&self.params);
-            // screen wrap
-            boid.screen_wrap(LEFT, RIGHT, TOP, BOTTOM);
+    fn handle_inspect_tool(&mut self, response: &egui::Response, rect: &Rect) {
+        if !response.clicked() {
+            return;
+        }
+        if let Some(position) = self.click_position(response, rect) {
+            self.selected_boid = self.boid_at(position);
         }
     }
 
-    pub fn update_forces(&mut self) {
-        let mut separation_forces: Vec<Vec2> = Vec::with_capacity(self.boids.len());
-        let mut cohesion_forces: Vec<Vec2> = Vec::with_capacity(self.boids.len());
-        let mut alignment_forces: Vec<Vec2> = Vec::with_capacity(self.boids.len());
-        let mut avoidance_forces: Vec<Vec2> = Vec::with_capacity(self.boids.len());
+    /// Id of the boid closest to `position`, if one is close enough to pick.
+    fn boid_at(&self, position: Pos2) -> Option<u64> {
+        self.boid_near(position, PICK_DISTANCE)
+            .map(|index| self.boids.boid(index).id)
+    }
 
-        for boid in &self.boids {
-            separation_forces.push(boid.calculate_separation_force(&self.boids, &self.params));
+    /// Index of the boid closest to `position`, if one is within `distance` screen pixels of it.
+    fn boid_near(&self, position: Pos2, distance: f32) -> Option<usize> {
+        let tolerance = distance / self.camera.zoom;
+        let positions: Vec<Pos2> = self.boids.iter().map(|boid| boid.position).collect();
+        SpatialGrid::from_positions(positions.iter().copied(), tolerance)
+            .nearest(&positions, position, tolerance)
+    }
 
-            alignment_forces.push(boid.calculate_alignment_force(&self.boids, &self.params));
+    /// Quick stats for the boid under the pointer, without having to select it.
+    fn draw_boid_tooltip(&self, ui: &Ui, response: &egui::Response, rect: &Rect) {
+        if !self.render_settings.tooltips || response.dragged() {
+            return;
+        }
+        let Some(pointer) = response.hover_pos() else {
+            return;
+        };
+        let position = self.camera.screen_to_world(rect, pointer);
+        let Some(index) = self.boid_near(position, TOOLTIP_DISTANCE) else {
+            return;
+        };
+        let boid = self.boids.boid(index);
+        egui::show_tooltip_at_pointer(ui.ctx(), ui.layer_id(), Id::new("boid_tooltip"), |ui| {
+            egui::Grid::new("boid_tooltip_stats").show(ui, |ui| {
+                ui.label("Speed");
+                ui.label(format!("{:.2}", boid.velocity.length()));
+                ui.end_row();
+                ui.label("Neighbors");
+                ui.label(boid.neighbor_count.to_string());
+                ui.end_row();
+                ui.label("Strongest Force");
+                ui.label(boid.dominant_force.unwrap_or("None"));
+                ui.end_row();
+            });
+        });
+    }
 
-            cohesion_forces.push(boid.calculate_cohesion_force(&self.boids, &self.params));
+    fn handle_annotate_tool(&mut self, ui: &Ui, response: &egui::Response, rect: &Rect) {
+        match self.track_drag(ui, response, rect) {
+            Some(ToolDrag::InProgress(start, end)) => {
+                ui.painter_at(*rect).rect_stroke(
+                    Rect::from_two_pos(
+                        self.camera.world_to_screen(rect, start),
+                        self.camera.world_to_screen(rect, end),
+                    ),
+                    4.0,
+                    Stroke::new(2.0, Color32::GRAY),
+                );
+            }
+            Some(ToolDrag::Finished(start, end)) => {
+                if start != end {
+                    let region = AnnotationTarget::Region(Rect::from_two_pos(start, end));
+                    self.annotations
+                        .push(self.annotation_brush.annotation(region));
+                }
+            }
+            None => {}
+        }
 
-            if let Some(predator_position) = self.predator_pos {
-                avoidance_forces
-                    .push(boid.calculate_avoidance_force(predator_position, &self.params));
-            } else {
-                avoidance_forces.push(Vec2::ZERO);
-            }
-        }
-
-        for i in 0..self.boids.len() {
-            self.boids[i].acceleration += separation_forces[i];
-            self.boids[i].acceleration += alignment_forces[i];
-            self.boids[i].acceleration += cohesion_forces[i];
-            self.boids[i].acceleration += avoidance_forces[i];
-
-            let separation_dominant = separation_forces[i].length_sq()
-                > alignment_forces[i].length_sq()
-                && separation_forces[i].length_sq() > cohesion_forces[i].length_sq()
-                && separation_forces[i].length_sq() > avoidance_forces[i].length_sq();
-            let alignment_dominant = alignment_forces[i].length_sq()
-                > separation_forces[i].length_sq()
-                && alignment_forces[i].length_sq() > cohesion_forces[i].length_sq()
-                && alignment_forces[i].length_sq() > avoidance_forces[i].length_sq();
-            let cohesion_dominant = cohesion_forces[i].length_sq()
-                > alignment_forces[i].length_sq()
-                && cohesion_forces[i].length_sq() > separation_forces[i].length_sq()
-                && cohesion_forces[i].length_sq() > avoidance_forces[i].length_sq();
-            let avoidance_dominant = avoidance_forces[i].length_sq()
-                > alignment_forces[i].length_sq()
-                && avoidance_forces[i].length_sq() > cohesion_forces[i].length_sq()
-                && avoidance_forces[i].length_sq() > separation_forces[i].length_sq();
-
-            if separation_dominant {
-                self.boids[i].color = SEPARATION_COLOR;
-            } else if alignment_dominant {
-                self.boids[i].color = ALIGNMENT_COLOR;
-            } else if cohesion_dominant {
-                self.boids[i].color = COHESION_COLOR;
-            } else if avoidance_dominant {
-                self.boids[i].color = AVOIDANCE_COLOR;
+        let Some(position) = self.click_position(response, rect) else {
+            return;
+        };
+        if response.clicked() {
+            // Clicking a boid attaches the annotation to it, anywhere else pins it in place
+            let target = self
+                .boid_at(position)
+                .map_or(AnnotationTarget::Point(position), AnnotationTarget::Boid);
+            self.annotations
+                .push(self.annotation_brush.annotation(target));
+        } else if response.secondary_clicked() {
+            let tolerance = PICK_DISTANCE / self.camera.zoom;
+            let closest = self
+                .annotations
+                .iter()
+                .enumerate()
+                .filter_map(|(index, annotation)| {
+                    let hit = match annotation.target {
+                        AnnotationTarget::Region(region) => region.contains(position),
+                        _ => annotation
+                            .anchor(&self.boids)
+                            .is_some_and(|anchor| anchor.distance(position) < tolerance),
+                    };
+                    hit.then_some(index)
+                })
+                .last();
+            if let Some(index) = closest {
+                self.annotations.remove(index);
+            }
+        }
+    }
+
+    fn selected_boid(&self) -> Option<BoidRef<'_>> {
+        let id = self.selected_boid?;
+        self.boids.iter().find(|boid| boid.id == id)
+    }
+
+    fn draw_inspector(&mut self, ctx: &egui::Context) {
+        let Some(id) = self.selected_boid else {
+            return;
+        };
+        let Some(mut boid) = self.boids.iter_mut().find(|boid| boid.id == id) else {
+            // The boid is gone
+            self.selected_boid = None;
+            return;
+        };
+
+        // Editing a genome isn't an input the replay system knows about
+        let editable = matches!(self.replay, ReplayState::Idle) && !self.net.is_client();
+        let groups = &self.params.groups;
+        let waypoint_path = &mut self.waypoint_path;
+        let mut open = true;
+        egui::Window::new("Boid Inspector")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("boid_stats").show(ui, |ui| {
+                    ui.label("Id");
+                    ui.label(boid.id.to_string());
+                    ui.end_row();
+                    ui.label("Position");
+                    ui.label(format!("{:.0}, {:.0}", boid.position.x, boid.position.y));
+                    ui.end_row();
+                    ui.label("Speed");
+                    ui.label(format!("{:.2}", boid.velocity.length()));
+                    ui.end_row();
+                    ui.label("Energy");
+                    ui.label(format!("{:.0}%", boid.energy * 100.0));
+                    ui.end_row();
+                    ui.label("Age");
+                    ui.label(format!("{:.0}s", boid.age as f32 / TICKS_PER_SECOND));
+                    ui.end_row();
+                    ui.label("Rank");
+                    ui.label(format!("{:.2}", boid.rank));
+                    ui.end_row();
+                    if self.params.adaptive_separation.enabled {
+                        ui.label("Separation Scale");
+                        ui.label(format!("{:.2}", boid.separation_scale));
+                        ui.end_row();
+                    }
+                });
+
+                if !waypoint_path.waypoints.is_empty() {
+                    let mut leads = waypoint_path.leader == Some(id);
+                    if ui
+                        .checkbox(&mut leads, "Leads The Path")
+                        .on_hover_text("Only this boid follows the waypoint path, the rest flock with it")
+                        .changed()
+                    {
+                        waypoint_path.leader = leads.then_some(id);
+                    }
+                }
+
+                ui.separator();
+                ui.add_enabled_ui(editable, |ui| {
+                    groups.draw_selector(ui, "Group", &mut boid.group);
+                    ui.label("Genome");
+                    boid.genome.draw_panel(ui);
+                    if ui.button("Reset Genome").clicked() {
+                        boid.genome = Genome::default();
+                    }
+                })
+                .response
+                .on_disabled_hover_text("Genomes can't be edited while recording, replaying or watching a network session");
+            });
+
+        if !open {
+            self.selected_boid = None;
+        }
+    }
+
+    /// Write the last recording to the working directory.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_recording(&mut self) {
+        use crate::{
+            error::BoidsError,
+            recording_file::{encode_recording, RECORDING_EXTENSION},
+        };
+
+        let Some(recording) = &self.last_recording else {
+            return;
+        };
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let path = format!("recording-{timestamp}.{RECORDING_EXTENSION}");
+        let bytes = encode_recording(recording);
+        match std::fs::write(&path, &bytes) {
+            Ok(()) => {
+                info!(path, bytes = bytes.len(), "saved recording");
+                self.toasts
+                    .info(format!("Saved {path} ({})", format_bytes(bytes.len())));
+            }
+            Err(err) => self
+                .toasts
+                .report("Could not save recording", &BoidsError::from(err)),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_html_export(&mut self, capture: &ReplayCapture) {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let path = format!("replay-{timestamp}.html");
+        let html = capture.to_html();
+        match std::fs::write(&path, &html) {
+            Ok(()) => {
+                info!(path, bytes = html.len(), "exported replay");
+                self.toasts
+                    .info(format!("Exported {path} ({})", format_bytes(html.len())));
+            }
+            Err(err) => self.toasts.report(
+                "Could not export replay",
+                &crate::error::BoidsError::from(err),
+            ),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_svg_export(&mut self, frame: &SvgFrame) {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let path = format!("frame-{timestamp}.svg");
+        let svg = frame.to_svg();
+        match std::fs::write(&path, &svg) {
+            Ok(()) => {
+                info!(path, bytes = svg.len(), "exported frame");
+                self.toasts
+                    .info(format!("Exported {path} ({})", format_bytes(svg.len())));
+            }
+            Err(err) => self.toasts.report(
+                "Could not export frame",
+                &crate::error::BoidsError::from(err),
+            ),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_clip_export(&mut self, capture: ClipCapture) {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let path = format!("highlight-{timestamp}.gif");
+        let saved = capture.finish().and_then(|gif| {
+            std::fs::write(&path, &gif)?;
+            Ok(gif.len())
+        });
+        match saved {
+            Ok(bytes) => {
+                info!(path, bytes, "exported highlight clip");
+                self.toasts
+                    .info(format!("Exported {path} ({})", format_bytes(bytes)));
+            }
+            Err(err) => self.toasts.report("Could not export clip", &err),
+        }
+    }
+
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped_files = ctx.input(|i| i.raw.dropped_files.clone());
+        for file in dropped_files {
+            let name = match &file.path {
+                Some(path) => path.display().to_string(),
+                None => file.name.clone(),
+            };
+            if is_recording_file(&name) {
+                match recording_from_dropped_file(&file) {
+                    Ok(recording) => {
+                        self.stop_replay();
+                        self.last_recording = Some(recording);
+                        self.last_recording_end = None;
+                        self.replay_start_seconds = 0.0;
+                        self.toasts
+                            .info(format!("Loaded {name}, press Replay to watch it"));
+                    }
+                    Err(err) => self.toasts.report("Could not load recording", &err),
+                }
+                continue;
+            }
+            match Scenario::from_dropped_file(&file) {
+                Ok(scenario) => {
+                    self.load_scenario(scenario);
+                    self.toasts.info(format!("Loaded {name}"));
+                }
+                Err(err) => self.toasts.report("Could not load dropped file", &err),
             }
         }
     }
@@ -151,13 +2630,46 @@ impl BoidsApp {
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
         cc.egui_ctx.set_visuals(Visuals::dark());
+        info!("starting boids");
+        #[allow(unused_mut)]
+        let mut app = Self::restore(cc);
+        #[cfg(not(target_arch = "wasm32"))]
+        app.load_plugins();
+        app
+    }
+
+    fn restore(cc: &eframe::CreationContext<'_>) -> Self {
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
-        }
+        cc.storage
+            .and_then(|storage| storage.get_string(eframe::APP_KEY))
+            .map(|saved| Self::from_saved(&saved))
+            .unwrap_or_default()
+    }
 
-        Default::default()
+    /// The app as it was saved, with anything out of range put back in range.
+    fn from_saved(saved: &str) -> Self {
+        match ron::from_str::<Self>(saved) {
+            Ok(mut app) => {
+                app.rng = StdRng::seed_from_u64(app.seed);
+                app.time_scale = app
+                    .time_scale
+                    .clamp(*TIME_SCALE_RANGE.start(), *TIME_SCALE_RANGE.end());
+                if let Err(err) = app.params.validate() {
+                    app.toasts.warning(format!(
+                        "Saved parameters were invalid and have been reset: {err}"
+                    ));
+                    app.params.reset();
+                }
+                app
+            }
+            Err(err) => {
+                let mut app = Self::default();
+                app.toasts
+                    .report("Could not restore saved settings", &err.into());
+                app
+            }
+        }
     }
 }
 
@@ -170,16 +2682,84 @@ impl eframe::App for BoidsApp {
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // App Update
-        let dt = Instant::now()
+        let axis = |negative: Action, positive: Action| {
+            self.bindings.down(ctx, positive) as i32 as f32
+                - self.bindings.down(ctx, negative) as i32 as f32
+        };
+        self.shepherd_input = Vec2::new(
+            axis(Action::ShepherdLeft, Action::ShepherdRight),
+            axis(Action::ShepherdUp, Action::ShepherdDown),
+        );
+        if self.bindings.pressed(ctx, Action::Pause) {
+            self.paused = !self.paused;
+            info!(paused = self.paused, "pause toggled");
+        }
+        let step = self.paused && self.bindings.pressed(ctx, Action::Step);
+        if self.bindings.pressed(ctx, Action::SplitFlock) {
+            self.toggle_split();
+        }
+        if self.bindings.pressed(ctx, Action::ForceMerge) {
+            self.toggle_merge();
+        }
+        for tool in Tool::ALL {
+            if self.bindings.pressed(ctx, Action::SelectTool(tool)) {
+                self.tool = tool;
+            }
+        }
+
+        let now = Instant::now();
+        let dt = now
             .saturating_duration_since(self.last_update_time)
             .as_secs_f32();
-        if dt >= FRAME_TIME && !self.paused {
-            self.last_update_time = Instant::now();
-            self.update_boids();
+        self.last_update_time = now;
+        self.update_network(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.update_species();
+
+        if self.paused || self.net.is_client() {
+            self.accumulator = 0.0;
+        } else {
+            self.accumulator += dt * self.time_scale;
+        }
+        if step && !self.net.is_client() {
+            self.tick_simulation();
+        }
+        // Fast forwarding needs more ticks every frame to begin with
+        let max_ticks = (MAX_TICKS_PER_FRAME as f32 * self.time_scale.max(1.0)).ceil() as u32;
+        let mut ticks = 0;
+        while self.accumulator >= FRAME_TIME && ticks < max_ticks {
+            self.tick_simulation();
+            self.accumulator -= FRAME_TIME;
+            ticks += 1;
+        }
+        if ticks == max_ticks {
+            // Too far behind to catch up, so let the simulation run slow for a while instead
+            self.accumulator = self.accumulator.min(FRAME_TIME);
+        }
+        if !self.paused {
             ctx.request_repaint();
         }
 
         // DRAW LOGIC
+        let frame_time = ctx.input(|i| i.unstable_dt);
+        self.render_settings
+            .colors
+            .update(&self.boids, &self.metrics, frame_time);
+        self.camera_path.update(frame_time);
+        self.particles.budget = self.render_settings.particle_budget;
+        if self.render_settings.effects {
+            self.particles.update(frame_time);
+        } else {
+            self.particles.clear();
+        }
+        // The flow stands still while the flock does
+        if let Some(area) = self.visible_world_rect() {
+            self.flow_field.update(
+                &self.boids,
+                area,
+                if self.paused { 0.0 } else { frame_time },
+            );
+        }
 
         // HACK! Idk why i ended up needing to do this in the update loop
         ctx.set_visuals(Visuals::dark());
@@ -190,69 +2770,635 @@ impl eframe::App for BoidsApp {
                 let is_web = cfg!(target_arch = "wasm32");
                 if !is_web {
                     ui.menu_button("File", |ui| {
+                        #[cfg(not(target_arch = "wasm32"))]
+                        if ui
+                            .button("Export Frame as SVG")
+                            .on_hover_text("Save the view as it is now as a picture that stays sharp at any size")
+                            .clicked()
+                        {
+                            self.svg_export_requested = true;
+                            ui.close_menu();
+                        }
                         if ui.button("Quit").clicked() {
                             ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                         }
                     });
                     ui.add_space(16.0);
                 }
+
+                ui.menu_button("View", |ui| {
+                    ui.checkbox(&mut self.log_viewer.open, "Log");
+                    ui.checkbox(&mut self.debug_panel.open, "Debug");
+                    ui.menu_button("HUD", |ui| self.hud.draw_menu(ui));
+                    ui.checkbox(&mut self.lane_plot.open, "Lane Order");
+                    ui.checkbox(&mut self.camera_path.open, "Camera Path");
+                    ui.checkbox(&mut self.timeline.open, "Timeline");
+                    ui.separator();
+                    ui.label(format!(
+                        "Hold {} and drag to draw",
+                        self.bindings.describe(Action::LaserPointer)
+                    ));
+                });
+
+                ui.menu_button("Experiments", |ui| {
+                    if ui
+                        .button("Lane Formation")
+                        .on_hover_text("Two groups fly opposite ways down a corridor and sort themselves into lanes")
+                        .clicked()
+                    {
+                        self.start_lane_experiment();
+                        ui.close_menu();
+                    }
+                });
             });
         });
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            let (rect, _response) = ui.allocate_exact_size(
+        if self.timeline.open {
+            egui::TopBottomPanel::bottom("timeline").show(ctx, |ui| {
+                let replayable = self.replayable_ticks();
+                if let Some(tick) = self.timeline.draw(ui, self.tick, replayable) {
+                    self.jump_to(tick);
+                }
+            });
+        }
+
+        let simulation_rect = egui::CentralPanel::default().show(ctx, |ui| {
+            let (rect, response) = ui.allocate_exact_size(
                 egui::vec2(SIMULATION_AREA_WIDTH, SIMULATION_AREA_HEIGHT),
-                egui::Sense::hover(),
+                egui::Sense::click_and_drag(),
             );
+            self.view_rect = rect;
 
-            if let Some(mouse_pos) = ctx.input(|i| i.pointer.hover_pos()) {
-                if rect.contains(mouse_pos) {
-                    self.predator_pos = Some(mouse_pos - rect.center().to_vec2());
-                    let painter: egui::Painter = ui.painter_at(rect);
-                    painter.circle_filled(mouse_pos, 5.0, Color32::RED);
-                    painter.circle_stroke(
-                        mouse_pos,
-                        self.params.avoidance_radius,
-                        Stroke::new(5.0, Color32::RED),
-                    );
-                } else {
+            match self.params.boundary_mode {
+                _ if self.manual_camera => {}
+                BoundaryMode::Wrap | BoundaryMode::Bounce | BoundaryMode::Contain => {
+                    self.camera = Camera::default()
+                }
+                BoundaryMode::Boundless => self.camera.follow(&self.boids, &rect),
+            }
+            self.handle_camera_gestures(ctx, &response, &rect);
+            if let Some(camera) = self.camera_path.camera(self.flock_centroid()) {
+                self.camera = camera;
+            }
+
+            if self.game.is_some() {
+                // The game is played with the predator
+                self.tool = Tool::Predator;
+            }
+
+            match self.tool {
+                // The recording is in control of the predator
+                _ if self.replay.is_playing() => self.draw_predator(ui, &rect),
+                // Drawing with the laser pointer leaves the simulation alone
+                _ if self.bindings.down(ctx, Action::LaserPointer) => self.predator_pos = None,
+                Tool::Predator => self.update_predator(ctx, ui, &rect),
+                Tool::Goal => {
                     self.predator_pos = None;
+                    self.handle_goal_tool(ui, &response, &rect);
+                }
+                Tool::Perch => {
+                    self.predator_pos = None;
+                    self.handle_perch_tool(ui, &response, &rect);
+                }
+                Tool::Current => {
+                    self.predator_pos = None;
+                    self.handle_current_tool(ui, &response, &rect);
+                }
+                Tool::Thermal => {
+                    self.predator_pos = None;
+                    self.handle_thermal_tool(ui, &response, &rect);
+                }
+                Tool::Gate => {
+                    self.predator_pos = None;
+                    self.handle_gate_tool(ui, &response, &rect);
+                }
+                Tool::Obstacle => {
+                    self.predator_pos = None;
+                    self.handle_obstacle_tool(ui, &response, &rect);
+                }
+                Tool::Gust => {
+                    self.predator_pos = None;
+                    self.handle_gust_tool(ui, &response, &rect);
+                }
+                Tool::Inspect => {
+                    self.predator_pos = None;
+                    self.handle_inspect_tool(&response, &rect);
+                }
+                Tool::Annotate => {
+                    self.predator_pos = None;
+                    self.handle_annotate_tool(ui, &response, &rect);
                 }
-            } else {
-                self.predator_pos = None;
             }
+            self.draw_boid_tooltip(ui, &response, &rect);
 
+            // Everything the view paints from here on goes into the SVG
+            #[cfg(not(target_arch = "wasm32"))]
+            let svg_start = ctx.graphics(|graphics| {
+                graphics
+                    .get(ui.layer_id())
+                    .map_or(0, |shapes| shapes.next_idx().0)
+            });
             if ui.is_rect_visible(rect) {
-                // Draw some lines around the box to help with visualization
-                draw_perimeter(ui, &rect);
+                match self.params.boundary_mode {
+                    // Draw some lines around the box to help with visualization
+                    BoundaryMode::Wrap | BoundaryMode::Bounce | BoundaryMode::Contain => self
+                        .params
+                        .arena
+                        .draw_perimeter(&ui.painter_at(rect), &rect, &self.camera, &WORLD_BOUNDS),
+                    BoundaryMode::Boundless => {
+                        self.camera.draw_grid(&ui.painter_at(rect), &rect);
+                        if let Some(active) = self.active_chunks() {
+                            self.params.chunks.draw_active_area(
+                                &ui.painter_at(rect),
+                                &rect,
+                                &self.camera,
+                                &active,
+                            );
+                        }
+                    }
+                }
+
+                if self.params.danger.enabled && self.params.danger.show {
+                    self.danger_map
+                        .draw(&ui.painter_at(rect), &rect, &self.camera);
+                }
+
+                for thermal in &self.thermals {
+                    thermal.draw(&ui.painter_at(rect), &rect, &self.camera);
+                }
+
+                for zone in &self.current_zones {
+                    zone.draw(&ui.painter_at(rect), &rect, &self.camera);
+                }
+                self.params.wind.draw(
+                    &ui.painter_at(rect),
+                    &rect,
+                    &self.camera,
+                    self.obstacle_clock,
+                );
+                self.force_field
+                    .draw(&ui.painter_at(rect), &rect, &self.camera);
+                self.flow_field
+                    .draw(&ui.painter_at(rect), &rect, &self.camera);
 
-                for boid in &self.boids {
-                    boid.draw(ui, &rect);
+                for obstacle in &self.obstacles {
+                    obstacle.draw(&ui.painter_at(rect), &rect, &self.camera);
                 }
+                for obstacle in &self.temporary_obstacles {
+                    obstacle.draw(
+                        &ui.painter_at(rect),
+                        &rect,
+                        &self.camera,
+                        self.obstacle_clock,
+                    );
+                }
+                for obstacle in &self.moving_obstacles {
+                    obstacle.draw_path(&ui.painter_at(rect), &rect, &self.camera);
+                    obstacle.at(self.obstacle_clock as f32).draw(
+                        &ui.painter_at(rect),
+                        &rect,
+                        &self.camera,
+                    );
+                }
+                if self.tool == Tool::Obstacle {
+                    self.obstacle_brush.motion.draw_points(
+                        &ui.painter_at(rect),
+                        &rect,
+                        &self.camera,
+                    );
+                }
+
+                for gate in &self.gates {
+                    gate.draw(&ui.painter_at(rect), &rect, &self.camera);
+                }
+
+                for perch in &self.perches {
+                    perch.draw(&ui.painter_at(rect), &rect, &self.camera);
+                }
+
+                for goal in &self.goal_points {
+                    goal.draw(&ui.painter_at(rect), &rect, &self.camera);
+                }
+                self.waypoint_path
+                    .draw(&ui.painter_at(rect), &rect, &self.camera, &self.boids);
+
+                if let Some(game) = &self.game {
+                    self.game_settings
+                        .draw_pen(&ui.painter_at(rect), &rect, &self.camera);
+                    game.draw_hud(
+                        &ui.painter_at(rect),
+                        &rect,
+                        &self.game_settings,
+                        self.boids.len(),
+                    );
+                    if let Some(shepherd) = &game.shepherd {
+                        shepherd.draw(
+                            &ui.painter_at(rect),
+                            &rect,
+                            &self.camera,
+                            &self.game_settings,
+                        );
+                    }
+                }
+
+                if self.params.migration.enabled {
+                    self.migration_target
+                        .draw(&ui.painter_at(rect), &rect, &self.camera);
+                }
+
+                self.split_merge
+                    .forces
+                    .draw(&ui.painter_at(rect), &rect, &self.camera);
+                self.ghost.draw(&ui.painter_at(rect), &rect, &self.camera);
+                let boids = self.interpolated_boids();
+                self.render_settings
+                    .draw_boids(ui, &rect, &self.camera, &boids, &self.params);
+                self.particles
+                    .draw(&ui.painter_at(rect), &rect, &self.camera);
+
+                #[cfg(not(target_arch = "wasm32"))]
+                self.plugins
+                    .draw(&ui.painter_at(rect), &rect, &self.camera, &boids);
+
+                for annotation in &self.annotations {
+                    annotation.draw(&ui.painter_at(rect), &rect, &self.camera, &self.boids);
+                }
+
+                if let Some(boid) = self.selected_boid() {
+                    ui.painter_at(rect).circle_stroke(
+                        self.camera.world_to_screen(&rect, boid.position),
+                        PICK_DISTANCE,
+                        Stroke::new(1.5, Color32::WHITE),
+                    );
+                    self.params.perception.draw_cone(
+                        &ui.painter_at(rect),
+                        &rect,
+                        &self.camera,
+                        boid,
+                        self.params.neighbor_radius,
+                    );
+                }
+
+                for predator in &self.predators {
+                    predator.draw(
+                        &ui.painter_at(rect),
+                        &rect,
+                        &self.camera,
+                        self.params.predators.pack_hunting,
+                    );
+                }
+
+                for predator in self.net.remote_predators() {
+                    ui.painter_at(rect).circle_filled(
+                        self.camera.world_to_screen(&rect, predator),
+                        5.0,
+                        REMOTE_PREDATOR_COLOR,
+                    );
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            if std::mem::take(&mut self.svg_export_requested) {
+                let mut frame = SvgFrame::new(rect, ui.visuals().panel_fill);
+                ctx.graphics(|graphics| {
+                    if let Some(shapes) = graphics.get(ui.layer_id()) {
+                        frame.extend(shapes.all_entries().skip(svg_start));
+                    }
+                });
+                self.save_svg_export(&frame);
             }
+
+            rect
         });
 
         egui::SidePanel::right("config_panel").show(ctx, |ui| {
-            ui.label("Configuration Panel");
-            ui.checkbox(&mut self.paused, "Pause Simulation");
-            ui.separator();
-            self.params.draw_panel(ui);
+            egui::ScrollArea::vertical().show(ui, |ui| self.draw_config_panel(ui));
+        });
+        self.ghost.take_request(ctx, &self.boids);
+
+        let hud_stats = self.hud_stats();
+        self.hud.show(ctx, &simulation_rect.inner, &hud_stats);
+
+        self.draw_inspector(ctx);
+        self.draw_game_results(ctx);
+        self.handle_dropped_files(ctx);
+        draw_drop_hint(ctx);
+
+        self.log_viewer.show(ctx);
+        let stats = self.debug_stats();
+        let integrator = self.params.integration.integrator;
+        self.debug_panel.show(ctx, &stats, |ui| {
+            self.integrator_comparison
+                .draw_panel(ui, integrator, &self.boids);
+        });
+        self.lane_plot.show(ctx);
+        self.camera_path.show(ctx, &self.camera);
+        self.toasts.show(ctx);
+        let laser = self.bindings.down(ctx, Action::LaserPointer);
+        self.laser_pointer.update(ctx, laser);
+    }
+}
+
+impl BoidsApp {
+    fn draw_config_panel(&mut self, ui: &mut Ui) {
+        ui.label("Configuration Panel");
+        self.tool.draw_selector(ui);
+        if self.tool == Tool::Goal {
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.goal_mode, GoalMode::Points, "Goal Points");
+                ui.radio_value(&mut self.goal_mode, GoalMode::Path, "Waypoint Path")
+                    .on_hover_text("Click to add waypoints, drag them to move them");
+            });
+            self.params.goal_seeking.draw_panel(ui);
+            match self.goal_mode {
+                GoalMode::Points => {
+                    if ui.button("Remove All Goals").clicked() {
+                        self.goal_points.clear();
+                    }
+                }
+                GoalMode::Path => self.waypoint_path.draw_panel(ui),
+            }
+        }
+        if self.tool == Tool::Current {
+            self.current_brush.draw_panel(ui);
+            if ui.button("Remove All Currents").clicked() {
+                self.current_zones.clear();
+            }
+        }
+        if self.tool == Tool::Gate {
+            self.gate_brush.draw_panel(ui);
+            if ui.button("Remove All Sources & Sinks").clicked() {
+                self.gates.clear();
+            }
+        }
+        if self.tool == Tool::Obstacle {
+            self.obstacle_brush.draw_panel(ui);
+            if ui.button("Remove All Obstacles").clicked() {
+                self.obstacles.clear();
+                self.temporary_obstacles.clear();
+                self.moving_obstacles.clear();
+            }
+        }
+        if self.tool == Tool::Gust {
+            self.gust_brush.draw_panel(ui);
+        }
+        if self.tool == Tool::Annotate {
+            self.annotation_brush.draw_panel(ui);
+            if ui.button("Remove All Annotations").clicked() {
+                self.annotations.clear();
+            }
+        }
+        if self.manual_camera && ui.button("Reset View").clicked() {
+            self.manual_camera = false;
+        }
+        ui.horizontal(|ui| {
+            if ui.checkbox(&mut self.paused, "Pause Simulation").changed() {
+                info!(paused = self.paused, "pause toggled");
+            }
+            let can_step = self.paused && !self.net.is_client();
+            if ui
+                .add_enabled(can_step, egui::Button::new("Step"))
+                .on_hover_text(format!(
+                    "Advance a single tick ({})",
+                    self.bindings.describe(Action::Step)
+                ))
+                .clicked()
+            {
+                self.tick_simulation();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Seed");
+            ui.add(egui::DragValue::new(&mut self.seed).speed(0.0));
+            if ui
+                .button("Restart")
+                .on_hover_text("Start the flock over from this seed")
+                .clicked()
+            {
+                self.restart_from_seed(self.seed);
+            }
+            if ui
+                .button("Reseed")
+                .on_hover_text("Start the flock over from a new random seed")
+                .clicked()
+            {
+                self.restart_from_seed(rand::random());
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::Slider::new(&mut self.time_scale, TIME_SCALE_RANGE)
+                    .logarithmic(true)
+                    .text("Time Scale")
+                    .suffix("×"),
+            )
+            .on_hover_text("Below 1 for slow motion, above 1 to fast forward");
+            if self.time_scale != 1.0 && ui.small_button("1×").clicked() {
+                self.time_scale = 1.0;
+            }
         });
+        if self.params.catching.enabled {
+            ui.horizontal(|ui| {
+                ui.label(format!("Boids Caught: {}", self.kills));
+                if ui.small_button("Reset").clicked() {
+                    self.kills = 0;
+                }
+            });
+        }
+        ui.separator();
+        // During a replay the parameters come from the recording
+        self.ghost.draw_panel(ui);
+        let before = self.params.clone();
+        ui.add_enabled_ui(!self.replay.is_playing(), |ui| {
+            self.params
+                .draw_panel(ui, WORLD_BOUNDS.size(), &self.steering_rules)
+        });
+        if self.params != before {
+            self.timeline
+                .mark(self.tick, MarkerKind::Parameters, "Parameters changed");
+        }
+        ui.separator();
+        egui::CollapsingHeader::new("Behaviors").show(ui, |ui| self.draw_behaviors_panel(ui));
+        egui::CollapsingHeader::new("Predator Game").show(ui, |ui| self.draw_game_panel(ui));
+        egui::CollapsingHeader::new("Rendering").show(ui, |ui| self.render_settings.draw_panel(ui));
+        egui::CollapsingHeader::new("Force Field").show(ui, |ui| self.force_field.draw_panel(ui));
+        egui::CollapsingHeader::new("Flow").show(ui, |ui| self.flow_field.draw_panel(ui));
+        egui::CollapsingHeader::new("Flock State").show(ui, |ui| self.draw_flock_state_panel(ui));
+        egui::CollapsingHeader::new("Breakpoints").show(ui, |ui| self.breakpoints.draw_panel(ui));
+        egui::CollapsingHeader::new("Transitions").show(ui, |ui| self.transitions.draw_panel(ui));
+        egui::CollapsingHeader::new("Automation")
+            .show(ui, |ui| self.lfos.draw_panel(ui, &mut self.params));
+        egui::CollapsingHeader::new("Playlist")
+            .show(ui, |ui| self.playlist.draw_panel(ui, &self.params));
+        egui::CollapsingHeader::new("Input Replay").show(ui, |ui| self.draw_replay_panel(ui));
+        egui::CollapsingHeader::new("Highlights").show(ui, |ui| self.draw_highlights_panel(ui));
+        egui::CollapsingHeader::new("Split And Merge")
+            .show(ui, |ui| self.draw_split_merge_panel(ui));
+        egui::CollapsingHeader::new("Input Bindings").show(ui, |ui| self.bindings.draw_panel(ui));
+        egui::CollapsingHeader::new("Network").show(ui, |ui| {
+            if let Err(err) = self.net_settings.draw_panel(ui, &mut self.net) {
+                self.toasts.report("Network", &err);
+            }
+        });
+    }
+}
+
+fn draw_drop_hint(ctx: &egui::Context) {
+    if ctx.input(|i| i.raw.hovered_files.is_empty()) {
+        return;
     }
+
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        egui::Id::new("drop_hint"),
+    ));
+    let screen_rect = ctx.screen_rect();
+    painter.rect_filled(screen_rect, 0.0, Color32::from_black_alpha(192));
+    painter.text(
+        screen_rect.center(),
+        egui::Align2::CENTER_CENTER,
+        "Drop a .ron or .json scenario to load it",
+        egui::TextStyle::Heading.resolve(&ctx.style()),
+        Color32::WHITE,
+    );
 }
 
-fn draw_perimeter(ui: &mut Ui, rect: &Rect) {
-    let painter: egui::Painter = ui.painter_at(*rect);
+#[cfg(test)]
+mod tests {
+    use egui::{Pos2, Rect, Vec2};
+
+    use super::{BoidsApp, ReplayState};
+    use crate::{
+        lfo::Lfo,
+        moving_obstacle::{MovingObstacle, ObstaclePath},
+        obstacle::Obstacle,
+        recording_file::{decode_recording, encode_recording},
+        replay::InputEvent,
+        tween::ParameterTween,
+    };
+
+    const TICKS: usize = 150;
+
+    /// A flock with obstacles in the way and predators hunting it.
+    fn hunted_flock() -> BoidsApp {
+        let mut app = BoidsApp::default();
+        app.params.num_boids = 200;
+        app.params.predators.count = 2;
+        app.obstacles = vec![
+            Obstacle::Circle {
+                center: Pos2::new(-200.0, 0.0),
+                radius: 80.0,
+            },
+            Obstacle::Rectangle(Rect::from_center_size(
+                Pos2::new(250.0, 100.0),
+                Vec2::new(120.0, 60.0),
+            )),
+        ];
+        app
+    }
+
+    /// Where every boid and then every predator is and how fast it's going after `app` is
+    /// restarted from `seed` and run for `TICKS` ticks.
+    fn run(app: &mut BoidsApp, seed: u64) -> Vec<(Pos2, Vec2)> {
+        app.restart_from_seed(seed);
+        for _ in 0..TICKS {
+            app.tick_simulation();
+        }
+        app.boids
+            .iter()
+            .map(|boid| (boid.position, boid.velocity))
+            .chain(
+                app.predators
+                    .iter()
+                    .map(|predator| (predator.position, predator.velocity)),
+            )
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_run() {
+        assert_eq!(run(&mut hunted_flock(), 265), run(&mut hunted_flock(), 265));
+    }
+
+    #[test]
+    fn different_seeds_different_runs() {
+        assert_ne!(run(&mut hunted_flock(), 265), run(&mut hunted_flock(), 266));
+    }
+
+    #[test]
+    fn restarting_part_way_through_replays_the_same_run() {
+        let mut app = hunted_flock();
+        app.params.wander.weight = 1.0;
+        app.moving_obstacles = vec![MovingObstacle {
+            obstacle: Obstacle::Circle {
+                center: Pos2::new(0.0, -150.0),
+                radius: 40.0,
+            },
+            path: ObstaclePath::Orbit { pivot: Pos2::ZERO },
+            speed: 2.0,
+            placed_at: 0,
+        }];
+        let first = run(&mut app, 265);
+        assert_eq!(run(&mut app, 265), first);
+    }
 
-    let top_left = rect.min;
-    let bottom_right = rect.max;
-    let top_right = Pos2::new(rect.max.x, rect.min.y);
-    let bottom_left = Pos2::new(rect.min.x, rect.max.y);
+    #[test]
+    fn saved_recording_loads_back() {
+        let mut app = hunted_flock();
+        app.restart_from_seed(250);
+        app.start_recording();
+        for _ in 0..TICKS {
+            app.tick_simulation();
+        }
+        app.stop_replay();
+        let recording = app.last_recording.take().unwrap();
+        let loaded = decode_recording(&encode_recording(&recording)).unwrap();
+        assert_eq!(loaded.length_ticks, recording.length_ticks);
+        assert_eq!(loaded.checksums, recording.checksums);
+        assert_eq!(loaded.keyframes.len(), recording.keyframes.len());
+    }
+
+    #[test]
+    fn swung_parameters_replay_without_being_recorded_every_tick() {
+        let mut app = hunted_flock();
+        app.lfos.lfos.push(Lfo::default());
+        app.restart_from_seed(217);
+        app.start_recording();
+        for tick in 0..TICKS {
+            if tick == TICKS / 2 {
+                let mut target = app.params.clone();
+                target.alignment_weight += 1.0;
+                app.tween = Some(ParameterTween::new(
+                    app.params.clone(),
+                    target,
+                    0.5,
+                    app.obstacle_clock,
+                ));
+            }
+            app.tick_simulation();
+        }
+        app.stop_replay();
+        let recording = app.last_recording.as_ref().unwrap();
+        let params_changes = recording
+            .events
+            .iter()
+            .filter(|event| matches!(event.event, InputEvent::Params(_)))
+            .count();
+        // Once when the tween starts and once when it's done
+        assert!(params_changes <= 2, "{params_changes} parameter changes");
 
-    let stroke = egui::Stroke::new(2.0, Color32::YELLOW);
+        app.start_replay();
+        for _ in 0..TICKS {
+            app.tick_simulation();
+        }
+        let ReplayState::Playing(player) = &app.replay else {
+            panic!("replay stopped early");
+        };
+        assert_eq!(player.diverged_at, None);
+    }
 
-    painter.line_segment([top_left, top_right], stroke);
-    painter.line_segment([top_left, bottom_left], stroke);
-    painter.line_segment([top_right, bottom_right], stroke);
-    painter.line_segment([bottom_left, bottom_right], stroke);
+    #[test]
+    fn state_saved_before_time_scale_runs_at_normal_speed() {
+        let app = BoidsApp::from_saved("()");
+        assert_eq!(app.time_scale, 1.0);
+    }
 }
@@ -0,0 +1,482 @@
+#[cfg(feature = "gui")]
+use egui::{Painter, Stroke, Ui};
+
+use crate::{
+    boid_store::BoidStore,
+    boids_simulation::BoundaryMode,
+    math::{Pos2, Rect, Vec2},
+};
+
+#[cfg(feature = "gui")]
+use crate::{camera::Camera, math::Color32};
+
+#[cfg(feature = "gui")]
+const PERIMETER_COLOR: Color32 = Color32::YELLOW;
+/// Longest distance anything moves between checks against the arena's edges, in world units.
+const SUBSTEP_LENGTH: f32 = 10.0;
+
+/// The shape of the simulation area when it has edges.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum ArenaShape {
+    #[default]
+    Rectangle,
+    /// The largest circle that fits in the simulation area
+    Circle,
+    /// A ring between the circle's edge and an inner radius, like a racetrack
+    Annulus,
+    /// A horizontal band with walls along the top and bottom that always wraps around its ends
+    Corridor,
+}
+
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ArenaParameters {
+    pub shape: ArenaShape,
+    /// Radius of the annulus' hole as a fraction of its outer radius
+    pub inner_radius: f32,
+    /// Width of the corridor as a fraction of the simulation area's height
+    pub corridor_width: f32,
+    /// Whether a wrapping rectangle wraps across its left and right edges, it bounces off them otherwise
+    pub wrap_x: bool,
+    /// Whether a wrapping rectangle wraps across its top and bottom edges, it bounces off them otherwise
+    pub wrap_y: bool,
+    /// Most pieces a single tick's movement is split into, so fast boids can't skip over an edge
+    pub max_substeps: u32,
+}
+
+impl Default for ArenaParameters {
+    fn default() -> Self {
+        Self {
+            shape: ArenaShape::default(),
+            inner_radius: 0.4,
+            corridor_width: 0.4,
+            wrap_x: true,
+            wrap_y: true,
+            max_substeps: 8,
+        }
+    }
+}
+
+impl ArenaParameters {
+    /// `mode` is how the world's edges behave, which decides what there is to set.
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui, mode: BoundaryMode) {
+        ui.label("Arena Shape");
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.shape, ArenaShape::Rectangle, "Rectangle");
+            ui.radio_value(&mut self.shape, ArenaShape::Circle, "Circle");
+            ui.radio_value(&mut self.shape, ArenaShape::Annulus, "Annulus");
+            ui.radio_value(&mut self.shape, ArenaShape::Corridor, "Corridor");
+        });
+        match self.shape {
+            ArenaShape::Annulus => {
+                ui.label("Inner Radius");
+                ui.add(egui::Slider::new(&mut self.inner_radius, 0.05..=0.9));
+            }
+            ArenaShape::Corridor => {
+                ui.label("Corridor Width");
+                ui.add(egui::Slider::new(&mut self.corridor_width, 0.05..=1.0));
+            }
+            ArenaShape::Rectangle if mode == BoundaryMode::Wrap => {
+                ui.horizontal(|ui| {
+                    ui.label("Wrap Across");
+                    ui.checkbox(&mut self.wrap_x, "Sides");
+                    ui.checkbox(&mut self.wrap_y, "Top And Bottom");
+                })
+                .response
+                .on_hover_text(
+                    "Edges that don't wrap bounce, wrapping only the sides makes an endless corridor",
+                );
+            }
+            ArenaShape::Rectangle | ArenaShape::Circle => {}
+        }
+        ui.label("Max Substeps");
+        ui.add(egui::Slider::new(&mut self.max_substeps, 1..=32))
+            .on_hover_text(
+                "Fast boids move in several small steps so they can't jump straight over an edge",
+            );
+    }
+
+    /// Outer and inner radius of the round shapes fitted into `bounds`.
+    fn radii(&self, bounds: &Rect) -> (f32, f32) {
+        let outer = bounds.width().min(bounds.height()) / 2.0;
+        match self.shape {
+            ArenaShape::Annulus => (outer, outer * self.inner_radius.clamp(0.0, 1.0)),
+            ArenaShape::Rectangle | ArenaShape::Circle | ArenaShape::Corridor => (outer, 0.0),
+        }
+    }
+
+    /// The corridor's band across the middle of `bounds`.
+    fn corridor(&self, bounds: &Rect) -> Rect {
+        let width = bounds.height() * self.corridor_width.clamp(0.0, 1.0);
+        Rect::from_center_size(bounds.center(), Vec2::new(bounds.width(), width))
+    }
+
+    /// Whether `position` is inside the arena fitted into `bounds`.
+    pub fn contains(&self, bounds: &Rect, position: Pos2) -> bool {
+        match self.shape {
+            ArenaShape::Rectangle => bounds.contains(position),
+            ArenaShape::Corridor => self.corridor(bounds).contains(position),
+            ArenaShape::Circle | ArenaShape::Annulus => {
+                let (outer, inner) = self.radii(bounds);
+                let distance = position.distance(bounds.center());
+                distance <= outer && distance >= inner
+            }
+        }
+    }
+
+    /// How far `position` is inside the nearest edge it could run into, and the direction back
+    /// in from that edge. `None` when there are no edges.
+    pub fn nearest_edge(&self, bounds: &Rect, position: Pos2) -> Option<(f32, Vec2)> {
+        let nearest = |edges: &[(f32, Vec2)]| {
+            edges
+                .iter()
+                .copied()
+                .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        };
+        match self.shape {
+            ArenaShape::Rectangle => nearest(&[
+                (position.x - bounds.min.x, Vec2::X),
+                (bounds.max.x - position.x, -Vec2::X),
+                (position.y - bounds.min.y, Vec2::Y),
+                (bounds.max.y - position.y, -Vec2::Y),
+            ]),
+            // The ends are open
+            ArenaShape::Corridor => {
+                let corridor = self.corridor(bounds);
+                nearest(&[
+                    (position.y - corridor.min.y, Vec2::Y),
+                    (corridor.max.y - position.y, -Vec2::Y),
+                ])
+            }
+            ArenaShape::Circle | ArenaShape::Annulus => {
+                let (outer, inner) = self.radii(bounds);
+                let offset = position - bounds.center();
+                let distance = offset.length();
+                let normal = if distance > 0.0 {
+                    offset / distance
+                } else {
+                    Vec2::X
+                };
+                if self.shape == ArenaShape::Annulus {
+                    nearest(&[(outer - distance, -normal), (distance - inner, normal)])
+                } else {
+                    Some((outer - distance, -normal))
+                }
+            }
+        }
+    }
+
+    /// Bring something that crossed an edge back into the arena, by wrapping or bouncing depending on `mode`.
+    pub fn contain(
+        &self,
+        position: &mut Pos2,
+        velocity: &mut Vec2,
+        bounds: &Rect,
+        mode: BoundaryMode,
+    ) {
+        match (self.shape, mode) {
+            (_, BoundaryMode::Boundless) => {}
+            (ArenaShape::Rectangle, BoundaryMode::Wrap) => {
+                wrap_rectangle(position, velocity, bounds, self.wrap_x, self.wrap_y)
+            }
+            (ArenaShape::Rectangle, BoundaryMode::Bounce | BoundaryMode::Contain) => {
+                bounce_rectangle(position, velocity, bounds)
+            }
+            (ArenaShape::Corridor, _) => {
+                // Always open at the ends and walled along the sides, that's what makes it a corridor
+                let corridor = self.corridor(bounds);
+                if position.x > corridor.max.x {
+                    position.x = corridor.min.x;
+                } else if position.x < corridor.min.x {
+                    position.x = corridor.max.x;
+                }
+                if position.y > corridor.max.y {
+                    reflect(velocity, Vec2::Y);
+                } else if position.y < corridor.min.y {
+                    reflect(velocity, -Vec2::Y);
+                }
+                position.y = position.y.clamp(corridor.min.y, corridor.max.y);
+            }
+            (ArenaShape::Circle | ArenaShape::Annulus, mode) => {
+                let (outer, inner) = self.radii(bounds);
+                let offset = *position - bounds.center();
+                let distance = offset.length();
+                // A boid sitting exactly on the center can't be pushed anywhere in particular
+                let normal = if distance > 0.0 {
+                    offset / distance
+                } else {
+                    Vec2::X
+                };
+
+                let radius = if distance > outer {
+                    match mode {
+                        // There's no opposite edge on a circle, so come back in on the far side.
+                        // An annulus wraps radially instead, from its outer edge to its inner one.
+                        BoundaryMode::Wrap if self.shape == ArenaShape::Circle => -outer,
+                        BoundaryMode::Wrap => inner,
+                        _ => {
+                            reflect(velocity, normal);
+                            outer
+                        }
+                    }
+                } else if distance < inner {
+                    match mode {
+                        BoundaryMode::Wrap => outer,
+                        _ => {
+                            reflect(velocity, -normal);
+                            inner
+                        }
+                    }
+                } else {
+                    return;
+                };
+                *position = bounds.center() + normal * radius;
+            }
+        }
+    }
+
+    /// How far apart the edges something wraps between are along each axis, zero along an axis
+    /// that doesn't wrap. Round arenas wrap radially, which has no sensible shortest way across.
+    pub fn wrap_period(&self, bounds: &Rect, mode: BoundaryMode) -> Vec2 {
+        match (self.shape, mode) {
+            (_, BoundaryMode::Boundless) => Vec2::ZERO,
+            (ArenaShape::Rectangle, BoundaryMode::Wrap) => Vec2::new(
+                if self.wrap_x { bounds.width() } else { 0.0 },
+                if self.wrap_y { bounds.height() } else { 0.0 },
+            ),
+            (ArenaShape::Corridor, _) => Vec2::new(bounds.width(), 0.0),
+            (ArenaShape::Rectangle | ArenaShape::Circle | ArenaShape::Annulus, _) => Vec2::ZERO,
+        }
+    }
+
+    /// Copies of the boids within `reach` of an edge they wrap across, moved to just past the
+    /// opposite edge, so boids there find them as neighbors at the shortest distance around.
+    pub fn wrapped_copies(
+        &self,
+        boids: &BoidStore,
+        bounds: &Rect,
+        mode: BoundaryMode,
+        reach: f32,
+    ) -> BoidStore {
+        let period = self.wrap_period(bounds, mode);
+        if period == Vec2::ZERO {
+            return BoidStore::default();
+        }
+        // Which way a copy of something at `position` goes along one axis, if it's near an edge
+        let shift = |position: f32, min: f32, max: f32, period: f32| {
+            if period == 0.0 {
+                0.0
+            } else if position - min < reach {
+                period
+            } else if max - position < reach {
+                -period
+            } else {
+                0.0
+            }
+        };
+
+        let mut copies = BoidStore::default();
+        for boid in boids {
+            let x = shift(boid.position.x, bounds.min.x, bounds.max.x, period.x);
+            let y = shift(boid.position.y, bounds.min.y, bounds.max.y, period.y);
+            let mut offsets = [Vec2::new(x, 0.0), Vec2::new(0.0, y), Vec2::ZERO];
+            // Near a corner the boid is also a neighbor diagonally across it
+            if x != 0.0 && y != 0.0 {
+                offsets[2] = Vec2::new(x, y);
+            }
+            for offset in offsets.into_iter().filter(|offset| *offset != Vec2::ZERO) {
+                let mut copy = boid.to_boid();
+                copy.position += offset;
+                copies.push(copy);
+            }
+        }
+        copies
+    }
+
+    /// Move `position` by `velocity`, keeping it in the arena the whole way rather than only where
+    /// it ends up.
+    pub fn advance(
+        &self,
+        position: &mut Pos2,
+        velocity: &mut Vec2,
+        bounds: &Rect,
+        mode: BoundaryMode,
+    ) {
+        let substeps = if mode == BoundaryMode::Boundless {
+            1
+        } else {
+            ((velocity.length() / SUBSTEP_LENGTH).ceil() as u32).clamp(1, self.max_substeps.max(1))
+        };
+        for _ in 0..substeps {
+            // Bouncing can turn the velocity around part way through
+            *position += *velocity / substeps as f32;
+            self.contain(position, velocity, bounds, mode);
+        }
+    }
+
+    /// Outline the arena fitted into `bounds` (in world coordinates).
+    #[cfg(feature = "gui")]
+    pub fn draw_perimeter(&self, painter: &Painter, rect: &Rect, camera: &Camera, bounds: &Rect) {
+        let stroke = Stroke::new(2.0, PERIMETER_COLOR);
+        match self.shape {
+            ArenaShape::Rectangle => {
+                let screen_bounds = Rect::from_two_pos(
+                    camera.world_to_screen(rect, bounds.min),
+                    camera.world_to_screen(rect, bounds.max),
+                );
+                painter.rect_stroke(screen_bounds, 0.0, stroke);
+            }
+            ArenaShape::Corridor => {
+                let corridor = self.corridor(bounds);
+                for y in [corridor.min.y, corridor.max.y] {
+                    painter.line_segment(
+                        [
+                            camera.world_to_screen(rect, Pos2::new(corridor.min.x, y)),
+                            camera.world_to_screen(rect, Pos2::new(corridor.max.x, y)),
+                        ],
+                        stroke,
+                    );
+                }
+            }
+            ArenaShape::Circle | ArenaShape::Annulus => {
+                let (outer, inner) = self.radii(bounds);
+                let center = camera.world_to_screen(rect, bounds.center());
+                painter.circle_stroke(center, outer * camera.zoom, stroke);
+                if inner > 0.0 {
+                    painter.circle_stroke(center, inner * camera.zoom, stroke);
+                }
+            }
+        }
+    }
+}
+
+/// How boids steer clear of the edges in [`BoundaryMode::Contain`].
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ContainmentParameters {
+    /// Boids start turning back this far from an edge
+    pub margin: f32,
+    pub strength: f32,
+}
+
+impl Default for ContainmentParameters {
+    fn default() -> Self {
+        Self {
+            margin: 100.0,
+            strength: 1.5,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl ContainmentParameters {
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.label("Containment Margin");
+        ui.add(egui::DragValue::new(&mut self.margin).range(1.0..=500.0));
+        ui.label("Containment Strength");
+        ui.add(egui::DragValue::new(&mut self.strength).speed(0.05))
+            .on_hover_text("How hard boids turn back at the edge, easing off towards the margin");
+    }
+}
+
+/// Anything leaving one edge reappears on the opposite edge, along the axes that wrap. It
+/// bounces off the edges along the others.
+fn wrap_rectangle(
+    position: &mut Pos2,
+    velocity: &mut Vec2,
+    bounds: &Rect,
+    wrap_x: bool,
+    wrap_y: bool,
+) {
+    wrap_or_bounce(
+        &mut position.x,
+        &mut velocity.x,
+        bounds.min.x,
+        bounds.max.x,
+        wrap_x,
+    );
+    wrap_or_bounce(
+        &mut position.y,
+        &mut velocity.y,
+        bounds.min.y,
+        bounds.max.y,
+        wrap_y,
+    );
+}
+
+fn bounce_rectangle(position: &mut Pos2, velocity: &mut Vec2, bounds: &Rect) {
+    wrap_rectangle(position, velocity, bounds, false, false);
+}
+
+/// Keep one coordinate between `min` and `max`, wrapping it round to the other end or bouncing it back.
+fn wrap_or_bounce(position: &mut f32, velocity: &mut f32, min: f32, max: f32, wrap: bool) {
+    if wrap {
+        if *position > max {
+            *position = min;
+        } else if *position < min {
+            *position = max;
+        }
+    } else if *position > max || *position < min {
+        *velocity = -*velocity;
+        *position = position.clamp(min, max);
+    }
+}
+
+/// Mirror `velocity` off a wall facing `normal`, if it's heading into the wall.
+fn reflect(velocity: &mut Vec2, normal: Vec2) {
+    let into_wall = velocity.dot(normal);
+    if into_wall > 0.0 {
+        *velocity -= 2.0 * into_wall * normal;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArenaParameters;
+    use crate::{
+        boid::Boid,
+        boid_store::BoidStore,
+        boids_simulation::BoundaryMode,
+        math::{Pos2, Rect, Vec2},
+        spatial_grid::{NeighborIndex, NeighborSearch},
+    };
+
+    const BOUNDS: Rect = Rect::from_min_max(Pos2::new(-500.0, -300.0), Pos2::new(500.0, 300.0));
+    const RADIUS: f32 = 50.0;
+
+    /// How many boids other than the one at `position` are within `RADIUS` of it, counting the
+    /// copies across edges `mode` wraps over.
+    fn neighbors(boids: &BoidStore, position: Pos2, mode: BoundaryMode) -> usize {
+        let mut flock = boids.clone();
+        flock.append(&mut ArenaParameters::default().wrapped_copies(boids, &BOUNDS, mode, RADIUS));
+        let mut candidates = Vec::new();
+        NeighborIndex::new(&flock, RADIUS, NeighborSearch::Grid)
+            .candidates(position, &mut candidates);
+        candidates
+            .iter()
+            .filter(|other| {
+                let distance = other.position.distance(position);
+                distance > 0.0 && distance < RADIUS
+            })
+            .count()
+    }
+
+    #[test]
+    fn neighbors_across_wrapped_edges() {
+        let left = Pos2::new(BOUNDS.left() + 10.0, 0.0);
+        let right = Pos2::new(BOUNDS.right() - 10.0, 0.0);
+        let corner = Pos2::new(BOUNDS.right() - 10.0, BOUNDS.bottom() - 10.0);
+        let opposite_corner = Pos2::new(BOUNDS.left() + 10.0, BOUNDS.top() + 10.0);
+        let boids: BoidStore = [left, right, corner, opposite_corner]
+            .into_iter()
+            .map(|position| Boid::new(position, Vec2::X))
+            .collect();
+
+        assert_eq!(neighbors(&boids, left, BoundaryMode::Wrap), 1);
+        assert_eq!(neighbors(&boids, right, BoundaryMode::Wrap), 1);
+        // Diagonally across the corner
+        assert_eq!(neighbors(&boids, corner, BoundaryMode::Wrap), 1);
+        assert_eq!(neighbors(&boids, left, BoundaryMode::Bounce), 0);
+    }
+}
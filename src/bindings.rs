@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+
+use egui::{InputState, Key, PointerButton, RichText, Ui};
+
+use crate::tools::Tool;
+
+/// Something the keyboard or mouse can do besides the tool under the cursor.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum Action {
+    Pause,
+    /// Advance one tick while paused
+    Step,
+    SelectTool(Tool),
+    ZoomIn,
+    ZoomOut,
+    /// Hand the camera back after zooming or panning it
+    ResetView,
+    /// Hold to draw with the laser pointer
+    LaserPointer,
+    ShepherdUp,
+    ShepherdDown,
+    ShepherdLeft,
+    ShepherdRight,
+    /// Put up or take down the wall that cuts the flock in two
+    SplitFlock,
+    /// Switch the attractor pulling the flock together on or off
+    ForceMerge,
+}
+
+impl Action {
+    pub fn all() -> impl Iterator<Item = Action> {
+        [Action::Pause, Action::Step]
+            .into_iter()
+            .chain(Tool::ALL.into_iter().map(Action::SelectTool))
+            .chain([
+                Action::ZoomIn,
+                Action::ZoomOut,
+                Action::ResetView,
+                Action::LaserPointer,
+                Action::ShepherdUp,
+                Action::ShepherdDown,
+                Action::ShepherdLeft,
+                Action::ShepherdRight,
+                Action::SplitFlock,
+                Action::ForceMerge,
+            ])
+    }
+
+    fn label(self) -> String {
+        match self {
+            Action::Pause => "Pause".to_owned(),
+            Action::Step => "Step".to_owned(),
+            Action::SelectTool(tool) => format!("{} Tool", tool.label()),
+            Action::ZoomIn => "Zoom In".to_owned(),
+            Action::ZoomOut => "Zoom Out".to_owned(),
+            Action::ResetView => "Reset View".to_owned(),
+            Action::LaserPointer => "Laser Pointer".to_owned(),
+            Action::ShepherdUp => "Shepherd Up".to_owned(),
+            Action::ShepherdDown => "Shepherd Down".to_owned(),
+            Action::ShepherdLeft => "Shepherd Left".to_owned(),
+            Action::ShepherdRight => "Shepherd Right".to_owned(),
+            Action::SplitFlock => "Split Flock".to_owned(),
+            Action::ForceMerge => "Force Merge".to_owned(),
+        }
+    }
+
+    fn default_bindings(self) -> Vec<Binding> {
+        const NUMBER_KEYS: [Key; 9] = [
+            Key::Num1,
+            Key::Num2,
+            Key::Num3,
+            Key::Num4,
+            Key::Num5,
+            Key::Num6,
+            Key::Num7,
+            Key::Num8,
+            Key::Num9,
+        ];
+        let keys = match self {
+            Action::Pause => vec![Key::P],
+            Action::Step => vec![Key::Period],
+            Action::SelectTool(tool) => Tool::ALL
+                .iter()
+                .position(|other| *other == tool)
+                .and_then(|index| NUMBER_KEYS.get(index))
+                .into_iter()
+                .copied()
+                .collect(),
+            Action::ZoomIn => vec![Key::Plus, Key::Equals],
+            Action::ZoomOut => vec![Key::Minus],
+            Action::ResetView => vec![Key::Num0],
+            Action::LaserPointer => vec![Key::L],
+            Action::ShepherdUp => vec![Key::W, Key::ArrowUp],
+            Action::ShepherdDown => vec![Key::S, Key::ArrowDown],
+            Action::ShepherdLeft => vec![Key::A, Key::ArrowLeft],
+            Action::ShepherdRight => vec![Key::D, Key::ArrowRight],
+            Action::SplitFlock => vec![Key::X],
+            Action::ForceMerge => vec![Key::M],
+        };
+        keys.into_iter().map(Binding::Key).collect()
+    }
+}
+
+/// A key or a mouse button an action is bound to.
+#[derive(Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Binding {
+    Key(Key),
+    Pointer(PointerButton),
+}
+
+/// Mouse buttons that can be bound, the primary and secondary buttons belong to the tools.
+const BINDABLE_BUTTONS: [PointerButton; 3] = [
+    PointerButton::Middle,
+    PointerButton::Extra1,
+    PointerButton::Extra2,
+];
+
+impl Binding {
+    fn pressed(self, input: &InputState) -> bool {
+        match self {
+            Binding::Key(key) => input.key_pressed(key),
+            Binding::Pointer(button) => input.pointer.button_pressed(button),
+        }
+    }
+
+    fn down(self, input: &InputState) -> bool {
+        match self {
+            Binding::Key(key) => input.key_down(key),
+            Binding::Pointer(button) => input.pointer.button_down(button),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Binding::Key(key) => key.symbol_or_name(),
+            Binding::Pointer(PointerButton::Middle) => "Middle Click",
+            Binding::Pointer(PointerButton::Extra1) => "Mouse Back",
+            Binding::Pointer(PointerButton::Extra2) => "Mouse Forward",
+            Binding::Pointer(_) => "Click",
+        }
+    }
+
+    /// The first bindable key or button pressed this frame.
+    fn captured(input: &InputState) -> Option<Binding> {
+        let key = input.events.iter().find_map(|event| match event {
+            egui::Event::Key {
+                key, pressed: true, ..
+            } => Some(Binding::Key(*key)),
+            _ => None,
+        });
+        key.or_else(|| {
+            BINDABLE_BUTTONS
+                .into_iter()
+                .find(|button| input.pointer.button_pressed(*button))
+                .map(Binding::Pointer)
+        })
+    }
+}
+
+/// Which keys and mouse buttons trigger which actions, editable from the config panel.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct InputBindings {
+    /// Only the actions the user has rebound, the rest keep their defaults
+    custom: HashMap<Action, Vec<Binding>>,
+    /// The action waiting for a key to be pressed, and which of its bindings that key replaces
+    #[serde(skip)]
+    listening: Option<(Action, Option<usize>)>,
+}
+
+impl InputBindings {
+    pub fn bindings(&self, action: Action) -> Vec<Binding> {
+        self.custom
+            .get(&action)
+            .cloned()
+            .unwrap_or_else(|| action.default_bindings())
+    }
+
+    /// Whether `action` was triggered this frame, never while the keys are going to a text field
+    /// or being captured for a binding.
+    pub fn pressed(&self, ctx: &egui::Context, action: Action) -> bool {
+        self.active(ctx) && ctx.input(|i| self.bindings(action).into_iter().any(|b| b.pressed(i)))
+    }
+
+    /// Whether `action` is held down, with the same exceptions as [`Self::pressed`].
+    pub fn down(&self, ctx: &egui::Context, action: Action) -> bool {
+        self.active(ctx) && ctx.input(|i| self.bindings(action).into_iter().any(|b| b.down(i)))
+    }
+
+    fn active(&self, ctx: &egui::Context) -> bool {
+        self.listening.is_none() && !ctx.wants_keyboard_input()
+    }
+
+    /// A short reminder of what triggers `action`, for hints elsewhere in the UI.
+    pub fn describe(&self, action: Action) -> String {
+        let bindings = self.bindings(action);
+        if bindings.is_empty() {
+            return "(unbound)".to_owned();
+        }
+        bindings
+            .iter()
+            .map(|binding| binding.label())
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        if let Some((action, index)) = self.listening {
+            let captured = ui.input(|i| {
+                if i.key_pressed(Key::Escape) {
+                    Some(None)
+                } else {
+                    Binding::captured(i).map(Some)
+                }
+            });
+            match captured {
+                // Escape cancels
+                Some(None) => self.listening = None,
+                Some(Some(binding)) => {
+                    // Otherwise a focused binding button takes Space or Enter as another click
+                    ui.memory_mut(|memory| {
+                        if let Some(focused) = memory.focused() {
+                            memory.surrender_focus(focused);
+                        }
+                    });
+                    let mut bindings = self.bindings(action);
+                    match index {
+                        Some(index) if index < bindings.len() => bindings[index] = binding,
+                        _ if !bindings.contains(&binding) => bindings.push(binding),
+                        _ => {}
+                    }
+                    self.custom.insert(action, bindings);
+                    self.listening = None;
+                }
+                None => {}
+            }
+        }
+
+        ui.label("Click a binding and press a key or mouse button to change it, right click to remove it");
+        egui::Grid::new("input_bindings").show(ui, |ui| {
+            for action in Action::all() {
+                ui.label(action.label());
+                ui.horizontal(|ui| {
+                    let bindings = self.bindings(action);
+                    for (index, binding) in bindings.iter().enumerate() {
+                        let text = if self.listening == Some((action, Some(index))) {
+                            RichText::new("Press a key…").italics()
+                        } else {
+                            RichText::new(binding.label()).monospace()
+                        };
+                        let response = ui.button(text);
+                        if response.clicked() {
+                            self.listening = Some((action, Some(index)));
+                        } else if response.secondary_clicked() {
+                            let mut bindings = bindings.clone();
+                            bindings.remove(index);
+                            self.custom.insert(action, bindings);
+                            self.listening = None;
+                        }
+                    }
+                    let adding = self.listening == Some((action, None));
+                    if ui
+                        .small_button(if adding { "Press a key…" } else { "+" })
+                        .on_hover_text("Add another binding")
+                        .clicked()
+                    {
+                        self.listening = Some((action, None));
+                    }
+                });
+                ui.end_row();
+            }
+        });
+
+        let conflicts = self.conflicts();
+        if !conflicts.is_empty() {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("Bound to more than one action: {}", conflicts.join(", ")),
+            );
+        }
+        if ui.button("Reset to Defaults").clicked() {
+            self.custom.clear();
+            self.listening = None;
+        }
+    }
+
+    /// Labels of bindings that trigger more than one action.
+    fn conflicts(&self) -> Vec<&'static str> {
+        let mut seen = Vec::new();
+        let mut conflicts = Vec::new();
+        for binding in Action::all().flat_map(|action| self.bindings(action)) {
+            if seen.contains(&binding) {
+                if !conflicts.contains(&binding.label()) {
+                    conflicts.push(binding.label());
+                }
+            } else {
+                seen.push(binding);
+            }
+        }
+        conflicts
+    }
+}
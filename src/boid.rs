@@ -1,195 +1,637 @@
-use egui::{Color32, Pos2, Rect, Ui, Vec2};
+use std::sync::atomic::{AtomicU64, Ordering};
 
-use crate::boids_simulation::BoidsSimulationParameters;
+#[cfg(feature = "gui")]
+use egui::Ui;
+
+use crate::{
+    boid_store::{BoidMut, BoidRef, BoidState},
+    boids_simulation::BoidsSimulationParameters,
+    game::TICKS_PER_SECOND,
+    genome::Genome,
+    math::{Color32, Pos2, Rot2, Vec2},
+    obstacle::Obstacle,
+    threat::Threat,
+};
+
+#[cfg(feature = "gui")]
+use crate::{altitude::ALTITUDE_SCREEN_OFFSET, camera::Camera, math::Rect, render::BoidShape};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+/// Boids that moved further than this in a tick jumped rather than flew, so they aren't interpolated.
+const MAX_INTERPOLATION_DISTANCE: f32 = 50.0;
 
-// Add vision cone
 // Add goals for groups
 
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
 pub struct Boid {
+    /// Stays the same while other boids come and go, unlike the boid's index
+    pub id: u64,
     pub velocity: Vec2,
     pub position: Pos2,
+    /// Where the boid was at the start of the last tick, to draw it part way between ticks
+    #[serde(skip)]
+    pub previous_position: Pos2,
     pub acceleration: Vec2,
+    /// The acceleration the boid was last stepped with, velocity Verlet averages it with the next
+    #[serde(default)]
+    pub last_acceleration: Vec2,
     pub color: Color32,
+    /// Ticks left to rest on a perch or the ground, `None` while flying
+    pub resting: Option<u32>,
+    /// Height above the ground, only used when altitude is enabled
+    pub altitude: f32,
+    /// Whether the boid is on its way down to land
+    pub descending: bool,
+    /// Between 0 (exhausted) and 1 (fully rested), only used when energy is enabled
+    pub energy: f32,
+    /// Whether the boid is low on energy and looking for a thermal
+    pub seeking_thermal: bool,
+    /// Ticks since the boid was spawned
+    pub age: u32,
+    #[serde(default)]
+    pub genome: Genome,
+    /// Id of the user-defined group the boid belongs to, if any
+    #[serde(default)]
+    pub group: Option<u32>,
+    /// Place in the pecking order, from 0 (bottom) to 1 (top)
+    #[serde(default)]
+    pub rank: f32,
+    /// Where the boid thought the middle of its neighbors was last tick, `None` without neighbors
+    #[serde(default)]
+    pub perceived_centroid: Option<Pos2>,
+    /// How many boids were within the neighbor radius at the last force update
+    #[serde(skip)]
+    pub neighbor_count: usize,
+    /// What the boid's separation radius is scaled by for how crowded it is, see
+    /// [`crate::density::AdaptiveSeparation`]
+    #[serde(skip)]
+    pub separation_scale: f32,
+    /// The steering rule that pulled on the boid the hardest at the last force update
+    #[serde(skip)]
+    pub dominant_force: Option<&'static str>,
 }
 
 impl Boid {
     pub fn new(position: Pos2, initial_velocity: Vec2) -> Self {
+        Self::with_id(
+            NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            position,
+            initial_velocity,
+        )
+    }
+
+    /// A boid outside of any flock, to see what a boid at `position` would feel.
+    ///
+    /// It doesn't use up an id, so the boids hatched after it are the same as without it.
+    pub fn probe(position: Pos2, velocity: Vec2) -> Self {
+        Self::with_id(u64::MAX, position, velocity)
+    }
+
+    /// A boid with an id its flock hands out itself, so the same flock gets the same ids however
+    /// many boids were hatched elsewhere before it.
+    pub fn with_id(id: u64, position: Pos2, initial_velocity: Vec2) -> Self {
         Boid {
+            id,
             velocity: initial_velocity,
             position,
+            previous_position: position,
             acceleration: Vec2::ZERO,
+            last_acceleration: Vec2::ZERO,
             color: Color32::WHITE,
+            resting: None,
+            altitude: 0.0,
+            descending: false,
+            energy: 1.0,
+            seeking_thermal: false,
+            age: 0,
+            genome: Genome::default(),
+            group: None,
+            rank: 0.0,
+            perceived_centroid: None,
+            neighbor_count: 0,
+            separation_scale: 1.0,
+            dominant_force: None,
         }
     }
 
-    pub fn draw(&self, ui: &mut Ui, rect: &Rect) {
-        let painter = ui.painter_at(*rect);
-        let size = 10.0;
+    /// Take the boid apart into the fields a [`crate::boid_store::BoidStore`] keeps apart.
+    pub fn split(self) -> (Pos2, Vec2, Vec2, Color32, BoidState) {
+        let state = BoidState {
+            id: self.id,
+            previous_position: self.previous_position,
+            last_acceleration: self.last_acceleration,
+            resting: self.resting,
+            altitude: self.altitude,
+            descending: self.descending,
+            energy: self.energy,
+            seeking_thermal: self.seeking_thermal,
+            age: self.age,
+            genome: self.genome,
+            group: self.group,
+            rank: self.rank,
+            perceived_centroid: self.perceived_centroid,
+            neighbor_count: self.neighbor_count,
+            separation_scale: self.separation_scale,
+            dominant_force: self.dominant_force,
+        };
+        (
+            self.position,
+            self.velocity,
+            self.acceleration,
+            self.color,
+            state,
+        )
+    }
 
-        // TODO: Fix me - arrow points in wrong direction/starts in wrong pos
-        let stroke = egui::Stroke::new(2.0, self.color);
-        let adjusted_pos = self.position + rect.center().to_vec2();
-        painter.arrow(adjusted_pos, self.velocity.normalized() * size, stroke);
+    /// Put a boid back together from what [`Self::split`] took it apart into.
+    pub fn join(
+        position: Pos2,
+        velocity: Vec2,
+        acceleration: Vec2,
+        color: Color32,
+        state: BoidState,
+    ) -> Self {
+        Boid {
+            id: state.id,
+            velocity,
+            position,
+            previous_position: state.previous_position,
+            acceleration,
+            last_acceleration: state.last_acceleration,
+            color,
+            resting: state.resting,
+            altitude: state.altitude,
+            descending: state.descending,
+            energy: state.energy,
+            seeking_thermal: state.seeking_thermal,
+            age: state.age,
+            genome: state.genome,
+            group: state.group,
+            rank: state.rank,
+            perceived_centroid: state.perceived_centroid,
+            neighbor_count: state.neighbor_count,
+            separation_scale: state.separation_scale,
+            dominant_force: state.dominant_force,
+        }
     }
+}
 
-    pub fn apply_forces(&mut self, params: &BoidsSimulationParameters) {
-        // Apply the acceleration to the velocity
-        self.velocity = self.velocity + self.acceleration;
-        // clamp the velocity - can do length squared if needed here
-        if self.velocity.length() > params.max_speed {
-            self.velocity = self.velocity.normalized() * params.max_speed;
+impl BoidRef<'_> {
+    /// Where the boid is `alpha` of the way from where it was at the start of the last tick to
+    /// where it is now.
+    pub fn interpolated_position(self, alpha: f32) -> Pos2 {
+        // Wrapping around or being moved elsewhere shouldn't be drawn as a streak across the world
+        if self.previous_position.distance(self.position) < MAX_INTERPOLATION_DISTANCE {
+            self.previous_position.lerp(self.position, alpha)
+        } else {
+            self.position
         }
-        // Zero out the acceleration
-        self.acceleration = Vec2::ZERO;
+    }
 
-        self.position += self.velocity;
+    /// The flock's max speed adjusted for this boid's genome and age.
+    pub fn max_speed(self, params: &BoidsSimulationParameters) -> f32 {
+        params.max_speed * self.genome.speed * params.lifecycle.speed_factor(self)
     }
 
-    pub fn screen_wrap(&mut self, left: f32, right: f32, top: f32, bottom: f32) {
-        if self.position.x > right {
-            self.position.x = left;
-        }
-        if self.position.x < left {
-            self.position.x = right;
+    #[cfg(feature = "gui")]
+    pub fn draw(
+        self,
+        ui: &mut Ui,
+        rect: &Rect,
+        camera: &Camera,
+        params: &BoidsSimulationParameters,
+        color: Color32,
+        mut size: f32,
+    ) {
+        let painter = ui.painter_at(*rect);
+
+        let mut adjusted_pos = camera.world_to_screen(rect, self.position);
+        if params.altitude.enabled && self.altitude > 0.0 {
+            // Fake some depth: a shadow on the ground and a bigger boid lifted up the screen
+            painter.circle_filled(adjusted_pos, 3.0, Color32::from_black_alpha(160));
+            adjusted_pos.y -= self.altitude * ALTITUDE_SCREEN_OFFSET * camera.zoom;
+            size *= 1.0 + 0.5 * self.altitude / params.altitude.cruise_altitude.max(1.0);
         }
-        if self.position.y > bottom {
-            self.position.y = top;
+
+        if self.resting.is_some() {
+            // Perched boids have no heading to show, so draw them as a dot
+            painter.circle_filled(adjusted_pos, size / 3.0, Color32::LIGHT_GRAY);
+            return;
         }
-        if self.position.y < top {
-            self.position.y = bottom;
+
+        let heading = self.velocity.normalized();
+        let shape = params
+            .groups
+            .style(self.group)
+            .map_or(BoidShape::Arrow, |style| style.shape);
+        match shape {
+            BoidShape::Arrow => {
+                // TODO: Fix me - arrow points in wrong direction/starts in wrong pos
+                let stroke = egui::Stroke::new(2.0, color);
+                painter.arrow(adjusted_pos, heading * size, stroke);
+            }
+            BoidShape::Triangle => {
+                let side = heading.rot90() * size / 3.0;
+                let tail = adjusted_pos - heading * size / 2.0;
+                painter.add(egui::Shape::convex_polygon(
+                    vec![
+                        adjusted_pos + heading * size / 2.0,
+                        tail + side,
+                        tail - side,
+                    ],
+                    color,
+                    egui::Stroke::NONE,
+                ));
+            }
+            BoidShape::Circle => {
+                painter.circle_filled(adjusted_pos, size / 3.0, color);
+            }
         }
     }
 
     pub fn calculate_separation_force(
-        &self,
-        boids: &[Boid],
+        self,
+        boids: &[BoidRef<'_>],
         params: &BoidsSimulationParameters,
     ) -> Vec2 {
         let mut sum = Vec2::ZERO;
         let mut count = 0;
+        let radius = params
+            .adaptive_separation
+            .radius(params.separation_radius(), self.separation_scale);
 
         for other in boids {
             let distance = (self.position - other.position).length();
 
-            // If the other boid is near us
-            if distance > 0.0 && distance < params.neighbor_radius {
+            // If the other boid is near us, and in sight
+            if distance > 0.0
+                && distance < params.topology.separation.reach(radius)
+                && params.perception.can_see(self, other.position)
+            {
                 // Try to move away from them
                 let desired_direction: Vec2 = (self.position - other.position).normalized();
-                sum += desired_direction;
+                sum += desired_direction
+                    * params.dominance.separation_weight(self, *other)
+                    * params.separation_falloff.weight(distance, radius);
                 count += 1;
             }
         }
 
         if count > 0 {
             let average_desired_direction = sum / count as f32;
-            let desired_velocity = average_desired_direction.normalized() * params.max_speed;
+            let desired_velocity = average_desired_direction.normalized() * self.max_speed(params);
 
             let mut steering_force = desired_velocity - self.velocity;
             if steering_force.length() > params.max_force {
                 steering_force = steering_force.normalized() * params.max_force;
             }
 
-            steering_force * params.separation_weight
+            steering_force * params.separation_weight * self.genome.separation
         } else {
             Vec2::ZERO
         }
     }
 
-    pub fn calculate_cohesion_force(
-        &self,
-        boids: &[Boid],
+    /// The middle of the boid's neighbors, eased in from where it was last tick.
+    ///
+    /// In a dense flock neighbors come and go every tick and the exact middle jumps around with
+    /// them, which makes boids chasing it vibrate.
+    pub fn perceived_centroid(
+        self,
+        boids: &[BoidRef<'_>],
         params: &BoidsSimulationParameters,
-    ) -> Vec2 {
+    ) -> Option<Pos2> {
         let mut sum = Vec2::ZERO;
-        let mut count = 0;
+        let mut total = 0.0;
 
         for other in boids {
             let distance = (self.position - other.position).length();
-            if distance > 0.0 && distance < params.neighbor_radius {
-                sum += other.position.to_vec2();
-                count += 1;
+            if distance > 0.0
+                && distance
+                    < params
+                        .topology
+                        .cohesion
+                        .reach(params.cohesion_radius() * params.lifecycle.perception_factor(self))
+                && params.perception.can_see(self, other.position)
+            {
+                let weight = params.perception.weight(self, other.position);
+                sum += other.position.to_vec2() * weight;
+                total += weight;
             }
         }
 
-        if count > 0 {
-            let average_position_of_neighbors = sum / count as f32;
+        if total <= 0.0 {
+            return None;
+        }
+        let centroid = Pos2::ZERO + sum / total;
+        match self.perceived_centroid {
+            // Don't ease in from somewhere the boid has left behind, like the far side of a wrap
+            Some(previous) if previous.distance(self.position) < params.cohesion_radius() => {
+                Some(previous + (centroid - previous) * (1.0 - params.cohesion_smoothing))
+            }
+            _ => Some(centroid),
+        }
+    }
 
+    /// Steer towards `centroid`, the middle of the boid's neighbors.
+    pub fn calculate_cohesion_force(
+        self,
+        centroid: Option<Pos2>,
+        params: &BoidsSimulationParameters,
+    ) -> Vec2 {
+        if let Some(centroid) = centroid {
             // We want to move at the max speed towards our neighbors
-            let desired_velocity = (average_position_of_neighbors - self.position.to_vec2())
-                .normalized()
-                * params.max_speed;
+            let desired_velocity = (centroid - self.position).normalized() * self.max_speed(params);
 
             let mut steering_force = desired_velocity - self.velocity;
             if steering_force.length() > params.max_force {
                 steering_force = steering_force.normalized() * params.max_force;
             }
-            return steering_force * params.cohesion_weight;
+            return steering_force
+                * params.cohesion_weight
+                * self.genome.cohesion
+                * params.dominance.cohesion_factor(self);
         }
 
         Vec2::ZERO
     }
 
     pub fn calculate_alignment_force(
-        &self,
-        boids: &[Boid],
+        self,
+        boids: &[BoidRef<'_>],
         params: &BoidsSimulationParameters,
     ) -> Vec2 {
         let mut sum = Vec2::ZERO;
-        let mut count = 0;
+        let mut total = 0.0;
 
         // Trying to match the average of its neighbors velocity
         for other in boids {
             let distance = (self.position - other.position).length();
-            if distance > 0.0 && distance < params.neighbor_radius {
-                sum += other.velocity;
-                count += 1;
+            if distance > 0.0
+                && distance
+                    < params
+                        .topology
+                        .alignment
+                        .reach(params.alignment_radius() * params.lifecycle.perception_factor(self))
+                && params.perception.can_see(self, other.position)
+            {
+                let weight = params.perception.weight(self, other.position);
+                sum += other.velocity * weight;
+                total += weight;
             }
         }
 
-        if count > 0 {
-            let average_velocity_of_neighbors = sum / count as f32;
+        if total > 0.0 {
+            let average_velocity_of_neighbors = sum / total;
 
             // We set the desired velocity to the max speed and not simply to the average speed of our neighbors because
             // if we just try to achieve the same velocity as our neighbors we get this stalling behavior where the average speed is falling
             // rather than sweeping up boids into the flock. So we want to align on direction but not match speed necessarily
-            let desired_velocity = average_velocity_of_neighbors.normalized() * params.max_speed;
+            let desired_velocity =
+                average_velocity_of_neighbors.normalized() * self.max_speed(params);
 
             // Here we're basically using force and acceleration interchangeably, which makes sense in the case where mass is some unit value
             // So now the steering force is just the difference in our current velocity and the desired velocity, and so the force is what would
             // need to be applied in order to match our direction to the neighbors
             let steer_force = desired_velocity - self.velocity;
+            let weight = params.alignment_weight * self.genome.alignment;
             // If the force exceeds our max force, make sure to cap it
             if steer_force.length() > params.max_force {
-                steer_force.normalized() * params.max_force * params.alignment_weight
+                steer_force.normalized() * params.max_force * weight
             } else {
-                steer_force * params.alignment_weight
+                steer_force * weight
             }
         } else {
             Vec2::ZERO
         }
     }
 
+    /// Flee from every threat within the avoidance radius at once, in a single blended direction.
+    ///
+    /// Closer threats count for more, and so do threats closing in fast, so a boid between two
+    /// predators runs from the one about to reach it rather than straight into it.
     pub fn calculate_avoidance_force(
-        &self,
-        predator_position: Pos2,
+        self,
+        threats: &[&Threat],
         params: &BoidsSimulationParameters,
     ) -> Vec2 {
-        let distance = (self.position - predator_position).length();
+        let mut escape = Vec2::ZERO;
+        for threat in threats {
+            let offset = self.position - threat.position;
+            let distance = offset.length();
+            if distance == 0.0 || distance >= params.avoidance_radius {
+                continue;
+            }
 
-        if distance < params.avoidance_radius {
-            // We know this will have the unit length of 1
-            let steer_direction = (self.position - predator_position).normalized();
-            let desired_steer_velocity = steer_direction * params.max_speed; // the boid wants to steer away from the predator as fast as it can
+            let away = offset / distance;
+            let proximity = 1.0 - distance / params.avoidance_radius;
+            // Positive when the threat and the boid are getting closer
+            let closing_speed = (threat.velocity - self.velocity).dot(away);
+            let direction = params
+                .escape_strategy
+                .direction(away, threat, self.velocity);
+            escape += direction
+                * proximity
+                * (1.0 + params.closing_speed_priority * closing_speed.max(0.0));
+        }
+        if escape == Vec2::ZERO {
+            return Vec2::ZERO;
+        }
 
-            let steer_force = desired_steer_velocity - self.velocity;
+        // The boid wants to get away from the threats as fast as it can
+        let desired_steer_velocity = escape.normalized() * self.max_speed(params);
+        let steer_force = desired_steer_velocity - self.velocity;
+        let weight = params.avoidance_weight * self.genome.avoidance;
+        let max_force = params.max_force * params.escape_strategy.force_multiplier();
 
-            if steer_force.length() > params.max_force {
-                steer_force.normalized() * params.max_force * params.avoidance_weight
-            } else {
-                steer_force * params.avoidance_weight
+        if steer_force.length() > max_force {
+            steer_force.normalized() * max_force * weight
+        } else {
+            steer_force * weight
+        }
+    }
+
+    /// Steer around obstacles coming up, turning harder the closer they are.
+    ///
+    /// Rather than turning straight away from an obstacle the boid bends its heading by the push
+    /// off each one, so it slides around them instead of bouncing back the way it came.
+    pub fn calculate_obstacle_avoidance_force(
+        self,
+        obstacles: &[Obstacle],
+        params: &BoidsSimulationParameters,
+    ) -> Vec2 {
+        let look_ahead = params.obstacle_avoidance.look_ahead;
+        let mut push = Vec2::ZERO;
+        for obstacle in obstacles {
+            let (distance, normal) = obstacle.surface(self.position);
+            if distance < look_ahead {
+                push += normal * (1.0 - distance.max(0.0) / look_ahead);
             }
+        }
+        if push == Vec2::ZERO {
+            return Vec2::ZERO;
+        }
+
+        let heading = (self.velocity.normalized() + push).normalized();
+        if !heading.is_finite() {
+            return Vec2::ZERO;
+        }
+        self.calculate_heading_force(heading, params.obstacle_avoidance.weight, params)
+    }
+
+    /// Steer towards `target` at full speed, scaled by `weight`.
+    pub fn calculate_seek_force(
+        self,
+        target: Pos2,
+        weight: f32,
+        params: &BoidsSimulationParameters,
+    ) -> Vec2 {
+        let offset = target - self.position;
+        if offset == Vec2::ZERO {
+            return Vec2::ZERO;
+        }
+
+        let desired_velocity = offset.normalized() * self.max_speed(params);
+        let steer_force = desired_velocity - self.velocity;
+        if steer_force.length() > params.max_force {
+            steer_force.normalized() * params.max_force * weight
         } else {
-            Vec2::ZERO
+            steer_force * weight
+        }
+    }
+
+    /// Fly in the direction of `heading` (a unit vector), scaled by `weight`.
+    pub fn calculate_heading_force(
+        self,
+        heading: Vec2,
+        weight: f32,
+        params: &BoidsSimulationParameters,
+    ) -> Vec2 {
+        self.calculate_seek_force(self.position + heading, weight, params)
+    }
+
+    /// Circle `center` counterclockwise at `radius`, scaled by `weight`.
+    pub fn calculate_orbit_force(
+        self,
+        center: Pos2,
+        radius: f32,
+        weight: f32,
+        params: &BoidsSimulationParameters,
+    ) -> Vec2 {
+        let offset = self.position - center;
+        let distance = offset.length();
+        if distance == 0.0 || radius <= 0.0 {
+            return Vec2::ZERO;
         }
+
+        // Fly along the circle while correcting back towards the orbit radius
+        let outward = offset / distance;
+        let radial_error = (radius - distance) / radius;
+        let desired_direction = outward.rot90() + outward * radial_error;
+        let desired_velocity = desired_direction.normalized() * self.max_speed(params);
+
+        let steer_force = desired_velocity - self.velocity;
+        if steer_force.length() > params.max_force {
+            steer_force.normalized() * params.max_force * weight
+        } else {
+            steer_force * weight
+        }
+    }
+}
+
+impl BoidMut<'_> {
+    /// Turn the forces acting on the boid into its new velocity, moving it is up to the arena.
+    ///
+    /// Returns how much further than its new velocity takes it the boid should move this step,
+    /// see [`crate::integrator::Integrator::step`]. `midpoint` is the acceleration halfway through the step for RK2.
+    pub fn apply_forces(
+        &mut self,
+        params: &BoidsSimulationParameters,
+        midpoint: Option<Vec2>,
+    ) -> Vec2 {
+        let boid = self.as_ref();
+        let max_speed = boid.max_speed(params) * params.energy.speed_factor(boid);
+        let integration = &params.integration;
+        let previous_velocity = *self.velocity;
+        let mut offset =
+            integration
+                .integrator
+                .step(self, midpoint, max_speed, integration.time_step);
+        if params.max_turn_rate > 0.0 {
+            let max_turn =
+                params.max_turn_rate.to_radians() / TICKS_PER_SECOND * integration.time_step;
+            let correction = limit_turn(self.velocity, previous_velocity, max_turn);
+            // The rest of the step turns with the velocity
+            offset = correction * offset;
+        }
+        // Zero out the acceleration
+        *self.acceleration = Vec2::ZERO;
+        offset
+    }
+}
+
+/// Turn `velocity` back towards `previous` until it's at most `max_turn` radians away, returning
+/// the rotation that took.
+fn limit_turn(velocity: &mut Vec2, previous: Vec2, max_turn: f32) -> Rot2 {
+    if *velocity == Vec2::ZERO || previous == Vec2::ZERO {
+        return Rot2::IDENTITY;
+    }
+    let turn = Rot2::from_angle(previous.angle()).inverse() * *velocity;
+    let angle = turn.angle();
+    if angle.abs() <= max_turn {
+        return Rot2::IDENTITY;
+    }
+    let correction = Rot2::from_angle(angle.signum() * max_turn - angle);
+    *velocity = correction * *velocity;
+    correction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Boid;
+    use crate::{
+        boid_store::BoidStore,
+        boids_simulation::BoidsSimulationParameters,
+        game::TICKS_PER_SECOND,
+        integrator::Integrator,
+        math::{Pos2, Vec2},
+    };
+
+    /// How far a boid flying along x turns in one step with `acceleration` on it.
+    fn turn(params: &BoidsSimulationParameters, acceleration: Vec2) -> f32 {
+        let mut boids: BoidStore = [Boid::new(Pos2::ZERO, Vec2::X * 2.0)].into_iter().collect();
+        let mut boid = boids.boid_mut(0);
+        *boid.acceleration = acceleration;
+        boid.apply_forces(params, None);
+        boid.velocity.angle().abs()
+    }
+
+    #[test]
+    fn turns_capped_at_max_turn_rate() {
+        for integrator in Integrator::ALL {
+            for time_step in [0.5, 1.0, 2.0] {
+                let mut params = BoidsSimulationParameters {
+                    max_turn_rate: 90.0,
+                    ..Default::default()
+                };
+                params.integration.integrator = integrator;
+                params.integration.time_step = time_step;
+                let max_turn = 90_f32.to_radians() / TICKS_PER_SECOND * time_step;
+
+                // Pushed back the way it came it only turns as fast as it's allowed to
+                let hard = turn(&params, Vec2::new(-3.0, -1.0));
+                assert!((hard - max_turn).abs() < 1e-4, "{hard} vs {max_turn}");
+                // A gentle nudge turns it less than that, and isn't touched
+                let gentle = turn(&params, Vec2::new(0.0, 0.001));
+                assert!(gentle > 0.0 && gentle < max_turn);
+            }
+        }
+    }
+
+    #[test]
+    fn no_max_turn_rate_turns_freely() {
+        let params = BoidsSimulationParameters::default();
+        let max_turn = 90_f32.to_radians() / TICKS_PER_SECOND;
+        assert!(turn(&params, Vec2::new(-3.0, -1.0)) > max_turn);
     }
 }
@@ -0,0 +1,352 @@
+use std::{
+    mem::size_of,
+    ops::{Deref, DerefMut, Range},
+    slice,
+};
+
+use crate::{
+    boid::Boid,
+    genome::Genome,
+    math::{Color32, Pos2, Vec2},
+};
+
+/// Everything about a boid besides the fields every tick reads for the whole flock.
+#[derive(Clone)]
+pub struct BoidState {
+    /// Stays the same while other boids come and go, unlike the boid's index
+    pub id: u64,
+    /// Where the boid was at the start of the last tick, to draw it part way between ticks
+    pub previous_position: Pos2,
+    /// The acceleration the boid was last stepped with, velocity Verlet averages it with the next
+    pub last_acceleration: Vec2,
+    /// Ticks left to rest on a perch or the ground, `None` while flying
+    pub resting: Option<u32>,
+    /// Height above the ground, only used when altitude is enabled
+    pub altitude: f32,
+    /// Whether the boid is on its way down to land
+    pub descending: bool,
+    /// Between 0 (exhausted) and 1 (fully rested), only used when energy is enabled
+    pub energy: f32,
+    /// Whether the boid is low on energy and looking for a thermal
+    pub seeking_thermal: bool,
+    /// Ticks since the boid was spawned
+    pub age: u32,
+    pub genome: Genome,
+    /// Id of the user-defined group the boid belongs to, if any
+    pub group: Option<u32>,
+    /// Place in the pecking order, from 0 (bottom) to 1 (top)
+    pub rank: f32,
+    /// Where the boid thought the middle of its neighbors was last tick, `None` without neighbors
+    pub perceived_centroid: Option<Pos2>,
+    /// How many boids were within the neighbor radius at the last force update
+    pub neighbor_count: usize,
+    /// See [`Boid::separation_scale`]
+    pub separation_scale: f32,
+    /// The steering rule that pulled on the boid the hardest at the last force update
+    pub dominant_force: Option<&'static str>,
+}
+
+/// The flock, with the fields neighbor searches and the force loop go through for every boid
+/// each in an array of their own.
+///
+/// Stored and sent over the network as a list of [`Boid`]s.
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(from = "Vec<Boid>", into = "Vec<Boid>")]
+pub struct BoidStore {
+    positions: Vec<Pos2>,
+    velocities: Vec<Vec2>,
+    accelerations: Vec<Vec2>,
+    colors: Vec<Color32>,
+    states: Vec<BoidState>,
+}
+
+/// A boid in a [`BoidStore`], which reads like a [`Boid`].
+#[derive(Clone, Copy)]
+pub struct BoidRef<'a> {
+    pub position: Pos2,
+    pub velocity: Vec2,
+    pub acceleration: Vec2,
+    pub color: Color32,
+    state: &'a BoidState,
+}
+
+/// A boid in a [`BoidStore`] that can be changed.
+pub struct BoidMut<'a> {
+    pub position: &'a mut Pos2,
+    pub velocity: &'a mut Vec2,
+    pub acceleration: &'a mut Vec2,
+    pub color: &'a mut Color32,
+    state: &'a mut BoidState,
+}
+
+impl Deref for BoidRef<'_> {
+    type Target = BoidState;
+
+    fn deref(&self) -> &BoidState {
+        self.state
+    }
+}
+
+impl Deref for BoidMut<'_> {
+    type Target = BoidState;
+
+    fn deref(&self) -> &BoidState {
+        self.state
+    }
+}
+
+impl DerefMut for BoidMut<'_> {
+    fn deref_mut(&mut self) -> &mut BoidState {
+        self.state
+    }
+}
+
+impl BoidRef<'_> {
+    /// An owned copy of the boid.
+    pub fn to_boid(self) -> Boid {
+        Boid::join(
+            self.position,
+            self.velocity,
+            self.acceleration,
+            self.color,
+            self.state.clone(),
+        )
+    }
+}
+
+impl BoidMut<'_> {
+    pub fn as_ref(&self) -> BoidRef<'_> {
+        BoidRef {
+            position: *self.position,
+            velocity: *self.velocity,
+            acceleration: *self.acceleration,
+            color: *self.color,
+            state: self.state,
+        }
+    }
+}
+
+impl BoidStore {
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Every boid's position, in the same order as the boids.
+    pub fn positions(&self) -> &[Pos2] {
+        &self.positions
+    }
+
+    pub fn get(&self, index: usize) -> Option<BoidRef<'_>> {
+        (index < self.len()).then(|| self.boid(index))
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<BoidMut<'_>> {
+        (index < self.len()).then(|| self.boid_mut(index))
+    }
+
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn boid(&self, index: usize) -> BoidRef<'_> {
+        BoidRef {
+            position: self.positions[index],
+            velocity: self.velocities[index],
+            acceleration: self.accelerations[index],
+            color: self.colors[index],
+            state: &self.states[index],
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn boid_mut(&mut self, index: usize) -> BoidMut<'_> {
+        BoidMut {
+            position: &mut self.positions[index],
+            velocity: &mut self.velocities[index],
+            acceleration: &mut self.accelerations[index],
+            color: &mut self.colors[index],
+            state: &mut self.states[index],
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            store: self,
+            indices: 0..self.len(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut {
+            positions: self.positions.iter_mut(),
+            velocities: self.velocities.iter_mut(),
+            accelerations: self.accelerations.iter_mut(),
+            colors: self.colors.iter_mut(),
+            states: self.states.iter_mut(),
+        }
+    }
+
+    pub fn push(&mut self, boid: Boid) {
+        let (position, velocity, acceleration, color, state) = boid.split();
+        self.positions.push(position);
+        self.velocities.push(velocity);
+        self.accelerations.push(acceleration);
+        self.colors.push(color);
+        self.states.push(state);
+    }
+
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.positions.truncate(len);
+        self.velocities.truncate(len);
+        self.accelerations.truncate(len);
+        self.colors.truncate(len);
+        self.states.truncate(len);
+    }
+
+    /// Move every boid in `other` to the end of this store.
+    pub fn append(&mut self, other: &mut BoidStore) {
+        self.positions.append(&mut other.positions);
+        self.velocities.append(&mut other.velocities);
+        self.accelerations.append(&mut other.accelerations);
+        self.colors.append(&mut other.colors);
+        self.states.append(&mut other.states);
+    }
+
+    /// Keep only the boids `keep` returns true for, in the order they were in.
+    pub fn retain(&mut self, mut keep: impl FnMut(BoidRef<'_>) -> bool) {
+        let mut kept = 0;
+        for index in 0..self.len() {
+            if keep(self.boid(index)) {
+                self.positions.swap(kept, index);
+                self.velocities.swap(kept, index);
+                self.accelerations.swap(kept, index);
+                self.colors.swap(kept, index);
+                self.states.swap(kept, index);
+                kept += 1;
+            }
+        }
+        self.truncate(kept);
+    }
+
+    /// Owned copies of every boid.
+    pub fn to_boids(&self) -> Vec<Boid> {
+        self.iter().map(BoidRef::to_boid).collect()
+    }
+
+    /// Bytes the store has allocated.
+    pub fn allocated_bytes(&self) -> usize {
+        self.positions.capacity() * size_of::<Pos2>()
+            + self.velocities.capacity() * size_of::<Vec2>()
+            + self.accelerations.capacity() * size_of::<Vec2>()
+            + self.colors.capacity() * size_of::<Color32>()
+            + self.states.capacity() * size_of::<BoidState>()
+    }
+}
+
+impl Extend<Boid> for BoidStore {
+    fn extend<T: IntoIterator<Item = Boid>>(&mut self, boids: T) {
+        for boid in boids {
+            self.push(boid);
+        }
+    }
+}
+
+impl FromIterator<Boid> for BoidStore {
+    fn from_iter<T: IntoIterator<Item = Boid>>(boids: T) -> Self {
+        let mut store = Self::default();
+        store.extend(boids);
+        store
+    }
+}
+
+impl From<Vec<Boid>> for BoidStore {
+    fn from(boids: Vec<Boid>) -> Self {
+        boids.into_iter().collect()
+    }
+}
+
+impl From<BoidStore> for Vec<Boid> {
+    fn from(store: BoidStore) -> Self {
+        store.to_boids()
+    }
+}
+
+pub struct Iter<'a> {
+    store: &'a BoidStore,
+    indices: Range<usize>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = BoidRef<'a>;
+
+    fn next(&mut self) -> Option<BoidRef<'a>> {
+        self.indices.next().map(|index| self.store.boid(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indices.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.indices.next_back().map(|index| self.store.boid(index))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+pub struct IterMut<'a> {
+    positions: slice::IterMut<'a, Pos2>,
+    velocities: slice::IterMut<'a, Vec2>,
+    accelerations: slice::IterMut<'a, Vec2>,
+    colors: slice::IterMut<'a, Color32>,
+    states: slice::IterMut<'a, BoidState>,
+}
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = BoidMut<'a>;
+
+    fn next(&mut self) -> Option<BoidMut<'a>> {
+        Some(BoidMut {
+            position: self.positions.next()?,
+            velocity: self.velocities.next()?,
+            acceleration: self.accelerations.next()?,
+            color: self.colors.next()?,
+            state: self.states.next()?,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.states.size_hint()
+    }
+}
+
+impl ExactSizeIterator for IterMut<'_> {}
+
+impl<'a> IntoIterator for &'a BoidStore {
+    type Item = BoidRef<'a>;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut BoidStore {
+    type Item = BoidMut<'a>;
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> IterMut<'a> {
+        self.iter_mut()
+    }
+}
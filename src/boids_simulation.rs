@@ -1,8 +1,115 @@
+#[cfg(feature = "gui")]
 use std::ops::RangeInclusive;
 
+#[cfg(feature = "gui")]
 use egui::Ui;
+use tracing::info;
 
-#[derive(serde::Deserialize, serde::Serialize)]
+use crate::{
+    altitude::AltitudeParameters,
+    arena::{ArenaParameters, ContainmentParameters},
+    chunk::ChunkParameters,
+    crowding::CrowdingParameters,
+    danger::DangerParameters,
+    density::AdaptiveSeparation,
+    dominance::DominanceParameters,
+    energy::EnergyParameters,
+    error::{BoidsError, Result},
+    goal_point::GoalSeekingParameters,
+    group::GroupParameters,
+    integrator::IntegrationParameters,
+    leader::LeaderParameters,
+    lifecycle::LifecycleParameters,
+    migration::MigrationParameters,
+    mixer::BehaviorMixer,
+    obstacle::ObstacleParameters,
+    perception::PerceptionParameters,
+    perch::PerchParameters,
+    predator::{CatchParameters, PredatorParameters},
+    spatial_grid::NeighborSearch,
+    threat::EscapeStrategy,
+    topology::TopologyParameters,
+    units::UnitParameters,
+    wander::WanderParameters,
+    wind::WindParameters,
+};
+
+#[cfg(feature = "gui")]
+use crate::{ghost, steering::SteeringRule, units::Quantity};
+
+/// What happens when boids reach the edge of the simulation area.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum BoundaryMode {
+    /// Boids leaving one edge reappear on the opposite edge
+    #[default]
+    Wrap,
+    /// Boids bounce off the edges like balls off a wall
+    Bounce,
+    /// There are no edges at all, instead the camera follows the flock around
+    Boundless,
+    /// Boids turn back before they reach the edges, and bounce off any they reach anyway
+    Contain,
+}
+
+/// How much a neighbor pushes a boid away for separation, by how close it is.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum SeparationFalloff {
+    /// Every neighbor in the radius pushes the same
+    #[default]
+    Constant,
+    /// Neighbors push less the further away they are, down to nothing at the edge of the radius
+    Linear,
+    /// Neighbors push in inverse proportion to their distance
+    InverseDistance,
+    /// Neighbors push in inverse proportion to the square of their distance, so the closest ones
+    /// all but decide where the boid goes
+    InverseSquare,
+}
+
+impl SeparationFalloff {
+    #[cfg(feature = "gui")]
+    const ALL: [SeparationFalloff; 4] = [
+        SeparationFalloff::Constant,
+        SeparationFalloff::Linear,
+        SeparationFalloff::InverseDistance,
+        SeparationFalloff::InverseSquare,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SeparationFalloff::Constant => "Constant",
+            SeparationFalloff::Linear => "Linear",
+            SeparationFalloff::InverseDistance => "Inverse Distance",
+            SeparationFalloff::InverseSquare => "Inverse Square",
+        }
+    }
+
+    /// How hard a neighbor `distance` away pushes, relative to one at the edge of `radius` for
+    /// the inverse falloffs.
+    pub fn weight(self, distance: f32, radius: f32) -> f32 {
+        // Neighbors right on top of each other would push infinitely hard
+        let distance = distance.max(radius * 0.01);
+        match self {
+            SeparationFalloff::Constant => 1.0,
+            SeparationFalloff::Linear => (1.0 - distance / radius).max(0.0),
+            SeparationFalloff::InverseDistance => radius / distance,
+            SeparationFalloff::InverseSquare => (radius / distance).powi(2),
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    fn draw_selector(&mut self, ui: &mut Ui) {
+        egui::ComboBox::from_id_salt("separation_falloff")
+            .selected_text(self.label())
+            .show_ui(ui, |ui| {
+                for falloff in Self::ALL {
+                    ui.selectable_value(self, falloff, falloff.label());
+                }
+            });
+    }
+}
+
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct BoidsSimulationParameters {
     #[serde(skip)]
@@ -11,6 +118,9 @@ pub struct BoidsSimulationParameters {
     pub max_speed: f32,
     #[serde(default)]
     pub max_force: f32,
+    /// Fastest a boid can change heading, in degrees per second, 0 lets the forces turn it at once
+    #[serde(default)]
+    pub max_turn_rate: f32,
     // Weights
     #[serde(default)]
     pub separation_weight: f32,
@@ -20,11 +130,85 @@ pub struct BoidsSimulationParameters {
     pub avoidance_weight: f32,
     #[serde(default)]
     pub cohesion_weight: f32,
+    /// Mutes and solos on the weights above
+    #[serde(default)]
+    pub mixer: BehaviorMixer,
+    #[serde(default)]
+    pub separation_falloff: SeparationFalloff,
+    #[serde(default)]
+    pub adaptive_separation: AdaptiveSeparation,
+    /// How much of last tick's neighbor centroid boids keep, 0 chases the exact centroid
+    #[serde(default)]
+    pub cohesion_smoothing: f32,
+    /// How much more a threat counts for every unit of speed it closes in on a boid with
+    pub closing_speed_priority: f32,
+    #[serde(default)]
+    pub escape_strategy: EscapeStrategy,
+    /// How far each new boid's genome strays from the flock, 0 makes every boid identical
+    #[serde(default)]
+    pub genome_variation: f32,
     // Radii
     #[serde(default)]
     pub neighbor_radius: f32,
+    /// Separation, alignment and cohesion all use `neighbor_radius` instead of their own radius.
+    /// Left to the struct's default, saves from before the rules had their own radius are linked.
+    pub link_radii: bool,
+    pub separation_radius: f32,
+    pub alignment_radius: f32,
+    pub cohesion_radius: f32,
     #[serde(default)]
     pub avoidance_radius: f32,
+    // World
+    #[serde(default)]
+    pub boundary_mode: BoundaryMode,
+    #[serde(default)]
+    pub arena: ArenaParameters,
+    #[serde(default)]
+    pub chunks: ChunkParameters,
+    #[serde(default)]
+    pub migration: MigrationParameters,
+    #[serde(default)]
+    pub goal_seeking: GoalSeekingParameters,
+    #[serde(default)]
+    pub perching: PerchParameters,
+    #[serde(default)]
+    pub altitude: AltitudeParameters,
+    #[serde(default)]
+    pub energy: EnergyParameters,
+    #[serde(default)]
+    pub predators: PredatorParameters,
+    #[serde(default)]
+    pub catching: CatchParameters,
+    #[serde(default)]
+    pub dominance: DominanceParameters,
+    #[serde(default)]
+    pub groups: GroupParameters,
+    #[serde(default)]
+    pub neighbor_search: NeighborSearch,
+    #[serde(default)]
+    pub topology: TopologyParameters,
+    #[serde(default)]
+    pub wander: WanderParameters,
+    #[serde(default)]
+    pub lifecycle: LifecycleParameters,
+    #[serde(default)]
+    pub leaders: LeaderParameters,
+    #[serde(default)]
+    pub danger: DangerParameters,
+    #[serde(default)]
+    pub wind: WindParameters,
+    #[serde(default)]
+    pub crowding: CrowdingParameters,
+    #[serde(default)]
+    pub perception: PerceptionParameters,
+    #[serde(default)]
+    pub integration: IntegrationParameters,
+    #[serde(default)]
+    pub containment: ContainmentParameters,
+    #[serde(default)]
+    pub obstacle_avoidance: ObstacleParameters,
+    #[serde(default)]
+    pub units: UnitParameters,
 }
 
 impl Default for BoidsSimulationParameters {
@@ -33,18 +217,62 @@ impl Default for BoidsSimulationParameters {
             num_boids: 100,
             max_speed: 5.0,
             max_force: 0.5,
+            max_turn_rate: 0.0,
             separation_weight: 1.0,
             alignment_weight: 1.0,
             avoidance_weight: 1.0,
             cohesion_weight: 1.0,
+            mixer: BehaviorMixer::default(),
+            separation_falloff: SeparationFalloff::default(),
+            adaptive_separation: AdaptiveSeparation::default(),
+            cohesion_smoothing: 0.0,
+            closing_speed_priority: 0.5,
+            escape_strategy: EscapeStrategy::default(),
+            genome_variation: 0.0,
             neighbor_radius: 50.0,
+            link_radii: true,
+            separation_radius: 50.0,
+            alignment_radius: 50.0,
+            cohesion_radius: 50.0,
             avoidance_radius: 75.0,
+            boundary_mode: BoundaryMode::default(),
+            arena: ArenaParameters::default(),
+            chunks: ChunkParameters::default(),
+            migration: MigrationParameters::default(),
+            goal_seeking: GoalSeekingParameters::default(),
+            perching: PerchParameters::default(),
+            altitude: AltitudeParameters::default(),
+            energy: EnergyParameters::default(),
+            predators: PredatorParameters::default(),
+            catching: CatchParameters::default(),
+            dominance: DominanceParameters::default(),
+            groups: GroupParameters::default(),
+            neighbor_search: NeighborSearch::default(),
+            topology: TopologyParameters::default(),
+            wander: WanderParameters::default(),
+            lifecycle: LifecycleParameters::default(),
+            leaders: LeaderParameters::default(),
+            danger: DangerParameters::default(),
+            wind: WindParameters::default(),
+            crowding: CrowdingParameters::default(),
+            perception: PerceptionParameters::default(),
+            integration: IntegrationParameters::default(),
+            containment: ContainmentParameters::default(),
+            obstacle_avoidance: ObstacleParameters::default(),
+            units: UnitParameters::default(),
         }
     }
 }
 
 impl BoidsSimulationParameters {
-    pub fn draw_panel(&mut self, ui: &mut Ui) {
+    /// `world_size` is the size of the simulation area, in world units.
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(
+        &mut self,
+        ui: &mut Ui,
+        world_size: egui::Vec2,
+        rules: &[Box<dyn SteeringRule>],
+    ) {
         ui.label("Number of Boids");
         ui.add(egui::Slider::new(
             &mut self.num_boids,
@@ -54,33 +282,455 @@ impl BoidsSimulationParameters {
         ui.separator();
 
         ui.label("Max Velocity");
-        ui.add(egui::DragValue::new(&mut self.max_speed));
+        self.preview_slider(
+            ui,
+            |params| &mut params.max_speed,
+            0.1..=20.0,
+            Quantity::Speed,
+        );
 
         ui.label("Max Force");
-        ui.add(egui::DragValue::new(&mut self.max_force));
+        self.preview_slider(
+            ui,
+            |params| &mut params.max_force,
+            0.0..=2.0,
+            Quantity::Acceleration,
+        );
 
-        ui.label("Separation Weight");
-        ui.add(egui::DragValue::new(&mut self.separation_weight));
-        ui.label("Cohesion Weight");
-        ui.add(egui::DragValue::new(&mut self.cohesion_weight));
-        ui.label("Alignment Weight");
-        ui.add(egui::DragValue::new(&mut self.alignment_weight));
-        ui.label("Avoidance Weight");
-        ui.add(egui::DragValue::new(&mut self.avoidance_weight));
+        ui.label("Max Turn Rate (°/s)");
+        self.preview_slider(
+            ui,
+            |params| &mut params.max_turn_rate,
+            0.0..=1080.0,
+            Quantity::Plain,
+        )
+        .on_hover_text("0 lets boids reverse at once, lower turns them in smooth arcs like birds");
+
+        ui.label("Behavior Weights");
+        BehaviorMixer::draw_panel(ui, self, rules);
+        ui.label("Separation Falloff");
+        self.separation_falloff.draw_selector(ui);
+        self.adaptive_separation.draw_panel(ui);
+        ui.label("Cohesion Smoothing");
+        self.preview_slider(ui, |params| &mut params.cohesion_smoothing, 0.0..=0.95, Quantity::Plain)
+            .on_hover_text("Ease towards the middle of the neighbors instead of chasing it exactly, which calms dense flocks");
+        ui.label("Threat Closing Speed Priority");
+        ui.add(egui::DragValue::new(&mut self.closing_speed_priority).speed(0.05))
+            .on_hover_text(
+                "With several predators, how much more boids fear the ones rushing at them",
+            );
+        ui.label("Escape Strategy");
+        self.escape_strategy.draw_selector(ui, "escape_strategy");
+        ui.label("Individual Variation");
+        ui.add(egui::Slider::new(&mut self.genome_variation, 0.0..=1.0))
+            .on_hover_text("How much newly spawned boids differ from each other");
 
         ui.separator();
 
         ui.label("Neighbor Radius");
-        ui.add(egui::DragValue::new(&mut self.neighbor_radius));
+        self.preview_slider(
+            ui,
+            |params| &mut params.neighbor_radius,
+            0.0..=200.0,
+            Quantity::Distance,
+        );
+        if ui
+            .checkbox(&mut self.link_radii, "Link Radii")
+            .on_hover_text("Separation, alignment and cohesion all use the neighbor radius")
+            .changed()
+            && !self.link_radii
+        {
+            // Start every rule off where the linked radius was
+            self.separation_radius = self.neighbor_radius;
+            self.alignment_radius = self.neighbor_radius;
+            self.cohesion_radius = self.neighbor_radius;
+        }
+        if !self.link_radii {
+            ui.label("Separation Radius");
+            self.preview_slider(
+                ui,
+                |params| &mut params.separation_radius,
+                0.0..=200.0,
+                Quantity::Distance,
+            );
+            ui.label("Alignment Radius");
+            self.preview_slider(
+                ui,
+                |params| &mut params.alignment_radius,
+                0.0..=200.0,
+                Quantity::Distance,
+            );
+            ui.label("Cohesion Radius");
+            self.preview_slider(
+                ui,
+                |params| &mut params.cohesion_radius,
+                0.0..=200.0,
+                Quantity::Distance,
+            );
+        }
         ui.label("Avoidance Radius");
-        ui.add(egui::DragValue::new(&mut self.avoidance_radius));
+        self.preview_slider(
+            ui,
+            |params| &mut params.avoidance_radius,
+            0.0..=300.0,
+            Quantity::Distance,
+        );
+        ui.label("Neighbor Search");
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.neighbor_search, NeighborSearch::Grid, "Grid");
+            ui.radio_value(
+                &mut self.neighbor_search,
+                NeighborSearch::BruteForce,
+                "Brute Force",
+            )
+            .on_hover_text("Checks every pair of boids, only useful for comparison");
+        });
+        egui::CollapsingHeader::new("Neighbors Per Rule")
+            .show(ui, |ui| self.topology.draw_panel(ui));
+        egui::CollapsingHeader::new("Perception").show(ui, |ui| self.perception.draw_panel(ui));
+        egui::CollapsingHeader::new("Wander").show(ui, |ui| self.wander.draw_panel(ui));
+        egui::CollapsingHeader::new("Lifecycle").show(ui, |ui| self.lifecycle.draw_panel(ui));
+        egui::CollapsingHeader::new("Leaders").show(ui, |ui| self.leaders.draw_panel(ui));
+        egui::CollapsingHeader::new("Visual Crowding").show(ui, |ui| self.crowding.draw_panel(ui));
+
+        ui.separator();
+
+        ui.label("World");
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.boundary_mode, BoundaryMode::Wrap, "Wrap");
+            ui.radio_value(&mut self.boundary_mode, BoundaryMode::Bounce, "Bounce");
+            ui.radio_value(&mut self.boundary_mode, BoundaryMode::Contain, "Contain")
+                .on_hover_text("Boids steer away from the edges instead of crossing them");
+            ui.radio_value(
+                &mut self.boundary_mode,
+                BoundaryMode::Boundless,
+                "Boundless",
+            )
+            .on_hover_text("No edges, the camera follows the flock");
+        });
+        if self.boundary_mode == BoundaryMode::Contain {
+            self.containment.draw_panel(ui);
+        }
+        if self.boundary_mode == BoundaryMode::Boundless {
+            egui::CollapsingHeader::new("Level of Detail")
+                .show(ui, |ui| self.chunks.draw_panel(ui));
+        } else {
+            self.arena.draw_panel(ui, self.boundary_mode);
+        }
+
+        egui::CollapsingHeader::new("Obstacles")
+            .show(ui, |ui| self.obstacle_avoidance.draw_panel(ui));
+        egui::CollapsingHeader::new("Wind").show(ui, |ui| self.wind.draw_panel(ui));
+        egui::CollapsingHeader::new("Migration").show(ui, |ui| self.migration.draw_panel(ui));
+        egui::CollapsingHeader::new("Perching").show(ui, |ui| self.perching.draw_panel(ui));
+        egui::CollapsingHeader::new("Altitude").show(ui, |ui| self.altitude.draw_panel(ui));
+        egui::CollapsingHeader::new("Energy & Thermals").show(ui, |ui| self.energy.draw_panel(ui));
+        egui::CollapsingHeader::new("Predators").show(ui, |ui| {
+            self.predators.draw_panel(ui);
+            ui.separator();
+            self.catching.draw_panel(ui);
+        });
+        egui::CollapsingHeader::new("Danger Memory").show(ui, |ui| self.danger.draw_panel(ui));
+        egui::CollapsingHeader::new("Pecking Order").show(ui, |ui| self.dominance.draw_panel(ui));
+        egui::CollapsingHeader::new("Groups").show(ui, |ui| self.draw_groups_panel(ui));
+        egui::CollapsingHeader::new("Integration").show(ui, |ui| self.integration.draw_panel(ui));
+        egui::CollapsingHeader::new("Units").show(ui, |ui| {
+            self.units.draw_panel(ui, world_size.x, world_size.y)
+        });
 
         if ui.button("Reset").clicked() {
             self.reset();
         }
     }
 
+    /// A slider for one of the parameters that, while hovered, asks for a ghost preview of the
+    /// value under the pointer. Values typed in can go past the end of `range`.
+    #[cfg(feature = "gui")]
+    fn preview_slider(
+        &mut self,
+        ui: &mut Ui,
+        field: fn(&mut Self) -> &mut f32,
+        range: RangeInclusive<f32>,
+        quantity: Quantity,
+    ) -> egui::Response {
+        let units = self.units.clone();
+        let response = ui.add(units.slider(
+            egui::Slider::new(field(self), range.clone()).clamping(egui::SliderClamping::Never),
+            quantity,
+        ));
+        if let Some(value) = ghost::hovered_slider_value(ui, &response, &range) {
+            let mut preview = self.clone();
+            *field(&mut preview) = value;
+            ghost::request(ui.ctx(), preview);
+        }
+        response
+    }
+
+    /// How far away separation looks for neighbors.
+    pub fn separation_radius(&self) -> f32 {
+        if self.link_radii {
+            self.neighbor_radius
+        } else {
+            self.separation_radius
+        }
+    }
+
+    /// How far away alignment looks for neighbors.
+    pub fn alignment_radius(&self) -> f32 {
+        if self.link_radii {
+            self.neighbor_radius
+        } else {
+            self.alignment_radius
+        }
+    }
+
+    /// How far away cohesion looks for neighbors.
+    pub fn cohesion_radius(&self) -> f32 {
+        if self.link_radii {
+            self.neighbor_radius
+        } else {
+            self.cohesion_radius
+        }
+    }
+
+    /// The largest neighbor radius any boid uses, taking group overrides, the radii of the
+    /// separate rules and separation adapting to density into account.
+    pub fn max_neighbor_radius(&self) -> f32 {
+        self.groups
+            .groups
+            .iter()
+            .filter_map(|group| group.overrides.neighbor_radius)
+            .map(|neighbor_radius| self.neighbor_reach(neighbor_radius))
+            .fold(self.neighbor_reach(self.neighbor_radius), f32::max)
+    }
+
+    /// The furthest any rule looks for boids whose neighbor radius is `neighbor_radius`.
+    fn neighbor_reach(&self, neighbor_radius: f32) -> f32 {
+        let rule_radius = |radius| {
+            if self.link_radii {
+                neighbor_radius
+            } else {
+                radius
+            }
+        };
+        [
+            self.adaptive_separation.radius(
+                rule_radius(self.separation_radius),
+                self.adaptive_separation.max_scale,
+            ),
+            // Elders see further for alignment and cohesion
+            rule_radius(self.alignment_radius) * self.lifecycle.max_perception_factor(),
+            rule_radius(self.cohesion_radius) * self.lifecycle.max_perception_factor(),
+        ]
+        .into_iter()
+        .fold(neighbor_radius, f32::max)
+    }
+
+    /// The largest avoidance radius any boid uses, taking group overrides into account.
+    pub fn max_avoidance_radius(&self) -> f32 {
+        self.groups
+            .groups
+            .iter()
+            .filter_map(|group| group.overrides.avoidance_radius)
+            .fold(self.avoidance_radius, f32::max)
+    }
+
+    /// The parameters groups can override, with their labels.
+    pub fn overridable_mut(&mut self) -> [(&'static str, &mut f32); 8] {
+        [
+            ("Max Velocity", &mut self.max_speed),
+            ("Max Force", &mut self.max_force),
+            ("Separation Weight", &mut self.separation_weight),
+            ("Alignment Weight", &mut self.alignment_weight),
+            ("Cohesion Weight", &mut self.cohesion_weight),
+            ("Avoidance Weight", &mut self.avoidance_weight),
+            ("Neighbor Radius", &mut self.neighbor_radius),
+            ("Avoidance Radius", &mut self.avoidance_radius),
+        ]
+    }
+
+    /// Every parameter that can sensibly be blended between two values, with their labels.
+    pub fn blendable_mut(&mut self) -> [(&'static str, &mut f32); 14] {
+        [
+            ("Max Velocity", &mut self.max_speed),
+            ("Max Force", &mut self.max_force),
+            ("Separation Weight", &mut self.separation_weight),
+            ("Alignment Weight", &mut self.alignment_weight),
+            ("Cohesion Weight", &mut self.cohesion_weight),
+            ("Avoidance Weight", &mut self.avoidance_weight),
+            ("Cohesion Smoothing", &mut self.cohesion_smoothing),
+            (
+                "Threat Closing Speed Priority",
+                &mut self.closing_speed_priority,
+            ),
+            ("Individual Variation", &mut self.genome_variation),
+            ("Neighbor Radius", &mut self.neighbor_radius),
+            ("Separation Radius", &mut self.separation_radius),
+            ("Alignment Radius", &mut self.alignment_radius),
+            ("Cohesion Radius", &mut self.cohesion_radius),
+            ("Avoidance Radius", &mut self.avoidance_radius),
+        ]
+    }
+
+    /// `target` with every blendable number `t` of the way there from this one's.
+    pub fn interpolate(&self, target: &Self, t: f32) -> Self {
+        let mut from = self.clone();
+        let mut params = target.clone();
+        for ((_, value), (_, start)) in params.blendable_mut().into_iter().zip(from.blendable_mut())
+        {
+            *value = *start + (*value - *start) * t;
+        }
+        params
+    }
+
+    /// Check that the parameters describe a simulation we can actually run.
+    pub fn validate(&self) -> Result<()> {
+        let values = [
+            ("max speed", self.max_speed),
+            ("max force", self.max_force),
+            ("max turn rate", self.max_turn_rate),
+            ("separation weight", self.separation_weight),
+            ("alignment weight", self.alignment_weight),
+            ("avoidance weight", self.avoidance_weight),
+            ("cohesion weight", self.cohesion_weight),
+            ("cohesion smoothing", self.cohesion_smoothing),
+            ("closing speed priority", self.closing_speed_priority),
+            ("neighbor radius", self.neighbor_radius),
+            ("separation radius", self.separation_radius),
+            ("alignment radius", self.alignment_radius),
+            ("cohesion radius", self.cohesion_radius),
+            ("avoidance radius", self.avoidance_radius),
+            ("kill radius", self.catching.kill_radius),
+            ("respawn delay", self.catching.respawn_seconds),
+            ("time step", self.integration.time_step),
+            ("world scale", self.units.meters_per_unit),
+        ];
+        for (name, value) in values {
+            if !value.is_finite() {
+                return Err(BoidsError::Invalid(format!("{name} must be a number")));
+            }
+        }
+
+        if self.max_speed <= 0.0 {
+            return Err(BoidsError::Invalid("max speed must be positive".to_owned()));
+        }
+        if self.integration.time_step <= 0.0 {
+            return Err(BoidsError::Invalid("time step must be positive".to_owned()));
+        }
+        if self.units.meters_per_unit <= 0.0 {
+            return Err(BoidsError::Invalid(
+                "world scale must be positive".to_owned(),
+            ));
+        }
+        if self.max_force < 0.0 {
+            return Err(BoidsError::Invalid(
+                "max force must not be negative".to_owned(),
+            ));
+        }
+        if self.max_turn_rate < 0.0 {
+            return Err(BoidsError::Invalid(
+                "max turn rate must not be negative".to_owned(),
+            ));
+        }
+        if [
+            self.neighbor_radius,
+            self.separation_radius,
+            self.alignment_radius,
+            self.cohesion_radius,
+            self.avoidance_radius,
+        ]
+        .into_iter()
+        .any(|radius| radius < 0.0)
+        {
+            return Err(BoidsError::Invalid("radii must not be negative".to_owned()));
+        }
+        if !(0.0..1.0).contains(&self.cohesion_smoothing) {
+            return Err(BoidsError::Invalid(
+                "cohesion smoothing must be at least 0 and below 1".to_owned(),
+            ));
+        }
+
+        Ok(())
+    }
+
     pub fn reset(&mut self) {
+        info!("resetting simulation parameters to defaults");
         *self = Self::default();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{BoidsSimulationParameters, SeparationFalloff};
+
+    const RADIUS: f32 = 25.0;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() <= b.abs() * 1e-4
+    }
+
+    #[test]
+    fn falloff_at_the_edge_of_the_radius() {
+        assert!(close(
+            SeparationFalloff::Constant.weight(RADIUS, RADIUS),
+            1.0
+        ));
+        assert!(close(SeparationFalloff::Linear.weight(RADIUS, RADIUS), 0.0));
+        assert!(close(
+            SeparationFalloff::InverseDistance.weight(RADIUS, RADIUS),
+            1.0
+        ));
+        assert!(close(
+            SeparationFalloff::InverseSquare.weight(RADIUS, RADIUS),
+            1.0
+        ));
+        // Past the edge linear doesn't go negative and pull neighbors in
+        assert!(close(
+            SeparationFalloff::Linear.weight(2.0 * RADIUS, RADIUS),
+            0.0
+        ));
+    }
+
+    #[test]
+    fn falloff_on_top_of_each_other() {
+        // Clamped to a hundredth of the radius rather than blowing up
+        assert!(close(SeparationFalloff::Constant.weight(0.0, RADIUS), 1.0));
+        assert!(close(SeparationFalloff::Linear.weight(0.0, RADIUS), 0.99));
+        assert!(close(
+            SeparationFalloff::InverseDistance.weight(0.0, RADIUS),
+            100.0
+        ));
+        assert!(close(
+            SeparationFalloff::InverseSquare.weight(0.0, RADIUS),
+            10_000.0
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "gui")]
+    fn falloff_never_grows_with_distance() {
+        for falloff in SeparationFalloff::ALL {
+            let weights: Vec<f32> = (0..=100)
+                .map(|step| falloff.weight(RADIUS * step as f32 / 100.0, RADIUS))
+                .collect();
+            assert!(
+                weights.windows(2).all(|pair| pair[1] <= pair[0]),
+                "{} grows",
+                falloff.label()
+            );
+        }
+    }
+
+    #[test]
+    fn neighbor_search_reaches_elders_in_overridden_groups() {
+        let mut params = BoidsSimulationParameters::default();
+        params.lifecycle.enabled = true;
+        params.lifecycle.elder_perception = 2.0;
+        params
+            .groups
+            .add("Far Sighted".to_owned())
+            .overrides
+            .neighbor_radius = Some(120.0);
+        assert!(close(params.max_neighbor_radius(), 240.0));
+    }
+}
@@ -0,0 +1,114 @@
+use egui::Ui;
+
+use crate::metrics::FlockMetrics;
+
+/// Something about the flock worth stopping the simulation for.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum BreakpointCondition {
+    #[default]
+    MillingAbove,
+    MillingBelow,
+}
+
+impl BreakpointCondition {
+    const ALL: [BreakpointCondition; 2] = [
+        BreakpointCondition::MillingAbove,
+        BreakpointCondition::MillingBelow,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            BreakpointCondition::MillingAbove => "Milling Above",
+            BreakpointCondition::MillingBelow => "Milling Below",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Breakpoint {
+    pub enabled: bool,
+    pub condition: BreakpointCondition,
+    pub threshold: f32,
+    /// Whether the condition held last tick, so a breakpoint only fires when it starts holding
+    #[serde(skip)]
+    was_met: bool,
+}
+
+impl Breakpoint {
+    fn is_met(&self, metrics: &FlockMetrics) -> bool {
+        match self.condition {
+            BreakpointCondition::MillingAbove => metrics.milling > self.threshold,
+            BreakpointCondition::MillingBelow => metrics.milling < self.threshold,
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} {:.2}",
+            self.condition.label().to_lowercase(),
+            self.threshold
+        )
+    }
+}
+
+/// Conditions that pause the simulation, like breakpoints in a debugger.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Breakpoints {
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl Breakpoints {
+    /// Check every breakpoint against the latest metrics, returning what triggered if one did.
+    pub fn check(&mut self, metrics: &FlockMetrics) -> Option<String> {
+        let mut triggered = None;
+        for breakpoint in &mut self.breakpoints {
+            let met = breakpoint.is_met(metrics);
+            if breakpoint.enabled && met && !breakpoint.was_met && triggered.is_none() {
+                triggered = Some(breakpoint.describe());
+            }
+            breakpoint.was_met = met;
+        }
+        triggered
+    }
+
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        let mut removed = None;
+        for (index, breakpoint) in self.breakpoints.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut breakpoint.enabled, "");
+                egui::ComboBox::from_id_salt(("breakpoint", index))
+                    .selected_text(breakpoint.condition.label())
+                    .show_ui(ui, |ui| {
+                        for condition in BreakpointCondition::ALL {
+                            ui.selectable_value(
+                                &mut breakpoint.condition,
+                                condition,
+                                condition.label(),
+                            );
+                        }
+                    });
+                ui.add(
+                    egui::DragValue::new(&mut breakpoint.threshold)
+                        .speed(0.01)
+                        .range(0.0..=1.0),
+                );
+                if ui.small_button("Remove").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+        if let Some(index) = removed {
+            self.breakpoints.remove(index);
+        }
+
+        if ui.button("Add Breakpoint").clicked() {
+            self.breakpoints.push(Breakpoint {
+                enabled: true,
+                condition: BreakpointCondition::default(),
+                threshold: 0.7,
+                was_met: false,
+            });
+        }
+    }
+}
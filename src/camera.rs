@@ -0,0 +1,128 @@
+#[cfg(feature = "gui")]
+use egui::{Painter, Stroke};
+
+use crate::{
+    boid_store::BoidStore,
+    math::{Color32, Pos2, Rect, Vec2},
+};
+
+/// How quickly the camera catches up with the flock each frame, between 0 (never) and 1 (instantly).
+const FOLLOW_SMOOTHING: f32 = 0.05;
+/// Extra room (in world units) kept around the flock when zooming to fit.
+const FIT_MARGIN: f32 = 200.0;
+const MIN_ZOOM: f32 = 0.05;
+const MAX_ZOOM: f32 = 2.0;
+
+/// Grid spacing in world units before it gets coarsened for zoomed out views.
+const GRID_SPACING: f32 = 100.0;
+/// Grid lines closer together than this on screen get too busy, so we double the spacing.
+const MIN_GRID_SCREEN_SPACING: f32 = 40.0;
+const GRID_COLOR: Color32 = Color32::from_gray(40);
+
+/// Maps between world coordinates (where the boids live) and screen coordinates.
+///
+/// World coordinates are centered on the origin, so the default camera just puts
+/// the world origin in the middle of the simulation area.
+pub struct Camera {
+    pub center: Pos2,
+    pub zoom: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            center: Pos2::ZERO,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl Camera {
+    pub fn world_to_screen(&self, rect: &Rect, world_pos: Pos2) -> Pos2 {
+        rect.center() + (world_pos - self.center) * self.zoom
+    }
+
+    pub fn screen_to_world(&self, rect: &Rect, screen_pos: Pos2) -> Pos2 {
+        self.center + (screen_pos - rect.center()) / self.zoom
+    }
+
+    /// The part of the world currently visible in `rect`.
+    pub fn visible_world_rect(&self, rect: &Rect) -> Rect {
+        Rect::from_center_size(self.center, rect.size() / self.zoom)
+    }
+
+    /// Zoom in by `factor` while keeping the world point under `focus` (in screen coordinates) in place.
+    pub fn zoom_around(&mut self, rect: &Rect, focus: Pos2, factor: f32) {
+        let anchor = self.screen_to_world(rect, focus);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.center = anchor - (focus - rect.center()) / self.zoom;
+    }
+
+    /// Drag the view so the world moves `delta` screen points along with the fingers.
+    pub fn pan(&mut self, delta: Vec2) {
+        self.center -= delta / self.zoom;
+    }
+
+    /// Ease the camera towards the flock's centroid while zooming so the whole flock fits in `rect`.
+    pub fn follow(&mut self, boids: &BoidStore, rect: &Rect) {
+        if boids.is_empty() {
+            return;
+        }
+
+        let mut bounds = Rect::NOTHING;
+        let mut sum = Vec2::ZERO;
+        for boid in boids {
+            bounds.extend_with(boid.position);
+            sum += boid.position.to_vec2();
+        }
+        let centroid = (sum / boids.len() as f32).to_pos2();
+
+        // The centroid isn't the middle of the bounds, so fit whichever side sticks out the most
+        let half_extent = Vec2::new(
+            (bounds.max.x - centroid.x).max(centroid.x - bounds.min.x),
+            (bounds.max.y - centroid.y).max(centroid.y - bounds.min.y),
+        ) + Vec2::splat(FIT_MARGIN);
+        let target_zoom = (rect.width() / (2.0 * half_extent.x))
+            .min(rect.height() / (2.0 * half_extent.y))
+            .clamp(MIN_ZOOM, MAX_ZOOM);
+
+        self.center += (centroid - self.center) * FOLLOW_SMOOTHING;
+        self.zoom += (target_zoom - self.zoom) * FOLLOW_SMOOTHING;
+    }
+
+    /// Draw a faint world-space grid so motion is visible even when the camera tracks the flock.
+    #[cfg(feature = "gui")]
+    pub fn draw_grid(&self, painter: &Painter, rect: &Rect) {
+        let mut spacing = GRID_SPACING;
+        while spacing * self.zoom < MIN_GRID_SCREEN_SPACING {
+            spacing *= 2.0;
+        }
+
+        let visible = self.visible_world_rect(rect);
+        let stroke = Stroke::new(1.0, GRID_COLOR);
+
+        let mut x = (visible.min.x / spacing).floor() * spacing;
+        while x <= visible.max.x {
+            painter.line_segment(
+                [
+                    self.world_to_screen(rect, Pos2::new(x, visible.min.y)),
+                    self.world_to_screen(rect, Pos2::new(x, visible.max.y)),
+                ],
+                stroke,
+            );
+            x += spacing;
+        }
+
+        let mut y = (visible.min.y / spacing).floor() * spacing;
+        while y <= visible.max.y {
+            painter.line_segment(
+                [
+                    self.world_to_screen(rect, Pos2::new(visible.min.x, y)),
+                    self.world_to_screen(rect, Pos2::new(visible.max.x, y)),
+                ],
+                stroke,
+            );
+            y += spacing;
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use egui::{Color32, Pos2, Sense, Stroke, Vec2};
+
+use crate::camera::Camera;
+
+const TIMELINE_SIZE: Vec2 = Vec2::new(360.0, 28.0);
+const KEYFRAME_COLOR: Color32 = Color32::from_rgb(240, 200, 80);
+const PLAYHEAD_COLOR: Color32 = Color32::from_rgb(90, 200, 220);
+/// Keyframes added with no others to go after land this far apart, in seconds.
+const DEFAULT_SPACING: f32 = 3.0;
+
+/// How the camera moves into a keyframe from the one before.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Easing {
+    Linear,
+    /// Starts and ends slowly, for smooth zooms
+    #[default]
+    EaseInOut,
+    /// Stays on the previous keyframe, then cuts
+    Cut,
+}
+
+impl Easing {
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Easing::Cut => 0.0,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Easing::Linear => "Linear",
+            Easing::EaseInOut => "Ease In/Out",
+            Easing::Cut => "Cut",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CameraKeyframe {
+    /// When the camera gets here, in seconds from the start of the path
+    pub seconds: f32,
+    pub center: Pos2,
+    pub zoom: f32,
+    /// Look at the flock's centroid instead of `center`, for follow shots
+    pub follow_flock: bool,
+    pub easing: Easing,
+}
+
+impl CameraKeyframe {
+    fn center(&self, flock_centroid: Option<Pos2>) -> Pos2 {
+        match flock_centroid {
+            Some(centroid) if self.follow_flock => centroid,
+            _ => self.center,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+enum PathState {
+    /// The camera is left alone
+    #[default]
+    Stopped,
+    Playing,
+    /// Holding the camera where the path is, e.g. while scrubbing
+    Paused,
+}
+
+/// Keyframed camera moves, to film slow zooms into the flock or shots following it.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct CameraPath {
+    /// Sorted by time
+    pub keyframes: Vec<CameraKeyframe>,
+    #[serde(skip)]
+    pub open: bool,
+    #[serde(skip)]
+    state: PathState,
+    /// Seconds since the start of the path
+    #[serde(skip)]
+    time: f32,
+}
+
+impl CameraPath {
+    fn duration(&self) -> f32 {
+        self.keyframes
+            .last()
+            .map_or(0.0, |keyframe| keyframe.seconds)
+    }
+
+    /// Move the path along by `dt` seconds of real time.
+    pub fn update(&mut self, dt: f32) {
+        if self.state != PathState::Playing {
+            return;
+        }
+        self.time += dt;
+        if self.time >= self.duration() {
+            self.time = self.duration();
+            self.state = PathState::Paused;
+        }
+    }
+
+    /// Where the path puts the camera right now, or `None` when it isn't in control.
+    pub fn camera(&self, flock_centroid: Option<Pos2>) -> Option<Camera> {
+        if self.state == PathState::Stopped {
+            return None;
+        }
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.seconds <= self.time);
+        let (from, to) = match (
+            self.keyframes.get(next.wrapping_sub(1)),
+            self.keyframes.get(next),
+        ) {
+            (Some(from), Some(to)) => (from, to),
+            (Some(only), None) | (None, Some(only)) => (only, only),
+            (None, None) => return None,
+        };
+
+        let span = to.seconds - from.seconds;
+        let t = if span > 0.0 {
+            to.easing.apply((self.time - from.seconds) / span)
+        } else {
+            1.0
+        };
+        let (start, end) = (from.center(flock_centroid), to.center(flock_centroid));
+        Some(Camera {
+            center: start + (end - start) * t,
+            // Zooming at a steady rate looks steady, unlike changing the zoom by a steady amount
+            zoom: from.zoom * (to.zoom / from.zoom).powf(t),
+        })
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, current: &Camera) {
+        let mut open = self.open;
+        egui::Window::new("Camera Path")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let playing = self.state == PathState::Playing;
+                    if ui
+                        .add_enabled(
+                            !self.keyframes.is_empty(),
+                            egui::Button::new(if playing { "Pause" } else { "Play" }),
+                        )
+                        .clicked()
+                    {
+                        if playing {
+                            self.state = PathState::Paused;
+                        } else {
+                            if self.time >= self.duration() {
+                                self.time = 0.0;
+                            }
+                            self.state = PathState::Playing;
+                        }
+                    }
+                    if ui
+                        .add_enabled(self.state != PathState::Stopped, egui::Button::new("Stop"))
+                        .on_hover_text("Hand the camera back")
+                        .clicked()
+                    {
+                        self.state = PathState::Stopped;
+                        self.time = 0.0;
+                    }
+                    if ui
+                        .button("Add Current View")
+                        .on_hover_text(
+                            "Add a keyframe at the playhead, or after the last one when stopped",
+                        )
+                        .clicked()
+                    {
+                        self.add_keyframe(current);
+                    }
+                    ui.label(format!("{:.1}s / {:.1}s", self.time, self.duration()));
+                });
+
+                self.draw_timeline(ui);
+                ui.separator();
+                self.draw_keyframes(ui);
+            });
+        self.open = open;
+
+        if self.state == PathState::Playing {
+            ctx.request_repaint();
+        }
+    }
+
+    fn add_keyframe(&mut self, current: &Camera) {
+        let seconds = match self.state {
+            PathState::Stopped if !self.keyframes.is_empty() => self.duration() + DEFAULT_SPACING,
+            _ => self.time,
+        };
+        let keyframe = CameraKeyframe {
+            seconds,
+            center: current.center,
+            zoom: current.zoom,
+            follow_flock: false,
+            easing: Easing::default(),
+        };
+        match self
+            .keyframes
+            .iter_mut()
+            .find(|keyframe| keyframe.seconds == seconds)
+        {
+            Some(existing) => *existing = keyframe,
+            None => {
+                let index = self
+                    .keyframes
+                    .partition_point(|keyframe| keyframe.seconds < seconds);
+                self.keyframes.insert(index, keyframe);
+            }
+        }
+    }
+
+    /// Keyframes along a strip with the playhead, click or drag it to scrub.
+    fn draw_timeline(&mut self, ui: &mut egui::Ui) {
+        let (rect, response) = ui.allocate_exact_size(TIMELINE_SIZE, Sense::click_and_drag());
+        let painter = ui.painter_at(rect);
+        painter.rect_stroke(rect, 2.0, Stroke::new(1.0, Color32::DARK_GRAY));
+
+        let duration = self.duration().max(f32::EPSILON);
+        let x = |seconds: f32| rect.left() + rect.width() * (seconds / duration).clamp(0.0, 1.0);
+        for keyframe in &self.keyframes {
+            let center = Pos2::new(x(keyframe.seconds), rect.center().y);
+            painter.add(egui::Shape::convex_polygon(
+                vec![
+                    center + Vec2::new(0.0, -6.0),
+                    center + Vec2::new(6.0, 0.0),
+                    center + Vec2::new(0.0, 6.0),
+                    center + Vec2::new(-6.0, 0.0),
+                ],
+                KEYFRAME_COLOR,
+                Stroke::NONE,
+            ));
+        }
+        if self.state != PathState::Stopped {
+            painter.line_segment(
+                [
+                    Pos2::new(x(self.time), rect.top()),
+                    Pos2::new(x(self.time), rect.bottom()),
+                ],
+                Stroke::new(2.0, PLAYHEAD_COLOR),
+            );
+        }
+
+        if let Some(pointer) = response.interact_pointer_pos() {
+            if !self.keyframes.is_empty() {
+                self.time = (pointer.x - rect.left()) / rect.width() * self.duration();
+                self.time = self.time.clamp(0.0, self.duration());
+                self.state = PathState::Paused;
+            }
+        }
+    }
+
+    fn draw_keyframes(&mut self, ui: &mut egui::Ui) {
+        let mut removed = None;
+        let mut reordered = false;
+        egui::Grid::new("camera_keyframes").show(ui, |ui| {
+            ui.label("Time");
+            ui.label("Center");
+            ui.label("Zoom");
+            ui.label("Follow Flock");
+            ui.label("Easing In");
+            ui.end_row();
+            for (index, keyframe) in self.keyframes.iter_mut().enumerate() {
+                reordered |= ui
+                    .add(
+                        egui::DragValue::new(&mut keyframe.seconds)
+                            .speed(0.1)
+                            .range(0.0..=3600.0)
+                            .suffix("s"),
+                    )
+                    .changed();
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut keyframe.center.x).prefix("x "));
+                    ui.add(egui::DragValue::new(&mut keyframe.center.y).prefix("y "));
+                });
+                ui.add(
+                    egui::DragValue::new(&mut keyframe.zoom)
+                        .speed(0.01)
+                        .range(0.05..=2.0),
+                );
+                ui.checkbox(&mut keyframe.follow_flock, "");
+                egui::ComboBox::from_id_salt(("camera_easing", index))
+                    .selected_text(keyframe.easing.label())
+                    .show_ui(ui, |ui| {
+                        for easing in [Easing::Linear, Easing::EaseInOut, Easing::Cut] {
+                            ui.selectable_value(&mut keyframe.easing, easing, easing.label());
+                        }
+                    });
+                if ui.button("🗑").on_hover_text("Remove").clicked() {
+                    removed = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(index) = removed {
+            self.keyframes.remove(index);
+        }
+        if reordered {
+            self.keyframes
+                .sort_by(|a, b| a.seconds.total_cmp(&b.seconds));
+        }
+        if self.keyframes.is_empty() {
+            self.state = PathState::Stopped;
+        }
+    }
+}
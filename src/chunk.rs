@@ -0,0 +1,119 @@
+#[cfg(feature = "gui")]
+use egui::{Painter, Stroke, Ui};
+
+use crate::{boid_store::BoidRef, math::Pos2};
+
+#[cfg(feature = "gui")]
+use crate::{
+    camera::Camera,
+    math::{Color32, Rect, Vec2},
+};
+
+#[cfg(feature = "gui")]
+const ACTIVE_AREA_COLOR: Color32 = Color32::from_rgb(60, 90, 60);
+
+/// Level of detail simulation for boundless worlds: the world is split into square chunks and
+/// boids far from the action only think every few ticks, coasting in a straight line in between.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ChunkParameters {
+    pub enabled: bool,
+    /// Side length of a chunk in world units
+    pub chunk_size: f32,
+    /// How many chunks around a focus point are simulated every tick
+    pub active_radius: i32,
+    /// Boids outside the active chunks update their forces once every this many ticks
+    pub distant_interval: u64,
+}
+
+impl Default for ChunkParameters {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            chunk_size: 500.0,
+            active_radius: 2,
+            distant_interval: 4,
+        }
+    }
+}
+
+impl ChunkParameters {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Enabled")
+            .on_hover_text("Only simulate boids near the flock and predators every tick");
+        ui.add_enabled_ui(self.enabled, |ui| {
+            ui.label("Chunk Size");
+            ui.add(egui::DragValue::new(&mut self.chunk_size).range(50.0..=f32::MAX));
+            ui.label("Active Radius (chunks)");
+            ui.add(egui::Slider::new(&mut self.active_radius, 0..=10));
+            ui.label("Distant Update Interval (ticks)");
+            ui.add(egui::Slider::new(&mut self.distant_interval, 1..=30));
+        });
+    }
+
+    fn chunk(&self, position: Pos2) -> (i32, i32) {
+        let size = self.chunk_size.max(1.0);
+        (
+            (position.x / size).floor() as i32,
+            (position.y / size).floor() as i32,
+        )
+    }
+
+    /// The chunks boids are fully simulated in, one square of chunks around each focus point.
+    pub fn active_chunks(&self, focus: &[Pos2]) -> ActiveChunks {
+        ActiveChunks {
+            centers: focus.iter().map(|point| self.chunk(*point)).collect(),
+            radius: self.active_radius,
+        }
+    }
+
+    /// Whether `boid` gets its forces updated on `tick`.
+    ///
+    /// Distant boids are spread over the interval by id so they don't all update on the same tick.
+    pub fn should_update(&self, boid: BoidRef<'_>, tick: u64, active: &ActiveChunks) -> bool {
+        active.contains(self.chunk(boid.position))
+            || (tick + boid.id) % self.distant_interval.max(1) == 0
+    }
+
+    /// Outline the chunks simulated every tick.
+    #[cfg(feature = "gui")]
+    pub fn draw_active_area(
+        &self,
+        painter: &Painter,
+        rect: &Rect,
+        camera: &Camera,
+        active: &ActiveChunks,
+    ) {
+        let size = self.chunk_size.max(1.0);
+        for (x, y) in &active.centers {
+            let min = Pos2::new(
+                (x - active.radius) as f32 * size,
+                (y - active.radius) as f32 * size,
+            );
+            let max = min + Vec2::splat((2 * active.radius + 1) as f32 * size);
+            painter.rect_stroke(
+                Rect::from_two_pos(
+                    camera.world_to_screen(rect, min),
+                    camera.world_to_screen(rect, max),
+                ),
+                0.0,
+                Stroke::new(1.0, ACTIVE_AREA_COLOR),
+            );
+        }
+    }
+}
+
+/// The set of chunks within the active radius of a focus point.
+pub struct ActiveChunks {
+    centers: Vec<(i32, i32)>,
+    radius: i32,
+}
+
+impl ActiveChunks {
+    fn contains(&self, (x, y): (i32, i32)) -> bool {
+        self.centers.iter().any(|(center_x, center_y)| {
+            (x - center_x).abs() <= self.radius && (y - center_y).abs() <= self.radius
+        })
+    }
+}
@@ -0,0 +1,109 @@
+use egui::{Color32, Pos2, Rect};
+
+use crate::{
+    boid_store::BoidStore,
+    error::Result,
+    game::TICKS_PER_SECOND,
+    obstacle::{Obstacle, OBSTACLE_COLOR},
+};
+
+/// Only every few ticks go into the GIF, which times its frames in hundredths of a second.
+const TICKS_PER_FRAME: u64 = 3;
+const WIDTH: u16 = 480;
+/// How long a boid is drawn, in pixels of the GIF.
+const BOID_LENGTH: f32 = 4.0;
+const BACKGROUND: u8 = 0;
+const OBSTACLE: u8 = 1;
+const BOID: u8 = 2;
+const PALETTE: [Color32; 3] = [
+    Color32::from_rgb(27, 27, 27),
+    OBSTACLE_COLOR,
+    Color32::from_rgb(230, 230, 230),
+];
+
+/// A clip of the flock encoded into a looping GIF as a replay plays through it.
+pub struct ClipCapture {
+    encoder: gif::Encoder<Vec<u8>>,
+    /// The world area the GIF shows
+    world: Rect,
+    height: u16,
+    /// The obstacles drawn over the background, which every frame starts from
+    background: Vec<u8>,
+    ticks: u64,
+    /// How many ticks the clip runs for
+    length_ticks: u64,
+}
+
+impl ClipCapture {
+    pub fn new(world: Rect, obstacles: &[Obstacle], length_ticks: u64) -> Result<Self> {
+        let height = (f32::from(WIDTH) * world.height() / world.width()).round() as u16;
+        let palette: Vec<u8> = PALETTE
+            .iter()
+            .flat_map(|color| [color.r(), color.g(), color.b()])
+            .collect();
+        let mut encoder = gif::Encoder::new(Vec::new(), WIDTH, height, &palette)?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        let mut capture = Self {
+            encoder,
+            world,
+            height,
+            background: vec![BACKGROUND; usize::from(WIDTH) * usize::from(height)],
+            ticks: 0,
+            length_ticks,
+        };
+        for y in 0..height {
+            for x in 0..WIDTH {
+                let position = capture.to_world(x, y);
+                if obstacles.iter().any(|obstacle| obstacle.contains(position)) {
+                    capture.background[usize::from(y) * usize::from(WIDTH) + usize::from(x)] =
+                        OBSTACLE;
+                }
+            }
+        }
+        Ok(capture)
+    }
+
+    /// Add the flock after a tick of the clip, returns whether the clip is done.
+    pub fn record(&mut self, boids: &BoidStore) -> Result<bool> {
+        self.ticks += 1;
+        if (self.ticks - 1) % TICKS_PER_FRAME == 0 {
+            let mut pixels = self.background.clone();
+            for boid in boids {
+                let head = self.to_pixel(boid.position);
+                let tail = head - boid.velocity.normalized() * BOID_LENGTH;
+                self.draw_line(&mut pixels, tail, head);
+            }
+            let mut frame = gif::Frame::from_indexed_pixels(WIDTH, self.height, pixels, None);
+            frame.delay = (100.0 * TICKS_PER_FRAME as f32 / TICKS_PER_SECOND).round() as u16;
+            self.encoder.write_frame(&frame)?;
+        }
+        Ok(self.ticks >= self.length_ticks)
+    }
+
+    pub fn finish(self) -> Result<Vec<u8>> {
+        Ok(self.encoder.into_inner()?)
+    }
+
+    fn to_world(&self, x: u16, y: u16) -> Pos2 {
+        let scale = self.world.width() / f32::from(WIDTH);
+        self.world.min + egui::vec2(f32::from(x) + 0.5, f32::from(y) + 0.5) * scale
+    }
+
+    fn to_pixel(&self, position: Pos2) -> Pos2 {
+        let scale = f32::from(WIDTH) / self.world.width();
+        Pos2::ZERO + (position - self.world.min) * scale
+    }
+
+    fn draw_line(&self, pixels: &mut [u8], from: Pos2, to: Pos2) {
+        let steps = from.distance(to).ceil().max(1.0) as usize;
+        for step in 0..=steps {
+            let point = from.lerp(to, step as f32 / steps as f32);
+            let (x, y) = (point.x.floor(), point.y.floor());
+            if x < 0.0 || y < 0.0 || x >= f32::from(WIDTH) || y >= f32::from(self.height) {
+                continue;
+            }
+            pixels[y as usize * usize::from(WIDTH) + x as usize] = BOID;
+        }
+    }
+}
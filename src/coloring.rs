@@ -0,0 +1,224 @@
+#[cfg(feature = "gui")]
+use std::f32::consts::{PI, TAU};
+
+#[cfg(feature = "gui")]
+use ecolor::Hsva;
+#[cfg(feature = "gui")]
+use egui::Ui;
+
+use crate::math::Vec2;
+
+#[cfg(feature = "gui")]
+use crate::{
+    boid_store::{BoidRef, BoidStore},
+    boids_simulation::BoidsSimulationParameters,
+    math::{Color32, Pos2},
+    metrics::FlockMetrics,
+    render::GroupPalette,
+};
+
+/// Flock density (boids per square world unit) that gives full saturation.
+#[cfg(feature = "gui")]
+const FULL_DENSITY: f32 = 0.001;
+/// Speed in world units per tick that gives full saturation.
+#[cfg(feature = "gui")]
+const FULL_SPEED: f32 = 5.0;
+/// Boids go from the first color to the last as they age, through the middle.
+#[cfg(feature = "gui")]
+const AGE_RAMP: [Color32; 3] = [
+    Color32::from_rgb(140, 230, 120),
+    Color32::from_rgb(240, 200, 80),
+    Color32::from_rgb(170, 80, 190),
+];
+
+/// What decides a boid's color.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum ColorMode {
+    /// Which steering force pulls on the boid the hardest
+    #[default]
+    DominantForce,
+    /// One palette for the whole flock that slowly drifts with the flock's state
+    FlockState,
+    /// The color of the boid's group, by dominant force for boids without one
+    Group,
+    /// From young to old, over the lifecycle's elder age
+    Age,
+}
+
+/// What the palette's hue follows in [`ColorMode::FlockState`].
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum HueMapping {
+    /// The direction the flock is heading goes around the color wheel
+    #[default]
+    Heading,
+    Polarization,
+    Milling,
+}
+
+/// What the palette's saturation follows in [`ColorMode::FlockState`].
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum SaturationMapping {
+    /// How tightly packed the flock is
+    #[default]
+    Density,
+    Speed,
+    Full,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ColorTheme {
+    pub mode: ColorMode,
+    pub hue: HueMapping,
+    pub saturation: SaturationMapping,
+    /// How quickly the palette catches up with the flock, per second
+    pub response: f32,
+    /// The hue as a point on the color wheel, so easing towards a new hue takes the short way round
+    #[serde(skip)]
+    current_hue: Vec2,
+    #[serde(skip)]
+    current_saturation: f32,
+}
+
+impl Default for ColorTheme {
+    fn default() -> Self {
+        Self {
+            mode: ColorMode::default(),
+            hue: HueMapping::default(),
+            saturation: SaturationMapping::default(),
+            response: 0.3,
+            current_hue: Vec2::X,
+            current_saturation: 1.0,
+        }
+    }
+}
+
+impl ColorTheme {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.label("Boid Colors");
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.mode, ColorMode::DominantForce, "Dominant Force");
+            ui.radio_value(&mut self.mode, ColorMode::FlockState, "Flock State")
+                .on_hover_text("A palette that drifts with the flock, for ambient displays");
+            ui.radio_value(&mut self.mode, ColorMode::Group, "Group");
+            ui.radio_value(&mut self.mode, ColorMode::Age, "Age")
+                .on_hover_text("Green when hatched to purple at the lifecycle's elder age");
+        });
+        if self.mode != ColorMode::FlockState {
+            return;
+        }
+
+        egui::ComboBox::from_label("Hue")
+            .selected_text(match self.hue {
+                HueMapping::Heading => "Heading",
+                HueMapping::Polarization => "Polarization",
+                HueMapping::Milling => "Milling",
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.hue, HueMapping::Heading, "Heading");
+                ui.selectable_value(&mut self.hue, HueMapping::Polarization, "Polarization");
+                ui.selectable_value(&mut self.hue, HueMapping::Milling, "Milling");
+            });
+        egui::ComboBox::from_label("Saturation")
+            .selected_text(match self.saturation {
+                SaturationMapping::Density => "Density",
+                SaturationMapping::Speed => "Speed",
+                SaturationMapping::Full => "Full",
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.saturation, SaturationMapping::Density, "Density");
+                ui.selectable_value(&mut self.saturation, SaturationMapping::Speed, "Speed");
+                ui.selectable_value(&mut self.saturation, SaturationMapping::Full, "Full");
+            });
+        ui.label("Response");
+        ui.add(egui::Slider::new(&mut self.response, 0.01..=2.0).logarithmic(true))
+            .on_hover_text("How quickly the colors follow the flock");
+    }
+
+    /// Ease the palette towards the flock's current state, `dt` seconds after the last update.
+    #[cfg(feature = "gui")]
+    pub fn update(&mut self, boids: &BoidStore, metrics: &FlockMetrics, dt: f32) {
+        if self.mode != ColorMode::FlockState || boids.is_empty() {
+            return;
+        }
+
+        let count = boids.len() as f32;
+        let average_velocity = boids
+            .iter()
+            .fold(Vec2::ZERO, |sum, boid| sum + boid.velocity)
+            / count;
+
+        let target_hue = match self.hue {
+            HueMapping::Heading => average_velocity.angle() / TAU,
+            HueMapping::Polarization => metrics.polarization * 0.8,
+            HueMapping::Milling => metrics.milling * 0.8,
+        };
+        let target_saturation = match self.saturation {
+            SaturationMapping::Density => density(boids) / FULL_DENSITY,
+            SaturationMapping::Speed => average_velocity.length() / FULL_SPEED,
+            SaturationMapping::Full => 1.0,
+        }
+        .clamp(0.2, 1.0);
+
+        let blend = (self.response * dt).clamp(0.0, 1.0);
+        self.current_hue += (Vec2::angled(target_hue * TAU) - self.current_hue) * blend;
+        self.current_saturation += (target_saturation - self.current_saturation) * blend;
+    }
+
+    /// The color to draw `boid` in, its group's palette first if it has one.
+    #[cfg(feature = "gui")]
+    pub fn boid_color(&self, boid: BoidRef<'_>, params: &BoidsSimulationParameters) -> Color32 {
+        let groups = &params.groups;
+        let palette = groups
+            .style(boid.group)
+            .map_or(GroupPalette::Theme, |style| style.palette);
+        let group_color = groups.color(boid.group).unwrap_or(boid.color);
+        match palette {
+            GroupPalette::Theme => self.theme_color(boid, params),
+            GroupPalette::Solid => group_color,
+            GroupPalette::Speed => {
+                let t = (boid.velocity.length() / FULL_SPEED).clamp(0.0, 1.0);
+                Color32::BLACK.lerp_to_gamma(group_color, 0.3 + 0.7 * t)
+            }
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    fn theme_color(&self, boid: BoidRef<'_>, params: &BoidsSimulationParameters) -> Color32 {
+        match self.mode {
+            ColorMode::DominantForce => boid.color,
+            ColorMode::Group => params.groups.color(boid.group).unwrap_or(boid.color),
+            ColorMode::Age => age_color(params.lifecycle.age_fraction(boid)),
+            ColorMode::FlockState => {
+                let hue = (self.current_hue.angle() / TAU).rem_euclid(1.0);
+                Hsva::new(hue, self.current_saturation, 1.0, 1.0).into()
+            }
+        }
+    }
+}
+
+/// Where `fraction` of the way along [`AGE_RAMP`] is.
+#[cfg(feature = "gui")]
+fn age_color(fraction: f32) -> Color32 {
+    let scaled = fraction.clamp(0.0, 1.0) * (AGE_RAMP.len() - 1) as f32;
+    let index = (scaled as usize).min(AGE_RAMP.len() - 2);
+    AGE_RAMP[index].lerp_to_gamma(AGE_RAMP[index + 1], scaled - index as f32)
+}
+
+/// Boids per square world unit within the flock's average distance from its centroid.
+#[cfg(feature = "gui")]
+fn density(boids: &BoidStore) -> f32 {
+    let count = boids.len() as f32;
+    let centroid = Pos2::ZERO
+        + boids
+            .iter()
+            .fold(Vec2::ZERO, |sum, boid| sum + boid.position.to_vec2())
+            / count;
+    let spread = boids
+        .iter()
+        .map(|boid| boid.position.distance(centroid))
+        .sum::<f32>()
+        / count;
+    count / (PI * spread.max(1.0).powi(2))
+}
@@ -0,0 +1,75 @@
+use std::f32::consts::TAU;
+
+#[cfg(feature = "gui")]
+use egui::Ui;
+
+use crate::{boid_store::BoidRef, math::Pos2};
+
+/// Visual crowding: a boid in a dense flock can only take in so many of its neighbors.
+///
+/// The view around a boid is cut into equal slices by direction, and in each slice only the
+/// nearest few neighbors are seen, the ones behind them are hidden. This roughly models the limited
+/// visual throughput of real birds, which keep track of a fixed number of neighbors rather than
+/// everyone within some distance.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct CrowdingParameters {
+    pub enabled: bool,
+    /// How many slices the view around a boid is cut into
+    pub sectors: u32,
+    /// How many neighbors a boid sees in each slice
+    pub visible_per_sector: usize,
+}
+
+impl Default for CrowdingParameters {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sectors: 8,
+            visible_per_sector: 1,
+        }
+    }
+}
+
+impl CrowdingParameters {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Enabled").on_hover_text(
+            "Boids only see their nearest neighbors in each direction, costly in dense flocks",
+        );
+        ui.label("Directions");
+        ui.add(egui::Slider::new(&mut self.sectors, 1..=32))
+            .on_hover_text("How many slices the view around a boid is cut into");
+        ui.label("Visible Per Direction");
+        ui.add(egui::Slider::new(&mut self.visible_per_sector, 1..=10));
+    }
+
+    /// Drop the neighbors a boid at `position` can't see past nearer ones.
+    ///
+    /// The boid itself (or anything right on top of it) stays, the rules skip it anyway.
+    pub fn occlude(&self, position: Pos2, neighbors: &mut Vec<BoidRef<'_>>) {
+        let sectors = self.sectors.max(1);
+        let mut seen: Vec<(u32, f32, BoidRef<'_>)> = neighbors
+            .drain(..)
+            .map(|other| {
+                let offset = other.position - position;
+                let turns = (offset.angle() / TAU).rem_euclid(1.0);
+                let sector = ((turns * sectors as f32) as u32).min(sectors - 1);
+                (sector, offset.length(), other)
+            })
+            .collect();
+        seen.sort_by(|(a, a_distance, _), (b, b_distance, _)| {
+            a.cmp(b).then(a_distance.total_cmp(b_distance))
+        });
+
+        let mut visible = vec![0; sectors as usize];
+        for (sector, distance, other) in seen {
+            if distance == 0.0 {
+                neighbors.push(other);
+            } else if visible[sector as usize] < self.visible_per_sector {
+                visible[sector as usize] += 1;
+                neighbors.push(other);
+            }
+        }
+    }
+}
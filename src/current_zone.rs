@@ -0,0 +1,245 @@
+#[cfg(feature = "gui")]
+use std::f32::consts::TAU;
+
+#[cfg(feature = "gui")]
+use egui::{Painter, Stroke, Ui};
+#[cfg(feature = "gui")]
+use rand::Rng;
+
+use crate::math::{Pos2, Rect, Vec2};
+
+#[cfg(feature = "gui")]
+use crate::{camera::Camera, math::Color32};
+
+#[cfg(feature = "gui")]
+const ZONE_COLOR: Color32 = Color32::from_rgba_premultiplied(40, 90, 140, 60);
+#[cfg(feature = "gui")]
+const ONE_WAY_COLOR: Color32 = Color32::from_rgba_premultiplied(140, 110, 30, 80);
+/// Fraction of the speed against a resisting one-way zone taken off each tick.
+const ONE_WAY_RESISTANCE: f32 = 0.5;
+/// Spacing of the arrows drawn inside a zone, in world units.
+#[cfg(feature = "gui")]
+const ARROW_SPACING: f32 = 60.0;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum ZoneShape {
+    #[default]
+    Rectangle,
+    Circle,
+}
+
+/// Whether a current zone also keeps boids from going against it, like a conveyor or a one-way street.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum OneWay {
+    /// Just a current, boids can still fight their way upstream
+    #[default]
+    Off,
+    /// Boids heading upstream are pushed back hard
+    Resist,
+    /// Boids can't move upstream at all
+    Enforce,
+}
+
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum ZoneArea {
+    Rectangle(Rect),
+    Circle { center: Pos2, radius: f32 },
+}
+
+impl ZoneArea {
+    /// Build an area from a drag: corner to corner for rectangles, center to edge for circles.
+    pub fn from_drag(shape: ZoneShape, start: Pos2, end: Pos2) -> Self {
+        match shape {
+            ZoneShape::Rectangle => ZoneArea::Rectangle(Rect::from_two_pos(start, end)),
+            ZoneShape::Circle => ZoneArea::Circle {
+                center: start,
+                radius: start.distance(end),
+            },
+        }
+    }
+
+    pub fn contains(&self, position: Pos2) -> bool {
+        match *self {
+            ZoneArea::Rectangle(rect) => rect.contains(position),
+            ZoneArea::Circle { center, radius } => center.distance_sq(position) <= radius * radius,
+        }
+    }
+
+    /// A point picked uniformly at random inside the area.
+    #[cfg(feature = "gui")]
+    pub fn random_point(&self, rng: &mut impl Rng) -> Pos2 {
+        match *self {
+            ZoneArea::Rectangle(rect) => Pos2::new(
+                rect.min.x + rng.gen::<f32>() * rect.width(),
+                rect.min.y + rng.gen::<f32>() * rect.height(),
+            ),
+            ZoneArea::Circle { center, radius } => {
+                // The square root keeps points from bunching up in the middle
+                center + Vec2::angled(rng.gen_range(0.0..TAU)) * radius * rng.gen::<f32>().sqrt()
+            }
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn bounding_rect(&self) -> Rect {
+        match *self {
+            ZoneArea::Rectangle(rect) => rect,
+            ZoneArea::Circle { center, radius } => {
+                Rect::from_center_size(center, Vec2::splat(2.0 * radius))
+            }
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw_outline(&self, painter: &Painter, rect: &Rect, camera: &Camera, stroke: Stroke) {
+        match *self {
+            ZoneArea::Rectangle(area) => {
+                let screen_area = Rect::from_two_pos(
+                    camera.world_to_screen(rect, area.min),
+                    camera.world_to_screen(rect, area.max),
+                );
+                painter.rect_stroke(screen_area, 0.0, stroke);
+            }
+            ZoneArea::Circle { center, radius } => {
+                painter.circle_stroke(
+                    camera.world_to_screen(rect, center),
+                    radius * camera.zoom,
+                    stroke,
+                );
+            }
+        }
+    }
+}
+
+/// A region with a steady current pushing boids in one direction, like a river or a gusty valley.
+///
+/// Unlike the flocking forces the current isn't limited by `max_force`, it's the environment moving the boid.
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CurrentZone {
+    pub area: ZoneArea,
+    /// Direction and strength of the current, in world units per tick squared
+    pub force: Vec2,
+    #[serde(default)]
+    pub one_way: OneWay,
+}
+
+impl CurrentZone {
+    /// The force on something at `position` moving at `velocity`.
+    pub fn force_at(&self, position: Pos2, velocity: Vec2) -> Vec2 {
+        if !self.area.contains(position) {
+            return Vec2::ZERO;
+        }
+        match self.upstream_speed(velocity) {
+            Some(upstream) if self.one_way == OneWay::Resist => {
+                self.force + self.force.normalized() * upstream * ONE_WAY_RESISTANCE
+            }
+            _ => self.force,
+        }
+    }
+
+    /// How fast `velocity` goes against the current, `None` for zones without a direction.
+    fn upstream_speed(&self, velocity: Vec2) -> Option<f32> {
+        if self.force == Vec2::ZERO {
+            return None;
+        }
+        Some((-velocity.dot(self.force.normalized())).max(0.0))
+    }
+
+    /// Take away any part of `velocity` that goes against an enforced one-way zone.
+    pub fn constrain(&self, position: Pos2, velocity: &mut Vec2) {
+        if self.one_way != OneWay::Enforce || !self.area.contains(position) {
+            return;
+        }
+        if let Some(upstream) = self.upstream_speed(*velocity) {
+            *velocity += self.force.normalized() * upstream;
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        let color = match self.one_way {
+            OneWay::Off => ZONE_COLOR,
+            OneWay::Resist | OneWay::Enforce => ONE_WAY_COLOR,
+        };
+        self.area
+            .draw_outline(painter, rect, camera, Stroke::new(1.0, color));
+
+        if self.force == Vec2::ZERO {
+            return;
+        }
+
+        let bounds = self.area.bounding_rect();
+        let arrow = self.force.normalized() * ARROW_SPACING * 0.4 * camera.zoom;
+        let stroke = Stroke::new(1.5, color);
+
+        let mut y = bounds.min.y + ARROW_SPACING / 2.0;
+        while y < bounds.max.y {
+            let mut x = bounds.min.x + ARROW_SPACING / 2.0;
+            while x < bounds.max.x {
+                let position = Pos2::new(x, y);
+                if self.area.contains(position) {
+                    let screen_pos = camera.world_to_screen(rect, position);
+                    painter.arrow(screen_pos - arrow / 2.0, arrow, stroke);
+                }
+                x += ARROW_SPACING;
+            }
+            y += ARROW_SPACING;
+        }
+    }
+}
+
+/// Total force from every current zone on something at `position` moving at `velocity`.
+pub fn current_force(zones: &[CurrentZone], position: Pos2, velocity: Vec2) -> Vec2 {
+    zones.iter().fold(Vec2::ZERO, |sum, zone| {
+        sum + zone.force_at(position, velocity)
+    })
+}
+
+/// Settings used for the next current zone placed with the current tool.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct CurrentZoneBrush {
+    pub shape: ZoneShape,
+    /// Direction of the current in degrees, clockwise from the positive x axis
+    pub direction_degrees: f32,
+    pub strength: f32,
+    pub one_way: OneWay,
+}
+
+impl Default for CurrentZoneBrush {
+    fn default() -> Self {
+        Self {
+            shape: ZoneShape::default(),
+            direction_degrees: 0.0,
+            strength: 0.2,
+            one_way: OneWay::default(),
+        }
+    }
+}
+
+impl CurrentZoneBrush {
+    #[cfg(feature = "gui")]
+    pub fn force(&self) -> Vec2 {
+        Vec2::angled(self.direction_degrees.to_radians()) * self.strength
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.shape, ZoneShape::Rectangle, "Rectangle");
+            ui.radio_value(&mut self.shape, ZoneShape::Circle, "Circle");
+        });
+        ui.label("Current Direction");
+        ui.add(egui::Slider::new(&mut self.direction_degrees, 0.0..=360.0).suffix("°"));
+        ui.label("Current Strength");
+        ui.add(egui::DragValue::new(&mut self.strength).speed(0.01));
+        ui.label("One-Way");
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.one_way, OneWay::Off, "Off");
+            ui.radio_value(&mut self.one_way, OneWay::Resist, "Resist")
+                .on_hover_text("Boids flying against the current are pushed back hard");
+            ui.radio_value(&mut self.one_way, OneWay::Enforce, "Enforce")
+                .on_hover_text("Boids can't fly against the current at all");
+        });
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "gui")]
+use egui::{Painter, Ui};
+
+use crate::{
+    boid_store::BoidRef,
+    boids_simulation::BoidsSimulationParameters,
+    math::{Pos2, Rect, Vec2},
+};
+
+#[cfg(feature = "gui")]
+use crate::{
+    boid_store::BoidStore, camera::Camera, game::TICKS_PER_SECOND, math::Color32, threat::Threat,
+};
+
+#[cfg(feature = "gui")]
+const DANGER_COLOR: Color32 = Color32::from_rgb(220, 40, 40);
+/// Cells are forgotten once they've faded below this.
+#[cfg(feature = "gui")]
+const FORGOTTEN: f32 = 0.01;
+
+/// The flock remembering where predators got close, so over time it steers clear of ambush spots.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct DangerParameters {
+    pub enabled: bool,
+    /// Width of the squares the world is divided into to remember danger by
+    pub cell_size: f32,
+    /// Danger left where a boid was caught, 1 is as dangerous as a cell gets
+    pub catch_danger: f32,
+    /// Danger left each tick where a boid has a predator within its avoidance radius
+    pub encounter_danger: f32,
+    /// Seconds for a cell's danger to fade to half
+    pub half_life: f32,
+    /// How hard boids steer away from dangerous cells
+    pub weight: f32,
+    /// Shade the world by how dangerous the flock remembers it being
+    pub show: bool,
+}
+
+impl Default for DangerParameters {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cell_size: 80.0,
+            catch_danger: 1.0,
+            encounter_danger: 0.01,
+            half_life: 60.0,
+            weight: 1.5,
+            show: true,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl DangerParameters {
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Enabled");
+        ui.add_enabled_ui(self.enabled, |ui| {
+            ui.label("Cell Size");
+            ui.add(egui::DragValue::new(&mut self.cell_size).range(10.0..=500.0))
+                .on_hover_text("Changing it forgets everything the flock remembers");
+            ui.label("Catch Danger");
+            ui.add(egui::Slider::new(&mut self.catch_danger, 0.0..=1.0));
+            ui.label("Encounter Danger");
+            ui.add(egui::Slider::new(&mut self.encounter_danger, 0.0..=0.1))
+                .on_hover_text("Left every tick a predator is within a boid's avoidance radius");
+            ui.label("Half Life");
+            ui.add(
+                egui::DragValue::new(&mut self.half_life)
+                    .range(1.0..=3600.0)
+                    .suffix("s"),
+            )
+            .on_hover_text("How long the flock takes to forget half of a spot's danger");
+            ui.label("Danger Weight");
+            ui.add(egui::DragValue::new(&mut self.weight).speed(0.05));
+            ui.checkbox(&mut self.show, "Show Danger Map");
+        });
+    }
+}
+
+/// How dangerous the flock remembers each part of the world being, shared by every boid.
+///
+/// Part of the simulation's state, kept in replay keyframes.
+#[derive(Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct DangerMap {
+    cell_size: f32,
+    /// Only cells with some danger left are kept
+    cells: BTreeMap<(i32, i32), f32>,
+}
+
+impl DangerMap {
+    fn cell(&self, position: Pos2) -> (i32, i32) {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn cell_rect(&self, (x, y): (i32, i32)) -> Rect {
+        Rect::from_min_size(
+            Pos2::new(x as f32, y as f32) * self.cell_size,
+            Vec2::splat(self.cell_size),
+        )
+    }
+
+    /// Add `amount` of danger where `position` is, up to the most a cell holds.
+    #[cfg(feature = "gui")]
+    pub fn deposit(&mut self, position: Pos2, amount: f32) {
+        if self.cell_size <= 0.0 || amount <= 0.0 {
+            return;
+        }
+        let danger = self.cells.entry(self.cell(position)).or_default();
+        *danger = (*danger + amount).min(1.0);
+    }
+
+    /// Fade every cell by a tick's worth of forgetting and remember this tick's close encounters.
+    #[cfg(feature = "gui")]
+    pub fn update(
+        &mut self,
+        boids: &BoidStore,
+        threats: &[Threat],
+        params: &DangerParameters,
+        avoidance_radius: f32,
+    ) {
+        if self.cell_size != params.cell_size {
+            *self = Self {
+                cell_size: params.cell_size,
+                cells: BTreeMap::new(),
+            };
+        }
+
+        let decay = 0.5f32.powf(1.0 / (params.half_life.max(1.0) * TICKS_PER_SECOND));
+        self.cells.retain(|_, danger| {
+            *danger *= decay;
+            *danger >= FORGOTTEN
+        });
+
+        if threats.is_empty() || params.encounter_danger <= 0.0 {
+            return;
+        }
+        let radius_sq = avoidance_radius * avoidance_radius;
+        for boid in boids {
+            if threats
+                .iter()
+                .any(|threat| threat.position.distance_sq(boid.position) <= radius_sq)
+            {
+                self.deposit(boid.position, params.encounter_danger);
+            }
+        }
+    }
+
+    /// The push on `boid` away from the dangerous cells around it.
+    pub fn force(
+        &self,
+        boid: BoidRef<'_>,
+        danger: &DangerParameters,
+        params: &BoidsSimulationParameters,
+    ) -> Vec2 {
+        if !danger.enabled || self.cells.is_empty() {
+            return Vec2::ZERO;
+        }
+        // Boids feel the cells they're next to as well as the one they're in
+        let reach = self.cell_size * 1.5;
+        let (x, y) = self.cell(boid.position);
+        let mut away = Vec2::ZERO;
+        let mut strongest: f32 = 0.0;
+        for cell_x in x - 1..=x + 1 {
+            for cell_y in y - 1..=y + 1 {
+                let Some(&level) = self.cells.get(&(cell_x, cell_y)) else {
+                    continue;
+                };
+                let offset = boid.position - self.cell_rect((cell_x, cell_y)).center();
+                let closeness = 1.0 - offset.length() / reach;
+                if closeness <= 0.0 {
+                    continue;
+                }
+                let felt = level * closeness;
+                away += offset.normalized() * felt;
+                strongest = strongest.max(felt);
+            }
+        }
+        if away == Vec2::ZERO {
+            return Vec2::ZERO;
+        }
+        boid.calculate_heading_force(away.normalized(), danger.weight * strongest, params)
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        for (&cell, &danger) in &self.cells {
+            let area = self.cell_rect(cell);
+            painter.rect_filled(
+                Rect::from_two_pos(
+                    camera.world_to_screen(rect, area.min),
+                    camera.world_to_screen(rect, area.max),
+                ),
+                0.0,
+                DANGER_COLOR.gamma_multiply(0.35 * danger),
+            );
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::time::Duration;
+
+use egui::RichText;
+
+use crate::metrics::Regime;
+
+/// What the debug window shows, gathered by the app every frame.
+pub struct DebugStats {
+    /// Ticks simulated since the app started
+    pub tick: u64,
+    pub boids: usize,
+    /// Checksum of the state after the last tick, if checksums are enabled
+    pub checksum: Option<u64>,
+    /// See [`crate::metrics::FlockMetrics`]
+    pub polarization: f32,
+    pub milling: f32,
+    pub regime: Regime,
+    /// Boids sources spawned and sinks removed per second, `None` without any sources or sinks
+    pub throughput: Option<(f32, f32)>,
+    /// Time working out the forces on the flock took over the last tick
+    pub force_time: Duration,
+    /// Time visual crowding took over the last tick, `None` while it's off
+    pub crowding_time: Option<Duration>,
+    /// Tick at which the running replay stopped matching its recording
+    pub divergence: Option<u64>,
+    /// Approximate memory use by what it's used for, only filled in while the window is open
+    pub memory: Vec<(&'static str, usize)>,
+}
+
+/// A byte count in the most readable unit.
+pub fn format_bytes(bytes: usize) -> String {
+    const KB: f32 = 1024.0;
+    let bytes = bytes as f32;
+    if bytes < KB {
+        format!("{bytes} B")
+    } else if bytes < KB * KB {
+        format!("{:.1} KB", bytes / KB)
+    } else {
+        format!("{:.1} MB", bytes / (KB * KB))
+    }
+}
+
+/// Window with diagnostics about the running simulation.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct DebugPanel {
+    pub open: bool,
+    /// Hash the simulation state every tick, recordings always do this
+    pub show_checksum: bool,
+    /// Work out the forces on one thread even though the build can use them all, to compare
+    #[cfg(feature = "parallel")]
+    pub single_threaded_forces: bool,
+}
+
+impl DebugPanel {
+    /// Show the window, with `extra` adding tools that need more than the stats at the bottom.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        stats: &DebugStats,
+        extra: impl FnOnce(&mut egui::Ui),
+    ) {
+        let mut open = self.open;
+        egui::Window::new("Debug")
+            .open(&mut open)
+            .default_width(250.0)
+            .show(ctx, |ui| {
+                self.draw(ui, stats);
+                ui.separator();
+                extra(ui);
+            });
+        self.open = open;
+    }
+
+    /// Whether to spread working out the forces over every core.
+    pub fn parallel_forces(&self) -> bool {
+        #[cfg(feature = "parallel")]
+        return !self.single_threaded_forces;
+        #[cfg(not(feature = "parallel"))]
+        false
+    }
+
+    fn draw(&mut self, ui: &mut egui::Ui, stats: &DebugStats) {
+        egui::Grid::new("debug_stats").show(ui, |ui| {
+            ui.label("Tick");
+            ui.label(RichText::new(stats.tick.to_string()).monospace());
+            ui.end_row();
+
+            ui.label("Boids");
+            ui.label(RichText::new(stats.boids.to_string()).monospace());
+            ui.end_row();
+
+            ui.label("Regime");
+            ui.label(stats.regime.label());
+            ui.end_row();
+
+            ui.label("Polarization");
+            ui.label(RichText::new(format!("{:.2}", stats.polarization)).monospace());
+            ui.end_row();
+
+            ui.label("Milling");
+            ui.label(RichText::new(format!("{:.2}", stats.milling)).monospace());
+            ui.end_row();
+
+            if let Some((spawned, removed)) = stats.throughput {
+                ui.label("Spawned");
+                ui.label(RichText::new(format!("{spawned:.1}/s")).monospace());
+                ui.end_row();
+
+                ui.label("Removed");
+                ui.label(RichText::new(format!("{removed:.1}/s")).monospace());
+                ui.end_row();
+            }
+
+            ui.label("Forces");
+            ui.label(
+                RichText::new(format!(
+                    "{:.2} ms/tick",
+                    stats.force_time.as_secs_f64() * 1e3
+                ))
+                .monospace(),
+            );
+            ui.end_row();
+
+            if let Some(time) = stats.crowding_time {
+                ui.label("Visual Crowding");
+                ui.label(
+                    RichText::new(format!("{:.0} µs/tick", time.as_secs_f64() * 1e6)).monospace(),
+                );
+                ui.end_row();
+            }
+
+            ui.label("Checksum");
+            match stats.checksum {
+                Some(checksum) => ui.label(RichText::new(format!("{checksum:016x}")).monospace()),
+                None => ui.label(RichText::new("-").weak()),
+            };
+            ui.end_row();
+        });
+
+        ui.separator();
+        ui.label("Memory (approximate)");
+        egui::Grid::new("debug_memory").show(ui, |ui| {
+            for (name, bytes) in &stats.memory {
+                ui.label(*name);
+                ui.label(RichText::new(format_bytes(*bytes)).monospace());
+                ui.end_row();
+            }
+            ui.strong("Total");
+            let total = stats.memory.iter().map(|(_, bytes)| bytes).sum();
+            ui.label(RichText::new(format_bytes(total)).monospace());
+            ui.end_row();
+        });
+
+        ui.separator();
+        ui.checkbox(&mut self.show_checksum, "Checksum Every Tick")
+            .on_hover_text(
+                "Compare it between machines or builds to spot where they stop agreeing",
+            );
+        #[cfg(feature = "parallel")]
+        ui.checkbox(&mut self.single_threaded_forces, "Single Threaded Forces")
+            .on_hover_text("Work out the forces on one core instead of all of them, to compare the time they take");
+
+        if let Some(tick) = stats.divergence {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("Replay diverged from the recording at tick {tick}"),
+            );
+        }
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(feature = "gui")]
+use egui::Ui;
+
+use crate::{boid_store::BoidStore, spatial_grid::SpatialGrid};
+
+/// Shrinks the separation radius where the flock is crowded and grows it where it's sparse, so a
+/// dense flock doesn't lock up into a lattice.
+///
+/// Boids are ranked by how crowded they are rather than scaled by the raw count, which spreads
+/// the scales evenly between the bounds however big the flock is.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct AdaptiveSeparation {
+    pub enabled: bool,
+    /// Scale on the separation radius of the most crowded boid
+    pub min_scale: f32,
+    /// Scale on the separation radius of the least crowded boid
+    pub max_scale: f32,
+}
+
+impl Default for AdaptiveSeparation {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_scale: 0.5,
+            max_scale: 1.5,
+        }
+    }
+}
+
+impl AdaptiveSeparation {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Adapt To Density")
+            .on_hover_text(
+                "Crowded boids keep less distance and lonely ones more, so dense flocks stay fluid",
+            );
+        if self.enabled {
+            ui.label("Scale Bounds");
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::DragValue::new(&mut self.min_scale)
+                        .speed(0.01)
+                        .range(0.0..=self.max_scale)
+                        .prefix("min "),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.max_scale)
+                        .speed(0.01)
+                        .range(self.min_scale..=10.0)
+                        .prefix("max "),
+                );
+            });
+        }
+    }
+
+    /// The separation radius of a boid with `scale`, `radius` while adapting is off.
+    pub fn radius(&self, radius: f32, scale: f32) -> f32 {
+        if self.enabled {
+            radius * scale
+        } else {
+            radius
+        }
+    }
+
+    /// Give every boid its separation scale from how many boids share the grid cells around it.
+    pub fn update(&self, boids: &mut BoidStore, radius: f32) {
+        let grid = SpatialGrid::new(boids, radius);
+        let mut nearby = Vec::new();
+        let densities: Vec<usize> = boids
+            .positions()
+            .iter()
+            .map(|position| {
+                grid.candidates(*position, radius, &mut nearby);
+                nearby.len()
+            })
+            .collect();
+        let mut sorted = densities.clone();
+        sorted.sort_unstable();
+
+        let last = (sorted.len() as f32 - 1.0).max(1.0);
+        for (mut boid, density) in boids.iter_mut().zip(densities) {
+            // Where the boid falls in the flock's crowding, boids tied share the middle of their run
+            let below = sorted.partition_point(|other| *other < density);
+            let tied = sorted.partition_point(|other| *other <= density) - below;
+            let percentile = (below as f32 + (tied - 1) as f32 / 2.0) / last;
+            boid.separation_scale =
+                self.max_scale + (self.min_scale - self.max_scale) * percentile.clamp(0.0, 1.0);
+        }
+    }
+}
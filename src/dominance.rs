@@ -0,0 +1,80 @@
+#[cfg(feature = "gui")]
+use egui::Ui;
+
+use crate::{boid_store::BoidRef, math::Color32};
+
+/// Brightness of the lowest ranked boid, the highest ranked is drawn at full brightness.
+const MIN_BRIGHTNESS: f32 = 0.35;
+
+/// A pecking order inside the flock.
+///
+/// Every boid has a rank between 0 (bottom) and 1 (top). Boids keep further out of the way of those
+/// that outrank them and the top of the hierarchy pulls harder towards the middle, so the dominant
+/// boids end up in the safe center with the rest spread around them.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct DominanceParameters {
+    pub enabled: bool,
+    /// How much harder a boid separates from a neighbor that fully outranks it
+    pub deference: f32,
+    /// How much more the top ranked boids are drawn to the middle, and the bottom ones less
+    pub center_pull: f32,
+    /// Draw higher ranked boids brighter
+    pub show_rank: bool,
+}
+
+impl Default for DominanceParameters {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            deference: 2.0,
+            center_pull: 0.5,
+            show_rank: true,
+        }
+    }
+}
+
+impl DominanceParameters {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Enabled");
+        ui.label("Deference");
+        ui.add(
+            egui::DragValue::new(&mut self.deference)
+                .speed(0.05)
+                .range(0.0..=10.0),
+        )
+        .on_hover_text("How much more room boids give to the ones that outrank them");
+        ui.label("Center Pull");
+        ui.add(egui::Slider::new(&mut self.center_pull, 0.0..=1.0))
+            .on_hover_text("How much more the top ranked boids crowd into the middle of the flock");
+        ui.checkbox(&mut self.show_rank, "Show Rank As Brightness");
+    }
+
+    /// How much `boid` separating from `other` counts.
+    pub fn separation_weight(&self, boid: BoidRef<'_>, other: BoidRef<'_>) -> f32 {
+        if self.enabled {
+            1.0 + self.deference * (other.rank - boid.rank).max(0.0)
+        } else {
+            1.0
+        }
+    }
+
+    /// Multiplier on `boid`'s cohesion.
+    pub fn cohesion_factor(&self, boid: BoidRef<'_>) -> f32 {
+        if self.enabled {
+            1.0 + self.center_pull * (2.0 * boid.rank - 1.0)
+        } else {
+            1.0
+        }
+    }
+
+    /// `color` dimmed the lower `boid` is in the pecking order.
+    pub fn shade(&self, boid: BoidRef<'_>, color: Color32) -> Color32 {
+        if self.enabled && self.show_rank {
+            color.gamma_multiply(MIN_BRIGHTNESS + (1.0 - MIN_BRIGHTNESS) * boid.rank)
+        } else {
+            color
+        }
+    }
+}
@@ -0,0 +1,153 @@
+#[cfg(feature = "gui")]
+use egui::{Painter, Stroke, Ui};
+
+use crate::{boid_store::BoidRef, math::Pos2};
+
+#[cfg(feature = "gui")]
+use crate::{
+    boid_store::BoidStore,
+    camera::Camera,
+    math::{Color32, Rect},
+};
+
+#[cfg(feature = "gui")]
+const THERMAL_COLOR: Color32 = Color32::from_rgba_premultiplied(120, 60, 10, 40);
+/// Boids circle a thermal at this fraction of its radius.
+const ORBIT_RADIUS_FRACTION: f32 = 0.6;
+/// Even a completely exhausted boid can still fly at this fraction of the max speed.
+const MIN_SPEED_FRACTION: f32 = 0.3;
+
+/// A rising column of warm air boids can circle in to regain energy.
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Thermal {
+    pub center: Pos2,
+    pub radius: f32,
+}
+
+impl Thermal {
+    pub fn contains(&self, position: Pos2) -> bool {
+        self.center.distance_sq(position) <= self.radius * self.radius
+    }
+
+    pub fn orbit_radius(&self) -> f32 {
+        self.radius * ORBIT_RADIUS_FRACTION
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        let center = camera.world_to_screen(rect, self.center);
+        painter.circle(
+            center,
+            self.radius * camera.zoom,
+            THERMAL_COLOR,
+            Stroke::new(1.0, THERMAL_COLOR.gamma_multiply(2.0)),
+        );
+        painter.circle_stroke(
+            center,
+            self.orbit_radius() * camera.zoom,
+            Stroke::new(1.0, THERMAL_COLOR),
+        );
+    }
+}
+
+/// Flying costs energy; thermals give it back.
+///
+/// Boids low on energy go looking for a thermal, circle in it until they are topped up and then leave,
+/// which gives the spiralling "kettles" you see with vultures and gliders.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct EnergyParameters {
+    pub enabled: bool,
+    /// Energy used per tick when flying at max speed, energy goes from 0 to 1
+    pub drain: f32,
+    /// Energy gained per tick while inside a thermal
+    pub thermal_lift: f32,
+    /// Below this energy boids go looking for a thermal
+    pub low_energy: f32,
+    /// Boids leave the thermal once they have this much energy again
+    pub full_energy: f32,
+    /// How strongly boids looking for energy seek and circle thermals
+    pub thermal_weight: f32,
+}
+
+impl Default for EnergyParameters {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            drain: 0.0005,
+            thermal_lift: 0.004,
+            low_energy: 0.3,
+            full_energy: 0.95,
+            thermal_weight: 2.0,
+        }
+    }
+}
+
+impl EnergyParameters {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Enabled");
+        ui.label("Energy Drain");
+        ui.add(egui::DragValue::new(&mut self.drain).speed(0.0001));
+        ui.label("Thermal Lift");
+        ui.add(egui::DragValue::new(&mut self.thermal_lift).speed(0.0001));
+        ui.label("Look For Thermals Below");
+        ui.add(egui::Slider::new(&mut self.low_energy, 0.0..=1.0));
+        ui.label("Leave Thermals Above");
+        ui.add(egui::Slider::new(&mut self.full_energy, 0.0..=1.0));
+        ui.label("Thermal Weight");
+        ui.add(egui::DragValue::new(&mut self.thermal_weight).speed(0.05));
+    }
+
+    /// Fraction of the max speed a boid can fly at with its current energy.
+    pub fn speed_factor(&self, boid: BoidRef<'_>) -> f32 {
+        if self.enabled {
+            MIN_SPEED_FRACTION + (1.0 - MIN_SPEED_FRACTION) * boid.energy
+        } else {
+            1.0
+        }
+    }
+}
+
+pub fn thermal_containing(thermals: &[Thermal], position: Pos2) -> Option<&Thermal> {
+    thermals.iter().find(|thermal| thermal.contains(position))
+}
+
+pub fn nearest_thermal(thermals: &[Thermal], position: Pos2) -> Option<&Thermal> {
+    thermals.iter().min_by(|a, b| {
+        a.center
+            .distance_sq(position)
+            .total_cmp(&b.center.distance_sq(position))
+    })
+}
+
+/// Drain energy from flying boids, top it up in thermals and decide who needs to go looking for lift.
+#[cfg(feature = "gui")]
+pub fn update_energy(
+    boids: &mut BoidStore,
+    thermals: &[Thermal],
+    params: &EnergyParameters,
+    max_speed: f32,
+) {
+    for mut boid in boids {
+        if boid.resting.is_some() {
+            // Resting is the other way to recover
+            boid.energy = (boid.energy + params.thermal_lift).min(1.0);
+            boid.seeking_thermal = false;
+            continue;
+        }
+
+        let effort = boid.velocity.length() / max_speed.max(f32::EPSILON);
+        boid.energy -= params.drain * effort;
+        if thermal_containing(thermals, *boid.position).is_some() {
+            boid.energy += params.thermal_lift;
+        }
+        boid.energy = boid.energy.clamp(0.0, 1.0);
+
+        if boid.energy < params.low_energy {
+            boid.seeking_thermal = true;
+        } else if boid.energy >= params.full_energy {
+            boid.seeking_thermal = false;
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::fmt;
+
+/// Errors produced by the parts of the app that talk to the outside world (files, saved state, ...).
+///
+/// Anything that can fail should surface one of these to the user through the toasts rather than panicking.
+#[derive(Debug)]
+pub enum BoidsError {
+    Io(std::io::Error),
+    /// The data could be read but not decoded, `format` names what we tried to decode it as.
+    Parse {
+        format: &'static str,
+        message: String,
+    },
+    /// The data decoded fine but doesn't describe something we can use.
+    Invalid(String),
+    /// Hosting, joining or staying connected to a shared simulation failed.
+    Network(String),
+}
+
+pub type Result<T, E = BoidsError> = std::result::Result<T, E>;
+
+impl fmt::Display for BoidsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoidsError::Io(err) => write!(f, "I/O error: {err}"),
+            BoidsError::Parse { format, message } => {
+                write!(f, "Could not parse {format}: {message}")
+            }
+            BoidsError::Invalid(message) => write!(f, "{message}"),
+            BoidsError::Network(message) => write!(f, "Network error: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for BoidsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BoidsError::Io(err) => Some(err),
+            BoidsError::Parse { .. } | BoidsError::Invalid(_) | BoidsError::Network(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for BoidsError {
+    fn from(err: std::io::Error) -> Self {
+        BoidsError::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for BoidsError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        BoidsError::Parse {
+            format: "RON",
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for BoidsError {
+    fn from(err: serde_json::Error) -> Self {
+        BoidsError::Parse {
+            format: "JSON",
+            message: err.to_string(),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<gif::EncodingError> for BoidsError {
+    fn from(err: gif::EncodingError) -> Self {
+        match err {
+            gif::EncodingError::Io(err) => BoidsError::Io(err),
+            gif::EncodingError::Format(err) => {
+                BoidsError::Invalid(format!("Could not encode GIF: {err}"))
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::VecDeque;
+
+use egui::{Color32, Pos2, Sense, Shape, Stroke, Vec2};
+
+use crate::{
+    arena::ArenaShape,
+    boids_simulation::{BoidsSimulationParameters, BoundaryMode},
+    game::TICKS_PER_SECOND,
+    group::GroupGoal,
+};
+
+/// How much of the lane order's history the plot keeps, in ticks.
+const PLOT_TICKS: usize = 60 * TICKS_PER_SECOND as usize;
+const PLOT_SIZE: Vec2 = Vec2::new(320.0, 120.0);
+const PLOT_COLOR: Color32 = Color32::from_rgb(90, 200, 220);
+
+/// Two groups flying opposite ways down a corridor, which sort themselves into lanes.
+///
+/// Returns the parameters along with the ids of the eastbound and westbound groups.
+pub fn lane_formation() -> (BoidsSimulationParameters, [u32; 2]) {
+    let mut params = BoidsSimulationParameters {
+        num_boids: 200,
+        boundary_mode: BoundaryMode::Bounce,
+        ..Default::default()
+    };
+    params.arena.shape = ArenaShape::Corridor;
+    params.arena.corridor_width = 0.5;
+
+    let mut ids = [0; 2];
+    for (id, (name, heading_degrees)) in ids
+        .iter_mut()
+        .zip([("Eastbound", 0.0), ("Westbound", 180.0)])
+    {
+        let group = params.groups.add(name.to_owned());
+        group.goal = Some(GroupGoal {
+            heading_degrees,
+            weight: 1.0,
+        });
+        // Boids going the other way are in the way rather than company
+        group.overrides.alignment_weight = Some(0.5);
+        *id = group.id;
+    }
+    (params, ids)
+}
+
+/// A plot of how well the flock has formed lanes over the last minute.
+#[derive(Default)]
+pub struct LaneOrderPlot {
+    pub open: bool,
+    /// Oldest first, one sample per tick
+    history: VecDeque<f32>,
+}
+
+impl LaneOrderPlot {
+    pub fn record(&mut self, lane_order: f32) {
+        if self.history.len() == PLOT_TICKS {
+            self.history.pop_front();
+        }
+        self.history.push_back(lane_order);
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut open = self.open;
+        egui::Window::new("Lane Order")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let current = self.history.back().copied().unwrap_or(0.0);
+                ui.label(format!("Lane order {current:.2}"))
+                    .on_hover_text("0 when boids going either way are mixed together, 1 when every boid only has neighbors going its way");
+
+                let (rect, _) = ui.allocate_exact_size(PLOT_SIZE, Sense::hover());
+                let painter = ui.painter_at(rect);
+                painter.rect_stroke(rect, 0.0, Stroke::new(1.0, Color32::DARK_GRAY));
+                let point = |index: usize, value: f32| {
+                    Pos2::new(
+                        rect.left() + rect.width() * index as f32 / (PLOT_TICKS - 1) as f32,
+                        rect.bottom() - rect.height() * value.clamp(0.0, 1.0),
+                    )
+                };
+                painter.add(Shape::line(
+                    self.history
+                        .iter()
+                        .enumerate()
+                        .map(|(index, value)| point(index, *value))
+                        .collect(),
+                    Stroke::new(1.5, PLOT_COLOR),
+                ));
+                ui.label(format!("Last {} seconds", PLOT_TICKS as f32 / TICKS_PER_SECOND));
+            });
+        self.open = open;
+    }
+}
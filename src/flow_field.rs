@@ -0,0 +1,293 @@
+use egui::{Color32, Painter, Pos2, Rect, Shape, Stroke, Ui, Vec2};
+use rand::Rng;
+
+use crate::{boid_store::BoidStore, camera::Camera, game::TICKS_PER_SECOND};
+
+const FLOW_COLOR: Color32 = Color32::from_rgb(110, 200, 255);
+/// Most cells the field is binned into, cells grow when zoomed far out to stay under it.
+const MAX_CELLS: usize = 20_000;
+/// A streamline starts every few cells.
+const STREAMLINE_SEED_SPACING: usize = 2;
+const STREAMLINE_STEPS: usize = 24;
+/// Seconds a speck of dye drifts before fading away and starting over somewhere else.
+const DYE_LIFETIME: f32 = 4.0;
+
+/// What the flock's velocity field is drawn as.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum FlowStyle {
+    /// Lines following the flow from evenly spread starting points
+    #[default]
+    Streamlines,
+    /// Specks carried along by the flow
+    Dye,
+}
+
+/// The flock drawn as a fluid: boid velocities averaged over a grid, smoothed, and shown as
+/// streamlines or dye drifting along with them.
+///
+/// Like particles, purely visual, it never touches the simulation or its random numbers.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct FlowField {
+    pub enabled: bool,
+    pub style: FlowStyle,
+    /// World units per cell velocities are averaged over
+    pub cell_size: f32,
+    /// How many times each cell is blurred with the ones around it
+    pub smoothing: usize,
+    pub dye_count: usize,
+    #[serde(skip)]
+    grid: VelocityGrid,
+    #[serde(skip)]
+    dye: Vec<Dye>,
+}
+
+impl Default for FlowField {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            style: FlowStyle::default(),
+            cell_size: 30.0,
+            smoothing: 2,
+            dye_count: 1500,
+            grid: VelocityGrid::default(),
+            dye: Vec::new(),
+        }
+    }
+}
+
+struct Dye {
+    position: Pos2,
+    /// Seconds
+    age: f32,
+}
+
+impl FlowField {
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Show Flow")
+            .on_hover_text("Draw the flock's average velocity as if it were a fluid");
+        ui.add_enabled_ui(self.enabled, |ui| {
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.style, FlowStyle::Streamlines, "Streamlines");
+                ui.radio_value(&mut self.style, FlowStyle::Dye, "Dye");
+            });
+            ui.label("Cell Size");
+            ui.add(egui::DragValue::new(&mut self.cell_size).range(5.0..=200.0))
+                .on_hover_text("World units boid velocities are averaged over");
+            ui.label("Smoothing");
+            ui.add(egui::Slider::new(&mut self.smoothing, 0..=8))
+                .on_hover_text("Spreads the flow into the empty space around the flock");
+            if self.style == FlowStyle::Dye {
+                ui.label("Dye Specks");
+                ui.add(egui::DragValue::new(&mut self.dye_count).range(0..=10_000));
+            }
+        });
+        if !self.enabled {
+            self.grid = VelocityGrid::default();
+            self.dye.clear();
+        }
+    }
+
+    /// Average `boids` into the field over `area` of the world and drift the dye along it for a
+    /// frame `dt` seconds long.
+    pub fn update(&mut self, boids: &BoidStore, area: Rect, dt: f32) {
+        if !self.enabled {
+            return;
+        }
+        self.grid = VelocityGrid::new(boids, area, self.cell_size, self.smoothing);
+        if self.style != FlowStyle::Dye {
+            self.dye.clear();
+            return;
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut random_position = || {
+            Pos2::new(
+                rng.gen_range(area.min.x..=area.max.x),
+                rng.gen_range(area.min.y..=area.max.y),
+            )
+        };
+        self.dye.truncate(self.dye_count);
+        while self.dye.len() < self.dye_count {
+            self.dye.push(Dye {
+                position: random_position(),
+                // Start part way through their lives so they don't all fade at once
+                age: DYE_LIFETIME * self.dye.len() as f32 / self.dye_count as f32,
+            });
+        }
+        for dye in &mut self.dye {
+            dye.age += dt;
+            dye.position += self.grid.sample(dye.position) * TICKS_PER_SECOND * dt;
+            if dye.age >= DYE_LIFETIME || !area.contains(dye.position) {
+                *dye = Dye {
+                    position: random_position(),
+                    age: 0.0,
+                };
+            }
+        }
+    }
+
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        if !self.enabled || self.grid.cells.is_empty() {
+            return;
+        }
+        let max_speed = self.grid.max_speed.max(f32::EPSILON);
+        match self.style {
+            FlowStyle::Streamlines => {
+                let cell_size = self.grid.cell_size;
+                for row in (0..self.grid.rows).step_by(STREAMLINE_SEED_SPACING) {
+                    for column in (0..self.grid.columns).step_by(STREAMLINE_SEED_SPACING) {
+                        let start = self.grid.cell_center(column, row);
+                        let speed = self.grid.sample(start).length() / max_speed;
+                        if speed < 0.05 {
+                            continue;
+                        }
+                        let mut position = start;
+                        let mut points = vec![camera.world_to_screen(rect, position)];
+                        for _ in 0..STREAMLINE_STEPS {
+                            let velocity = self.grid.sample(position);
+                            if velocity == Vec2::ZERO {
+                                break;
+                            }
+                            position += velocity.normalized() * cell_size / 2.0;
+                            points.push(camera.world_to_screen(rect, position));
+                        }
+                        let color = FLOW_COLOR.gamma_multiply(0.2 + 0.6 * speed.min(1.0));
+                        painter.circle_filled(points[0], 1.5, color);
+                        painter.add(Shape::line(points, Stroke::new(1.0, color)));
+                    }
+                }
+            }
+            FlowStyle::Dye => {
+                for dye in &self.dye {
+                    // Fade in and out over the speck's life so none of them pop
+                    let life = dye.age / DYE_LIFETIME;
+                    let fade = (life * (1.0 - life) * 4.0).min(1.0);
+                    let speed = self.grid.sample(dye.position).length() / max_speed;
+                    if speed == 0.0 {
+                        continue;
+                    }
+                    painter.circle_filled(
+                        camera.world_to_screen(rect, dye.position),
+                        1.5,
+                        FLOW_COLOR.gamma_multiply(fade * (0.3 + 0.7 * speed.min(1.0))),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Boid velocities averaged over the cells of a grid laid over part of the world.
+struct VelocityGrid {
+    area: Rect,
+    cell_size: f32,
+    columns: usize,
+    rows: usize,
+    /// Row by row, zero where there's no flow
+    cells: Vec<Vec2>,
+    /// The fastest any cell flows
+    max_speed: f32,
+}
+
+impl Default for VelocityGrid {
+    fn default() -> Self {
+        Self {
+            area: Rect::NOTHING,
+            cell_size: 1.0,
+            columns: 0,
+            rows: 0,
+            cells: Vec::new(),
+            max_speed: 0.0,
+        }
+    }
+}
+
+impl VelocityGrid {
+    fn new(boids: &BoidStore, area: Rect, cell_size: f32, smoothing: usize) -> Self {
+        let mut cell_size = cell_size.max(1.0);
+        let cell_count = (area.width() / cell_size).ceil() * (area.height() / cell_size).ceil();
+        if cell_count > MAX_CELLS as f32 {
+            cell_size *= (cell_count / MAX_CELLS as f32).sqrt();
+        }
+        let columns = ((area.width() / cell_size).ceil() as usize).max(1);
+        let rows = ((area.height() / cell_size).ceil() as usize).max(1);
+
+        let mut sums = vec![Vec2::ZERO; columns * rows];
+        let mut counts = vec![0.0; columns * rows];
+        for boid in boids {
+            if !area.contains(boid.position) {
+                continue;
+            }
+            let offset = (boid.position - area.min) / cell_size;
+            let column = (offset.x as usize).min(columns - 1);
+            let row = (offset.y as usize).min(rows - 1);
+            sums[row * columns + column] += boid.velocity;
+            counts[row * columns + column] += 1.0;
+        }
+        // Blurring the sums and counts together keeps it an average weighted by how many boids
+        // went into each cell, and spreads it into the empty cells around the flock
+        for _ in 0..smoothing {
+            sums = blur(&sums, columns, rows);
+            counts = blur(&counts, columns, rows);
+        }
+        let cells: Vec<Vec2> = sums
+            .iter()
+            .zip(&counts)
+            .map(|(&sum, &count)| if count > 0.0 { sum / count } else { Vec2::ZERO })
+            .collect();
+        let max_speed = cells.iter().map(|cell| cell.length()).fold(0.0, f32::max);
+        Self {
+            area,
+            cell_size,
+            columns,
+            rows,
+            cells,
+            max_speed,
+        }
+    }
+
+    fn cell_center(&self, column: usize, row: usize) -> Pos2 {
+        self.area.min + Vec2::new(column as f32 + 0.5, row as f32 + 0.5) * self.cell_size
+    }
+
+    /// The flow at `position`, eased between the centers of the cells around it.
+    fn sample(&self, position: Pos2) -> Vec2 {
+        if self.cells.is_empty() || !self.area.contains(position) {
+            return Vec2::ZERO;
+        }
+        let offset = (position - self.area.min) / self.cell_size - Vec2::splat(0.5);
+        let (column, row) = (offset.x.floor(), offset.y.floor());
+        let (tx, ty) = (offset.x - column, offset.y - row);
+        let cell = |column: f32, row: f32| {
+            let column = (column.max(0.0) as usize).min(self.columns - 1);
+            let row = (row.max(0.0) as usize).min(self.rows - 1);
+            self.cells[row * self.columns + column]
+        };
+        let top = cell(column, row) * (1.0 - tx) + cell(column + 1.0, row) * tx;
+        let bottom = cell(column, row + 1.0) * (1.0 - tx) + cell(column + 1.0, row + 1.0) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
+
+/// Each cell averaged with the ones around it.
+fn blur<T>(cells: &[T], columns: usize, rows: usize) -> Vec<T>
+where
+    T: Copy + Default + std::ops::Add<Output = T> + std::ops::Div<f32, Output = T>,
+{
+    let mut blurred = Vec::with_capacity(cells.len());
+    for row in 0..rows {
+        for column in 0..columns {
+            let mut sum = T::default();
+            let mut count = 0.0;
+            for around_row in row.saturating_sub(1)..=(row + 1).min(rows - 1) {
+                for around_column in column.saturating_sub(1)..=(column + 1).min(columns - 1) {
+                    sum = sum + cells[around_row * columns + around_column];
+                    count += 1.0;
+                }
+            }
+            blurred.push(sum / count);
+        }
+    }
+    blurred
+}
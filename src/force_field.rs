@@ -0,0 +1,117 @@
+use egui::{Color32, Painter, Pos2, Rect, Stroke, Ui, Vec2};
+
+use crate::{
+    boid::Boid, boid_store::BoidStore, camera::Camera, simulation::World, spatial_grid::SpatialGrid,
+};
+
+const ARROW_COLOR: Color32 = Color32::from_rgb(240, 150, 90);
+
+/// Arrows over the world showing the steering force a boid would feel at each point of a grid,
+/// to see why boids move where they do.
+///
+/// Each point gets a made up boid flying the way the flock around it does, which feels the real
+/// flock, predators and obstacles but isn't felt by them.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ForceField {
+    pub enabled: bool,
+    /// World units between the points the force is sampled at
+    pub spacing: f32,
+    /// Screen points of arrow for a force as strong as the max force
+    pub arrow_length: f32,
+    /// Where each sample was taken and the force there, from the last tick
+    #[serde(skip)]
+    samples: Vec<(Pos2, Vec2)>,
+    /// The max force when the samples were taken, which full length arrows stand for
+    #[serde(skip)]
+    max_force: f32,
+}
+
+impl Default for ForceField {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            spacing: 40.0,
+            arrow_length: 30.0,
+            samples: Vec::new(),
+            max_force: 1.0,
+        }
+    }
+}
+
+impl ForceField {
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Show Force Field")
+            .on_hover_text("Arrows for the steering force a boid would feel at each point");
+        ui.add_enabled_ui(self.enabled, |ui| {
+            ui.label("Grid Spacing");
+            ui.add(egui::DragValue::new(&mut self.spacing).range(10.0..=200.0))
+                .on_hover_text("World units between arrows, smaller is slower");
+            ui.label("Arrow Length");
+            ui.add(egui::Slider::new(&mut self.arrow_length, 5.0..=100.0))
+                .on_hover_text("How long an arrow for a force as strong as the max force is");
+        });
+        if !self.enabled {
+            self.samples.clear();
+        }
+    }
+
+    /// Work out the force at every grid point in `area` of the world, as a boid there would feel
+    /// `flock` and the rest of `world`.
+    pub fn sample(&mut self, world: &World<'_>, flock: &BoidStore, area: Rect) {
+        let obstacles = world.obstacles_ahead();
+        let spacing = self.spacing.max(1.0);
+        let radius = world.params.neighbor_radius;
+        let grid = SpatialGrid::new(flock, radius);
+        let mut nearby = Vec::new();
+        let positions = flock.positions();
+
+        let mut probes = BoidStore::default();
+        let first = (area.min / spacing).floor() * spacing;
+        let mut y = first.y;
+        while y <= area.max.y {
+            let mut x = first.x;
+            while x <= area.max.x {
+                let position = Pos2::new(x, y);
+                x += spacing;
+                if obstacles.iter().any(|obstacle| obstacle.contains(position)) {
+                    continue;
+                }
+                grid.candidates(position, radius, &mut nearby);
+                let heading = nearby
+                    .iter()
+                    .filter(|&&index| positions[index].distance(position) < radius)
+                    .fold(Vec2::ZERO, |sum, &index| sum + flock.boid(index).velocity);
+                // With nobody around to follow, the probe flies right at a cruising speed
+                let velocity = if heading == Vec2::ZERO {
+                    Vec2::X * world.params.max_speed / 2.0
+                } else {
+                    heading.normalized() * world.params.max_speed / 2.0
+                };
+                probes.push(Boid::probe(position, velocity));
+            }
+            y += spacing;
+        }
+
+        let forces = world.probe_forces(flock, &probes);
+        self.samples = probes.positions().iter().copied().zip(forces).collect();
+        self.max_force = world.params.max_force;
+    }
+
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        if !self.enabled {
+            return;
+        }
+        let stroke = Stroke::new(1.0, ARROW_COLOR);
+        for &(position, force) in &self.samples {
+            let length = (force.length() / self.max_force.max(f32::EPSILON)).min(1.0);
+            if length == 0.0 {
+                continue;
+            }
+            let arrow = force.normalized() * length * self.arrow_length;
+            let screen_pos = camera.world_to_screen(rect, position);
+            painter.circle_filled(screen_pos, 1.0, ARROW_COLOR);
+            painter.arrow(screen_pos, arrow, stroke);
+        }
+    }
+}
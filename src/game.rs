@@ -0,0 +1,357 @@
+#[cfg(feature = "gui")]
+use egui::{Align2, FontId, Painter, Stroke, Ui};
+
+use crate::math::Pos2;
+
+#[cfg(feature = "gui")]
+use crate::{
+    boid_store::BoidStore,
+    camera::Camera,
+    math::{Color32, Rect, Vec2},
+};
+
+/// The simulation runs at a fixed number of ticks per second, which we use to show times in seconds.
+pub const TICKS_PER_SECOND: f32 = 60.0;
+#[cfg(feature = "gui")]
+const MAX_LEADERBOARD_ENTRIES: usize = 10;
+#[cfg(feature = "gui")]
+const POINTS_PER_CATCH: u32 = 10;
+#[cfg(feature = "gui")]
+const POINTS_PER_HERDED_BOID: u32 = 5;
+#[cfg(feature = "gui")]
+const PEN_COLOR: Color32 = Color32::from_rgb(90, 160, 90);
+#[cfg(feature = "gui")]
+const SHEPHERD_COLOR: Color32 = Color32::from_rgb(80, 140, 255);
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum GameMode {
+    /// One player hunts with the mouse, scoring for catches and for scaring boids into the pen
+    #[default]
+    Solo,
+    /// The mouse predator hunts while a second player steers a shepherd with the keyboard to protect the flock
+    Versus,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct GameSettings {
+    pub mode: GameMode,
+    pub player_name: String,
+    pub flock_size: usize,
+    pub round_seconds: f32,
+    /// Boids closer than this to the predator get caught
+    pub catch_radius: f32,
+    pub pen_center: Pos2,
+    pub pen_radius: f32,
+    /// Shepherd movement per tick
+    pub shepherd_speed: f32,
+    /// Boids within this distance of the shepherd follow it and can't be caught
+    pub shepherd_radius: f32,
+    /// How strongly boids follow the shepherd
+    pub shepherd_weight: f32,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            mode: GameMode::default(),
+            player_name: "Player".to_owned(),
+            flock_size: 100,
+            round_seconds: 60.0,
+            catch_radius: 12.0,
+            pen_center: Pos2::new(-650.0, -325.0),
+            pen_radius: 120.0,
+            shepherd_speed: 4.0,
+            shepherd_radius: 120.0,
+            shepherd_weight: 1.5,
+        }
+    }
+}
+
+impl GameSettings {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.mode, GameMode::Solo, "Solo");
+            ui.radio_value(&mut self.mode, GameMode::Versus, "Predator vs Shepherd")
+                .on_hover_text("Player 1 hunts with the mouse, player 2 moves the shepherd with WASD or the arrow keys");
+        });
+        if self.mode == GameMode::Solo {
+            ui.label("Player Name");
+            ui.text_edit_singleline(&mut self.player_name);
+        }
+        ui.label("Flock Size");
+        ui.add(egui::Slider::new(&mut self.flock_size, 1..=1000));
+        ui.label("Round Length (seconds)");
+        ui.add(egui::DragValue::new(&mut self.round_seconds).range(5.0..=600.0));
+        ui.label("Catch Radius");
+        ui.add(egui::DragValue::new(&mut self.catch_radius).range(0.0..=f32::MAX));
+        ui.label("Pen Radius");
+        ui.add(egui::DragValue::new(&mut self.pen_radius).range(0.0..=f32::MAX));
+        if self.mode == GameMode::Versus {
+            ui.label("Shepherd Speed");
+            ui.add(egui::DragValue::new(&mut self.shepherd_speed).speed(0.1));
+            ui.label("Shepherd Radius");
+            ui.add(egui::DragValue::new(&mut self.shepherd_radius).range(0.0..=f32::MAX));
+            ui.label("Shepherd Weight");
+            ui.add(egui::DragValue::new(&mut self.shepherd_weight).speed(0.05));
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn round_ticks(&self) -> u64 {
+        (self.round_seconds * TICKS_PER_SECOND) as u64
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn in_pen(&self, position: Pos2) -> bool {
+        self.pen_center.distance(position) <= self.pen_radius
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw_pen(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        painter.circle_stroke(
+            camera.world_to_screen(rect, self.pen_center),
+            self.pen_radius * camera.zoom,
+            Stroke::new(2.0, PEN_COLOR),
+        );
+    }
+}
+
+/// The second player's sheepdog, which the flock gathers around.
+pub struct Shepherd {
+    pub position: Pos2,
+}
+
+impl Shepherd {
+    /// Move by `direction` (from the keyboard, each axis in -1..=1).
+    #[cfg(feature = "gui")]
+    pub fn update(&mut self, direction: Vec2, settings: &GameSettings) {
+        if direction != Vec2::ZERO {
+            self.position += direction.normalized() * settings.shepherd_speed;
+        }
+    }
+
+    pub fn protects(&self, position: Pos2, settings: &GameSettings) -> bool {
+        self.position.distance(position) <= settings.shepherd_radius
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera, settings: &GameSettings) {
+        let center = camera.world_to_screen(rect, self.position);
+        painter.circle_filled(center, 8.0, SHEPHERD_COLOR);
+        painter.circle_stroke(
+            center,
+            settings.shepherd_radius * camera.zoom,
+            Stroke::new(1.0, SHEPHERD_COLOR),
+        );
+    }
+}
+
+/// Statistics for a single round of the predator game.
+#[cfg(feature = "gui")]
+#[derive(Default)]
+pub struct GameSession {
+    pub elapsed_ticks: u64,
+    pub catches: u32,
+    /// How long each caught boid survived, in ticks
+    pub survival_ticks: Vec<u32>,
+    /// Boids in the pen, updated every tick
+    pub herded: u32,
+    pub finished: bool,
+    /// Only there in versus mode
+    pub shepherd: Option<Shepherd>,
+}
+
+#[cfg(feature = "gui")]
+impl GameSession {
+    pub fn new(settings: &GameSettings) -> Self {
+        let shepherd = match settings.mode {
+            GameMode::Solo => None,
+            GameMode::Versus => Some(Shepherd {
+                position: Pos2::ZERO,
+            }),
+        };
+
+        Self {
+            shepherd,
+            ..Default::default()
+        }
+    }
+
+    /// Solo score, the hunter gets points for both catching and herding.
+    pub fn score(&self) -> u32 {
+        self.catches * POINTS_PER_CATCH + self.herded * POINTS_PER_HERDED_BOID
+    }
+
+    /// In versus mode the predator only scores by catching.
+    pub fn predator_score(&self) -> u32 {
+        self.catches * POINTS_PER_CATCH
+    }
+
+    /// In versus mode the shepherd scores for every boid in the pen and a point per survivor.
+    pub fn shepherd_score(&self, survivors: usize) -> u32 {
+        self.herded * POINTS_PER_HERDED_BOID + survivors as u32
+    }
+
+    /// Average survival time of the boids in seconds, counting survivors as lasting the whole round.
+    pub fn average_survival_seconds(&self, survivors: usize) -> f32 {
+        let total = self
+            .survival_ticks
+            .iter()
+            .map(|ticks| *ticks as u64)
+            .sum::<u64>()
+            + survivors as u64 * self.elapsed_ticks;
+        let count = self.survival_ticks.len() + survivors;
+        if count == 0 {
+            0.0
+        } else {
+            total as f32 / count as f32 / TICKS_PER_SECOND
+        }
+    }
+
+    /// Advance the round: catch boids near the predator and count the herded ones.
+    ///
+    /// Returns where boids were caught this tick.
+    pub fn update(
+        &mut self,
+        boids: &mut BoidStore,
+        predator_pos: Option<Pos2>,
+        settings: &GameSettings,
+    ) -> Vec<Pos2> {
+        let mut caught_at = Vec::new();
+        if self.finished {
+            return caught_at;
+        }
+
+        if let Some(predator) = predator_pos {
+            let catch_radius_sq = settings.catch_radius * settings.catch_radius;
+            boids.retain(|boid| {
+                let protected = self
+                    .shepherd
+                    .as_ref()
+                    .is_some_and(|shepherd| shepherd.protects(boid.position, settings));
+                let caught = !protected && boid.position.distance_sq(predator) <= catch_radius_sq;
+                if caught {
+                    self.catches += 1;
+                    self.survival_ticks.push(boid.age);
+                    caught_at.push(boid.position);
+                }
+                !caught
+            });
+        }
+
+        self.herded = boids
+            .iter()
+            .filter(|boid| settings.in_pen(boid.position))
+            .count() as u32;
+
+        self.elapsed_ticks += 1;
+        if self.elapsed_ticks >= settings.round_ticks() || boids.is_empty() {
+            self.finished = true;
+        }
+        caught_at
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw_hud(
+        &self,
+        painter: &Painter,
+        rect: &Rect,
+        settings: &GameSettings,
+        survivors: usize,
+    ) {
+        let remaining =
+            settings.round_ticks().saturating_sub(self.elapsed_ticks) as f32 / TICKS_PER_SECOND;
+        let text = if self.shepherd.is_some() {
+            format!(
+                "Time {remaining:.0}s   Predator {}   Shepherd {}   (Catches {}   Herded {})",
+                self.predator_score(),
+                self.shepherd_score(survivors),
+                self.catches,
+                self.herded,
+            )
+        } else {
+            format!(
+                "Time {remaining:.0}s   Catches {}   Herded {}   Score {}",
+                self.catches,
+                self.herded,
+                self.score()
+            )
+        };
+        painter.text(
+            rect.left_top() + egui::vec2(10.0, 10.0),
+            Align2::LEFT_TOP,
+            text,
+            FontId::proportional(20.0),
+            Color32::WHITE,
+        );
+    }
+}
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub score: u32,
+    pub catches: u32,
+    pub herded: u32,
+    pub average_survival_seconds: f32,
+}
+
+/// Best scores across sessions, persisted with the rest of the app state.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Leaderboard {
+    entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    /// Add a finished round, returning its rank if it made the board.
+    #[cfg(feature = "gui")]
+    pub fn submit(&mut self, entry: LeaderboardEntry) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|existing| entry.score > existing.score)
+            .unwrap_or(self.entries.len());
+        if rank >= MAX_LEADERBOARD_ENTRIES {
+            return None;
+        }
+
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_LEADERBOARD_ENTRIES);
+        Some(rank)
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw(&mut self, ui: &mut Ui) {
+        if self.entries.is_empty() {
+            ui.label("No scores yet");
+            return;
+        }
+
+        egui::Grid::new("leaderboard").striped(true).show(ui, |ui| {
+            ui.strong("#");
+            ui.strong("Name");
+            ui.strong("Score");
+            ui.strong("Catches");
+            ui.strong("Herded");
+            ui.strong("Avg Survival");
+            ui.end_row();
+
+            for (rank, entry) in self.entries.iter().enumerate() {
+                ui.label(format!("{}", rank + 1));
+                ui.label(&entry.name);
+                ui.label(entry.score.to_string());
+                ui.label(entry.catches.to_string());
+                ui.label(entry.herded.to_string());
+                ui.label(format!("{:.1}s", entry.average_survival_seconds));
+                ui.end_row();
+            }
+        });
+
+        if ui.button("Clear Leaderboard").clicked() {
+            self.entries.clear();
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::VecDeque;
+
+use egui::{Color32, Painter, Rect, Stroke, Ui, Vec2};
+use rand::Rng;
+
+use crate::{
+    camera::Camera,
+    current_zone::{ZoneArea, ZoneShape},
+    game::TICKS_PER_SECOND,
+};
+
+const SOURCE_COLOR: Color32 = Color32::from_rgb(60, 180, 90);
+const SINK_COLOR: Color32 = Color32::from_rgb(150, 60, 170);
+/// Throughput is averaged over this many ticks.
+const METER_TICKS: usize = 5 * TICKS_PER_SECOND as usize;
+
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum GateKind {
+    /// Emits boids flying at `velocity`
+    Source {
+        /// Boids per second
+        rate: f32,
+        velocity: Vec2,
+    },
+    /// Removes every boid that flies in
+    Sink,
+}
+
+/// A region boids enter or leave the simulation through, for steady flows instead of a fixed flock.
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Gate {
+    pub area: ZoneArea,
+    pub kind: GateKind,
+}
+
+impl Gate {
+    /// How many boids the gate emits this tick.
+    ///
+    /// Drawn from the simulation's random numbers rather than counted with a timer, so replays
+    /// emit the same boids without the gate having to carry any state.
+    pub fn emit_count(&self, rng: &mut impl Rng) -> usize {
+        let GateKind::Source { rate, .. } = self.kind else {
+            return 0;
+        };
+        let per_tick = rate.max(0.0) / TICKS_PER_SECOND;
+        let whole = per_tick.floor();
+        whole as usize + usize::from(rng.gen::<f32>() < per_tick - whole)
+    }
+
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        let color = match self.kind {
+            GateKind::Source { .. } => SOURCE_COLOR,
+            GateKind::Sink => SINK_COLOR,
+        };
+        self.area
+            .draw_outline(painter, rect, camera, Stroke::new(2.0, color));
+
+        let center = camera.world_to_screen(rect, self.area.bounding_rect().center());
+        match self.kind {
+            GateKind::Source { velocity, .. } if velocity != Vec2::ZERO => {
+                painter.arrow(
+                    center,
+                    velocity.normalized() * 20.0,
+                    Stroke::new(2.0, color),
+                );
+            }
+            GateKind::Source { .. } => {
+                painter.circle_filled(center, 3.0, color);
+            }
+            GateKind::Sink => {
+                // A cross where boids disappear
+                let stroke = Stroke::new(2.0, color);
+                for arm in [Vec2::new(5.0, 5.0), Vec2::new(5.0, -5.0)] {
+                    painter.line_segment([center - arm, center + arm], stroke);
+                }
+            }
+        }
+    }
+}
+
+/// Settings used for the next gate placed with the gate tool.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct GateBrush {
+    pub shape: ZoneShape,
+    pub sink: bool,
+    /// Boids per second a new source emits
+    pub rate: f32,
+    /// Heading of the boids a new source emits, in degrees clockwise from the positive x axis
+    pub direction_degrees: f32,
+    pub speed: f32,
+}
+
+impl Default for GateBrush {
+    fn default() -> Self {
+        Self {
+            shape: ZoneShape::default(),
+            sink: false,
+            rate: 5.0,
+            direction_degrees: 0.0,
+            speed: 3.0,
+        }
+    }
+}
+
+impl GateBrush {
+    pub fn gate(&self, area: ZoneArea) -> Gate {
+        let kind = if self.sink {
+            GateKind::Sink
+        } else {
+            GateKind::Source {
+                rate: self.rate,
+                velocity: Vec2::angled(self.direction_degrees.to_radians()) * self.speed,
+            }
+        };
+        Gate { area, kind }
+    }
+
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.sink, false, "Source");
+            ui.radio_value(&mut self.sink, true, "Sink");
+        });
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.shape, ZoneShape::Rectangle, "Rectangle");
+            ui.radio_value(&mut self.shape, ZoneShape::Circle, "Circle");
+        });
+        if self.sink {
+            return;
+        }
+        ui.label("Boids Per Second");
+        ui.add(
+            egui::DragValue::new(&mut self.rate)
+                .speed(0.1)
+                .range(0.0..=TICKS_PER_SECOND * 10.0),
+        );
+        ui.label("Heading");
+        ui.add(egui::Slider::new(&mut self.direction_degrees, 0.0..=360.0).suffix("°"));
+        ui.label("Speed");
+        ui.add(egui::DragValue::new(&mut self.speed).speed(0.1));
+    }
+}
+
+/// Counts the boids gates add and remove, to measure how much flows through the simulation.
+#[derive(Default)]
+pub struct FlowMeter {
+    /// Boids spawned and removed in each of the last ticks, oldest first
+    ticks: VecDeque<(usize, usize)>,
+}
+
+impl FlowMeter {
+    pub fn record(&mut self, spawned: usize, removed: usize) {
+        if self.ticks.len() == METER_TICKS {
+            self.ticks.pop_front();
+        }
+        self.ticks.push_back((spawned, removed));
+    }
+
+    pub fn clear(&mut self) {
+        self.ticks.clear();
+    }
+
+    /// Boids spawned and removed per second, averaged over the last few seconds.
+    pub fn rates(&self) -> (f32, f32) {
+        if self.ticks.is_empty() {
+            return (0.0, 0.0);
+        }
+        let (spawned, removed) = self.ticks.iter().fold((0, 0), |(spawned, removed), tick| {
+            (spawned + tick.0, removed + tick.1)
+        });
+        let seconds = self.ticks.len() as f32 / TICKS_PER_SECOND;
+        (spawned as f32 / seconds, removed as f32 / seconds)
+    }
+}
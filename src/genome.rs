@@ -0,0 +1,69 @@
+#[cfg(feature = "gui")]
+use egui::Ui;
+use rand::Rng;
+
+/// A boid's individual make-up: multipliers on the flock-wide parameters.
+///
+/// Everything at 1.0 behaves exactly like the shared parameters say, a separation of 10 makes a loner.
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Genome {
+    pub separation: f32,
+    pub alignment: f32,
+    pub cohesion: f32,
+    pub avoidance: f32,
+    pub speed: f32,
+}
+
+impl Default for Genome {
+    fn default() -> Self {
+        Self {
+            separation: 1.0,
+            alignment: 1.0,
+            cohesion: 1.0,
+            avoidance: 1.0,
+            speed: 1.0,
+        }
+    }
+}
+
+impl Genome {
+    /// Every multiplier picked at random within `variation` of 1.0.
+    pub fn random(variation: f32, rng: &mut impl Rng) -> Self {
+        let variation = variation.clamp(0.0, 1.0);
+        if variation == 0.0 {
+            return Self::default();
+        }
+
+        let mut gene = || rng.gen_range(1.0 - variation..=1.0 + variation);
+        Self {
+            separation: gene(),
+            alignment: gene(),
+            cohesion: gene(),
+            avoidance: gene(),
+            speed: gene(),
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        egui::Grid::new("genome").show(ui, |ui| {
+            for (label, gene) in [
+                ("Separation", &mut self.separation),
+                ("Alignment", &mut self.alignment),
+                ("Cohesion", &mut self.cohesion),
+                ("Avoidance", &mut self.avoidance),
+                ("Speed", &mut self.speed),
+            ] {
+                ui.label(label);
+                ui.add(
+                    egui::DragValue::new(gene)
+                        .speed(0.05)
+                        .range(0.0..=100.0)
+                        .suffix("x"),
+                );
+                ui.end_row();
+            }
+        });
+    }
+}
@@ -0,0 +1,130 @@
+#[cfg(feature = "gui")]
+use std::ops::RangeInclusive;
+
+#[cfg(feature = "gui")]
+use egui::{Id, Painter, Response, Stroke, TextStyle, Ui};
+
+use crate::{boid_store::BoidStore, boids_simulation::BoidsSimulationParameters};
+
+#[cfg(feature = "gui")]
+use crate::{
+    camera::Camera,
+    game::TICKS_PER_SECOND,
+    math::{Color32, Rect},
+};
+
+/// How long a ghost runs before it's forked from the live flock again.
+#[cfg(feature = "gui")]
+const GHOST_TICKS: u32 = (2.0 * TICKS_PER_SECOND) as u32;
+#[cfg(feature = "gui")]
+const GHOST_COLOR: Color32 = Color32::from_rgba_premultiplied(90, 140, 180, 90);
+#[cfg(feature = "gui")]
+const GHOST_SIZE: f32 = 8.0;
+/// Where a slider hovered this frame leaves the parameters it wants previewed.
+#[cfg(feature = "gui")]
+const REQUEST_ID: &str = "ghost_preview_request";
+
+/// Ask for a preview of `params` this frame, the app picks it up once the panels are drawn.
+#[cfg(feature = "gui")]
+pub fn request(ctx: &egui::Context, params: BoidsSimulationParameters) {
+    ctx.data_mut(|data| data.insert_temp(Id::new(REQUEST_ID), params));
+}
+
+/// The value under the pointer while it's over the rail of a slider spanning `range`.
+#[cfg(feature = "gui")]
+pub fn hovered_slider_value(
+    ui: &Ui,
+    response: &Response,
+    range: &RangeInclusive<f32>,
+) -> Option<f32> {
+    let pointer = response.hover_pos()?;
+    // Laid out the same way the slider lays out its rail and handle
+    let width = ui.spacing().slider_width;
+    let thickness = ui
+        .text_style_height(&TextStyle::Body)
+        .max(ui.spacing().interact_size.y);
+    let handle_radius = thickness / 2.5;
+    let offset = pointer.x - response.rect.left();
+    if !(0.0..=width).contains(&offset) {
+        return None;
+    }
+    let t = ((offset - handle_radius) / (width - 2.0 * handle_radius)).clamp(0.0, 1.0);
+    Some(range.start() + (range.end() - range.start()) * t)
+}
+
+/// A faint copy of the flock running with a parameter value the user is only hovering over, to
+/// see what it would do before committing to it.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct GhostPreview {
+    pub enabled: bool,
+    /// What the ghost runs with, `None` while nothing is hovered
+    #[serde(skip)]
+    pub params: Option<BoidsSimulationParameters>,
+    #[serde(skip)]
+    pub boids: BoidStore,
+    /// Ticks since the ghost was forked
+    #[serde(skip)]
+    ticks: u32,
+}
+
+impl GhostPreview {
+    /// Pick up this frame's request, forking a new ghost from `boids` if it's for different
+    /// parameters. Without one the ghost goes away.
+    #[cfg(feature = "gui")]
+    pub fn take_request(&mut self, ctx: &egui::Context, boids: &BoidStore) {
+        let request =
+            ctx.data_mut(|data| data.remove_temp::<BoidsSimulationParameters>(Id::new(REQUEST_ID)));
+        match request.filter(|_| self.enabled) {
+            Some(params) if self.params.as_ref() != Some(&params) => {
+                self.params = Some(params);
+                self.fork(boids);
+            }
+            Some(_) => {}
+            None => {
+                self.params = None;
+                self.boids.clear();
+            }
+        }
+    }
+
+    /// Count a tick the ghost was stepped, starting it over from `boids` once it has run its course.
+    #[cfg(feature = "gui")]
+    pub fn advance(&mut self, boids: &BoidStore) {
+        self.ticks += 1;
+        if self.ticks >= GHOST_TICKS {
+            self.fork(boids);
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    fn fork(&mut self, boids: &BoidStore) {
+        self.boids = boids.clone();
+        self.ticks = 0;
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        if self.params.is_none() {
+            return;
+        }
+        // Fade out as the ghost runs, so restarting it doesn't flash
+        let fade = 1.0 - self.ticks as f32 / GHOST_TICKS as f32;
+        let stroke = Stroke::new(1.5, GHOST_COLOR.gamma_multiply(fade));
+        for boid in &self.boids {
+            painter.arrow(
+                camera.world_to_screen(rect, boid.position),
+                boid.velocity.normalized() * GHOST_SIZE,
+                stroke,
+            );
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Preview Slider Changes")
+            .on_hover_text(
+                "Hovering a slider shows a faint flock running with the value under the pointer",
+            );
+    }
+}
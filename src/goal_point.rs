@@ -0,0 +1,294 @@
+#[cfg(feature = "gui")]
+use egui::{Align2, FontId, Painter, Shape, Stroke, Ui};
+
+use crate::{
+    boid_store::BoidRef,
+    boids_simulation::BoidsSimulationParameters,
+    math::{Pos2, Vec2},
+};
+
+#[cfg(feature = "gui")]
+use crate::{
+    boid_store::BoidStore,
+    camera::Camera,
+    math::{Color32, Rect},
+};
+
+#[cfg(feature = "gui")]
+const GOAL_COLOR: Color32 = Color32::from_rgb(120, 230, 120);
+
+/// A destination placed in the world that boids head for, to herd the flock somewhere.
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct GoalPoint {
+    pub position: Pos2,
+}
+
+#[cfg(feature = "gui")]
+impl GoalPoint {
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        let center = camera.world_to_screen(rect, self.position);
+        let stroke = Stroke::new(2.0, GOAL_COLOR);
+        painter.circle_stroke(center, 10.0, stroke);
+        painter.line_segment([center - Vec2::X * 14.0, center + Vec2::X * 14.0], stroke);
+        painter.line_segment([center - Vec2::Y * 14.0, center + Vec2::Y * 14.0], stroke);
+    }
+}
+
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct GoalSeekingParameters {
+    /// How strongly boids seek the nearest goal point
+    pub weight: f32,
+    /// Boids ease off seeking within this distance of the goal, so they gather rather than overshoot
+    pub arrival_radius: f32,
+}
+
+impl Default for GoalSeekingParameters {
+    fn default() -> Self {
+        Self {
+            weight: 0.5,
+            arrival_radius: 50.0,
+        }
+    }
+}
+
+impl GoalSeekingParameters {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.label("Goal Weight");
+        ui.add(egui::DragValue::new(&mut self.weight).speed(0.05));
+        ui.label("Arrival Radius");
+        ui.add(egui::DragValue::new(&mut self.arrival_radius).range(0.0..=f32::MAX))
+            .on_hover_text("Boids slow their seeking down this close to a goal");
+    }
+
+    /// The pull on `boid` towards the nearest of `goals`.
+    pub fn force(
+        &self,
+        boid: BoidRef<'_>,
+        goals: &[GoalPoint],
+        params: &BoidsSimulationParameters,
+    ) -> Vec2 {
+        let nearest = goals
+            .iter()
+            .map(|goal| (goal.position, goal.position.distance(boid.position)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+        let Some((position, distance)) = nearest else {
+            return Vec2::ZERO;
+        };
+        let easing = if self.arrival_radius > 0.0 {
+            (distance / self.arrival_radius).min(1.0)
+        } else {
+            1.0
+        };
+        boid.calculate_seek_force(position, self.weight * easing, params)
+    }
+}
+
+/// Which way the goal tool's clicks go.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum GoalMode {
+    /// Goal points, the nearest of which every boid heads for
+    #[default]
+    Points,
+    /// Waypoints the flock visits one after another
+    Path,
+}
+
+/// Waypoints the flock, or just its leader, visits in order, moving on to the next once it gets
+/// within the arrival radius of the one it's heading for.
+#[derive(Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct WaypointPath {
+    pub waypoints: Vec<Pos2>,
+    /// Start over from the first waypoint after the last, rather than stopping there
+    pub looped: bool,
+    /// Index of the waypoint being headed for, past the end once a path that doesn't loop is done
+    pub next: usize,
+    /// Id of the only boid that follows the path, the rest just flock with it. `None` has the
+    /// whole flock follow it.
+    pub leader: Option<u64>,
+}
+
+impl WaypointPath {
+    pub fn target(&self) -> Option<Pos2> {
+        self.waypoints.get(self.next).copied()
+    }
+
+    /// The pull on `boid` towards the waypoint being headed for.
+    ///
+    /// Unlike goal points there's no easing off on arrival, the flock keeps its speed up as it
+    /// moves on to the next waypoint.
+    pub fn force(
+        &self,
+        boid: BoidRef<'_>,
+        seeking: &GoalSeekingParameters,
+        params: &BoidsSimulationParameters,
+    ) -> Vec2 {
+        let Some(target) = self.target() else {
+            return Vec2::ZERO;
+        };
+        if self.leader.is_some_and(|leader| leader != boid.id) {
+            return Vec2::ZERO;
+        }
+        boid.calculate_seek_force(target, seeking.weight, params)
+    }
+
+    /// Move on to the next waypoint if whoever follows the path in `boids` has arrived at the one
+    /// it was heading for, returning the index of the waypoint reached.
+    ///
+    /// A leader that's gone hands the path back to the whole flock.
+    #[cfg(feature = "gui")]
+    pub fn update(&mut self, boids: &BoidStore, arrival_radius: f32) -> Option<usize> {
+        let target = self.target()?;
+        if let Some(leader) = self.leader {
+            if !boids.iter().any(|boid| boid.id == leader) {
+                self.leader = None;
+            }
+        }
+        let position = match self.leader {
+            Some(leader) => boids.iter().find(|boid| boid.id == leader)?.position,
+            None => {
+                let flying: Vec<Pos2> = boids
+                    .iter()
+                    .filter(|boid| boid.resting.is_none())
+                    .map(|boid| boid.position)
+                    .collect();
+                if flying.is_empty() {
+                    return None;
+                }
+                let sum = flying
+                    .iter()
+                    .fold(Vec2::ZERO, |sum, position| sum + position.to_vec2());
+                Pos2::ZERO + sum / flying.len() as f32
+            }
+        };
+        if position.distance(target) > arrival_radius {
+            return None;
+        }
+        let reached = self.next;
+        self.next += 1;
+        if self.looped && self.next == self.waypoints.len() {
+            self.next = 0;
+        }
+        Some(reached)
+    }
+
+    /// Index of the waypoint within `tolerance` of `position`, the closest if there are several.
+    #[cfg(feature = "gui")]
+    pub fn waypoint_at(&self, position: Pos2, tolerance: f32) -> Option<usize> {
+        self.waypoints
+            .iter()
+            .enumerate()
+            .map(|(index, waypoint)| (index, waypoint.distance(position)))
+            .filter(|(_, distance)| *distance < tolerance)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn remove(&mut self, index: usize) {
+        self.waypoints.remove(index);
+        // Keep heading for the same waypoint, unless it was the one removed
+        if index < self.next {
+            self.next -= 1;
+        }
+        if self.looped && self.next >= self.waypoints.len() {
+            self.next = 0;
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn clear(&mut self) {
+        self.waypoints.clear();
+        self.next = 0;
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera, boids: &BoidStore) {
+        if self.waypoints.is_empty() {
+            return;
+        }
+        let mut points: Vec<Pos2> = self
+            .waypoints
+            .iter()
+            .map(|waypoint| camera.world_to_screen(rect, *waypoint))
+            .collect();
+        let markers = points.clone();
+        if self.looped {
+            points.push(points[0]);
+        }
+        let faded = GOAL_COLOR.gamma_multiply(0.5);
+        painter.add(Shape::dashed_line(
+            &points,
+            Stroke::new(1.5, faded),
+            8.0,
+            5.0,
+        ));
+        for (index, center) in markers.into_iter().enumerate() {
+            let color = if index == self.next {
+                GOAL_COLOR
+            } else {
+                faded
+            };
+            painter.circle_stroke(center, 9.0, Stroke::new(2.0, color));
+            painter.text(
+                center,
+                Align2::CENTER_CENTER,
+                (index + 1).to_string(),
+                FontId::proportional(11.0),
+                color,
+            );
+        }
+        if let Some(leader) = self
+            .leader
+            .and_then(|leader| boids.iter().find(|boid| boid.id == leader))
+        {
+            let center = camera.world_to_screen(rect, leader.position);
+            painter.circle_stroke(center, 12.0, Stroke::new(2.0, GOAL_COLOR));
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.looped, "Loop Path")
+            .on_hover_text("Start over from the first waypoint after the last");
+        let status = match self.target() {
+            Some(_) => format!(
+                "Heading for waypoint {} of {}",
+                self.next + 1,
+                self.waypoints.len()
+            ),
+            None if self.waypoints.is_empty() => "Click to add waypoints".to_owned(),
+            None => "Path finished".to_owned(),
+        };
+        ui.label(status);
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    !self.waypoints.is_empty(),
+                    egui::Button::new("Restart Path"),
+                )
+                .clicked()
+            {
+                self.next = 0;
+            }
+            if ui.button("Clear Path").clicked() {
+                self.clear();
+            }
+        });
+        match self.leader {
+            Some(leader) => {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Led by boid {leader}"));
+                    if ui.small_button("Whole Flock").clicked() {
+                        self.leader = None;
+                    }
+                });
+            }
+            None => {
+                ui.weak("The whole flock follows, pick a leader from the boid inspector");
+            }
+        }
+    }
+}
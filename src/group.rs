@@ -0,0 +1,319 @@
+#[cfg(feature = "gui")]
+use egui::Ui;
+
+use crate::{
+    boids_simulation::BoidsSimulationParameters,
+    interaction::InteractionMatrix,
+    math::{Color32, Vec2},
+    render::GroupStyle,
+    threat::EscapeStrategy,
+};
+
+/// Colors given to new groups in turn, picked to tell apart at a glance.
+const GROUP_PALETTE: [Color32; 6] = [
+    Color32::from_rgb(230, 90, 80),
+    Color32::from_rgb(80, 170, 230),
+    Color32::from_rgb(240, 200, 70),
+    Color32::from_rgb(120, 210, 110),
+    Color32::from_rgb(200, 120, 220),
+    Color32::from_rgb(240, 150, 60),
+];
+
+/// Global parameters a group can replace with its own value, `None` keeps the global one.
+#[derive(Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ParameterOverrides {
+    pub max_speed: Option<f32>,
+    pub max_force: Option<f32>,
+    pub separation_weight: Option<f32>,
+    pub alignment_weight: Option<f32>,
+    pub cohesion_weight: Option<f32>,
+    pub avoidance_weight: Option<f32>,
+    pub neighbor_radius: Option<f32>,
+    pub avoidance_radius: Option<f32>,
+    /// Lets a group act as a species that escapes predators its own way
+    pub escape_strategy: Option<EscapeStrategy>,
+}
+
+impl ParameterOverrides {
+    /// `global` with every overridden value swapped in.
+    pub fn apply(&self, global: &BoidsSimulationParameters) -> BoidsSimulationParameters {
+        let mut params = global.clone();
+        for (value, field) in self.fields().into_iter().zip(params.overridable_mut()) {
+            if let (Some(value), (_, field)) = (value, field) {
+                *field = value;
+            }
+        }
+        if let Some(strategy) = self.escape_strategy {
+            params.escape_strategy = strategy;
+        }
+        params
+    }
+
+    fn fields(&self) -> [Option<f32>; 8] {
+        [
+            self.max_speed,
+            self.max_force,
+            self.separation_weight,
+            self.alignment_weight,
+            self.cohesion_weight,
+            self.avoidance_weight,
+            self.neighbor_radius,
+            self.avoidance_radius,
+        ]
+    }
+
+    #[cfg(feature = "gui")]
+    fn fields_mut(&mut self) -> [&mut Option<f32>; 8] {
+        [
+            &mut self.max_speed,
+            &mut self.max_force,
+            &mut self.separation_weight,
+            &mut self.alignment_weight,
+            &mut self.cohesion_weight,
+            &mut self.avoidance_weight,
+            &mut self.neighbor_radius,
+            &mut self.avoidance_radius,
+        ]
+    }
+
+    /// One row per parameter: whether it's overridden and the value the group ends up using.
+    #[cfg(feature = "gui")]
+    fn draw_panel(&mut self, ui: &mut Ui, id: u32, global: &mut BoidsSimulationParameters) {
+        egui::Grid::new(("group_overrides", id)).show(ui, |ui| {
+            for (field, (label, global_value)) in
+                self.fields_mut().into_iter().zip(global.overridable_mut())
+            {
+                let mut overridden = field.is_some();
+                if ui.checkbox(&mut overridden, label).changed() {
+                    *field = overridden.then_some(*global_value);
+                }
+                match field {
+                    Some(value) => ui.add(egui::DragValue::new(value).speed(0.05)),
+                    // Show the effective value, which is the global one
+                    None => ui.add_enabled(false, egui::DragValue::new(global_value)),
+                };
+                ui.end_row();
+            }
+
+            let mut overridden = self.escape_strategy.is_some();
+            if ui.checkbox(&mut overridden, "Escape Strategy").changed() {
+                self.escape_strategy = overridden.then_some(global.escape_strategy);
+            }
+            match &mut self.escape_strategy {
+                Some(strategy) => strategy.draw_selector(ui, ("group_escape_strategy", id)),
+                None => {
+                    ui.add_enabled(false, egui::Label::new(global.escape_strategy.label()));
+                }
+            }
+            ui.end_row();
+        });
+    }
+}
+
+/// A direction every boid in a group wants to fly in, on top of flocking.
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct GroupGoal {
+    /// Clockwise from the positive x axis
+    pub heading_degrees: f32,
+    pub weight: f32,
+}
+
+impl Default for GroupGoal {
+    fn default() -> Self {
+        Self {
+            heading_degrees: 0.0,
+            weight: 0.5,
+        }
+    }
+}
+
+impl GroupGoal {
+    pub fn heading(&self) -> Vec2 {
+        Vec2::angled(self.heading_degrees.to_radians())
+    }
+}
+
+/// A user-defined set of boids that can behave differently from the rest of the flock.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct BoidGroup {
+    /// Boids refer to their group by id, so removing a group doesn't move boids into another one
+    pub id: u32,
+    pub name: String,
+    pub overrides: ParameterOverrides,
+    #[serde(default)]
+    pub goal: Option<GroupGoal>,
+    /// What the group's boids are drawn in when coloring by group, `None` leaves them alone
+    #[serde(default)]
+    pub color: Option<Color32>,
+    #[serde(default)]
+    pub style: GroupStyle,
+}
+
+#[derive(Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct GroupParameters {
+    pub groups: Vec<BoidGroup>,
+    /// Group newly spawned boids join
+    pub spawn_group: Option<u32>,
+    /// Boids only align with and flock towards boids in their own group, so groups behave like
+    /// separate species that still keep out of each other's way
+    pub flock_apart: bool,
+    pub interactions: InteractionMatrix,
+    next_id: u32,
+}
+
+impl GroupParameters {
+    pub fn name(&self, id: Option<u32>) -> &str {
+        id.and_then(|id| self.groups.iter().find(|group| group.id == id))
+            .map_or("None", |group| group.name.as_str())
+    }
+
+    /// The color of the group with `id`, if it has one.
+    pub fn color(&self, id: Option<u32>) -> Option<Color32> {
+        let id = id?;
+        self.groups
+            .iter()
+            .find(|group| group.id == id)
+            .and_then(|group| group.color)
+    }
+
+    /// How the group with `id` is drawn, `None` for boids without a (surviving) group.
+    pub fn style(&self, id: Option<u32>) -> Option<&GroupStyle> {
+        let id = id?;
+        self.groups
+            .iter()
+            .find(|group| group.id == id)
+            .map(|group| &group.style)
+    }
+
+    /// The goal of the group with `id`, if it has one.
+    pub fn goal(&self, id: Option<u32>) -> Option<&GroupGoal> {
+        let id = id?;
+        self.groups
+            .iter()
+            .find(|group| group.id == id)
+            .and_then(|group| group.goal.as_ref())
+    }
+
+    /// Add a group with a fresh id and return it.
+    pub fn add(&mut self, name: String) -> &mut BoidGroup {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.groups.push(BoidGroup {
+            id,
+            name,
+            overrides: ParameterOverrides::default(),
+            goal: None,
+            color: Some(GROUP_PALETTE[id as usize % GROUP_PALETTE.len()]),
+            style: GroupStyle::default(),
+        });
+        self.groups.last_mut().expect("just pushed a group")
+    }
+
+    /// Pick a group (or none) from a combo box, returning true if the choice changed.
+    #[cfg(feature = "gui")]
+    pub fn draw_selector(&self, ui: &mut Ui, label: &str, selected: &mut Option<u32>) -> bool {
+        let before = *selected;
+        egui::ComboBox::from_label(label)
+            .selected_text(self.name(*selected))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(selected, None, "None");
+                for group in &self.groups {
+                    ui.selectable_value(selected, Some(group.id), &group.name);
+                }
+            });
+        *selected != before
+    }
+}
+
+impl BoidsSimulationParameters {
+    /// The parameters every group actually runs with, by group id.
+    pub fn resolve_groups(&self) -> Vec<(u32, BoidsSimulationParameters)> {
+        self.groups
+            .groups
+            .iter()
+            .map(|group| (group.id, group.overrides.apply(self)))
+            .collect()
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw_groups_panel(&mut self, ui: &mut Ui) {
+        let mut groups = std::mem::take(&mut self.groups);
+
+        let mut removed = None;
+        for (index, group) in groups.groups.iter_mut().enumerate() {
+            egui::CollapsingHeader::new(&group.name)
+                .id_salt(("group", group.id))
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut group.name);
+                        let mut has_color = group.color.is_some();
+                        if ui
+                            .checkbox(&mut has_color, "Color")
+                            .on_hover_text("Shown when boids are colored by group")
+                            .changed()
+                        {
+                            group.color = has_color
+                                .then_some(GROUP_PALETTE[group.id as usize % GROUP_PALETTE.len()]);
+                        }
+                        if let Some(color) = &mut group.color {
+                            ui.color_edit_button_srgba(color);
+                        }
+                    });
+                    egui::CollapsingHeader::new("Style")
+                        .id_salt(("group_style_header", group.id))
+                        .show(ui, |ui| group.style.draw_panel(ui, group.id));
+                    group.overrides.draw_panel(ui, group.id, self);
+
+                    let mut has_goal = group.goal.is_some();
+                    if ui.checkbox(&mut has_goal, "Heading Goal").changed() {
+                        group.goal = has_goal.then(GroupGoal::default);
+                    }
+                    if let Some(goal) = &mut group.goal {
+                        ui.add(
+                            egui::Slider::new(&mut goal.heading_degrees, 0.0..=360.0).suffix("°"),
+                        );
+                        ui.label("Goal Weight");
+                        ui.add(egui::DragValue::new(&mut goal.weight).speed(0.05));
+                    }
+                    if ui.button("Remove Group").clicked() {
+                        removed = Some(index);
+                    }
+                });
+        }
+        if let Some(index) = removed {
+            let group = groups.groups.remove(index);
+            groups.interactions.remove_group(group.id);
+        }
+
+        if ui.button("Add Group").clicked() {
+            let name = format!("Group {}", groups.next_id + 1);
+            groups.add(name);
+        }
+        ui.checkbox(&mut groups.flock_apart, "Groups Flock Apart")
+            .on_hover_text(
+                "Boids only align and flock with their own group, but still keep their distance from everyone",
+            );
+        egui::CollapsingHeader::new("Interactions").show(ui, |ui| {
+            groups.interactions.draw_panel(ui, &groups.groups);
+        });
+
+        let mut spawn_group = groups.spawn_group;
+        groups.draw_selector(ui, "New Boids Join", &mut spawn_group);
+        groups.spawn_group = spawn_group;
+
+        self.groups = groups;
+    }
+}
+
+/// The parameters `group` runs with, falling back to the global ones for boids without a (surviving) group.
+pub fn group_params<'a>(
+    global: &'a BoidsSimulationParameters,
+    resolved: &'a [(u32, BoidsSimulationParameters)],
+    group: Option<u32>,
+) -> &'a BoidsSimulationParameters {
+    group
+        .and_then(|group| resolved.iter().find(|(id, _)| *id == group))
+        .map_or(global, |(_, params)| params)
+}
@@ -0,0 +1,176 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    boid::Boid,
+    boid_store::BoidStore,
+    boids_simulation::BoidsSimulationParameters,
+    danger::DangerMap,
+    game::GameSettings,
+    goal_point::WaypointPath,
+    math::{Pos2, Vec2},
+    observer::FlockState,
+    obstacle::Obstacle,
+    simulation::{World, WORLD_BOUNDS},
+    split_merge::ExperimentForces,
+    steering::{flocking_rules, SteeringRule},
+    threat::Threat,
+};
+
+/// The flock on its own, without a window, for tests, benchmarks and other frontends.
+///
+/// Steps through the same [`World`] as the app, so boids fly the same with or without one.
+pub struct HeadlessSimulation {
+    /// Read every tick, so they can be changed between any two
+    pub params: BoidsSimulationParameters,
+    boids: BoidStore,
+    rules: Vec<Box<dyn SteeringRule>>,
+    pub obstacles: Vec<Obstacle>,
+    /// Predators the flock runs from, which stay where they're put
+    pub threats: Vec<Threat>,
+    waypoint_path: WaypointPath,
+    danger_map: DangerMap,
+    game_settings: GameSettings,
+    experiment: ExperimentForces,
+    tick: u64,
+}
+
+impl HeadlessSimulation {
+    /// A flock of `num_boids` with the default parameters, scattered over the world by `seed`.
+    pub fn new(num_boids: usize, seed: u64) -> Self {
+        let params = BoidsSimulationParameters {
+            num_boids,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(seed);
+        let boids = (0..num_boids as u64)
+            .map(|id| {
+                let position = Pos2::new(
+                    rng.gen_range(WORLD_BOUNDS.left()..WORLD_BOUNDS.right()),
+                    rng.gen_range(WORLD_BOUNDS.top()..WORLD_BOUNDS.bottom()),
+                );
+                let velocity = Vec2::new(
+                    rng.gen_range(-params.max_speed..params.max_speed),
+                    rng.gen_range(-params.max_speed..params.max_speed),
+                );
+                Boid::with_id(id, position, velocity)
+            })
+            .collect();
+        Self {
+            params,
+            boids,
+            rules: flocking_rules(),
+            obstacles: Vec::new(),
+            threats: Vec::new(),
+            waypoint_path: WaypointPath::default(),
+            danger_map: DangerMap::default(),
+            game_settings: GameSettings::default(),
+            experiment: ExperimentForces::default(),
+            tick: 0,
+        }
+    }
+
+    /// Simulate one tick.
+    pub fn step(&mut self) {
+        for mut boid in &mut self.boids {
+            boid.previous_position = *boid.position;
+        }
+        let world = World {
+            params: &self.params,
+            rules: &self.rules,
+            threats: self.threats.clone(),
+            migration_target: Pos2::ZERO,
+            goal_points: &[],
+            waypoint_path: &self.waypoint_path,
+            danger_map: &self.danger_map,
+            perches: &[],
+            thermals: &[],
+            shepherd: None,
+            game_settings: &self.game_settings,
+            obstacles: &self.obstacles,
+            temporary_obstacles: &[],
+            moving_obstacles: &[],
+            obstacle_clock: self.tick,
+            current_zones: &[],
+            impulses: &[],
+            experiment: &self.experiment,
+            active_chunks: None,
+            tick: self.tick,
+            bounds: WORLD_BOUNDS,
+            parallel: false,
+        };
+        world.step(&mut self.boids);
+        self.tick += 1;
+    }
+
+    /// The flock as it is now, to read or nudge between ticks.
+    pub fn flock(&mut self) -> FlockState<'_> {
+        FlockState {
+            tick: self.tick,
+            predator: self.threats.first().map(|threat| threat.position),
+            boids: &mut self.boids,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        math::{Pos2, Vec2},
+        obstacle::Obstacle,
+        threat::Threat,
+    };
+
+    use super::HeadlessSimulation;
+
+    const TICKS: usize = 200;
+
+    fn positions(simulation: &mut HeadlessSimulation) -> Vec<Pos2> {
+        simulation
+            .flock()
+            .boids()
+            .map(|boid| boid.position())
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_flock() {
+        let mut first = HeadlessSimulation::new(300, 7);
+        let mut second = HeadlessSimulation::new(300, 7);
+        for _ in 0..TICKS {
+            first.step();
+            second.step();
+        }
+        assert_eq!(positions(&mut first), positions(&mut second));
+    }
+
+    #[test]
+    fn different_seeds_different_flocks() {
+        let mut first = HeadlessSimulation::new(300, 7);
+        let mut second = HeadlessSimulation::new(300, 8);
+        for _ in 0..TICKS {
+            first.step();
+            second.step();
+        }
+        assert_ne!(positions(&mut first), positions(&mut second));
+    }
+
+    #[test]
+    fn boids_stay_out_of_obstacles() {
+        let obstacle = Obstacle::Circle {
+            center: Pos2::ZERO,
+            radius: 120.0,
+        };
+        let mut simulation = HeadlessSimulation::new(300, 7);
+        simulation.obstacles.push(obstacle);
+        simulation.threats.push(Threat {
+            position: Pos2::new(300.0, 0.0),
+            velocity: Vec2::ZERO,
+        });
+        for _ in 0..TICKS {
+            simulation.step();
+        }
+        assert!(positions(&mut simulation)
+            .iter()
+            .all(|&position| !obstacle.contains(position)));
+    }
+}
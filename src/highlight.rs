@@ -0,0 +1,225 @@
+use std::{collections::VecDeque, ops::RangeInclusive};
+
+use egui::Ui;
+use tracing::info;
+
+use crate::{
+    boid_store::BoidStore, game::TICKS_PER_SECOND, metrics::flock_sizes, timeline::format_ticks,
+};
+
+/// How far back the flocks are compared against, a scatter or a merge plays out within about a second.
+const WINDOW_TICKS: u64 = TICKS_PER_SECOND as u64;
+/// Finding the flocks means visiting every boid's neighbors, so it's only done every few ticks.
+const SAMPLE_TICKS: u64 = 6;
+/// Fewer boids than this together are stragglers rather than a flock.
+const MIN_FLOCK_SIZE: usize = 3;
+/// One event trips the detector over several samples, so it stays quiet this long after a highlight.
+const COOLDOWN_TICKS: u64 = 5 * TICKS_PER_SECOND as u64;
+/// Oldest highlights are dropped past this.
+const MAX_HIGHLIGHTS: usize = 100;
+/// How much of the flock an exported clip shows before and after its highlight.
+pub const CLIP_HALF_TICKS: u64 = 5 * TICKS_PER_SECOND as u64;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HighlightKind {
+    /// The biggest flock broke up into smaller ones
+    Scatter,
+    /// Flocks came together into a much bigger one
+    Merge,
+}
+
+impl HighlightKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            HighlightKind::Scatter => "Scatter",
+            HighlightKind::Merge => "Flock Merge",
+        }
+    }
+}
+
+pub struct Highlight {
+    pub tick: u64,
+    pub kind: HighlightKind,
+}
+
+impl Highlight {
+    /// The ticks of the clip around the highlight, cut short at the start of `replayable`.
+    ///
+    /// `None` unless the replay buffer covers the highlight and the time after it.
+    pub fn clip(&self, replayable: &RangeInclusive<u64>) -> Option<RangeInclusive<u64>> {
+        let start = self
+            .tick
+            .saturating_sub(CLIP_HALF_TICKS)
+            .max(*replayable.start());
+        let end = self.tick + CLIP_HALF_TICKS;
+        (replayable.contains(&self.tick) && end <= *replayable.end()).then_some(start..=end)
+    }
+}
+
+/// The flocks at one sample.
+struct FlockShape {
+    tick: u64,
+    flocks: usize,
+    largest: usize,
+    boids: usize,
+}
+
+/// What the user asked for from the highlights panel.
+pub enum HighlightAction {
+    /// Replay from the start of the highlight's clip
+    Watch(RangeInclusive<u64>),
+    /// Replay the highlight's clip and save it as a GIF
+    #[cfg(not(target_arch = "wasm32"))]
+    Export(RangeInclusive<u64>),
+}
+
+/// Picks out dramatic moments from the flocks splitting and joining: a big scatter when the biggest
+/// flock loses a good share of the boids to smaller ones, a merge when flocks join into a much bigger one.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct HighlightDetector {
+    pub enabled: bool,
+    /// The share of the boids the biggest flock has to lose or gain within a second to count
+    pub threshold: f32,
+    /// Oldest first, within [`WINDOW_TICKS`]
+    #[serde(skip)]
+    history: VecDeque<FlockShape>,
+    /// Oldest first
+    #[serde(skip)]
+    pub highlights: Vec<Highlight>,
+    #[serde(skip)]
+    last: Option<u64>,
+}
+
+impl Default for HighlightDetector {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold: 0.3,
+            history: VecDeque::new(),
+            highlights: Vec::new(),
+            last: None,
+        }
+    }
+}
+
+impl HighlightDetector {
+    /// Look at the flocks after `tick`, returning the kind of highlight if this is one.
+    pub fn update(&mut self, boids: &BoidStore, radius: f32, tick: u64) -> Option<HighlightKind> {
+        if !self.enabled || tick % SAMPLE_TICKS != 0 {
+            return None;
+        }
+
+        let sizes = flock_sizes(boids, radius);
+        let shape = FlockShape {
+            tick,
+            flocks: sizes.iter().filter(|size| **size >= MIN_FLOCK_SIZE).count(),
+            largest: sizes.iter().copied().max().unwrap_or(0),
+            boids: boids.len(),
+        };
+        while self
+            .history
+            .front()
+            .is_some_and(|old| tick - old.tick > WINDOW_TICKS)
+        {
+            self.history.pop_front();
+        }
+        let kind = self
+            .history
+            .front()
+            .and_then(|old| self.classify(old, &shape));
+        self.history.push_back(shape);
+
+        let kind = kind.filter(|_| {
+            self.last
+                .map_or(true, |last| tick.saturating_sub(last) >= COOLDOWN_TICKS)
+        })?;
+        info!(tick, kind = kind.label(), "highlight");
+        self.last = Some(tick);
+        if self.highlights.len() == MAX_HIGHLIGHTS {
+            self.highlights.remove(0);
+        }
+        self.highlights.push(Highlight { tick, kind });
+        Some(kind)
+    }
+
+    fn classify(&self, old: &FlockShape, new: &FlockShape) -> Option<HighlightKind> {
+        let boids = old.boids.max(new.boids);
+        if boids == 0 {
+            return None;
+        }
+        let change = (new.largest as f32 - old.largest as f32) / boids as f32;
+        if new.flocks > old.flocks && change <= -self.threshold {
+            Some(HighlightKind::Scatter)
+        } else if new.flocks < old.flocks && change >= self.threshold {
+            Some(HighlightKind::Merge)
+        } else {
+            None
+        }
+    }
+
+    /// Forget the flocks seen so far, after the flock jumped to another state (like the start of
+    /// a replay) that shouldn't be compared against them.
+    pub fn interrupt(&mut self) {
+        self.history.clear();
+    }
+
+    /// `replayable` is the ticks of the session the replay buffer covers, `exporting` whether
+    /// a clip is being exported already.
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    pub fn draw_panel(
+        &mut self,
+        ui: &mut Ui,
+        replayable: Option<RangeInclusive<u64>>,
+        exporting: bool,
+    ) -> Option<HighlightAction> {
+        ui.checkbox(&mut self.enabled, "Detect Highlights")
+            .on_hover_text("Marks big scatters and flock merges on the timeline");
+        ui.add(egui::Slider::new(&mut self.threshold, 0.05..=0.9).text("Threshold"))
+            .on_hover_text(
+                "The share of the boids the biggest flock has to lose or gain within a second",
+            );
+        if self.highlights.is_empty() {
+            ui.label("No highlights yet");
+            return None;
+        }
+
+        let mut action = None;
+        egui::ScrollArea::vertical()
+            .max_height(150.0)
+            .show(ui, |ui| {
+                for highlight in self.highlights.iter().rev() {
+                    let clip = replayable
+                        .as_ref()
+                        .and_then(|replayable| highlight.clip(replayable));
+                    ui.horizontal(|ui| {
+                        ui.label(format!(
+                            "{} at {}",
+                            highlight.kind.label(),
+                            format_ticks(highlight.tick)
+                        ));
+                        let Some(clip) = clip else {
+                            ui.weak("(not in the replay buffer)");
+                            return;
+                        };
+                        if ui.small_button("Watch").clicked() {
+                            action = Some(HighlightAction::Watch(clip.clone()));
+                        }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        if ui
+                            .add_enabled(!exporting, egui::Button::new("Export GIF").small())
+                            .on_hover_text("Plays the clip around the highlight through once, then saves it as a GIF")
+                            .on_disabled_hover_text("Another clip is being exported")
+                            .clicked()
+                        {
+                            action = Some(HighlightAction::Export(clip));
+                        }
+                    });
+                }
+            });
+        if ui.button("Clear").clicked() {
+            self.highlights.clear();
+        }
+        action
+    }
+}
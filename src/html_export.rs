@@ -0,0 +1,164 @@
+use base64::Engine;
+use egui::{Pos2, Rect};
+
+use crate::{boid_store::BoidStore, game::TICKS_PER_SECOND};
+
+/// Only every few ticks are kept, the player doesn't need the full tick rate to look smooth.
+const TICKS_PER_FRAME: u64 = 2;
+/// Bytes per boid in a frame: x and y as `i16` world units and the heading as a `u8`.
+const BYTES_PER_BOID: usize = 5;
+
+/// Boid positions captured while a replay plays, to bake into a page anyone can open in a browser.
+///
+/// The page can't run the simulation itself, so rather than the recorded inputs it gets where
+/// every boid was, quantized to whole world units.
+#[derive(Default)]
+pub struct ReplayCapture {
+    /// Per frame: the boid count as a `u16`, then every boid
+    frames: Vec<u8>,
+    frame_count: usize,
+    ticks: u64,
+    /// Everywhere a boid went, so the page can fit the whole run on screen
+    bounds: Option<Rect>,
+}
+
+impl ReplayCapture {
+    pub fn record(&mut self, boids: &BoidStore) {
+        self.ticks += 1;
+        if (self.ticks - 1) % TICKS_PER_FRAME != 0 {
+            return;
+        }
+
+        let count = boids.len().min(u16::MAX as usize);
+        self.frames.extend((count as u16).to_le_bytes());
+        self.frames.reserve(count * BYTES_PER_BOID);
+        for boid in boids.iter().take(count) {
+            let position = boid.position.round();
+            self.frames
+                .extend((position.x.clamp(-32768.0, 32767.0) as i16).to_le_bytes());
+            self.frames
+                .extend((position.y.clamp(-32768.0, 32767.0) as i16).to_le_bytes());
+            let turns = boid.velocity.angle() / std::f32::consts::TAU;
+            self.frames.push((turns.rem_euclid(1.0) * 256.0) as u8);
+
+            let point = Rect::from_center_size(position, egui::Vec2::ZERO);
+            self.bounds = Some(self.bounds.map_or(point, |bounds| bounds.union(point)));
+        }
+        self.frame_count += 1;
+    }
+
+    /// A standalone page with the captured frames and a small player with a scrubber.
+    pub fn to_html(&self) -> String {
+        let bounds = self
+            .bounds
+            .unwrap_or(Rect::from_center_size(Pos2::ZERO, egui::Vec2::splat(1.0)))
+            .expand(20.0);
+        let data = base64::engine::general_purpose::STANDARD.encode(&self.frames);
+        PLAYER_TEMPLATE
+            .replace(
+                "{FPS}",
+                &(TICKS_PER_SECOND / TICKS_PER_FRAME as f32).to_string(),
+            )
+            .replace(
+                "{BOUNDS}",
+                &format!(
+                    "[{}, {}, {}, {}]",
+                    bounds.min.x, bounds.min.y, bounds.max.x, bounds.max.y
+                ),
+            )
+            .replace("{FRAMES}", &self.frame_count.to_string())
+            .replace("{DATA}", &data)
+    }
+}
+
+const PLAYER_TEMPLATE: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Boids Replay</title>
+<style>
+  body { margin: 0; background: #1b1b1b; color: #ddd; font-family: sans-serif; }
+  canvas { display: block; width: 100vw; height: calc(100vh - 40px); }
+  #controls { display: flex; gap: 8px; align-items: center; height: 40px; padding: 0 8px; }
+  #scrubber { flex: 1; }
+</style>
+</head>
+<body>
+<canvas id="view"></canvas>
+<div id="controls">
+  <button id="play">Pause</button>
+  <input id="scrubber" type="range" min="0" max="0" value="0">
+  <span id="time"></span>
+</div>
+<script>
+const FPS = {FPS};
+const BOUNDS = {BOUNDS};
+const FRAMES = {FRAMES};
+const bytes = Uint8Array.from(atob("{DATA}"), c => c.charCodeAt(0));
+const data = new DataView(bytes.buffer);
+const offsets = [];
+for (let offset = 0; offsets.length < FRAMES; offset += 2 + data.getUint16(offset, true) * 5) {
+  offsets.push(offset);
+}
+
+const canvas = document.getElementById("view");
+const context = canvas.getContext("2d");
+const scrubber = document.getElementById("scrubber");
+const playButton = document.getElementById("play");
+const time = document.getElementById("time");
+scrubber.max = Math.max(FRAMES - 1, 0);
+let frame = 0;
+let playing = true;
+
+function draw() {
+  canvas.width = canvas.clientWidth;
+  canvas.height = canvas.clientHeight;
+  const [left, top, right, bottom] = BOUNDS;
+  const scale = Math.min(canvas.width / (right - left), canvas.height / (bottom - top));
+  context.setTransform(scale, 0, 0, scale,
+    (canvas.width - (right - left) * scale) / 2 - left * scale,
+    (canvas.height - (bottom - top) * scale) / 2 - top * scale);
+  context.clearRect(left, top, right - left, bottom - top);
+  context.fillStyle = "#1b1b1b";
+  context.fillRect(left, top, right - left, bottom - top);
+  context.fillStyle = "#e0e0e0";
+  if (FRAMES > 0) {
+    let offset = offsets[frame];
+    const count = data.getUint16(offset, true);
+    offset += 2;
+    for (let i = 0; i < count; i++, offset += 5) {
+      const x = data.getInt16(offset, true);
+      const y = data.getInt16(offset + 2, true);
+      const angle = data.getUint8(offset + 4) / 256 * 2 * Math.PI;
+      const size = 6 / scale;
+      context.beginPath();
+      context.moveTo(x + Math.cos(angle) * size * 2, y + Math.sin(angle) * size * 2);
+      context.lineTo(x + Math.cos(angle + 2.5) * size, y + Math.sin(angle + 2.5) * size);
+      context.lineTo(x + Math.cos(angle - 2.5) * size, y + Math.sin(angle - 2.5) * size);
+      context.fill();
+    }
+  }
+  scrubber.value = frame;
+  time.textContent = (frame / FPS).toFixed(1) + "s / " + (FRAMES / FPS).toFixed(1) + "s";
+}
+
+playButton.onclick = () => {
+  playing = !playing;
+  playButton.textContent = playing ? "Pause" : "Play";
+};
+scrubber.oninput = () => {
+  frame = Number(scrubber.value);
+  draw();
+};
+setInterval(() => {
+  if (playing && FRAMES > 0) {
+    frame = (frame + 1) % FRAMES;
+    draw();
+  }
+}, 1000 / FPS);
+window.onresize = draw;
+draw();
+</script>
+</body>
+</html>
+"##;
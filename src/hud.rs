@@ -0,0 +1,205 @@
+use egui::{Align2, Color32, FontId, Id, Pos2, Rect, RichText, Sense, Stroke, Ui, Vec2};
+use tracing::Level;
+
+use crate::{
+    log_viewer::{level_color, recent_messages},
+    metrics::Regime,
+};
+
+/// How many log lines the ticker shows.
+const TICKER_LINES: usize = 3;
+const COMPASS_RADIUS: f32 = 36.0;
+/// Weight of the newest frame time in the smoothed FPS.
+const FPS_SMOOTHING: f32 = 0.05;
+
+/// An overlay that can float over the simulation area.
+#[derive(Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum HudElement {
+    Fps,
+    Stats,
+    /// Points where the flock is heading on average
+    Compass,
+    /// The latest log messages
+    EventTicker,
+}
+
+impl HudElement {
+    const ALL: [HudElement; 4] = [
+        HudElement::Fps,
+        HudElement::Stats,
+        HudElement::Compass,
+        HudElement::EventTicker,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            HudElement::Fps => "FPS",
+            HudElement::Stats => "Stats",
+            HudElement::Compass => "Compass Rose",
+            HudElement::EventTicker => "Event Ticker",
+        }
+    }
+
+    /// Where the element sits until the user drags it somewhere else, relative to the simulation area.
+    fn default_offset(self) -> Vec2 {
+        match self {
+            HudElement::Fps => Vec2::new(10.0, 40.0),
+            HudElement::Stats => Vec2::new(10.0, 70.0),
+            HudElement::Compass => Vec2::new(1600.0, 40.0),
+            HudElement::EventTicker => Vec2::new(10.0, 870.0),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct HudWidget {
+    pub element: HudElement,
+    pub visible: bool,
+    /// Top left corner relative to the top left of the simulation area
+    pub offset: Vec2,
+}
+
+/// What the HUD elements show, gathered by the app every frame.
+pub struct HudStats {
+    pub boids: usize,
+    pub average_velocity: Vec2,
+    pub milling: f32,
+    pub regime: Regime,
+    pub tick: u64,
+}
+
+/// Which overlays are shown and where, persisted with the rest of the app state.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct HudLayout {
+    widgets: Vec<HudWidget>,
+    #[serde(skip)]
+    smoothed_frame_time: f32,
+}
+
+impl Default for HudLayout {
+    fn default() -> Self {
+        Self {
+            widgets: HudElement::ALL
+                .into_iter()
+                .map(|element| HudWidget {
+                    element,
+                    visible: false,
+                    offset: element.default_offset(),
+                })
+                .collect(),
+            smoothed_frame_time: 0.0,
+        }
+    }
+}
+
+impl HudLayout {
+    /// Checkboxes for every element, for the View menu.
+    pub fn draw_menu(&mut self, ui: &mut Ui) {
+        // Layouts saved before an element existed don't know about it yet
+        for element in HudElement::ALL {
+            if !self.widgets.iter().any(|widget| widget.element == element) {
+                self.widgets.push(HudWidget {
+                    element,
+                    visible: false,
+                    offset: element.default_offset(),
+                });
+            }
+        }
+
+        for widget in &mut self.widgets {
+            ui.checkbox(&mut widget.visible, widget.element.label());
+        }
+        if ui.button("Reset Layout").clicked() {
+            for widget in &mut self.widgets {
+                widget.offset = widget.element.default_offset();
+            }
+        }
+    }
+
+    /// Draw the visible elements over `rect`, letting the user drag them around.
+    pub fn show(&mut self, ctx: &egui::Context, rect: &Rect, stats: &HudStats) {
+        let frame_time = ctx.input(|i| i.unstable_dt);
+        self.smoothed_frame_time += (frame_time - self.smoothed_frame_time) * FPS_SMOOTHING;
+        let fps = 1.0 / self.smoothed_frame_time.max(f32::EPSILON);
+
+        for widget in self.widgets.iter_mut().filter(|widget| widget.visible) {
+            let id = Id::new(("hud", widget.element.label()));
+            // Pinned to the simulation area, egui only reports the drag and the offset moves it
+            let area = egui::Area::new(id)
+                .current_pos(rect.min + widget.offset)
+                .movable(true)
+                .show(ctx, |ui| {
+                    // Otherwise labels grab the drag to select text
+                    ui.style_mut().interaction.selectable_labels = false;
+                    egui::Frame::popup(ui.style())
+                        .show(ui, |ui| draw_element(ui, widget.element, stats, fps))
+                });
+
+            if area.response.dragged() {
+                widget.offset += area.response.drag_delta();
+            }
+        }
+    }
+}
+
+fn draw_element(ui: &mut Ui, element: HudElement, stats: &HudStats, fps: f32) {
+    match element {
+        HudElement::Fps => {
+            ui.label(RichText::new(format!("{fps:.0} fps")).monospace());
+        }
+        HudElement::Stats => {
+            ui.label(RichText::new(format!("Boids {}", stats.boids)).monospace());
+            ui.label(
+                RichText::new(format!("Avg Speed {:.2}", stats.average_velocity.length()))
+                    .monospace(),
+            );
+            ui.label(RichText::new(format!("Milling {:.2}", stats.milling)).monospace());
+            ui.label(RichText::new(format!("Regime {}", stats.regime.label())).monospace());
+            ui.label(RichText::new(format!("Tick {}", stats.tick)).monospace());
+        }
+        HudElement::Compass => draw_compass(ui, stats.average_velocity),
+        HudElement::EventTicker => {
+            let messages = recent_messages(TICKER_LINES, Level::INFO);
+            if messages.is_empty() {
+                ui.weak("No events yet");
+            }
+            for (level, message) in messages {
+                ui.label(RichText::new(message).monospace().color(level_color(level)));
+            }
+        }
+    }
+}
+
+fn draw_compass(ui: &mut Ui, heading: Vec2) {
+    let size = Vec2::splat(COMPASS_RADIUS * 2.0 + 20.0);
+    let (response, painter) = ui.allocate_painter(size, Sense::hover());
+    let center = response.rect.center();
+    let stroke = Stroke::new(1.0, Color32::GRAY);
+
+    painter.circle_stroke(center, COMPASS_RADIUS, stroke);
+    // Screen y points down, so north is up the screen
+    for (label, direction) in [
+        ("N", Vec2::new(0.0, -1.0)),
+        ("E", Vec2::new(1.0, 0.0)),
+        ("S", Vec2::new(0.0, 1.0)),
+        ("W", Vec2::new(-1.0, 0.0)),
+    ] {
+        let position: Pos2 = center + direction * (COMPASS_RADIUS + 8.0);
+        painter.text(
+            position,
+            Align2::CENTER_CENTER,
+            label,
+            FontId::proportional(11.0),
+            Color32::GRAY,
+        );
+    }
+
+    if heading != Vec2::ZERO {
+        painter.arrow(
+            center,
+            heading.normalized() * COMPASS_RADIUS * 0.9,
+            Stroke::new(2.5, Color32::WHITE),
+        );
+    }
+}
@@ -0,0 +1,78 @@
+#[cfg(feature = "gui")]
+use egui::Ui;
+
+use crate::{
+    boid_store::{BoidMut, BoidStore},
+    math::{Pos2, Vec2},
+};
+
+/// A one-off change in velocity for every boid within `radius` of `center`, like a gust of wind.
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Impulse {
+    pub center: Pos2,
+    pub radius: f32,
+    pub velocity: Vec2,
+}
+
+impl Impulse {
+    /// Push `boid` if the impulse reaches it. Its speed limit reins it back in over the next
+    /// tick, but it keeps the new heading.
+    pub fn push(&self, boid: &mut BoidMut<'_>) {
+        if self.center.distance_sq(*boid.position) <= self.radius * self.radius {
+            *boid.velocity += self.velocity;
+        }
+    }
+
+    pub fn apply(&self, boids: &mut BoidStore) {
+        for mut boid in boids {
+            self.push(&mut boid);
+        }
+    }
+}
+
+/// Settings used for the next gust blown with the gust tool.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct GustBrush {
+    pub radius: f32,
+    /// Velocity the gust adds per world unit dragged
+    pub strength: f32,
+}
+
+impl Default for GustBrush {
+    fn default() -> Self {
+        Self {
+            radius: 120.0,
+            strength: 0.05,
+        }
+    }
+}
+
+impl GustBrush {
+    /// The gust blown by dragging from `start` to `end`.
+    #[cfg(feature = "gui")]
+    pub fn impulse(&self, start: Pos2, end: Pos2) -> Impulse {
+        Impulse {
+            center: start,
+            radius: self.radius,
+            velocity: (end - start) * self.strength,
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.label("Gust Radius");
+        ui.add(
+            egui::DragValue::new(&mut self.radius)
+                .speed(1.0)
+                .range(1.0..=1000.0),
+        );
+        ui.label("Gust Strength");
+        ui.add(
+            egui::DragValue::new(&mut self.strength)
+                .speed(0.001)
+                .range(0.0..=1.0),
+        )
+        .on_hover_text("How much faster boids go per unit dragged");
+    }
+}
@@ -0,0 +1,258 @@
+#[cfg(feature = "gui")]
+use std::time::Duration;
+
+#[cfg(feature = "gui")]
+use egui::{RichText, Ui};
+
+use crate::{boid_store::BoidMut, math::Vec2};
+
+#[cfg(feature = "gui")]
+use crate::boid_store::BoidStore;
+
+/// How a boid's acceleration turns into its new velocity and position each tick.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Integrator {
+    /// Velocity first, then position with the new velocity, cheap and stable enough for flocking
+    #[default]
+    SymplecticEuler,
+    /// Moves with the acceleration over the whole step and averages the last two accelerations
+    /// into the velocity
+    VelocityVerlet,
+    /// Works the forces out again halfway through the step and steps with those, at twice the cost
+    Rk2,
+}
+
+impl Integrator {
+    pub const ALL: [Integrator; 3] = [
+        Integrator::SymplecticEuler,
+        Integrator::VelocityVerlet,
+        Integrator::Rk2,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Integrator::SymplecticEuler => "Symplectic Euler",
+            Integrator::VelocityVerlet => "Velocity Verlet",
+            Integrator::Rk2 => "RK2 (Midpoint)",
+        }
+    }
+
+    /// Step `boid` by `dt` ticks with the acceleration worked out for it this tick.
+    ///
+    /// `midpoint` is the acceleration halfway through the step, which only RK2 needs. Returns how
+    /// much further than its new velocity takes it the boid should move, since only Euler moves
+    /// it with exactly that.
+    pub fn step(
+        self,
+        boid: &mut BoidMut<'_>,
+        midpoint: Option<Vec2>,
+        max_speed: f32,
+        dt: f32,
+    ) -> Vec2 {
+        let acceleration = *boid.acceleration;
+        let (velocity, drift) = match self {
+            Integrator::SymplecticEuler => {
+                let velocity = *boid.velocity + acceleration * dt;
+                (velocity, velocity)
+            }
+            Integrator::VelocityVerlet => (
+                *boid.velocity + (boid.last_acceleration + acceleration) * (dt / 2.0),
+                *boid.velocity + acceleration * (dt / 2.0),
+            ),
+            Integrator::Rk2 => (
+                *boid.velocity + midpoint.unwrap_or(acceleration) * dt,
+                *boid.velocity + acceleration * (dt / 2.0),
+            ),
+        };
+        *boid.velocity = clamp_length(velocity, max_speed);
+        boid.last_acceleration = acceleration;
+        (clamp_length(drift, max_speed) - *boid.velocity) * dt
+    }
+}
+
+fn clamp_length(vector: Vec2, max: f32) -> Vec2 {
+    if vector.length() > max {
+        vector.normalized() * max
+    } else {
+        vector
+    }
+}
+
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct IntegrationParameters {
+    pub integrator: Integrator,
+    /// Ticks of simulated time per tick, forces and speeds are all per tick
+    pub time_step: f32,
+}
+
+impl Default for IntegrationParameters {
+    fn default() -> Self {
+        Self {
+            integrator: Integrator::default(),
+            time_step: 1.0,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl IntegrationParameters {
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.label("Integrator");
+        integrator_combo(ui, "integrator", &mut self.integrator);
+        ui.label("Time Step");
+        ui.add(egui::Slider::new(&mut self.time_step, 0.1..=5.0))
+            .on_hover_text(
+                "Larger steps run faster but show up the difference between integrators",
+            );
+    }
+}
+
+#[cfg(feature = "gui")]
+fn integrator_combo(ui: &mut Ui, id: &str, integrator: &mut Integrator) {
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(integrator.label())
+        .show_ui(ui, |ui| {
+            for choice in Integrator::ALL {
+                ui.selectable_value(integrator, choice, choice.label());
+            }
+        });
+}
+
+/// A copy of the flock stepped with another integrator next to the real one, to see how far
+/// apart they drift and what each costs.
+///
+/// Only the forces and the integration are repeated for the copy, perching, altitude and energy
+/// still come from the real flock's tick, so compare with those turned off.
+#[cfg(feature = "gui")]
+#[derive(Default)]
+pub struct IntegratorComparison {
+    pub enabled: bool,
+    /// What the copy is stepped with
+    pub integrator: Integrator,
+    /// Same boids in the same order as the flock, empty until the comparison starts
+    pub shadow: BoidStore,
+    /// Mean distance between each boid and its copy
+    divergence: f32,
+    /// Time spent on the forces and integration of the flock and of the copy last tick
+    step_times: (Duration, Duration),
+}
+
+#[cfg(feature = "gui")]
+impl IntegratorComparison {
+    /// Start over from `boids` whenever the flock changes size, as the copies no longer line up.
+    pub fn sync(&mut self, boids: &BoidStore) {
+        if self.shadow.len() != boids.len() {
+            self.shadow = boids.clone();
+        }
+    }
+
+    pub fn record(&mut self, boids: &BoidStore, step_times: (Duration, Duration)) {
+        self.step_times = step_times;
+        self.divergence = if boids.is_empty() {
+            0.0
+        } else {
+            boids
+                .iter()
+                .zip(&self.shadow)
+                .map(|(boid, copy)| boid.position.distance(copy.position))
+                .sum::<f32>()
+                / boids.len() as f32
+        };
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui, current: Integrator, boids: &BoidStore) {
+        if ui
+            .checkbox(&mut self.enabled, "Compare Integrators")
+            .on_hover_text("Step a copy of the flock with another integrator alongside it")
+            .changed()
+        {
+            self.shadow = boids.clone();
+        }
+        if !self.enabled {
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{} vs", current.label()));
+            integrator_combo(ui, "comparison_integrator", &mut self.integrator);
+            if ui.button("Resync").clicked() {
+                self.shadow = boids.clone();
+            }
+        });
+        egui::Grid::new("integrator_comparison").show(ui, |ui| {
+            ui.label("Divergence");
+            ui.label(RichText::new(format!("{:.2}", self.divergence)).monospace());
+            ui.end_row();
+
+            let (flock, copy) = self.step_times;
+            ui.label("Step Time");
+            ui.label(
+                RichText::new(format!(
+                    "{:.2} ms vs {:.2} ms",
+                    flock.as_secs_f64() * 1000.0,
+                    copy.as_secs_f64() * 1000.0
+                ))
+                .monospace(),
+            );
+            ui.end_row();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Integrator;
+    use crate::{
+        math::{Pos2, Vec2},
+        simulation::WORLD_BOUNDS,
+        HeadlessSimulation,
+    };
+
+    const TICKS: usize = 300;
+
+    /// Where every boid is and how fast it's going after `TICKS` ticks stepped with `integrator`.
+    fn run(integrator: Integrator) -> (Vec<(Pos2, Vec2)>, f32) {
+        let mut simulation = HeadlessSimulation::new(200, 256);
+        simulation.params.integration.integrator = integrator;
+        for _ in 0..TICKS {
+            simulation.step();
+        }
+        let boids = simulation
+            .flock()
+            .boids()
+            .map(|boid| (boid.position(), boid.velocity()))
+            .collect();
+        (boids, simulation.params.max_speed)
+    }
+
+    #[test]
+    fn integrators_stay_stable() {
+        for integrator in Integrator::ALL {
+            let (boids, max_speed) = run(integrator);
+            for (position, velocity) in boids {
+                assert!(
+                    WORLD_BOUNDS.expand(1.0).contains(position),
+                    "{} flew off to {position:?}",
+                    integrator.label()
+                );
+                assert!(
+                    velocity.length() <= max_speed + 1e-3,
+                    "{} sped up to {velocity:?}",
+                    integrator.label()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn integrators_take_different_paths() {
+        let trajectories = Integrator::ALL.map(|integrator| run(integrator).0);
+        for (i, first) in trajectories.iter().enumerate() {
+            for second in &trajectories[i + 1..] {
+                assert_ne!(first, second);
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+#[cfg(feature = "gui")]
+use egui::Ui;
+
+use crate::{
+    boid_store::BoidRef,
+    boids_simulation::BoidsSimulationParameters,
+    math::{Pos2, Vec2},
+};
+
+#[cfg(feature = "gui")]
+use crate::group::BoidGroup;
+
+/// How boids of one group react to boids of another within their neighbor radius.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Reaction {
+    /// Treat them like any other neighbor
+    #[default]
+    Flock,
+    /// Head for them without matching their heading, like scavengers trailing a flock
+    Attract,
+    /// Act as if they weren't there, not even keeping out of their way
+    Ignore,
+    /// Get away from them, like prey from a predator
+    Flee,
+}
+
+impl Reaction {
+    #[cfg(feature = "gui")]
+    const ALL: [Reaction; 4] = [
+        Reaction::Flock,
+        Reaction::Attract,
+        Reaction::Ignore,
+        Reaction::Flee,
+    ];
+
+    #[cfg(feature = "gui")]
+    fn label(self) -> &'static str {
+        match self {
+            Reaction::Flock => "Flock",
+            Reaction::Attract => "Attract",
+            Reaction::Ignore => "Ignore",
+            Reaction::Flee => "Flee",
+        }
+    }
+}
+
+/// The reaction of boids in group `from` to boids in group `to`.
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Interaction {
+    pub from: u32,
+    pub to: u32,
+    pub reaction: Reaction,
+}
+
+/// Reactions between every pair of groups, so groups can chase, trail and run from each other.
+///
+/// Pairs that aren't listed flock as usual, as do boids without a group.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct InteractionMatrix {
+    /// Only reactions other than [`Reaction::Flock`]
+    pub pairs: Vec<Interaction>,
+    /// Strength of attracting and fleeing compared to the flocking rules
+    pub weight: f32,
+}
+
+impl Default for InteractionMatrix {
+    fn default() -> Self {
+        Self {
+            pairs: Vec::new(),
+            weight: 1.5,
+        }
+    }
+}
+
+impl InteractionMatrix {
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    pub fn reaction(&self, from: Option<u32>, to: Option<u32>) -> Reaction {
+        let (Some(from), Some(to)) = (from, to) else {
+            return Reaction::Flock;
+        };
+        self.pairs
+            .iter()
+            .find(|pair| pair.from == from && pair.to == to)
+            .map_or(Reaction::Flock, |pair| pair.reaction)
+    }
+
+    pub fn set(&mut self, from: u32, to: u32, reaction: Reaction) {
+        self.pairs
+            .retain(|pair| !(pair.from == from && pair.to == to));
+        if reaction != Reaction::Flock {
+            self.pairs.push(Interaction { from, to, reaction });
+        }
+    }
+
+    /// Go back to `from` flocking with every group.
+    pub fn clear_row(&mut self, from: u32) {
+        self.pairs.retain(|pair| pair.from != from);
+    }
+
+    /// Forget the reactions to and from a group that's gone.
+    pub fn remove_group(&mut self, id: u32) {
+        self.pairs.retain(|pair| pair.from != id && pair.to != id);
+    }
+
+    /// Steer towards the neighbors `boid` is attracted to and away from the ones it flees.
+    pub fn force(
+        &self,
+        boid: BoidRef<'_>,
+        neighbors: &[BoidRef<'_>],
+        params: &BoidsSimulationParameters,
+    ) -> Vec2 {
+        if self.is_empty() {
+            return Vec2::ZERO;
+        }
+
+        let mut attract = (Vec2::ZERO, 0);
+        let mut flee = (Vec2::ZERO, 0);
+        for other in neighbors {
+            let distance = boid.position.distance(other.position);
+            if distance <= 0.0 || distance >= params.neighbor_radius {
+                continue;
+            }
+            match self.reaction(boid.group, other.group) {
+                Reaction::Attract => {
+                    attract.0 += other.position.to_vec2();
+                    attract.1 += 1;
+                }
+                Reaction::Flee => {
+                    // Closer boids are more urgent to get away from
+                    flee.0 += (boid.position - other.position) / distance.powi(2);
+                    flee.1 += 1;
+                }
+                Reaction::Flock | Reaction::Ignore => {}
+            }
+        }
+
+        let mut force = Vec2::ZERO;
+        if attract.1 > 0 {
+            let target = Pos2::ZERO + attract.0 / attract.1 as f32;
+            force += boid.calculate_seek_force(target, self.weight, params);
+        }
+        if flee.1 > 0 && flee.0 != Vec2::ZERO {
+            force += boid.calculate_heading_force(flee.0.normalized(), self.weight, params);
+        }
+        force
+    }
+
+    /// A grid with a row for each group's reactions to every group.
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui, groups: &[BoidGroup]) {
+        if groups.is_empty() {
+            ui.label("Add groups to set how they react to each other");
+            return;
+        }
+
+        ui.label("Interaction Weight");
+        ui.add(egui::DragValue::new(&mut self.weight).speed(0.05));
+        egui::Grid::new("group_interactions").show(ui, |ui| {
+            ui.label("Reacts to →");
+            for to in groups {
+                ui.label(&to.name);
+            }
+            ui.end_row();
+            for from in groups {
+                ui.label(&from.name);
+                for to in groups {
+                    let mut reaction = self.reaction(Some(from.id), Some(to.id));
+                    egui::ComboBox::from_id_salt(("group_interaction", from.id, to.id))
+                        .selected_text(reaction.label())
+                        .width(70.0)
+                        .show_ui(ui, |ui| {
+                            for choice in Reaction::ALL {
+                                ui.selectable_value(&mut reaction, choice, choice.label());
+                            }
+                        });
+                    if reaction != self.reaction(Some(from.id), Some(to.id)) {
+                        self.set(from.id, to.id, reaction);
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    }
+}
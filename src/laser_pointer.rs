@@ -0,0 +1,69 @@
+use egui::{Color32, Id, LayerId, Order, PointerButton, Pos2, Stroke};
+
+/// How long a stroke stays on screen after it was drawn, in seconds.
+const FADE_SECONDS: f64 = 3.0;
+const LASER_COLOR: Color32 = Color32::from_rgb(255, 40, 120);
+
+/// A point of a stroke and when it was drawn.
+struct LaserPoint {
+    position: Pos2,
+    time: f64,
+}
+
+/// Fading strokes drawn over everything for live presentations.
+///
+/// The strokes live in screen space and never touch the simulation, the camera or the tools.
+#[derive(Default)]
+pub struct LaserPointer {
+    strokes: Vec<Vec<LaserPoint>>,
+    drawing: bool,
+}
+
+impl LaserPointer {
+    /// Extend the current stroke while `active` (the laser binding is held), drop faded ones and
+    /// draw the rest.
+    pub fn update(&mut self, ctx: &egui::Context, active: bool) {
+        let (time, pointer, primary_down) = ctx.input(|i| {
+            (
+                i.time,
+                i.pointer.latest_pos(),
+                i.pointer.button_down(PointerButton::Primary),
+            )
+        });
+
+        match pointer.filter(|_| primary_down && active) {
+            Some(position) => {
+                if !self.drawing {
+                    self.strokes.push(Vec::new());
+                    self.drawing = true;
+                }
+                if let Some(stroke) = self.strokes.last_mut() {
+                    stroke.push(LaserPoint { position, time });
+                }
+            }
+            None => self.drawing = false,
+        }
+
+        for stroke in &mut self.strokes {
+            stroke.retain(|point| time - point.time < FADE_SECONDS);
+        }
+        self.strokes.retain(|stroke| !stroke.is_empty());
+        if self.strokes.is_empty() {
+            return;
+        }
+
+        let painter = ctx.layer_painter(LayerId::new(Order::Foreground, Id::new("laser_pointer")));
+        for stroke in &self.strokes {
+            for pair in stroke.windows(2) {
+                let age = (time - pair[1].time) / FADE_SECONDS;
+                let color = LASER_COLOR.gamma_multiply(1.0 - age as f32);
+                painter.line_segment(
+                    [pair[0].position, pair[1].position],
+                    Stroke::new(4.0, color),
+                );
+            }
+        }
+        // Keep repainting so the strokes fade out smoothly
+        ctx.request_repaint();
+    }
+}
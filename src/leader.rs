@@ -0,0 +1,124 @@
+#[cfg(feature = "gui")]
+use egui::Ui;
+
+use crate::{
+    boid_store::{BoidRef, BoidStore},
+    boids_simulation::BoidsSimulationParameters,
+    math::{Pos2, Vec2},
+};
+
+#[cfg(feature = "gui")]
+use crate::math::Color32;
+
+/// Leaders are drawn in this instead of their usual color.
+#[cfg(feature = "gui")]
+pub const LEADER_COLOR: Color32 = Color32::from_rgb(255, 215, 0);
+
+/// A few boids the rest of the flock follows, tucking in behind whichever is nearest.
+///
+/// The leaders are the oldest boids, the ones with the lowest ids, so picking them needs nothing
+/// remembered between ticks and a leader that's caught is replaced by the next oldest.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct LeaderParameters {
+    /// 0 turns following off
+    pub count: usize,
+    pub follow_weight: f32,
+    /// How far behind a leader followers aim for
+    pub follow_distance: f32,
+    /// How much bigger leaders are drawn
+    pub leader_size: f32,
+}
+
+impl Default for LeaderParameters {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            follow_weight: 1.0,
+            follow_distance: 40.0,
+            leader_size: 1.5,
+        }
+    }
+}
+
+/// Where a leader is, for the followers to find the nearest one.
+#[derive(Clone, Copy)]
+pub struct Leader {
+    pub id: u64,
+    pub position: Pos2,
+    pub velocity: Vec2,
+}
+
+impl LeaderParameters {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.label("Leader Count");
+        ui.add(egui::DragValue::new(&mut self.count).range(0..=20))
+            .on_hover_text("How many of the oldest boids lead, 0 for none");
+        ui.add_enabled_ui(self.count > 0, |ui| {
+            ui.label("Follow Weight");
+            ui.add(egui::DragValue::new(&mut self.follow_weight).speed(0.05));
+            ui.label("Follow Distance");
+            ui.add(egui::DragValue::new(&mut self.follow_distance).range(0.0..=300.0))
+                .on_hover_text("How far behind the nearest leader the rest of the flock keeps");
+            ui.label("Leader Size");
+            ui.add(egui::Slider::new(&mut self.leader_size, 1.0..=3.0));
+        });
+    }
+
+    /// The highest id a leader in `boids` has, every boid with an id up to it leads. `None` when
+    /// nobody does.
+    pub fn cutoff(&self, boids: &BoidStore) -> Option<u64> {
+        if self.count == 0 || boids.is_empty() {
+            return None;
+        }
+        let mut ids: Vec<u64> = boids.iter().map(|boid| boid.id).collect();
+        let nth = self.count.min(ids.len()) - 1;
+        Some(*ids.select_nth_unstable(nth).1)
+    }
+
+    /// Every leader in `boids`.
+    pub fn leaders(&self, boids: &BoidStore) -> Vec<Leader> {
+        let Some(cutoff) = self.cutoff(boids) else {
+            return Vec::new();
+        };
+        boids
+            .iter()
+            .filter(|boid| boid.id <= cutoff)
+            .map(|boid| Leader {
+                id: boid.id,
+                position: boid.position,
+                velocity: boid.velocity,
+            })
+            .collect()
+    }
+
+    /// The pull on `boid` towards a spot behind the nearest of `leaders`, nothing for the leaders
+    /// themselves.
+    pub fn force(
+        &self,
+        boid: BoidRef<'_>,
+        leaders: &[Leader],
+        params: &BoidsSimulationParameters,
+    ) -> Vec2 {
+        if leaders.iter().any(|leader| leader.id == boid.id) {
+            return Vec2::ZERO;
+        }
+        let nearest = leaders
+            .iter()
+            .map(|leader| (leader, leader.position.distance(boid.position)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+        let Some((leader, _)) = nearest else {
+            return Vec2::ZERO;
+        };
+        let behind = leader.position - leader.velocity.normalized() * self.follow_distance;
+        // Ease off close to the spot so followers fall in behind rather than circling it
+        let distance = behind.distance(boid.position);
+        let easing = if self.follow_distance > 0.0 {
+            (distance / self.follow_distance).min(1.0)
+        } else {
+            1.0
+        };
+        boid.calculate_seek_force(behind, self.follow_weight * easing, params)
+    }
+}
@@ -0,0 +1,191 @@
+use std::f32::consts::TAU;
+
+use egui::Ui;
+
+use crate::{boids_simulation::BoidsSimulationParameters, game::TICKS_PER_SECOND};
+
+/// The shape an LFO swings its parameter in.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Waveform {
+    #[default]
+    Sine,
+    Triangle,
+}
+
+impl Waveform {
+    /// Between -1 and 1, starting from 0 and rising at `phase` 0.
+    fn sample(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Sine => (phase * TAU).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * ((phase + 0.25).fract() - 0.5).abs(),
+        }
+    }
+}
+
+/// A low frequency oscillator swinging one parameter back and forth around where it was set.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Lfo {
+    pub enabled: bool,
+    /// Label of the parameter, see [`BoidsSimulationParameters::blendable_mut`]
+    pub parameter: String,
+    pub waveform: Waveform,
+    /// Swings per second
+    pub rate: f32,
+    /// How far the parameter swings either way, in its own units
+    pub depth: f32,
+}
+
+impl Default for Lfo {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            parameter: "Cohesion Weight".to_owned(),
+            waveform: Waveform::Sine,
+            rate: 0.1,
+            depth: 0.5,
+        }
+    }
+}
+
+/// Where a modulated parameter swings around, and what the LFOs last set it to.
+#[derive(Clone)]
+struct Modulation {
+    parameter: &'static str,
+    center: f32,
+    last: f32,
+}
+
+/// Every LFO, several of which can swing the same parameter at once.
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct LfoBank {
+    pub lfos: Vec<Lfo>,
+    #[serde(skip)]
+    modulations: Vec<Modulation>,
+}
+
+impl LfoBank {
+    /// Set every modulated parameter to where its LFOs have swung it by `tick`.
+    ///
+    /// Changing a modulated parameter by hand moves the middle it swings around, and a parameter
+    /// goes back to its middle once no LFO swings it any more.
+    pub fn step(&mut self, params: &mut BoidsSimulationParameters, tick: u64) {
+        let seconds = tick as f32 / TICKS_PER_SECOND;
+        let mut modulations = Vec::new();
+        for (parameter, value) in params.blendable_mut() {
+            let previous = self
+                .modulations
+                .iter()
+                .find(|modulation| modulation.parameter == parameter);
+            // Unless someone else changed it since, the parameter is where we left it
+            let center = match previous {
+                Some(modulation) if *value == modulation.last => modulation.center,
+                _ => *value,
+            };
+            let mut lfos = self
+                .lfos
+                .iter()
+                .filter(|lfo| lfo.enabled && lfo.parameter == parameter)
+                .peekable();
+            if lfos.peek().is_none() {
+                if previous.is_some() {
+                    *value = center;
+                }
+                continue;
+            }
+            let offset: f32 = lfos
+                .map(|lfo| lfo.depth * lfo.waveform.sample((seconds * lfo.rate).fract()))
+                .sum();
+            // None of the parameters make sense below zero
+            *value = (center + offset).max(0.0);
+            modulations.push(Modulation {
+                parameter,
+                center,
+                last: *value,
+            });
+        }
+        self.modulations = modulations;
+    }
+
+    /// `params` with every parameter the LFOs swing put back in the middle it swings around.
+    ///
+    /// Stepping from these ends up where stepping from `params` would, so a replay only needs to
+    /// record them again when someone changes a parameter by hand.
+    pub fn unmodulated(&self, params: &BoidsSimulationParameters) -> BoidsSimulationParameters {
+        let mut params = params.clone();
+        for (parameter, value) in params.blendable_mut() {
+            let modulation = self
+                .modulations
+                .iter()
+                .find(|modulation| modulation.parameter == parameter);
+            if let Some(modulation) = modulation.filter(|modulation| *value == modulation.last) {
+                *value = modulation.center;
+            }
+        }
+        params
+    }
+
+    /// Forget where the LFOs left the parameters, for when something else sets them all.
+    pub fn forget_modulations(&mut self) {
+        self.modulations.clear();
+    }
+
+    /// Takes the parameters mutably only to list them, it doesn't change them.
+    pub fn draw_panel(&mut self, ui: &mut Ui, params: &mut BoidsSimulationParameters) {
+        let parameters = params.blendable_mut().map(|(label, value)| (label, *value));
+        let mut removed = None;
+        for (index, lfo) in self.lfos.iter_mut().enumerate() {
+            ui.push_id(("lfo", index), |ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut lfo.enabled, "");
+                    egui::ComboBox::from_id_salt("parameter")
+                        .selected_text(&lfo.parameter)
+                        .show_ui(ui, |ui| {
+                            for (label, _) in parameters {
+                                ui.selectable_value(&mut lfo.parameter, label.to_owned(), label);
+                            }
+                        });
+                    if ui.small_button("Remove").clicked() {
+                        removed = Some(index);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut lfo.waveform, Waveform::Sine, "Sine");
+                    ui.radio_value(&mut lfo.waveform, Waveform::Triangle, "Triangle");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Rate");
+                    ui.add(
+                        egui::DragValue::new(&mut lfo.rate)
+                            .speed(0.01)
+                            .range(0.001..=10.0)
+                            .suffix(" Hz"),
+                    );
+                    ui.label("Depth");
+                    ui.add(
+                        egui::DragValue::new(&mut lfo.depth)
+                            .speed(0.01)
+                            .range(0.0..=f32::MAX),
+                    );
+                });
+                if let Some((_, value)) =
+                    parameters.iter().find(|(label, _)| *label == lfo.parameter)
+                {
+                    ui.weak(format!("Now {value:.2}"));
+                }
+            });
+            ui.separator();
+        }
+        if let Some(index) = removed {
+            self.lfos.remove(index);
+        }
+        if ui
+            .button("Add LFO")
+            .on_hover_text("Swing a parameter back and forth on its own, like a flock breathing")
+            .clicked()
+        {
+            self.lfos.push(Lfo::default());
+        }
+    }
+}
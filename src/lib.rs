@@ -1,7 +1,122 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+#[cfg(feature = "gui")]
 mod app;
+#[cfg(feature = "gui")]
 pub use app::BoidsApp;
 
+mod altitude;
+#[cfg(feature = "gui")]
+mod annotation;
+mod arena;
+#[cfg(feature = "gui")]
+mod bindings;
 mod boid;
+mod boid_store;
 mod boids_simulation;
+pub use boids_simulation::BoidsSimulationParameters;
+#[cfg(feature = "gui")]
+mod breakpoint;
+#[cfg(feature = "gui")]
+mod camera;
+#[cfg(feature = "gui")]
+mod camera_path;
+mod chunk;
+#[cfg(all(feature = "gui", not(target_arch = "wasm32")))]
+mod clip_export;
+mod coloring;
+mod crowding;
+mod current_zone;
+mod danger;
+#[cfg(feature = "gui")]
+mod debug_panel;
+mod density;
+mod dominance;
+mod energy;
+mod error;
+#[cfg(feature = "gui")]
+mod experiment;
+#[cfg(feature = "gui")]
+mod flow_field;
+#[cfg(feature = "gui")]
+mod force_field;
+mod game;
+#[cfg(feature = "gui")]
+mod gate;
+mod genome;
+mod ghost;
+mod goal_point;
+mod group;
+mod headless;
+pub use headless::HeadlessSimulation;
+#[cfg(feature = "gui")]
+mod highlight;
+#[cfg(all(feature = "gui", not(target_arch = "wasm32")))]
+mod html_export;
+#[cfg(feature = "gui")]
+mod hud;
+mod impulse;
+mod integrator;
+mod interaction;
+#[cfg(feature = "gui")]
+mod laser_pointer;
+mod leader;
+#[cfg(feature = "gui")]
+mod lfo;
+mod lifecycle;
+#[cfg(feature = "gui")]
+mod log_viewer;
+#[cfg(feature = "gui")]
+pub use log_viewer::init_tracing;
+mod math;
+pub use math::{Pos2, Rect, Vec2};
+#[cfg(feature = "gui")]
+mod metrics;
+mod migration;
+mod mixer;
+mod moving_obstacle;
+#[cfg(feature = "gui")]
+mod net;
+mod observer;
+pub use observer::{FlockState, ObservedBoid};
+mod obstacle;
+pub use obstacle::Obstacle;
+#[cfg(feature = "gui")]
+mod particles;
+mod perception;
+mod perch;
+#[cfg(feature = "gui")]
+mod playlist;
+#[cfg(all(feature = "gui", not(target_arch = "wasm32")))]
+mod plugin;
+mod predator;
+#[cfg(feature = "gui")]
+mod recording_file;
+mod render;
+#[cfg(feature = "gui")]
+mod replay;
+#[cfg(feature = "gui")]
+mod scenario;
+mod simulation;
+mod spatial_grid;
+#[cfg(all(feature = "gui", not(target_arch = "wasm32")))]
+mod species;
+mod split_merge;
+mod steering;
+#[cfg(all(feature = "gui", not(target_arch = "wasm32")))]
+mod svg_export;
+mod temporary_obstacle;
+mod threat;
+pub use threat::Threat;
+#[cfg(feature = "gui")]
+mod timeline;
+#[cfg(feature = "gui")]
+mod toasts;
+#[cfg(feature = "gui")]
+mod tools;
+mod topology;
+#[cfg(feature = "gui")]
+mod tween;
+mod units;
+mod wander;
+mod wind;
@@ -0,0 +1,85 @@
+#[cfg(feature = "gui")]
+use egui::Ui;
+
+use crate::{boid_store::BoidRef, game::TICKS_PER_SECOND};
+
+/// Boids slowing down but seeing further as they get older, so a long run grows a flock of
+/// quick young boids led by far sighted elders.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct LifecycleParameters {
+    pub enabled: bool,
+    /// Seconds until a boid is as old as it gets, it ages steadily until then
+    pub elder_age: f32,
+    /// How much of its max speed an elder keeps
+    pub elder_speed: f32,
+    /// How much further an elder sees its flockmates for alignment and cohesion
+    pub elder_perception: f32,
+}
+
+impl Default for LifecycleParameters {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            elder_age: 120.0,
+            elder_speed: 0.7,
+            elder_perception: 1.5,
+        }
+    }
+}
+
+impl LifecycleParameters {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Enabled")
+            .on_hover_text("Boids slow down and see further as they get older");
+        ui.add_enabled_ui(self.enabled, |ui| {
+            ui.label("Elder Age");
+            ui.add(
+                egui::DragValue::new(&mut self.elder_age)
+                    .range(1.0..=3600.0)
+                    .suffix("s"),
+            )
+            .on_hover_text("How long boids take to grow as old as they get");
+            ui.label("Elder Speed");
+            ui.add(egui::Slider::new(&mut self.elder_speed, 0.1..=1.0))
+                .on_hover_text("The share of their max speed the oldest boids keep");
+            ui.label("Elder Perception");
+            ui.add(egui::Slider::new(&mut self.elder_perception, 1.0..=3.0))
+                .on_hover_text("How much further the oldest boids see for alignment and cohesion");
+        });
+    }
+
+    /// How far `boid` is through growing old, from 0 when it hatched to 1 at the elder age.
+    ///
+    /// Counts whether or not the lifecycle is enabled, for coloring by age.
+    pub fn age_fraction(&self, boid: BoidRef<'_>) -> f32 {
+        let seconds = boid.age as f32 / TICKS_PER_SECOND;
+        (seconds / self.elder_age.max(1.0)).min(1.0)
+    }
+
+    /// How much of the flock's max speed `boid` has at its age.
+    pub fn speed_factor(&self, boid: BoidRef<'_>) -> f32 {
+        if !self.enabled {
+            return 1.0;
+        }
+        1.0 + (self.elder_speed - 1.0) * self.age_fraction(boid)
+    }
+
+    /// How much further than the flock's alignment and cohesion radius `boid` sees at its age.
+    pub fn perception_factor(&self, boid: BoidRef<'_>) -> f32 {
+        if !self.enabled {
+            return 1.0;
+        }
+        1.0 + (self.elder_perception - 1.0) * self.age_fraction(boid)
+    }
+
+    /// The furthest any boid sees relative to the flock's radius, for sizing neighbor searches.
+    pub fn max_perception_factor(&self) -> f32 {
+        if self.enabled {
+            self.elder_perception.max(1.0)
+        } else {
+            1.0
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use std::{collections::VecDeque, fmt::Write as _, sync::Mutex};
+
+use egui::{Color32, RichText};
+use tracing::{field::Field, Event, Level, Subscriber};
+use tracing_subscriber::{filter::Targets, layer::Context, prelude::*, Layer};
+
+/// How many log lines we keep around for the in-app viewer.
+const MAX_LOG_ENTRIES: usize = 1000;
+
+static LOG_ENTRIES: Mutex<VecDeque<LogEntry>> = Mutex::new(VecDeque::new());
+
+pub struct LogEntry {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// The latest `count` messages at `min_level` or above, oldest first.
+pub fn recent_messages(count: usize, min_level: Level) -> Vec<(Level, String)> {
+    let Ok(entries) = LOG_ENTRIES.lock() else {
+        return Vec::new();
+    };
+    let mut recent: Vec<_> = entries
+        .iter()
+        .rev()
+        .filter(|entry| entry.level <= min_level)
+        .take(count)
+        .map(|entry| (entry.level, entry.message.clone()))
+        .collect();
+    recent.reverse();
+    recent
+}
+
+/// Roughly how much memory the captured log lines take up.
+pub fn approximate_log_bytes() -> usize {
+    LOG_ENTRIES.lock().map_or(0, |entries| {
+        entries
+            .iter()
+            .map(|entry| std::mem::size_of::<LogEntry>() + entry.target.len() + entry.message.len())
+            .sum()
+    })
+}
+
+/// Install the global tracing subscriber.
+///
+/// Natively this also prints to stderr (filtered by `RUST_LOG`, defaulting to `info`).
+/// On both native and web every event is captured for the in-app log window.
+pub fn init_tracing() {
+    // Our own crate can be chatty at debug level, everything else (eframe, winit, ...) only at info
+    let collector_filter = Targets::new()
+        .with_target("boids", Level::DEBUG)
+        .with_default(Level::INFO);
+    let registry = tracing_subscriber::registry().with(LogCollector.with_filter(collector_filter));
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use tracing_subscriber::{util::SubscriberInitExt as _, EnvFilter};
+
+        let env_filter =
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+        registry
+            .with(tracing_subscriber::fmt::layer().with_filter(env_filter))
+            .init();
+    }
+
+    // On the web eframe's `WebLogger` already owns the `log` facade, so we only register the collector
+    #[cfg(target_arch = "wasm32")]
+    {
+        tracing::subscriber::set_global_default(registry).ok();
+    }
+}
+
+/// Tracing layer that copies every event into `LOG_ENTRIES`.
+struct LogCollector;
+
+impl<S: Subscriber> Layer<S> for LogCollector {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let entry = LogEntry {
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_owned(),
+            message: visitor.message,
+        };
+
+        if let Ok(mut entries) = LOG_ENTRIES.lock() {
+            if entries.len() >= MAX_LOG_ENTRIES {
+                entries.pop_front();
+            }
+            entries.push_back(entry);
+        }
+    }
+}
+
+/// Flattens an event into a single line: the message followed by any `key=value` fields.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl tracing::field::Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let fields = std::mem::take(&mut self.message);
+            let _ = write!(self.message, "{value:?}{fields}");
+        } else {
+            let _ = write!(self.message, " {}={value:?}", field.name());
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    const ALL: [LogLevel; 5] = [
+        LogLevel::Trace,
+        LogLevel::Debug,
+        LogLevel::Info,
+        LogLevel::Warn,
+        LogLevel::Error,
+    ];
+
+    fn as_level(self) -> Level {
+        match self {
+            LogLevel::Trace => Level::TRACE,
+            LogLevel::Debug => Level::DEBUG,
+            LogLevel::Info => Level::INFO,
+            LogLevel::Warn => Level::WARN,
+            LogLevel::Error => Level::ERROR,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            LogLevel::Trace => "Trace",
+            LogLevel::Debug => "Debug",
+            LogLevel::Info => "Info",
+            LogLevel::Warn => "Warn",
+            LogLevel::Error => "Error",
+        }
+    }
+}
+
+pub fn level_color(level: Level) -> Color32 {
+    match level {
+        Level::ERROR => Color32::RED,
+        Level::WARN => Color32::YELLOW,
+        Level::INFO => Color32::LIGHT_GREEN,
+        Level::DEBUG => Color32::LIGHT_BLUE,
+        Level::TRACE => Color32::GRAY,
+    }
+}
+
+/// Window listing captured log lines, filterable by level and text.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct LogViewer {
+    pub open: bool,
+    min_level: LogLevel,
+    filter: String,
+}
+
+impl Default for LogViewer {
+    fn default() -> Self {
+        Self {
+            open: false,
+            min_level: LogLevel::Info,
+            filter: String::new(),
+        }
+    }
+}
+
+impl LogViewer {
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut open = self.open;
+        egui::Window::new("Log")
+            .open(&mut open)
+            .default_size([600.0, 300.0])
+            .show(ctx, |ui| self.draw(ui));
+        self.open = open;
+    }
+
+    fn draw(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Level")
+                .selected_text(self.min_level.label())
+                .show_ui(ui, |ui| {
+                    for level in LogLevel::ALL {
+                        ui.selectable_value(&mut self.min_level, level, level.label());
+                    }
+                });
+            ui.label("Filter");
+            ui.text_edit_singleline(&mut self.filter);
+            if ui.button("Clear").clicked() {
+                if let Ok(mut entries) = LOG_ENTRIES.lock() {
+                    entries.clear();
+                }
+            }
+        });
+        ui.separator();
+
+        // `Level` orders more verbose levels as greater, so "at least as severe" is `<=`
+        let min_level = self.min_level.as_level();
+        let Ok(entries) = LOG_ENTRIES.lock() else {
+            return;
+        };
+        egui::ScrollArea::vertical()
+            .stick_to_bottom(true)
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for entry in entries.iter().filter(|entry| {
+                    entry.level <= min_level
+                        && (self.filter.is_empty()
+                            || entry.message.contains(&self.filter)
+                            || entry.target.contains(&self.filter))
+                }) {
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            RichText::new(format!("{:5}", entry.level))
+                                .monospace()
+                                .color(level_color(entry.level)),
+                        );
+                        ui.label(RichText::new(&entry.target).monospace().weak());
+                        ui.label(RichText::new(&entry.message).monospace());
+                    });
+                }
+            });
+    }
+}
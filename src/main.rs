@@ -4,9 +4,7 @@
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result {
-    use std::env;
-    env::set_var("RUST_LOG", "info");
-    env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
+    boids::init_tracing(); // Log to stderr (if you run with `RUST_LOG=debug`) and to the in-app log window.
 
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
@@ -33,6 +31,7 @@ fn main() {
 
     // Redirect `log` message to `console.log` and friends:
     eframe::WebLogger::init(log::LevelFilter::Debug).ok();
+    boids::init_tracing();
 
     let web_options = eframe::WebOptions::default();
 
@@ -0,0 +1,7 @@
+//! The geometry and colors the simulation is worked out in.
+//!
+//! These are the same types egui draws with, taken straight from `emath` and `ecolor` so the
+//! simulation doesn't need egui to run.
+
+pub use ecolor::Color32;
+pub use emath::{Pos2, Rect, Rot2, Vec2};
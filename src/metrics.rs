@@ -0,0 +1,262 @@
+#[cfg(feature = "gui")]
+use egui::Ui;
+use tracing::info;
+
+use crate::{
+    boid_store::{BoidRef, BoidStore},
+    math::{Pos2, Vec2},
+    spatial_grid::SpatialGrid,
+};
+
+/// How many ticks in a row a new regime has to be seen before we switch to it, so a flock
+/// hovering around a threshold doesn't flicker between regimes.
+const REGIME_HOLD_TICKS: u32 = 30;
+
+/// Measures of the flock's collective state, taken after every tick.
+#[derive(Clone, Copy, Default)]
+pub struct FlockMetrics {
+    /// How much the boids head the same way, from 0 (every which way) to 1 (all in parallel)
+    pub polarization: f32,
+    /// How much the flock rotates around its center, from 0 (not at all) to 1 (a perfect mill)
+    pub milling: f32,
+}
+
+impl FlockMetrics {
+    /// Resting boids aren't moving with the flock, so only flying ones are measured.
+    pub fn measure(boids: &BoidStore) -> Self {
+        let flying: Vec<BoidRef<'_>> = boids.iter().filter(|boid| boid.resting.is_none()).collect();
+        Self {
+            polarization: polarization(&flying),
+            milling: milling(&flying),
+        }
+    }
+}
+
+/// The collective states a flock is known to settle into.
+#[cfg(feature = "gui")]
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Regime {
+    /// A disordered cloud that sticks together without heading anywhere in particular
+    #[default]
+    Swarm,
+    /// The boids circle around an empty center
+    Torus,
+    /// The boids all head the same way
+    Polarized,
+}
+
+#[cfg(feature = "gui")]
+impl Regime {
+    pub fn label(self) -> &'static str {
+        match self {
+            Regime::Swarm => "Swarm",
+            Regime::Torus => "Torus",
+            Regime::Polarized => "Polarized",
+        }
+    }
+}
+
+/// Where the regimes are split, in terms of [`FlockMetrics`].
+#[cfg(feature = "gui")]
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct RegimeThresholds {
+    /// Polarization above which the flock counts as polarized
+    pub polarized: f32,
+    /// Milling above which the flock counts as a torus
+    pub torus: f32,
+}
+
+#[cfg(feature = "gui")]
+impl Default for RegimeThresholds {
+    fn default() -> Self {
+        Self {
+            polarized: 0.65,
+            torus: 0.5,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl RegimeThresholds {
+    /// A polarized flock has next to no angular momentum and a mill has next to no polarization,
+    /// so whichever is over its threshold wins, with polarization first for the odd flock that's both.
+    pub fn classify(&self, metrics: &FlockMetrics) -> Regime {
+        if metrics.polarization > self.polarized {
+            Regime::Polarized
+        } else if metrics.milling > self.torus {
+            Regime::Torus
+        } else {
+            Regime::Swarm
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.label("Polarized Above");
+        ui.add(egui::Slider::new(&mut self.polarized, 0.0..=1.0));
+        ui.label("Torus Above");
+        ui.add(egui::Slider::new(&mut self.torus, 0.0..=1.0))
+            .on_hover_text("Milling, the flock's angular momentum about its center");
+    }
+}
+
+/// The flock's current regime, logging every change.
+#[cfg(feature = "gui")]
+#[derive(Default)]
+pub struct RegimeTracker {
+    pub current: Regime,
+    /// A different regime the flock has been in lately, and for how many ticks
+    candidate: Option<(Regime, u32)>,
+}
+
+#[cfg(feature = "gui")]
+impl RegimeTracker {
+    pub fn update(&mut self, metrics: &FlockMetrics, thresholds: &RegimeThresholds, tick: u64) {
+        let regime = thresholds.classify(metrics);
+        if regime == self.current {
+            self.candidate = None;
+            return;
+        }
+
+        let ticks = match self.candidate {
+            Some((candidate, ticks)) if candidate == regime => ticks + 1,
+            _ => 1,
+        };
+        if ticks < REGIME_HOLD_TICKS {
+            self.candidate = Some((regime, ticks));
+            return;
+        }
+
+        info!(
+            tick,
+            from = self.current.label(),
+            to = regime.label(),
+            "flock regime changed to {}",
+            regime.label()
+        );
+        self.current = regime;
+        self.candidate = None;
+    }
+}
+
+/// How well boids flowing both ways along `axis` have sorted themselves into lanes, from 0 (mixed
+/// in with each other) to 1 (every boid only has neighbors going its way).
+///
+/// For every flying boid the neighbors within `radius` going its way count for it and those going
+/// the other way against it, and the balance is averaged over every boid with any neighbors.
+pub fn lane_order(boids: &BoidStore, axis: Vec2, radius: f32) -> f32 {
+    let flying: Vec<BoidRef<'_>> = boids.iter().filter(|boid| boid.resting.is_none()).collect();
+    let grid = SpatialGrid::from_positions(flying.iter().map(|boid| boid.position), radius);
+    let mut candidates = Vec::new();
+
+    let mut total = 0.0;
+    let mut counted = 0;
+    for boid in &flying {
+        let forwards = boid.velocity.dot(axis) >= 0.0;
+        grid.candidates(boid.position, radius, &mut candidates);
+        let (mut same, mut opposite) = (0, 0);
+        for other in candidates.iter().map(|index| flying[*index]) {
+            let distance = other.position.distance(boid.position);
+            if distance == 0.0 || distance >= radius {
+                continue;
+            }
+            if (other.velocity.dot(axis) >= 0.0) == forwards {
+                same += 1;
+            } else {
+                opposite += 1;
+            }
+        }
+        if same + opposite > 0 {
+            total += (same as f32 - opposite as f32) / (same + opposite) as f32;
+            counted += 1;
+        }
+    }
+
+    if counted > 0 {
+        (total / counted as f32).max(0.0)
+    } else {
+        0.0
+    }
+}
+
+/// The sizes of the flocks the flying boids make up, where a flock is every boid that can be
+/// reached from another one by hops shorter than `radius`.
+pub fn flock_sizes(boids: &BoidStore, radius: f32) -> Vec<usize> {
+    let flying: Vec<BoidRef<'_>> = boids.iter().filter(|boid| boid.resting.is_none()).collect();
+    let grid = SpatialGrid::from_positions(flying.iter().map(|boid| boid.position), radius);
+    let mut candidates = Vec::new();
+    let mut visited = vec![false; flying.len()];
+    let mut sizes = Vec::new();
+
+    for start in 0..flying.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut stack = vec![start];
+        let mut size = 0;
+        while let Some(index) = stack.pop() {
+            size += 1;
+            let position = flying[index].position;
+            grid.candidates(position, radius, &mut candidates);
+            for &other in &candidates {
+                if !visited[other] && flying[other].position.distance(position) < radius {
+                    visited[other] = true;
+                    stack.push(other);
+                }
+            }
+        }
+        sizes.push(size);
+    }
+    sizes
+}
+
+/// Length of the average heading, where a heading is a boid's velocity scaled to unit length.
+fn polarization(boids: &[BoidRef<'_>]) -> f32 {
+    let headings: Vec<Vec2> = boids
+        .iter()
+        .filter(|boid| boid.velocity != Vec2::ZERO)
+        .map(|boid| boid.velocity.normalized())
+        .collect();
+    if headings.is_empty() {
+        return 0.0;
+    }
+    (headings
+        .iter()
+        .fold(Vec2::ZERO, |sum, heading| sum + *heading)
+        / headings.len() as f32)
+        .length()
+}
+
+/// The flock's angular momentum about its centroid, normalized by what it would be if every boid
+/// circled the centroid in the same direction.
+///
+/// In a wrapping world a flock straddling an edge has its centroid pulled towards the middle, which
+/// reads as less milling than there really is.
+fn milling(boids: &[BoidRef<'_>]) -> f32 {
+    if boids.len() < 2 {
+        return 0.0;
+    }
+
+    let centroid = boids
+        .iter()
+        .fold(Vec2::ZERO, |sum, boid| sum + boid.position.to_vec2())
+        / boids.len() as f32;
+    let centroid = Pos2::ZERO + centroid;
+
+    let mut angular_momentum = 0.0;
+    let mut maximum = 0.0;
+    for boid in boids {
+        let arm = boid.position - centroid;
+        // The z component of the cross product, whose sign says which way the boid goes around
+        angular_momentum += arm.x * boid.velocity.y - arm.y * boid.velocity.x;
+        maximum += arm.length() * boid.velocity.length();
+    }
+
+    if maximum > 0.0 {
+        (angular_momentum / maximum).abs()
+    } else {
+        0.0
+    }
+}
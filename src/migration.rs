@@ -0,0 +1,134 @@
+#[cfg(feature = "gui")]
+use std::f32::consts::TAU;
+
+#[cfg(feature = "gui")]
+use egui::{Painter, Stroke, Ui};
+#[cfg(feature = "gui")]
+use rand::Rng;
+
+use crate::math::Pos2;
+
+#[cfg(feature = "gui")]
+use crate::{
+    camera::Camera,
+    math::{Color32, Rect, Vec2},
+};
+
+#[cfg(feature = "gui")]
+const TARGET_COLOR: Color32 = Color32::from_rgb(255, 165, 0);
+/// Largest change of heading per tick when wandering, in radians.
+#[cfg(feature = "gui")]
+const WANDER_TURN: f32 = 0.05;
+
+/// How the migration target moves through the world.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum MigrationPath {
+    /// A big circle around the origin
+    #[default]
+    Loop,
+    /// A random walk that slowly changes heading
+    Wander,
+}
+
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct MigrationParameters {
+    pub enabled: bool,
+    pub path: MigrationPath,
+    /// How strongly boids seek the target
+    pub weight: f32,
+    /// Radius of the loop path in world units
+    pub loop_radius: f32,
+    /// Average speed of the target in world units per tick
+    pub speed: f32,
+    /// Length of a full year of seasons in ticks
+    pub season_length: f32,
+    /// How much the seasons change the target's speed, 0 keeps the speed constant
+    pub seasonal_variation: f32,
+}
+
+impl Default for MigrationParameters {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: MigrationPath::default(),
+            weight: 0.5,
+            loop_radius: 2000.0,
+            speed: 1.5,
+            season_length: 3600.0,
+            seasonal_variation: 0.8,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl MigrationParameters {
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Enabled")
+            .on_hover_text("Works best in a boundless world");
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.path, MigrationPath::Loop, "Loop");
+            ui.radio_value(&mut self.path, MigrationPath::Wander, "Wander");
+        });
+
+        ui.label("Migration Weight");
+        ui.add(egui::DragValue::new(&mut self.weight).speed(0.05));
+        ui.label("Target Speed");
+        ui.add(egui::DragValue::new(&mut self.speed).speed(0.1));
+        if self.path == MigrationPath::Loop {
+            ui.label("Loop Radius");
+            ui.add(egui::DragValue::new(&mut self.loop_radius));
+        }
+        ui.label("Season Length (ticks)");
+        ui.add(egui::DragValue::new(&mut self.season_length).range(1.0..=f32::MAX));
+        ui.label("Seasonal Variation");
+        ui.add(egui::Slider::new(&mut self.seasonal_variation, 0.0..=1.0));
+    }
+}
+
+/// The goal point the flock migrates towards.
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct MigrationTarget {
+    pub position: Pos2,
+    /// Direction of travel in radians
+    heading: f32,
+    /// Ticks since the migration started, used to work out the season
+    elapsed: u64,
+}
+
+impl MigrationTarget {
+    /// Speed of the target at the current point in the year.
+    ///
+    /// The speed peaks in "spring" and bottoms out in "autumn", like birds rushing to breeding grounds.
+    #[cfg(feature = "gui")]
+    pub fn seasonal_speed(&self, params: &MigrationParameters) -> f32 {
+        let year_fraction = self.elapsed as f32 / params.season_length.max(1.0);
+        params.speed * (1.0 + params.seasonal_variation * (year_fraction * TAU).sin()).max(0.0)
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn update(&mut self, params: &MigrationParameters, rng: &mut impl Rng) {
+        let speed = self.seasonal_speed(params);
+        self.elapsed += 1;
+
+        match params.path {
+            MigrationPath::Loop => {
+                let radius = params.loop_radius.max(1.0);
+                // The heading doubles as the angle around the loop
+                self.heading = (self.heading + speed / radius) % TAU;
+                self.position = Pos2::new(radius * self.heading.cos(), radius * self.heading.sin());
+            }
+            MigrationPath::Wander => {
+                self.heading += rng.gen_range(-WANDER_TURN..WANDER_TURN);
+                self.position += Vec2::angled(self.heading) * speed;
+            }
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        let screen_pos = camera.world_to_screen(rect, self.position);
+        painter.circle_stroke(screen_pos, 12.0, Stroke::new(2.0, TARGET_COLOR));
+        painter.circle_filled(screen_pos, 3.0, TARGET_COLOR);
+    }
+}
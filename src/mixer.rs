@@ -0,0 +1,88 @@
+use std::collections::BTreeSet;
+
+#[cfg(feature = "gui")]
+use egui::Ui;
+
+#[cfg(feature = "gui")]
+use crate::boids_simulation::BoidsSimulationParameters;
+use crate::steering::SteeringRule;
+
+/// The steering rules as channels on a mixing desk, each of which can be muted or soloed
+/// without losing its weight.
+///
+/// Channels go by [`SteeringRule::name`], so plugin rules get one alongside the built-in ones.
+#[derive(Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct BehaviorMixer {
+    muted_rules: BTreeSet<String>,
+    /// While any rule is soloed, every rule that isn't is silenced
+    soloed_rules: BTreeSet<String>,
+}
+
+impl BehaviorMixer {
+    /// Whether the rule called `name` is off for now, by being muted or by another being soloed.
+    pub fn is_silenced(&self, name: &str) -> bool {
+        self.muted_rules.contains(name)
+            || (!self.soloed_rules.is_empty() && !self.soloed_rules.contains(name))
+    }
+
+    /// Whether each of `rules` is silenced, in the same order.
+    pub fn silenced(&self, rules: &[Box<dyn SteeringRule>]) -> Vec<bool> {
+        rules
+            .iter()
+            .map(|rule| self.is_silenced(rule.name()))
+            .collect()
+    }
+
+    /// A channel for each of `rules`, with a fader for the ones that have a weight in `params`.
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(
+        ui: &mut Ui,
+        params: &mut BoidsSimulationParameters,
+        rules: &[Box<dyn SteeringRule>],
+    ) {
+        ui.horizontal_wrapped(|ui| {
+            for rule in rules {
+                let name = rule.name();
+                ui.vertical(|ui| {
+                    if let Some(weight) = rule.weight_mut(params) {
+                        ui.add(
+                            egui::Slider::new(weight, 0.0..=5.0)
+                                .vertical()
+                                .clamping(egui::SliderClamping::Never),
+                        );
+                    }
+                    let mixer = &mut params.mixer;
+                    ui.horizontal(|ui| {
+                        if ui
+                            .selectable_label(mixer.soloed_rules.contains(name), "S")
+                            .on_hover_text("Solo: silence every behavior that isn't soloed")
+                            .clicked()
+                        {
+                            toggle(&mut mixer.soloed_rules, name);
+                        }
+                        if ui
+                            .selectable_label(mixer.muted_rules.contains(name), "M")
+                            .on_hover_text("Mute: turn the behavior off, keeping its weight")
+                            .clicked()
+                        {
+                            toggle(&mut mixer.muted_rules, name);
+                        }
+                    });
+                    if mixer.is_silenced(name) {
+                        ui.weak(name);
+                    } else {
+                        ui.label(name);
+                    }
+                });
+            }
+        });
+    }
+}
+
+#[cfg(feature = "gui")]
+fn toggle(names: &mut BTreeSet<String>, name: &str) {
+    if !names.remove(name) {
+        names.insert(name.to_owned());
+    }
+}
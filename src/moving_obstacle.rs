@@ -0,0 +1,255 @@
+use std::{borrow::Cow, f32::consts::TAU};
+
+#[cfg(feature = "gui")]
+use egui::{Painter, Shape, Stroke, Ui};
+
+use crate::{
+    math::{Pos2, Rot2},
+    obstacle::Obstacle,
+    temporary_obstacle::TemporaryObstacle,
+};
+
+#[cfg(feature = "gui")]
+use crate::{
+    camera::Camera,
+    math::{Color32, Rect},
+};
+
+#[cfg(feature = "gui")]
+const PATH_COLOR: Color32 = Color32::from_rgb(130, 130, 145);
+
+/// How an obstacle placed with the obstacle tool moves.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum MotionKind {
+    #[default]
+    Still,
+    /// Circling the last point shift clicked
+    Orbit,
+    /// Along the points shift clicked, in order
+    Patrol,
+}
+
+/// The way a moving obstacle goes.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum ObstaclePath {
+    /// Circling `pivot` counterclockwise at the distance it was placed from it, like a fan blade
+    Orbit { pivot: Pos2 },
+    /// Through `waypoints` from where it was placed. A looped patrol goes straight back to the
+    /// start from the last waypoint, otherwise it turns around and comes back the way it went.
+    Patrol { waypoints: Vec<Pos2>, looped: bool },
+}
+
+/// An obstacle going round and round a path, somewhere different every tick but always in the
+/// same place at the same time.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct MovingObstacle {
+    /// Where the obstacle starts out, at time 0
+    pub obstacle: Obstacle,
+    pub path: ObstaclePath,
+    /// World units per tick along the path
+    pub speed: f32,
+    /// The obstacle clock when it was placed, so it sets off from where it was drawn
+    pub placed_at: u64,
+}
+
+impl MovingObstacle {
+    /// The obstacle where it is at `clock`, which can be part way through a tick.
+    pub fn at(&self, clock: f32) -> Obstacle {
+        let start = self.obstacle.center();
+        let distance = self.speed * (clock - self.placed_at as f32).max(0.0);
+        let position = match &self.path {
+            ObstaclePath::Orbit { pivot } => {
+                let radius = start.distance(*pivot);
+                if radius == 0.0 {
+                    start
+                } else {
+                    let angle = (distance / radius) % TAU;
+                    *pivot + Rot2::from_angle(angle) * (start - *pivot)
+                }
+            }
+            ObstaclePath::Patrol { waypoints, looped } => {
+                patrol_position(start, waypoints, *looped, distance)
+            }
+        };
+        self.obstacle.translated(position - start)
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw_path(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        let stroke = Stroke::new(1.0, PATH_COLOR.gamma_multiply(0.5));
+        let start = self.obstacle.center();
+        match &self.path {
+            ObstaclePath::Orbit { pivot } => {
+                painter.circle_stroke(
+                    camera.world_to_screen(rect, *pivot),
+                    start.distance(*pivot) * camera.zoom,
+                    stroke,
+                );
+            }
+            ObstaclePath::Patrol { waypoints, looped } => {
+                let mut points: Vec<Pos2> = std::iter::once(start)
+                    .chain(waypoints.iter().copied())
+                    .map(|point| camera.world_to_screen(rect, point))
+                    .collect();
+                if *looped {
+                    points.push(points[0]);
+                }
+                painter.add(Shape::dashed_line(&points, stroke, 6.0, 4.0));
+            }
+        }
+    }
+}
+
+/// Where a patrol starting at `start` through `waypoints` is after going `distance`.
+fn patrol_position(start: Pos2, waypoints: &[Pos2], looped: bool, distance: f32) -> Pos2 {
+    let mut points: Vec<Pos2> = std::iter::once(start)
+        .chain(waypoints.iter().copied())
+        .collect();
+    if looped {
+        points.push(start);
+    }
+    let length: f32 = points
+        .windows(2)
+        .map(|pair| pair[0].distance(pair[1]))
+        .sum();
+    if length == 0.0 {
+        return start;
+    }
+
+    let mut along = if looped {
+        distance.rem_euclid(length)
+    } else {
+        // Out and back again is twice the length
+        let along = distance.rem_euclid(2.0 * length);
+        if along > length {
+            2.0 * length - along
+        } else {
+            along
+        }
+    };
+    for pair in points.windows(2) {
+        let segment = pair[0].distance(pair[1]);
+        if along <= segment && segment > 0.0 {
+            return pair[0].lerp(pair[1], along / segment);
+        }
+        along -= segment;
+    }
+    *points.last().unwrap_or(&start)
+}
+
+/// How the obstacle tool's next obstacle moves, and the points shift clicked for its path.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct MotionBrush {
+    pub kind: MotionKind,
+    /// World units per tick
+    pub speed: f32,
+    pub looped: bool,
+    /// Kept after placing an obstacle, so several can share a pivot or follow the same patrol
+    #[serde(skip)]
+    pub points: Vec<Pos2>,
+}
+
+impl Default for MotionBrush {
+    fn default() -> Self {
+        Self {
+            kind: MotionKind::Still,
+            speed: 1.0,
+            looped: true,
+            points: Vec::new(),
+        }
+    }
+}
+
+impl MotionBrush {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Motion");
+            ui.radio_value(&mut self.kind, MotionKind::Still, "Still");
+            ui.radio_value(&mut self.kind, MotionKind::Orbit, "Orbit")
+                .on_hover_text("Shift click the point new obstacles circle around");
+            ui.radio_value(&mut self.kind, MotionKind::Patrol, "Patrol")
+                .on_hover_text("Shift click the points new obstacles patrol through, in order");
+        });
+        if self.kind == MotionKind::Still {
+            return;
+        }
+        ui.label("Obstacle Speed");
+        ui.add(
+            egui::DragValue::new(&mut self.speed)
+                .speed(0.05)
+                .range(0.0..=50.0),
+        );
+        if self.kind == MotionKind::Patrol {
+            ui.checkbox(&mut self.looped, "Loop")
+                .on_hover_text("Go straight back to the start, rather than back the way it came");
+        }
+        ui.horizontal(|ui| {
+            ui.label(format!("{} path points", self.points.len()));
+            if ui.small_button("Clear Path").clicked() {
+                self.points.clear();
+            }
+        });
+    }
+
+    /// The path an obstacle placed now follows, `None` if it stands still or has nowhere to go.
+    #[cfg(feature = "gui")]
+    pub fn path(&self) -> Option<ObstaclePath> {
+        match self.kind {
+            MotionKind::Still => None,
+            MotionKind::Orbit => self
+                .points
+                .last()
+                .map(|pivot| ObstaclePath::Orbit { pivot: *pivot }),
+            MotionKind::Patrol => (!self.points.is_empty()).then(|| ObstaclePath::Patrol {
+                waypoints: self.points.clone(),
+                looped: self.looped,
+            }),
+        }
+    }
+
+    /// Mark the path points while the obstacle tool is out.
+    #[cfg(feature = "gui")]
+    pub fn draw_points(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        if self.kind == MotionKind::Still {
+            return;
+        }
+        for point in &self.points {
+            let center = camera.world_to_screen(rect, *point);
+            painter.circle_stroke(center, 4.0, Stroke::new(1.5, PATH_COLOR));
+        }
+        if self.kind == MotionKind::Patrol {
+            let points: Vec<Pos2> = self
+                .points
+                .iter()
+                .map(|point| camera.world_to_screen(rect, *point))
+                .collect();
+            painter.add(Shape::dashed_line(
+                &points,
+                Stroke::new(1.0, PATH_COLOR),
+                6.0,
+                4.0,
+            ));
+        }
+    }
+}
+
+/// Every obstacle where it is at `clock`: the ones standing still, then the temporary ones, then
+/// the moving ones.
+pub fn obstacles_at<'a>(
+    still: &'a [Obstacle],
+    temporary: &[TemporaryObstacle],
+    moving: &[MovingObstacle],
+    clock: f32,
+) -> Cow<'a, [Obstacle]> {
+    if temporary.is_empty() && moving.is_empty() {
+        return Cow::Borrowed(still);
+    }
+    still
+        .iter()
+        .copied()
+        .chain(temporary.iter().map(|temporary| temporary.obstacle))
+        .chain(moving.iter().map(|obstacle| obstacle.at(clock)))
+        .collect()
+}
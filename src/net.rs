@@ -0,0 +1,682 @@
+use egui::{Pos2, Ui, Vec2};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    boid::Boid,
+    boid_store::BoidStore,
+    error::{BoidsError, Result},
+};
+
+pub const DEFAULT_PORT: u16 = 9002;
+/// Client ids start after this, so the host's own predator can share the id space.
+const HOST_ID: u32 = 0;
+/// A full keyframe is sent at least this often (in ticks) so late joiners and dropped deltas recover quickly.
+const KEYFRAME_INTERVAL: u64 = 60;
+/// Positions are sent in fixed point with this many steps per world unit.
+const POSITION_SCALE: f32 = 4.0;
+
+/// Messages the authoritative host sends to everyone watching.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum HostMessage {
+    Welcome {
+        client_id: u32,
+    },
+    /// Every boid's absolute position
+    Keyframe {
+        frame: u64,
+        boids: Vec<KeyframeBoid>,
+    },
+    /// How far every boid moved since the last frame, only valid if the flock size didn't change
+    Delta {
+        frame: u64,
+        boids: Vec<DeltaBoid>,
+    },
+    /// Everyone's predator cursors by client id, the host's own has id 0
+    Predators(Vec<(u32, [f32; 2])>),
+}
+
+/// Messages clients send to the host.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum ClientMessage {
+    Cursor(Option<[f32; 2]>),
+}
+
+#[derive(Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub struct KeyframeBoid {
+    x: i32,
+    y: i32,
+    heading: u8,
+}
+
+#[derive(Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub struct DeltaBoid {
+    dx: i8,
+    dy: i8,
+    heading: u8,
+}
+
+fn encode<T: Serialize>(message: &T) -> Vec<u8> {
+    // Serializing our own plain data types into a Vec can't fail
+    bincode::serialize(message).unwrap_or_default()
+}
+
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    bincode::deserialize(bytes).map_err(|err| BoidsError::Parse {
+        format: "network message",
+        message: err.to_string(),
+    })
+}
+
+fn quantize_position(position: Pos2) -> [i32; 2] {
+    [
+        (position.x * POSITION_SCALE).round() as i32,
+        (position.y * POSITION_SCALE).round() as i32,
+    ]
+}
+
+fn quantize_heading(velocity: Vec2) -> u8 {
+    let turns = velocity.angle() / std::f32::consts::TAU;
+    (turns.rem_euclid(1.0) * 256.0) as u8
+}
+
+fn heading_to_direction(heading: u8) -> Vec2 {
+    Vec2::angled(heading as f32 / 256.0 * std::f32::consts::TAU)
+}
+
+/// Turns the host's flock into keyframes and small deltas.
+///
+/// The encoder keeps a copy of exactly what the clients have so rounding and clamped deltas
+/// never accumulate: each delta moves the clients' copy towards the real position.
+#[derive(Default)]
+struct StateEncoder {
+    frame: u64,
+    sent_positions: Vec<[i32; 2]>,
+}
+
+impl StateEncoder {
+    fn encode(&mut self, boids: &BoidStore, force_keyframe: bool) -> HostMessage {
+        self.frame += 1;
+
+        let keyframe = force_keyframe
+            || self.frame % KEYFRAME_INTERVAL == 0
+            || self.sent_positions.len() != boids.len();
+        if keyframe {
+            self.sent_positions = boids
+                .iter()
+                .map(|boid| quantize_position(boid.position))
+                .collect();
+            return HostMessage::Keyframe {
+                frame: self.frame,
+                boids: boids
+                    .iter()
+                    .zip(&self.sent_positions)
+                    .map(|(boid, [x, y])| KeyframeBoid {
+                        x: *x,
+                        y: *y,
+                        heading: quantize_heading(boid.velocity),
+                    })
+                    .collect(),
+            };
+        }
+
+        let deltas = boids
+            .iter()
+            .zip(&mut self.sent_positions)
+            .map(|(boid, sent)| {
+                let [x, y] = quantize_position(boid.position);
+                let dx = (x - sent[0]).clamp(i8::MIN as i32, i8::MAX as i32);
+                let dy = (y - sent[1]).clamp(i8::MIN as i32, i8::MAX as i32);
+                sent[0] += dx;
+                sent[1] += dy;
+                DeltaBoid {
+                    dx: dx as i8,
+                    dy: dy as i8,
+                    heading: quantize_heading(boid.velocity),
+                }
+            })
+            .collect();
+        HostMessage::Delta {
+            frame: self.frame,
+            boids: deltas,
+        }
+    }
+}
+
+/// Rebuilds the host's flock on the client from keyframes and deltas.
+#[derive(Default)]
+struct StateDecoder {
+    positions: Vec<[i32; 2]>,
+    headings: Vec<u8>,
+}
+
+impl StateDecoder {
+    fn apply(&mut self, message: &HostMessage) {
+        match message {
+            HostMessage::Keyframe { boids, .. } => {
+                self.positions = boids.iter().map(|boid| [boid.x, boid.y]).collect();
+                self.headings = boids.iter().map(|boid| boid.heading).collect();
+            }
+            HostMessage::Delta { boids, .. } => {
+                // We missed the keyframe that set up this flock, wait for the next one
+                if boids.len() != self.positions.len() {
+                    return;
+                }
+                for ((position, heading), delta) in
+                    self.positions.iter_mut().zip(&mut self.headings).zip(boids)
+                {
+                    position[0] += delta.dx as i32;
+                    position[1] += delta.dy as i32;
+                    *heading = delta.heading;
+                }
+            }
+            HostMessage::Welcome { .. } | HostMessage::Predators(_) => {}
+        }
+    }
+
+    fn boids(&self) -> BoidStore {
+        self.positions
+            .iter()
+            .zip(&self.headings)
+            .map(|([x, y], heading)| {
+                let position = Pos2::new(*x as f32, *y as f32) / POSITION_SCALE;
+                Boid::new(position, heading_to_direction(*heading))
+            })
+            .collect()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use host::NetHost;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod host {
+    use std::{
+        collections::BTreeMap,
+        io::ErrorKind,
+        net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc::{self, Receiver, Sender, SyncSender, TrySendError},
+            Arc, Mutex,
+        },
+        thread::JoinHandle,
+        time::Duration,
+    };
+
+    use egui::Pos2;
+    use tracing::{info, warn};
+    use tungstenite::{protocol::WebSocketConfig, Message, WebSocket};
+
+    use super::{decode, encode, ClientMessage, HostMessage, StateEncoder, HOST_ID};
+    use crate::{
+        boid_store::BoidStore,
+        error::{BoidsError, Result},
+    };
+
+    /// How long client threads sleep between polling their socket.
+    const POLL_INTERVAL: Duration = Duration::from_millis(2);
+    /// How long the accepting thread sleeps between checking for new connections and whether
+    /// hosting stopped.
+    const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+    /// How long a new connection gets to finish the WebSocket handshake.
+    const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+    /// Messages waiting for a client thread to send them, a couple of seconds' worth of ticks.
+    const OUTGOING_CAPACITY: usize = 256;
+    /// Bytes the socket may hold back for a client that reads slower than the flock streams.
+    const MAX_WRITE_BUFFER: usize = 4 * 1024 * 1024;
+
+    enum HostEvent {
+        Joined(u32),
+        Left(u32),
+        Message(u32, ClientMessage),
+    }
+
+    struct ClientHandle {
+        id: u32,
+        outgoing: SyncSender<Vec<u8>>,
+    }
+
+    /// The authoritative side of a shared simulation: runs a WebSocket server and streams the flock to clients.
+    ///
+    /// Dropping it stops accepting connections, frees the port and closes every client's connection.
+    pub struct NetHost {
+        /// Where the server listens, only on this machine unless hosting for the network
+        pub address: SocketAddr,
+        clients: Arc<Mutex<Vec<ClientHandle>>>,
+        /// Tells the accepting thread to stop
+        shutdown: Arc<AtomicBool>,
+        accept_thread: Option<JoinHandle<()>>,
+        events: Receiver<HostEvent>,
+        encoder: StateEncoder,
+        /// Cursor positions of every connected client that is hovering the simulation
+        pub remote_predators: BTreeMap<u32, Pos2>,
+        needs_keyframe: bool,
+    }
+
+    impl NetHost {
+        /// Listen on `port`, only for connections from this machine unless `public`.
+        pub fn start(port: u16, public: bool) -> Result<Self> {
+            let ip = if public {
+                Ipv4Addr::UNSPECIFIED
+            } else {
+                Ipv4Addr::LOCALHOST
+            };
+            let listener = TcpListener::bind((ip, port)).map_err(|err| {
+                BoidsError::Network(format!("could not listen on {ip}:{port}: {err}"))
+            })?;
+            // Polled so the thread can notice hosting stopped and let go of the port
+            listener.set_nonblocking(true)?;
+            let address = listener.local_addr()?;
+            info!(%address, "hosting shared simulation");
+
+            let clients = Arc::new(Mutex::new(Vec::new()));
+            let shutdown = Arc::new(AtomicBool::new(false));
+            let (event_sender, events) = mpsc::channel();
+
+            let thread_clients = clients.clone();
+            let thread_shutdown = shutdown.clone();
+            let accept_thread = std::thread::Builder::new()
+                .name("net-host".to_owned())
+                .spawn(move || {
+                    accept_clients(listener, thread_clients, event_sender, thread_shutdown)
+                })?;
+
+            Ok(Self {
+                address,
+                clients,
+                shutdown,
+                accept_thread: Some(accept_thread),
+                events,
+                encoder: StateEncoder::default(),
+                remote_predators: BTreeMap::new(),
+                needs_keyframe: false,
+            })
+        }
+
+        pub fn client_count(&self) -> usize {
+            self.clients.lock().map_or(0, |clients| clients.len())
+        }
+
+        /// Handle everything the clients sent since the last call.
+        pub fn poll(&mut self) {
+            while let Ok(event) = self.events.try_recv() {
+                match event {
+                    HostEvent::Joined(id) => {
+                        info!(client_id = id, "client joined");
+                        self.needs_keyframe = true;
+                    }
+                    HostEvent::Left(id) => {
+                        info!(client_id = id, "client left");
+                        self.remote_predators.remove(&id);
+                        if let Ok(mut clients) = self.clients.lock() {
+                            clients.retain(|client| client.id != id);
+                        }
+                    }
+                    HostEvent::Message(id, ClientMessage::Cursor(Some([x, y]))) => {
+                        // Anything else would poison every force it went into
+                        if !x.is_finite() || !y.is_finite() {
+                            warn!(client_id = id, "ignoring cursor at {x}, {y}");
+                            continue;
+                        }
+                        self.remote_predators.insert(id, Pos2::new(x, y));
+                    }
+                    HostEvent::Message(id, ClientMessage::Cursor(None)) => {
+                        self.remote_predators.remove(&id);
+                    }
+                }
+            }
+        }
+
+        /// Send this tick's flock and predators to every client.
+        pub fn broadcast_state(&mut self, boids: &BoidStore, local_predator: Option<Pos2>) {
+            let state = encode(&self.encoder.encode(boids, self.needs_keyframe));
+            self.needs_keyframe = false;
+
+            let predators = local_predator
+                .map(|position| (HOST_ID, position))
+                .into_iter()
+                .chain(
+                    self.remote_predators
+                        .iter()
+                        .map(|(id, position)| (*id, *position)),
+                )
+                .map(|(id, position)| (id, [position.x, position.y]))
+                .collect();
+            let predators = encode(&HostMessage::Predators(predators));
+
+            if let Ok(mut clients) = self.clients.lock() {
+                clients.retain(|client| {
+                    let sent = client
+                        .outgoing
+                        .try_send(state.clone())
+                        .and_then(|()| client.outgoing.try_send(predators.clone()));
+                    match sent {
+                        // Dropping its sender makes the client thread close the connection
+                        Err(TrySendError::Full(_)) => {
+                            warn!(client_id = client.id, "dropping client that can't keep up");
+                            false
+                        }
+                        // A disconnected client thread is gone, its `Left` event cleans it up
+                        Ok(()) | Err(TrySendError::Disconnected(_)) => true,
+                    }
+                });
+            }
+        }
+    }
+
+    impl Drop for NetHost {
+        fn drop(&mut self) {
+            info!(address = %self.address, "stopped hosting");
+            self.shutdown.store(true, Ordering::Relaxed);
+            // Dropping the senders makes every client thread close its connection, the flag catches
+            // the ones still in their handshake
+            if let Ok(mut clients) = self.clients.lock() {
+                clients.clear();
+            }
+            // Wait for the listener to close, so hosting again on the same port works right away
+            if let Some(thread) = self.accept_thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    fn accept_clients(
+        listener: TcpListener,
+        clients: Arc<Mutex<Vec<ClientHandle>>>,
+        events: Sender<HostEvent>,
+        shutdown: Arc<AtomicBool>,
+    ) {
+        let mut next_id = HOST_ID + 1;
+        while !shutdown.load(Ordering::Relaxed) {
+            let stream = match listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                    std::thread::sleep(ACCEPT_INTERVAL);
+                    continue;
+                }
+                Err(err) => {
+                    warn!("failed to accept connection: {err}");
+                    continue;
+                }
+            };
+
+            let id = next_id;
+            next_id += 1;
+            let clients = clients.clone();
+            let events = events.clone();
+            let shutdown = shutdown.clone();
+            let spawned = std::thread::Builder::new()
+                .name(format!("net-client-{id}"))
+                .spawn(move || {
+                    let socket = match handshake(stream) {
+                        Ok(socket) => socket,
+                        Err(err) => {
+                            warn!(client_id = id, "client connection failed: {err}");
+                            return;
+                        }
+                    };
+                    // Only clients past the handshake get sent the flock
+                    let (outgoing, outgoing_receiver) = mpsc::sync_channel(OUTGOING_CAPACITY);
+                    if let Ok(mut clients) = clients.lock() {
+                        clients.push(ClientHandle { id, outgoing });
+                    }
+                    if let Err(err) =
+                        serve_client(id, socket, outgoing_receiver, &events, &shutdown)
+                    {
+                        warn!(client_id = id, "client connection failed: {err}");
+                    }
+                    let _ = events.send(HostEvent::Left(id));
+                });
+            if let Err(err) = spawned {
+                warn!("failed to start client thread: {err}");
+            }
+        }
+    }
+
+    /// Upgrade a new connection to a WebSocket, giving up on peers that stall.
+    fn handshake(stream: TcpStream) -> Result<WebSocket<TcpStream>> {
+        // The handshake blocks, the client thread goes nonblocking once it's done
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        stream.set_write_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        let config = WebSocketConfig {
+            max_write_buffer_size: MAX_WRITE_BUFFER,
+            ..WebSocketConfig::default()
+        };
+        let socket = tungstenite::accept_with_config(stream, Some(config))
+            .map_err(|err| BoidsError::Network(format!("handshake failed: {err}")))?;
+        socket.get_ref().set_nonblocking(true)?;
+        Ok(socket)
+    }
+
+    fn serve_client(
+        id: u32,
+        mut socket: WebSocket<TcpStream>,
+        outgoing: Receiver<Vec<u8>>,
+        events: &Sender<HostEvent>,
+        shutdown: &AtomicBool,
+    ) -> Result<()> {
+        send(&mut socket, encode(&HostMessage::Welcome { client_id: id }))?;
+        let _ = events.send(HostEvent::Joined(id));
+
+        loop {
+            match socket.read() {
+                Ok(Message::Binary(bytes)) => match decode::<ClientMessage>(&bytes) {
+                    Ok(message) => {
+                        let _ = events.send(HostEvent::Message(id, message));
+                    }
+                    Err(err) => warn!(client_id = id, "ignoring bad message: {err}"),
+                },
+                Ok(Message::Close(_)) => return Ok(()),
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(err)) if err.kind() == ErrorKind::WouldBlock => {}
+                Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+                Err(err) => return Err(BoidsError::Network(err.to_string())),
+            }
+
+            loop {
+                match outgoing.try_recv() {
+                    Ok(bytes) => send(&mut socket, bytes)?,
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    // The host stopped, so close the connection
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        let _ = socket.close(None);
+                        return Ok(());
+                    }
+                }
+            }
+
+            if shutdown.load(Ordering::Relaxed) {
+                let _ = socket.close(None);
+                return Ok(());
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Queue a message, a full socket buffer just means it goes out on a later flush.
+    ///
+    /// Fails once more than [`MAX_WRITE_BUFFER`] is waiting, which drops a client that can't keep
+    /// up instead of buffering for it forever.
+    fn send(socket: &mut WebSocket<TcpStream>, bytes: Vec<u8>) -> Result<()> {
+        match socket.send(Message::Binary(bytes)) {
+            Ok(()) => Ok(()),
+            Err(tungstenite::Error::Io(err)) if err.kind() == ErrorKind::WouldBlock => Ok(()),
+            Err(err) => Err(BoidsError::Network(err.to_string())),
+        }
+    }
+}
+
+/// A view onto someone else's simulation.
+pub struct NetClient {
+    pub url: String,
+    sender: ewebsock::WsSender,
+    receiver: ewebsock::WsReceiver,
+    decoder: StateDecoder,
+    pub client_id: Option<u32>,
+    /// Every other predator cursor in the shared simulation
+    pub predators: Vec<Pos2>,
+    sent_cursor: Option<Pos2>,
+}
+
+impl NetClient {
+    pub fn connect(url: &str, ctx: &egui::Context) -> Result<Self> {
+        let ctx = ctx.clone();
+        let (sender, receiver) =
+            ewebsock::connect_with_wakeup(url, ewebsock::Options::default(), move || {
+                ctx.request_repaint()
+            })
+            .map_err(BoidsError::Network)?;
+
+        Ok(Self {
+            url: url.to_owned(),
+            sender,
+            receiver,
+            decoder: StateDecoder::default(),
+            client_id: None,
+            predators: Vec::new(),
+            sent_cursor: None,
+        })
+    }
+
+    /// Apply everything the host sent since the last call, failing once the connection is gone.
+    pub fn poll(&mut self) -> Result<()> {
+        while let Some(event) = self.receiver.try_recv() {
+            match event {
+                ewebsock::WsEvent::Opened => tracing::info!(url = self.url, "connected"),
+                ewebsock::WsEvent::Message(ewebsock::WsMessage::Binary(bytes)) => {
+                    match decode::<HostMessage>(&bytes)? {
+                        HostMessage::Welcome { client_id } => self.client_id = Some(client_id),
+                        HostMessage::Predators(predators) => {
+                            // The host echoes our own cursor back, we already draw that one
+                            self.predators = predators
+                                .into_iter()
+                                .filter(|(id, _)| Some(*id) != self.client_id)
+                                .map(|(_, [x, y])| Pos2::new(x, y))
+                                .collect();
+                        }
+                        message => self.decoder.apply(&message),
+                    }
+                }
+                ewebsock::WsEvent::Message(_) => {}
+                ewebsock::WsEvent::Error(err) => return Err(BoidsError::Network(err)),
+                ewebsock::WsEvent::Closed => {
+                    return Err(BoidsError::Network(
+                        "the host closed the connection".to_owned(),
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Tell the host where our predator is, if it moved since the last call.
+    pub fn send_cursor(&mut self, position: Option<Pos2>) {
+        if position == self.sent_cursor {
+            return;
+        }
+        self.sent_cursor = position;
+        let message = ClientMessage::Cursor(position.map(|position| [position.x, position.y]));
+        self.sender
+            .send(ewebsock::WsMessage::Binary(encode(&message)));
+    }
+
+    pub fn boids(&self) -> BoidStore {
+        self.decoder.boids()
+    }
+}
+
+/// Whether this instance is on its own, running a shared simulation or watching one.
+#[derive(Default)]
+pub enum NetSession {
+    #[default]
+    Offline,
+    #[cfg(not(target_arch = "wasm32"))]
+    Hosting(NetHost),
+    Connected(NetClient),
+}
+
+impl NetSession {
+    pub fn is_client(&self) -> bool {
+        matches!(self, NetSession::Connected(_))
+    }
+
+    /// Predators controlled by other instances.
+    pub fn remote_predators(&self) -> Vec<Pos2> {
+        match self {
+            NetSession::Offline => Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            NetSession::Hosting(host) => host.remote_predators.values().copied().collect(),
+            NetSession::Connected(client) => client.predators.clone(),
+        }
+    }
+}
+
+/// Connection settings, persisted so reconnecting is a single click.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct NetSettings {
+    pub port: u16,
+    /// Whether other machines can join when hosting, rather than only this one
+    pub public: bool,
+    pub url: String,
+}
+
+impl Default for NetSettings {
+    fn default() -> Self {
+        Self {
+            port: DEFAULT_PORT,
+            public: false,
+            url: format!("ws://127.0.0.1:{DEFAULT_PORT}"),
+        }
+    }
+}
+
+impl NetSettings {
+    /// Draw the network controls, returning an error if starting or joining a session failed.
+    pub fn draw_panel(&mut self, ui: &mut Ui, session: &mut NetSession) -> Result<()> {
+        match session {
+            NetSession::Offline => {
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.horizontal(|ui| {
+                    ui.label("Port");
+                    ui.add(egui::DragValue::new(&mut self.port));
+                });
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.checkbox(&mut self.public, "Open to the Network")
+                    .on_hover_text("Let other machines join, not just this one");
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("Host").clicked() {
+                    *session = NetSession::Hosting(NetHost::start(self.port, self.public)?);
+                }
+
+                ui.label("Host Address");
+                ui.text_edit_singleline(&mut self.url);
+                if ui.button("Join").clicked() {
+                    *session = NetSession::Connected(NetClient::connect(&self.url, ui.ctx())?);
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            NetSession::Hosting(host) => {
+                ui.label(format!(
+                    "Hosting on {} with {} viewer(s)",
+                    host.address,
+                    host.client_count()
+                ));
+                if ui.button("Stop Hosting").clicked() {
+                    *session = NetSession::Offline;
+                }
+            }
+            NetSession::Connected(client) => {
+                match client.client_id {
+                    Some(id) => ui.label(format!("Connected to {} as viewer {id}", client.url)),
+                    None => ui.label(format!("Connecting to {}...", client.url)),
+                };
+                if ui.button("Disconnect").clicked() {
+                    *session = NetSession::Offline;
+                }
+            }
+        }
+        Ok(())
+    }
+}
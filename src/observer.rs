@@ -0,0 +1,85 @@
+use crate::{
+    boid_store::{BoidMut, BoidStore},
+    impulse::Impulse,
+    math::{Pos2, Vec2},
+};
+
+/// Called between simulation ticks, see [`crate::BoidsApp::set_observer`].
+#[cfg(feature = "gui")]
+pub type Observer = Box<dyn FnMut(&mut FlockState<'_>)>;
+
+/// The flock between two ticks, as an embedding application's observer sees it.
+///
+/// Changes made through it aren't recorded as inputs, so a replay of a run whose observer changed
+/// anything won't match the run.
+pub struct FlockState<'a> {
+    pub(crate) tick: u64,
+    pub(crate) predator: Option<Pos2>,
+    pub(crate) boids: &'a mut BoidStore,
+}
+
+impl FlockState<'_> {
+    /// Ticks simulated so far.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Where the player's predator is, if there is one.
+    pub fn predator(&self) -> Option<Pos2> {
+        self.predator
+    }
+
+    pub fn len(&self) -> usize {
+        self.boids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boids.is_empty()
+    }
+
+    pub fn boids(&mut self) -> impl Iterator<Item = ObservedBoid<'_>> {
+        self.boids.iter_mut().map(ObservedBoid)
+    }
+
+    /// Add `velocity` to every boid within `radius` of `center`, the same push the gust tool gives.
+    pub fn apply_impulse_in_circle(&mut self, center: Pos2, radius: f32, velocity: Vec2) {
+        Impulse {
+            center,
+            radius,
+            velocity,
+        }
+        .apply(self.boids);
+    }
+}
+
+/// One boid of the flock, which an observer can read and nudge.
+pub struct ObservedBoid<'a>(BoidMut<'a>);
+
+impl ObservedBoid<'_> {
+    /// Stays the same while other boids come and go.
+    pub fn id(&self) -> u64 {
+        self.0.id
+    }
+
+    pub fn position(&self) -> Pos2 {
+        *self.0.position
+    }
+
+    pub fn velocity(&self) -> Vec2 {
+        *self.0.velocity
+    }
+
+    pub fn set_position(&mut self, position: Pos2) {
+        *self.0.position = position;
+    }
+
+    pub fn set_velocity(&mut self, velocity: Vec2) {
+        *self.0.velocity = velocity;
+    }
+
+    /// Add `velocity` to the boid's. Its speed limit reins it back in over the next tick, but it
+    /// keeps the new heading.
+    pub fn apply_impulse(&mut self, velocity: Vec2) {
+        *self.0.velocity += velocity;
+    }
+}
@@ -0,0 +1,215 @@
+#[cfg(feature = "gui")]
+use egui::{Painter, Stroke, Ui};
+
+use crate::{
+    current_zone::{ZoneArea, ZoneShape},
+    math::{Pos2, Rect, Vec2},
+    moving_obstacle::MotionBrush,
+    temporary_obstacle::DurabilityBrush,
+};
+
+#[cfg(feature = "gui")]
+use crate::{camera::Camera, math::Color32, moving_obstacle::MotionKind};
+
+#[cfg(feature = "gui")]
+pub const OBSTACLE_COLOR: Color32 = Color32::from_rgb(70, 70, 80);
+#[cfg(feature = "gui")]
+const OBSTACLE_OUTLINE: Color32 = Color32::from_rgb(130, 130, 145);
+
+/// Something solid in the world that boids have to fly around.
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum Obstacle {
+    Circle { center: Pos2, radius: f32 },
+    Rectangle(Rect),
+}
+
+impl Obstacle {
+    /// Build an obstacle from a drag the same way current zones are, see [`ZoneArea::from_drag`].
+    pub fn from_drag(shape: ZoneShape, start: Pos2, end: Pos2) -> Self {
+        match ZoneArea::from_drag(shape, start, end) {
+            ZoneArea::Rectangle(rect) => Obstacle::Rectangle(rect),
+            ZoneArea::Circle { center, radius } => Obstacle::Circle { center, radius },
+        }
+    }
+
+    pub fn center(&self) -> Pos2 {
+        match *self {
+            Obstacle::Circle { center, .. } => center,
+            Obstacle::Rectangle(rect) => rect.center(),
+        }
+    }
+
+    pub fn translated(self, offset: Vec2) -> Self {
+        match self {
+            Obstacle::Circle { center, radius } => Obstacle::Circle {
+                center: center + offset,
+                radius,
+            },
+            Obstacle::Rectangle(rect) => Obstacle::Rectangle(rect.translate(offset)),
+        }
+    }
+
+    /// How far `position` is from the obstacle's surface, negative inside it, and the direction
+    /// out of the obstacle at the nearest point of its surface.
+    pub fn surface(&self, position: Pos2) -> (f32, Vec2) {
+        match *self {
+            Obstacle::Circle { center, radius } => {
+                let offset = position - center;
+                let distance = offset.length();
+                // Dead center has no nearest point, any way out will do
+                let normal = if distance > 0.0 {
+                    offset / distance
+                } else {
+                    Vec2::X
+                };
+                (distance - radius, normal)
+            }
+            Obstacle::Rectangle(rect) if !rect.contains(position) => {
+                let offset = position - rect.clamp(position);
+                let distance = offset.length();
+                (distance, offset / distance)
+            }
+            Obstacle::Rectangle(rect) => [
+                (position.x - rect.min.x, -Vec2::X),
+                (rect.max.x - position.x, Vec2::X),
+                (position.y - rect.min.y, -Vec2::Y),
+                (rect.max.y - position.y, Vec2::Y),
+            ]
+            .into_iter()
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(depth, normal)| (-depth, normal))
+            .unwrap_or((0.0, Vec2::X)),
+        }
+    }
+
+    pub fn contains(&self, position: Pos2) -> bool {
+        self.surface(position).0 <= 0.0
+    }
+
+    /// How far the obstacle reaches from its center.
+    pub fn extent(&self) -> f32 {
+        match *self {
+            Obstacle::Circle { radius, .. } => radius,
+            Obstacle::Rectangle(rect) => rect.size().length() / 2.0,
+        }
+    }
+
+    /// Move something that got inside the obstacle back out, bouncing it off the surface.
+    ///
+    /// Returns whether it had to.
+    pub fn push_out(&self, position: &mut Pos2, velocity: &mut Vec2) -> bool {
+        let (distance, normal) = self.surface(*position);
+        if distance >= 0.0 {
+            return false;
+        }
+        *position -= normal * distance;
+        let into_surface = velocity.dot(normal);
+        if into_surface < 0.0 {
+            *velocity -= 2.0 * into_surface * normal;
+        }
+        true
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        self.draw_faded(painter, rect, camera, 1.0);
+    }
+
+    /// Draw the obstacle `opacity` of the way in, from 0 for invisible to 1 for solid.
+    #[cfg(feature = "gui")]
+    pub fn draw_faded(&self, painter: &Painter, rect: &Rect, camera: &Camera, opacity: f32) {
+        let fill = OBSTACLE_COLOR.gamma_multiply(opacity);
+        let stroke = Stroke::new(2.0, OBSTACLE_OUTLINE.gamma_multiply(opacity));
+        match *self {
+            Obstacle::Circle { center, radius } => {
+                painter.circle(
+                    camera.world_to_screen(rect, center),
+                    radius * camera.zoom,
+                    fill,
+                    stroke,
+                );
+            }
+            Obstacle::Rectangle(area) => {
+                painter.rect(
+                    Rect::from_two_pos(
+                        camera.world_to_screen(rect, area.min),
+                        camera.world_to_screen(rect, area.max),
+                    ),
+                    0.0,
+                    fill,
+                    stroke,
+                );
+            }
+        }
+    }
+}
+
+/// Settings used for the next obstacle placed with the obstacle tool.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ObstacleBrush {
+    pub shape: ZoneShape,
+    pub motion: MotionBrush,
+    pub durability: DurabilityBrush,
+}
+
+#[cfg(feature = "gui")]
+impl ObstacleBrush {
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.shape, ZoneShape::Rectangle, "Rectangle");
+            ui.radio_value(&mut self.shape, ZoneShape::Circle, "Circle");
+        });
+        self.motion.draw_panel(ui);
+        // Only obstacles that stand still wear out
+        if self.motion.kind == MotionKind::Still {
+            self.durability.draw_panel(ui);
+        }
+    }
+}
+
+/// What a drag with the obstacle tool is doing, decided by where it started.
+#[cfg(feature = "gui")]
+#[derive(Clone, Copy, Default)]
+pub enum ObstacleEdit {
+    #[default]
+    Idle,
+    /// Dragging out a new obstacle
+    Placing,
+    /// Dragging the obstacle at `index`, which was at `from` when the drag started
+    Moving { index: usize, from: Obstacle },
+}
+
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ObstacleParameters {
+    /// Boids start steering around an obstacle this far from its surface
+    pub look_ahead: f32,
+    pub weight: f32,
+    /// How many ticks ahead boids steer around moving obstacles, where they're about to be
+    pub prediction: f32,
+}
+
+impl Default for ObstacleParameters {
+    fn default() -> Self {
+        Self {
+            look_ahead: 60.0,
+            weight: 2.0,
+            prediction: 20.0,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl ObstacleParameters {
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.label("Look Ahead");
+        ui.add(egui::DragValue::new(&mut self.look_ahead).range(1.0..=500.0))
+            .on_hover_text("How far from an obstacle boids start steering around it");
+        ui.label("Avoidance Weight");
+        ui.add(egui::DragValue::new(&mut self.weight).speed(0.05));
+        ui.label("Prediction (ticks)");
+        ui.add(egui::DragValue::new(&mut self.prediction).range(0.0..=300.0))
+            .on_hover_text("Boids steer around moving obstacles where they'll be this far ahead");
+    }
+}
@@ -0,0 +1,288 @@
+use std::f32::consts::TAU;
+
+use ecolor::Rgba;
+#[cfg(feature = "gui")]
+use egui::{Painter, Stroke};
+use rand::Rng;
+
+use crate::math::{Color32, Pos2, Vec2};
+
+#[cfg(feature = "gui")]
+use crate::{camera::Camera, math::Rect};
+
+/// Most particles alive at once, unless the render settings say otherwise.
+pub const DEFAULT_BUDGET: usize = 2000;
+
+const HATCH_COLOR: Color32 = Color32::from_rgb(255, 240, 180);
+const VANISH_COLOR: Color32 = Color32::from_rgb(150, 150, 170);
+const CATCH_COLOR: Color32 = Color32::from_rgb(255, 90, 40);
+/// Catch sparks cool to a dim red rather than just fading out.
+const CATCH_END_COLOR: Color32 = Color32::from_rgba_premultiplied(60, 0, 0, 0);
+const DUST_COLOR: Color32 = Color32::from_rgb(170, 150, 120);
+const DEBRIS_COLOR: Color32 = Color32::from_rgb(130, 130, 145);
+const CONFETTI_COLORS: [Color32; 4] = [
+    Color32::from_rgb(255, 200, 60),
+    Color32::from_rgb(90, 200, 255),
+    Color32::from_rgb(120, 230, 120),
+    Color32::from_rgb(240, 120, 220),
+];
+/// Sparks thrown off by a hatching boid.
+const HATCH_SPARKS: usize = 6;
+const CATCH_SPARKS: usize = 14;
+const DUST_SPECKS: usize = 5;
+const CONFETTI_PIECES: usize = 30;
+/// Debris from a broken obstacle, per 10 world units it reached out to.
+const DEBRIS_DENSITY: f32 = 2.0;
+const MAX_DEBRIS: usize = 60;
+
+#[cfg(feature = "gui")]
+#[derive(Clone, Copy)]
+pub enum ParticleShape {
+    /// A circle whose radius goes from `from` to `to` (in world units) as it fades
+    Ring { from: f32, to: f32 },
+    /// A small dot
+    Spark,
+}
+
+/// A purely visual speck that lives for a moment and fades away.
+///
+/// Particles aren't part of the simulation, they don't use its random numbers and never end up
+/// in checksums or replays.
+#[cfg(feature = "gui")]
+#[derive(Clone, Copy)]
+pub struct Particle {
+    pub position: Pos2,
+    /// World units per second
+    pub velocity: Vec2,
+    pub shape: ParticleShape,
+    /// The color when the particle appears, blending into `end_color` over its life
+    pub color: Color32,
+    pub end_color: Color32,
+    /// Seconds
+    pub lifetime: f32,
+    age: f32,
+}
+
+#[cfg(feature = "gui")]
+impl Particle {
+    /// A particle that fades from `color` to nothing, see [`Particle::fading_to`] for other endings.
+    pub fn new(
+        position: Pos2,
+        velocity: Vec2,
+        shape: ParticleShape,
+        color: Color32,
+        lifetime: f32,
+    ) -> Self {
+        Self {
+            position,
+            velocity,
+            shape,
+            color,
+            end_color: Color32::TRANSPARENT,
+            lifetime,
+            age: 0.0,
+        }
+    }
+
+    pub fn fading_to(self, end_color: Color32) -> Self {
+        Self { end_color, ..self }
+    }
+
+    /// How far through its life the particle is, from 0 to 1.
+    fn progress(&self) -> f32 {
+        (self.age / self.lifetime.max(f32::EPSILON)).min(1.0)
+    }
+
+    fn current_color(&self) -> Color32 {
+        let t = self.progress();
+        (Rgba::from(self.color) * (1.0 - t) + Rgba::from(self.end_color) * t).into()
+    }
+}
+
+/// Every live particle, which other features emit effects into.
+#[cfg(feature = "gui")]
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    /// Most particles alive at once, anything emitted beyond it is dropped
+    pub budget: usize,
+}
+
+#[cfg(feature = "gui")]
+impl Default for ParticleSystem {
+    fn default() -> Self {
+        Self {
+            particles: Vec::new(),
+            budget: DEFAULT_BUDGET,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl ParticleSystem {
+    pub fn emit(&mut self, particle: Particle) {
+        if self.particles.len() < self.budget {
+            self.particles.push(particle);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    /// A ring and a little burst of sparks where a boid appeared.
+    pub fn hatch(&mut self, position: Pos2) {
+        self.emit(Particle::new(
+            position,
+            Vec2::ZERO,
+            ParticleShape::Ring {
+                from: 0.0,
+                to: 15.0,
+            },
+            HATCH_COLOR,
+            0.5,
+        ));
+        let mut rng = rand::thread_rng();
+        for _ in 0..HATCH_SPARKS {
+            let velocity = Vec2::angled(rng.gen_range(0.0..TAU)) * rng.gen_range(20.0..60.0);
+            self.emit(Particle::new(
+                position,
+                velocity,
+                ParticleShape::Spark,
+                HATCH_COLOR,
+                rng.gen_range(0.2..0.5),
+            ));
+        }
+    }
+
+    /// A ring closing in where a boid disappeared.
+    pub fn vanish(&mut self, position: Pos2) {
+        self.emit(Particle::new(
+            position,
+            Vec2::ZERO,
+            ParticleShape::Ring {
+                from: 12.0,
+                to: 0.0,
+            },
+            VANISH_COLOR,
+            0.4,
+        ));
+    }
+
+    /// A flash and a spray of hot sparks where a predator caught a boid.
+    pub fn catch_burst(&mut self, position: Pos2) {
+        self.emit(
+            Particle::new(
+                position,
+                Vec2::ZERO,
+                ParticleShape::Ring {
+                    from: 4.0,
+                    to: 25.0,
+                },
+                CATCH_COLOR,
+                0.35,
+            )
+            .fading_to(CATCH_END_COLOR),
+        );
+        let mut rng = rand::thread_rng();
+        for _ in 0..CATCH_SPARKS {
+            let velocity = Vec2::angled(rng.gen_range(0.0..TAU)) * rng.gen_range(60.0..150.0);
+            self.emit(
+                Particle::new(
+                    position,
+                    velocity,
+                    ParticleShape::Spark,
+                    CATCH_COLOR,
+                    rng.gen_range(0.3..0.7),
+                )
+                .fading_to(CATCH_END_COLOR),
+            );
+        }
+    }
+
+    /// A puff of dust where a boid bounced off a wall, kicked up along `velocity` after the bounce.
+    pub fn dust(&mut self, position: Pos2, velocity: Vec2) {
+        let direction = velocity.normalized();
+        let mut rng = rand::thread_rng();
+        for _ in 0..DUST_SPECKS {
+            let spread = Vec2::angled(rng.gen_range(0.0..TAU)) * 0.7;
+            self.emit(Particle::new(
+                position,
+                (direction + spread) * rng.gen_range(10.0..30.0),
+                ParticleShape::Spark,
+                DUST_COLOR,
+                rng.gen_range(0.3..0.6),
+            ));
+        }
+    }
+
+    /// Debris flung out over an obstacle reaching `extent` from `center` as it breaks apart.
+    pub fn crumble(&mut self, center: Pos2, extent: f32) {
+        let mut rng = rand::thread_rng();
+        let pieces = ((extent / 10.0 * DEBRIS_DENSITY) as usize).clamp(CATCH_SPARKS, MAX_DEBRIS);
+        for _ in 0..pieces {
+            let direction = Vec2::angled(rng.gen_range(0.0..TAU));
+            self.emit(Particle::new(
+                center + direction * extent * rng.gen_range(0.0..1.0f32).sqrt(),
+                direction * rng.gen_range(20.0..70.0),
+                ParticleShape::Spark,
+                DEBRIS_COLOR,
+                rng.gen_range(0.5..1.2),
+            ));
+        }
+        self.emit(Particle::new(
+            center,
+            Vec2::ZERO,
+            ParticleShape::Ring {
+                from: extent,
+                to: extent * 1.5,
+            },
+            DEBRIS_COLOR,
+            0.5,
+        ));
+    }
+
+    /// Confetti in every color, for reaching a goal.
+    pub fn celebrate(&mut self, position: Pos2) {
+        let mut rng = rand::thread_rng();
+        for _ in 0..CONFETTI_PIECES {
+            let velocity = Vec2::angled(rng.gen_range(0.0..TAU)) * rng.gen_range(40.0..120.0);
+            let color = CONFETTI_COLORS[rng.gen_range(0..CONFETTI_COLORS.len())];
+            self.emit(Particle::new(
+                position,
+                velocity,
+                ParticleShape::Spark,
+                color,
+                rng.gen_range(0.6..1.2),
+            ));
+        }
+    }
+
+    /// Move every particle along by `dt` seconds and drop the ones that have faded out.
+    pub fn update(&mut self, dt: f32) {
+        for particle in &mut self.particles {
+            particle.age += dt;
+            particle.position += particle.velocity * dt;
+        }
+        self.particles
+            .retain(|particle| particle.age < particle.lifetime);
+        // The budget may have been lowered since these were emitted
+        self.particles.truncate(self.budget);
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        for particle in &self.particles {
+            let color = particle.current_color();
+            let center = camera.world_to_screen(rect, particle.position);
+            match particle.shape {
+                ParticleShape::Ring { from, to } => {
+                    let radius = from + (to - from) * particle.progress();
+                    painter.circle_stroke(center, radius * camera.zoom, Stroke::new(1.5, color));
+                }
+                ParticleShape::Spark => {
+                    painter.circle_filled(center, 1.5, color);
+                }
+            }
+        }
+    }
+}
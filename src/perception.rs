@@ -0,0 +1,108 @@
+#[cfg(feature = "gui")]
+use egui::{Painter, Shape, Stroke, Ui};
+
+use crate::{
+    boid_store::BoidRef,
+    math::{Pos2, Vec2},
+};
+
+#[cfg(feature = "gui")]
+use crate::{
+    camera::Camera,
+    math::{Color32, Rect},
+};
+
+#[cfg(feature = "gui")]
+const CONE_COLOR: Color32 = Color32::from_rgba_premultiplied(150, 150, 150, 150);
+/// Points along the edge of the vision cone when drawn.
+#[cfg(feature = "gui")]
+const CONE_SEGMENTS: usize = 32;
+
+/// How boids see their neighbors, beyond how far away they are.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct PerceptionParameters {
+    /// How much less a neighbor right behind a boid counts for alignment and cohesion, from 0 (as
+    /// much as one in front) to 1 (not at all). Neighbors to the side are in between.
+    pub rear_attenuation: f32,
+    /// How wide boids see ahead of them for separation, alignment and cohesion, 360 sees all around
+    pub vision_angle: f32,
+    /// Draw the vision cone of the selected boid
+    pub show_cone: bool,
+}
+
+impl Default for PerceptionParameters {
+    fn default() -> Self {
+        Self {
+            rear_attenuation: 0.0,
+            vision_angle: 360.0,
+            show_cone: true,
+        }
+    }
+}
+
+impl PerceptionParameters {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.label("Vision Angle");
+        ui.add(egui::Slider::new(&mut self.vision_angle, 0.0..=360.0).suffix("°"))
+            .on_hover_text("Neighbors outside this cone ahead of a boid are ignored for separation, alignment and cohesion");
+        ui.checkbox(&mut self.show_cone, "Show Selected Boid's Vision Cone");
+        ui.label("Rear Attenuation");
+        ui.add(egui::Slider::new(&mut self.rear_attenuation, 0.0..=1.0))
+            .on_hover_text(
+                "How much less neighbors behind a boid count for alignment and cohesion, like the blind spot of a starling",
+            );
+    }
+
+    /// Whether a neighbor at `position` is inside `boid`'s vision cone.
+    pub fn can_see(&self, boid: BoidRef<'_>, position: Pos2) -> bool {
+        if self.vision_angle >= 360.0 || boid.velocity == Vec2::ZERO {
+            return true;
+        }
+        let offset = position - boid.position;
+        if offset == Vec2::ZERO {
+            return true;
+        }
+        let cos = boid.velocity.normalized().dot(offset.normalized());
+        cos >= (self.vision_angle.to_radians() / 2.0).cos()
+    }
+
+    /// Outline `boid`'s vision cone out to `radius`, nothing when it sees all around.
+    #[cfg(feature = "gui")]
+    pub fn draw_cone(
+        &self,
+        painter: &Painter,
+        rect: &Rect,
+        camera: &Camera,
+        boid: BoidRef<'_>,
+        radius: f32,
+    ) {
+        if !self.show_cone || self.vision_angle >= 360.0 || boid.velocity == Vec2::ZERO {
+            return;
+        }
+        let center = camera.world_to_screen(rect, boid.position);
+        let heading = boid.velocity.angle();
+        let half = self.vision_angle.to_radians() / 2.0;
+        let mut points = vec![center];
+        points.extend((0..=CONE_SEGMENTS).map(|step| {
+            let angle = heading - half + 2.0 * half * step as f32 / CONE_SEGMENTS as f32;
+            center + Vec2::angled(angle) * radius * camera.zoom
+        }));
+        points.push(center);
+        painter.add(Shape::line(points, Stroke::new(1.0, CONE_COLOR)));
+    }
+
+    /// How much a neighbor at `position` counts for `boid`, from its direction relative to the
+    /// boid's heading.
+    pub fn weight(&self, boid: BoidRef<'_>, position: Pos2) -> f32 {
+        if self.rear_attenuation == 0.0 || boid.velocity == Vec2::ZERO {
+            return 1.0;
+        }
+        let behind = -boid
+            .velocity
+            .normalized()
+            .dot((position - boid.position).normalized());
+        1.0 - self.rear_attenuation * behind.max(0.0)
+    }
+}
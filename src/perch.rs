@@ -0,0 +1,161 @@
+#[cfg(feature = "gui")]
+use egui::{Painter, Stroke, Ui};
+#[cfg(feature = "gui")]
+use rand::Rng;
+
+use crate::math::Pos2;
+
+#[cfg(feature = "gui")]
+use crate::{
+    boid_store::BoidStore,
+    boids_simulation::BoidsSimulationParameters,
+    camera::Camera,
+    math::{Color32, Rect, Vec2},
+};
+
+#[cfg(feature = "gui")]
+const PERCH_COLOR: Color32 = Color32::from_rgb(139, 90, 43);
+
+/// A place boids can land on, either a single point or a line segment like a wire or branch.
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PerchSite {
+    pub start: Pos2,
+    /// Same as `start` for a point perch
+    pub end: Pos2,
+}
+
+impl PerchSite {
+    pub fn closest_point(&self, position: Pos2) -> Pos2 {
+        let segment = self.end - self.start;
+        let length_sq = segment.length_sq();
+        if length_sq == 0.0 {
+            return self.start;
+        }
+
+        let t = ((position - self.start).dot(segment) / length_sq).clamp(0.0, 1.0);
+        self.start + segment * t
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        let start = camera.world_to_screen(rect, self.start);
+        let end = camera.world_to_screen(rect, self.end);
+        if start == end {
+            painter.circle_filled(start, 5.0, PERCH_COLOR);
+        } else {
+            painter.line_segment([start, end], Stroke::new(4.0, PERCH_COLOR));
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct PerchParameters {
+    /// Boids closer than this to a perch get pulled towards it
+    pub attraction_radius: f32,
+    pub attraction_weight: f32,
+    /// How close a boid needs to be to a perch to land on it
+    pub landing_distance: f32,
+    /// Chance per tick that a boid close enough to a perch lands
+    pub landing_chance: f32,
+    /// Shortest rest on a perch, in ticks
+    pub min_rest: u32,
+    /// Longest rest on a perch, in ticks
+    pub max_rest: u32,
+}
+
+impl Default for PerchParameters {
+    fn default() -> Self {
+        Self {
+            attraction_radius: 150.0,
+            attraction_weight: 0.5,
+            landing_distance: 10.0,
+            landing_chance: 0.05,
+            min_rest: 120,
+            max_rest: 600,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl PerchParameters {
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.label("Attraction Radius");
+        ui.add(egui::DragValue::new(&mut self.attraction_radius));
+        ui.label("Attraction Weight");
+        ui.add(egui::DragValue::new(&mut self.attraction_weight).speed(0.05));
+        ui.label("Landing Distance");
+        ui.add(egui::DragValue::new(&mut self.landing_distance));
+        ui.label("Landing Chance");
+        ui.add(egui::Slider::new(&mut self.landing_chance, 0.0..=1.0));
+        ui.label("Rest Time (ticks)");
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut self.min_rest).range(0..=self.max_rest));
+            ui.label("to");
+            ui.add(egui::DragValue::new(&mut self.max_rest).range(self.min_rest..=u32::MAX));
+        });
+    }
+}
+
+/// Closest point on any perch within `max_distance` of `position`.
+pub fn nearest_perch_point(sites: &[PerchSite], position: Pos2, max_distance: f32) -> Option<Pos2> {
+    sites
+        .iter()
+        .map(|site| site.closest_point(position))
+        .map(|point| (point, point.distance_sq(position)))
+        .filter(|(_, distance_sq)| *distance_sq <= max_distance * max_distance)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(point, _)| point)
+}
+
+/// Land boids that reached a perch and send resting boids back into the air.
+///
+/// Resting boids take off when their rest is over or as soon as the predator comes close.
+#[cfg(feature = "gui")]
+pub fn update_perching(
+    boids: &mut BoidStore,
+    sites: &[PerchSite],
+    predator_pos: Option<Pos2>,
+    params: &BoidsSimulationParameters,
+    rng: &mut impl Rng,
+) {
+    let perching = &params.perching;
+
+    for mut boid in boids {
+        match boid.resting {
+            Some(ticks_left) => {
+                let startled_by = predator_pos
+                    .filter(|predator| predator.distance(*boid.position) < params.avoidance_radius);
+
+                if let Some(predator) = startled_by {
+                    // Flee straight away from the predator
+                    let away = (*boid.position - predator).normalized();
+                    *boid.velocity = away * params.max_speed;
+                    boid.resting = None;
+                } else if ticks_left == 0 {
+                    let direction = Vec2::angled(rng.gen_range(0.0..std::f32::consts::TAU));
+                    *boid.velocity = direction * params.max_speed;
+                    boid.resting = None;
+                } else {
+                    boid.resting = Some(ticks_left - 1);
+                }
+            }
+            None => {
+                let Some(point) =
+                    nearest_perch_point(sites, *boid.position, perching.landing_distance)
+                else {
+                    continue;
+                };
+
+                if rng.gen_bool(perching.landing_chance.clamp(0.0, 1.0) as f64) {
+                    *boid.position = point;
+                    *boid.velocity = Vec2::ZERO;
+                    *boid.acceleration = Vec2::ZERO;
+                    boid.resting = Some(
+                        rng.gen_range(perching.min_rest..=perching.max_rest.max(perching.min_rest)),
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use egui::Ui;
+use tracing::info;
+
+use crate::{boids_simulation::BoidsSimulationParameters, game::TICKS_PER_SECOND};
+
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PlaylistEntry {
+    pub name: String,
+    pub params: BoidsSimulationParameters,
+    /// How long the entry plays before moving on to the next
+    pub seconds: f32,
+}
+
+/// Presets played one after another on a timer, for displays nobody is looking after.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Playlist {
+    pub entries: Vec<PlaylistEntry>,
+    #[serde(skip)]
+    playing: bool,
+    /// Stay on the current entry until released
+    #[serde(skip)]
+    held: bool,
+    #[serde(skip)]
+    current: usize,
+    /// Ticks the current entry has played for
+    #[serde(skip)]
+    ticks: u32,
+    /// Set by the controls, picked up by the next update
+    #[serde(skip)]
+    skip_requested: bool,
+}
+
+impl Playlist {
+    /// Draw the playlist editor and controls, `current` is what "Add Current Parameters" adds.
+    pub fn draw_panel(&mut self, ui: &mut Ui, current: &BoidsSimulationParameters) {
+        let mut removed = None;
+        let mut raised = None;
+        egui::Grid::new("playlist").show(ui, |ui| {
+            for (index, entry) in self.entries.iter_mut().enumerate() {
+                let playing = self.playing && index == self.current;
+                ui.label(if playing { "▶" } else { "" });
+                ui.add(egui::TextEdit::singleline(&mut entry.name).desired_width(100.0));
+                ui.add(
+                    egui::DragValue::new(&mut entry.seconds)
+                        .speed(1.0)
+                        .range(1.0..=3600.0)
+                        .suffix("s"),
+                );
+                if ui
+                    .add_enabled(index > 0, egui::Button::new("⬆"))
+                    .on_hover_text("Play earlier")
+                    .clicked()
+                {
+                    raised = Some(index);
+                }
+                if ui.button("🗑").on_hover_text("Remove").clicked() {
+                    removed = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(index) = raised {
+            self.entries.swap(index - 1, index);
+        }
+        if let Some(index) = removed {
+            self.entries.remove(index);
+            if index < self.current {
+                self.current -= 1;
+            }
+        }
+
+        if ui
+            .button("Add Current Parameters")
+            .on_hover_text("Load a scenario or tweak the parameters first, then add them here")
+            .clicked()
+        {
+            self.entries.push(PlaylistEntry {
+                name: format!("Preset {}", self.entries.len() + 1),
+                params: current.clone(),
+                seconds: 60.0,
+            });
+        }
+
+        ui.add_enabled_ui(!self.entries.is_empty(), |ui| {
+            ui.horizontal(|ui| {
+                let label = if self.playing { "Stop" } else { "Play" };
+                if ui.button(label).clicked() {
+                    self.playing = !self.playing;
+                    self.ticks = 0;
+                    // Starting over plays the first entry straight away
+                    self.skip_requested = self.playing;
+                    self.current = self.entries.len() - 1;
+                    self.held = false;
+                }
+                if ui
+                    .add_enabled(self.playing, egui::Button::new("Skip"))
+                    .clicked()
+                {
+                    self.skip_requested = true;
+                }
+                ui.add_enabled_ui(self.playing, |ui| {
+                    ui.checkbox(&mut self.held, "Hold")
+                        .on_hover_text("Stay on the current entry until released");
+                });
+            });
+        });
+    }
+
+    /// Advance the playlist by a tick, returning the next entry when it's time to switch to it.
+    pub fn update(&mut self) -> Option<&PlaylistEntry> {
+        if !self.playing || self.entries.is_empty() {
+            return None;
+        }
+        self.current = self.current.min(self.entries.len() - 1);
+
+        if !self.held {
+            self.ticks += 1;
+        }
+        let duration = (self.entries[self.current].seconds * TICKS_PER_SECOND) as u32;
+        if !self.skip_requested && self.ticks < duration {
+            return None;
+        }
+
+        self.skip_requested = false;
+        self.ticks = 0;
+        self.current = (self.current + 1) % self.entries.len();
+        let entry = &self.entries[self.current];
+        info!(entry = entry.name, "playlist moving on");
+        Some(entry)
+    }
+}
@@ -0,0 +1,483 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeSet,
+    ffi::{c_char, CStr},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use egui::{Color32, Painter, Pos2, Rect, Stroke, Ui, Vec2};
+use libloading::Library;
+
+use crate::{
+    boid_store::{BoidRef, BoidStore},
+    camera::Camera,
+    error::{BoidsError, Result},
+    steering::{SteeringContext, SteeringRule},
+};
+
+/// Where plugins are loaded from, relative to the working directory.
+const PLUGIN_DIR: &str = "plugins";
+/// Bumped whenever the types below change, plugins declaring another version aren't loaded.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+/// The function every plugin exports, which returns a pointer to its [`PluginDeclaration`].
+const ENTRY_POINT: &[u8] = b"boids_plugin\0";
+/// The most lines one visualizer can draw in a frame.
+const MAX_VISUALIZER_LINES: usize = 10_000;
+
+/// Every behavior name plugins have had, see [`intern`].
+static BEHAVIOR_NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+thread_local! {
+    /// The neighbors handed to a plugin's force, kept between calls so every boid doesn't
+    /// allocate its own.
+    static PLUGIN_NEIGHBORS: RefCell<Vec<PluginBoid>> = const { RefCell::new(Vec::new()) };
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct PluginVec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// A boid as plugins see it.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct PluginBoid {
+    pub id: u64,
+    pub position: PluginVec2,
+    pub velocity: PluginVec2,
+    /// Id of the boid's group, -1 without one
+    pub group: i64,
+    /// Ticks since the boid was spawned
+    pub age: u32,
+}
+
+/// The flock parameters a plugin behavior can scale its force by, with the boid's group
+/// overrides applied.
+#[repr(C)]
+pub struct PluginParameters {
+    pub max_speed: f32,
+    pub max_force: f32,
+    pub neighbor_radius: f32,
+}
+
+/// A steering behavior, added up with the built-in ones into every boid's acceleration.
+#[repr(C)]
+pub struct PluginBehavior {
+    /// NUL-terminated, shown in the UI
+    pub name: *const c_char,
+    /// RGBA that boids this behavior pulls on the hardest are drawn in, fully transparent for none
+    pub color: [u8; 4],
+    /// The force on `boid` from the `neighbor_count` boids at `neighbors` that might be within
+    /// the neighbor radius. Called from several threads at once. A plugin leaving it null
+    /// doesn't load.
+    pub force: Option<ForceFn>,
+}
+
+pub type ForceFn = unsafe extern "C" fn(
+    boid: *const PluginBoid,
+    neighbors: *const PluginBoid,
+    neighbor_count: usize,
+    params: *const PluginParameters,
+) -> PluginVec2;
+
+/// A line in world coordinates for a visualizer to draw.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct PluginLine {
+    pub start: PluginVec2,
+    pub end: PluginVec2,
+    /// RGBA
+    pub color: [u8; 4],
+}
+
+/// Something drawn over the flock each frame.
+#[repr(C)]
+pub struct PluginVisualizer {
+    /// NUL-terminated, shown in the UI
+    pub name: *const c_char,
+    /// Writes up to `capacity` lines for the `boid_count` boids at `boids` into `lines` and
+    /// returns how many it wrote. A plugin leaving it null doesn't load.
+    pub draw: Option<DrawFn>,
+}
+
+pub type DrawFn = unsafe extern "C" fn(
+    boids: *const PluginBoid,
+    boid_count: usize,
+    lines: *mut PluginLine,
+    capacity: usize,
+) -> usize;
+
+/// Everything a plugin adds, which has to stay valid for as long as the plugin is loaded.
+///
+/// A plugin is a dynamic library in the `plugins/` directory exporting `boids_plugin`, in C:
+///
+/// ```c
+/// static PluginVec2 spin(const PluginBoid *boid, const PluginBoid *neighbors,
+///                        size_t neighbor_count, const PluginParameters *params) {
+///     return (PluginVec2){ -boid->velocity.y * 0.01f, boid->velocity.x * 0.01f };
+/// }
+///
+/// static const PluginBehavior behaviors[] = { { "Spin", { 0, 0, 0, 0 }, spin } };
+/// static const PluginDeclaration declaration = { 1, behaviors, 1, NULL, 0 };
+///
+/// const PluginDeclaration *boids_plugin(void) { return &declaration; }
+/// ```
+#[repr(C)]
+pub struct PluginDeclaration {
+    /// [`PLUGIN_ABI_VERSION`] as of when the plugin was built
+    pub abi_version: u32,
+    pub behaviors: *const PluginBehavior,
+    pub behavior_count: usize,
+    pub visualizers: *const PluginVisualizer,
+    pub visualizer_count: usize,
+}
+
+impl From<Vec2> for PluginVec2 {
+    fn from(vec: Vec2) -> Self {
+        Self { x: vec.x, y: vec.y }
+    }
+}
+
+impl From<Pos2> for PluginVec2 {
+    fn from(pos: Pos2) -> Self {
+        Self { x: pos.x, y: pos.y }
+    }
+}
+
+impl From<BoidRef<'_>> for PluginBoid {
+    fn from(boid: BoidRef<'_>) -> Self {
+        Self {
+            id: boid.id,
+            position: boid.position.into(),
+            velocity: boid.velocity.into(),
+            group: boid.group.map_or(-1, i64::from),
+            age: boid.age,
+        }
+    }
+}
+
+fn rgba([r, g, b, a]: [u8; 4]) -> Color32 {
+    Color32::from_rgba_unmultiplied(r, g, b, a)
+}
+
+/// A plugin's behavior as one of the flock's steering rules.
+pub struct PluginRule {
+    name: &'static str,
+    color: Option<Color32>,
+    force: ForceFn,
+    /// Keeps the code `force` points into loaded
+    _library: Arc<Library>,
+}
+
+impl SteeringRule for PluginRule {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn force(&self, boid: BoidRef<'_>, context: &SteeringContext<'_>) -> Vec2 {
+        let boid = PluginBoid::from(boid);
+        let params = PluginParameters {
+            max_speed: context.params.max_speed,
+            max_force: context.params.max_force,
+            neighbor_radius: context.params.neighbor_radius,
+        };
+        let force = PLUGIN_NEIGHBORS.with_borrow_mut(|neighbors| {
+            neighbors.clear();
+            neighbors.extend(context.neighbors.iter().copied().map(PluginBoid::from));
+            // SAFETY: the plugin promised this signature by declaring the ABI version, and every
+            // pointer is valid for the duration of the call
+            unsafe { (self.force)(&boid, neighbors.as_ptr(), neighbors.len(), &params) }
+        });
+        let force = Vec2::new(force.x, force.y);
+        // A misbehaving plugin shouldn't be able to send the flock off to infinity
+        if force.is_finite() {
+            force
+        } else {
+            Vec2::ZERO
+        }
+    }
+
+    fn color(&self) -> Option<Color32> {
+        self.color
+    }
+}
+
+struct LoadedBehavior {
+    name: &'static str,
+    color: Option<Color32>,
+    force: ForceFn,
+}
+
+struct LoadedVisualizer {
+    name: String,
+    draw: DrawFn,
+    enabled: bool,
+}
+
+struct LoadedPlugin {
+    path: PathBuf,
+    library: Arc<Library>,
+    behaviors: Vec<LoadedBehavior>,
+    visualizers: Vec<LoadedVisualizer>,
+}
+
+impl LoadedPlugin {
+    fn load(path: &Path) -> Result<Self> {
+        let invalid =
+            |message: &str| BoidsError::Invalid(format!("plugin {}: {message}", path.display()));
+        // SAFETY: loading a library runs its initializers, which we have to trust like the rest
+        // of the plugin. libloading's errors already name the file.
+        let library =
+            unsafe { Library::new(path) }.map_err(|err| BoidsError::Invalid(err.to_string()))?;
+        // SAFETY: `boids_plugin` is documented to have this signature
+        let declaration = unsafe {
+            let entry = library
+                .get::<unsafe extern "C" fn() -> *const PluginDeclaration>(ENTRY_POINT)
+                .map_err(|err| invalid(&err.to_string()))?;
+            entry().as_ref()
+        }
+        .ok_or_else(|| invalid("boids_plugin returned null"))?;
+        if declaration.abi_version != PLUGIN_ABI_VERSION {
+            return Err(invalid(&format!(
+                "built for plugin ABI {}, this app is on {PLUGIN_ABI_VERSION}",
+                declaration.abi_version
+            )));
+        }
+
+        // SAFETY: the declaration stays valid while the library is loaded, and we own the library
+        let (behaviors, visualizers) = unsafe {
+            (
+                slice(declaration.behaviors, declaration.behavior_count),
+                slice(declaration.visualizers, declaration.visualizer_count),
+            )
+        };
+        let behaviors = behaviors
+            .iter()
+            .map(|behavior| {
+                // SAFETY: names are documented to be NUL-terminated
+                let name =
+                    unsafe { name(behavior.name) }.ok_or_else(|| invalid("unnamed behavior"))?;
+                let force = behavior
+                    .force
+                    .ok_or_else(|| invalid(&format!("behavior {name} has no force function")))?;
+                Ok(LoadedBehavior {
+                    name: intern(name),
+                    color: (behavior.color[3] > 0).then(|| rgba(behavior.color)),
+                    force,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let visualizers = visualizers
+            .iter()
+            .map(|visualizer| {
+                // SAFETY: names are documented to be NUL-terminated
+                let name = unsafe { name(visualizer.name) }
+                    .ok_or_else(|| invalid("unnamed visualizer"))?;
+                let draw = visualizer
+                    .draw
+                    .ok_or_else(|| invalid(&format!("visualizer {name} has no draw function")))?;
+                Ok(LoadedVisualizer {
+                    name,
+                    draw,
+                    enabled: true,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            path: path.to_owned(),
+            library: Arc::new(library),
+            behaviors,
+            visualizers,
+        })
+    }
+
+    fn file_name(&self) -> String {
+        self.path.file_name().map_or_else(
+            || self.path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        )
+    }
+}
+
+/// `name` for the life of the app, which rule names have to be.
+///
+/// Each name is only leaked the first time it's seen, so rescanning the same plugins over and
+/// over doesn't keep using more memory.
+fn intern(name: String) -> &'static str {
+    let Ok(mut names) = BEHAVIOR_NAMES.lock() else {
+        return Box::leak(name.into_boxed_str());
+    };
+    if let Some(interned) = names.get(name.as_str()) {
+        return interned;
+    }
+    let interned = Box::leak(name.into_boxed_str());
+    names.insert(interned);
+    interned
+}
+
+/// # Safety
+///
+/// Unless `len` is 0, `data` has to point to `len` valid `T`s that outlive `'a`.
+unsafe fn slice<'a, T>(data: *const T, len: usize) -> &'a [T] {
+    if data.is_null() || len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(data, len)
+    }
+}
+
+/// # Safety
+///
+/// `name` has to be null or point to a NUL-terminated string.
+unsafe fn name(name: *const c_char) -> Option<String> {
+    if name.is_null() {
+        return None;
+    }
+    Some(CStr::from_ptr(name).to_string_lossy().into_owned())
+}
+
+/// Behaviors and visualizers from compiled plugins in the `plugins/` directory.
+pub struct PluginHost {
+    dir: PathBuf,
+    plugins: Vec<LoadedPlugin>,
+    /// Why each library in the directory that didn't load failed to
+    failures: Vec<String>,
+}
+
+impl Default for PluginHost {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from(PLUGIN_DIR),
+            plugins: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+}
+
+impl PluginHost {
+    /// Load every library in the plugins directory, replacing what was loaded before.
+    ///
+    /// Libraries that fail to load are left out and listed in the panel, returns how many did.
+    pub fn load(&mut self) -> Result<usize> {
+        self.plugins.clear();
+        self.failures.clear();
+        let paths = match self.scan() {
+            Ok(paths) => paths,
+            // No directory just means no plugins
+            Err(BoidsError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        for path in paths {
+            match LoadedPlugin::load(&path) {
+                Ok(plugin) => self.plugins.push(plugin),
+                Err(err) => self.failures.push(err.to_string()),
+            }
+        }
+        Ok(self.plugins.len())
+    }
+
+    fn scan(&self) -> Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == std::env::consts::DLL_EXTENSION)
+            {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    pub fn failure_count(&self) -> usize {
+        self.failures.len()
+    }
+
+    /// A steering rule for every behavior the plugins add, in the order they were loaded.
+    pub fn rules(&self) -> Vec<Box<dyn SteeringRule>> {
+        self.plugins
+            .iter()
+            .flat_map(|plugin| {
+                plugin.behaviors.iter().map(|behavior| {
+                    Box::new(PluginRule {
+                        name: behavior.name,
+                        color: behavior.color,
+                        force: behavior.force,
+                        _library: Arc::clone(&plugin.library),
+                    }) as Box<dyn SteeringRule>
+                })
+            })
+            .collect()
+    }
+
+    /// Run every enabled visualizer over the flock and draw the lines they come up with.
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera, boids: &BoidStore) {
+        let visualizers = || {
+            self.plugins
+                .iter()
+                .flat_map(|plugin| &plugin.visualizers)
+                .filter(|visualizer| visualizer.enabled)
+        };
+        if visualizers().next().is_none() {
+            return;
+        }
+        let boids: Vec<PluginBoid> = boids.iter().map(Into::into).collect();
+        let mut lines = vec![PluginLine::default(); MAX_VISUALIZER_LINES];
+        for visualizer in visualizers() {
+            // SAFETY: the plugin promised this signature by declaring the ABI version, and both
+            // buffers are as long as we say they are
+            let count = unsafe {
+                (visualizer.draw)(boids.as_ptr(), boids.len(), lines.as_mut_ptr(), lines.len())
+            };
+            for line in &lines[..count.min(lines.len())] {
+                painter.line_segment(
+                    [
+                        camera.world_to_screen(rect, Pos2::new(line.start.x, line.start.y)),
+                        camera.world_to_screen(rect, Pos2::new(line.end.x, line.end.y)),
+                    ],
+                    Stroke::new(1.0, rgba(line.color)),
+                );
+            }
+        }
+    }
+
+    /// Returns true when the plugins should be loaded again.
+    pub fn draw_panel(&mut self, ui: &mut Ui) -> bool {
+        let rescan = ui
+            .button("Rescan Plugins")
+            .on_hover_text(format!(
+                "Load the plugins in the {PLUGIN_DIR}/ directory again, new ones included"
+            ))
+            .clicked();
+        if self.plugins.is_empty() && self.failures.is_empty() {
+            ui.weak(format!(
+                "No plugins, put .{} files in {PLUGIN_DIR}/ to add behaviors",
+                std::env::consts::DLL_EXTENSION
+            ));
+        }
+        for plugin in &mut self.plugins {
+            ui.label(plugin.file_name());
+            ui.indent(plugin.file_name(), |ui| {
+                for behavior in &plugin.behaviors {
+                    ui.label(format!("Behavior: {}", behavior.name));
+                }
+                for visualizer in &mut plugin.visualizers {
+                    ui.checkbox(
+                        &mut visualizer.enabled,
+                        format!("Visualizer: {}", visualizer.name),
+                    );
+                }
+            });
+        }
+        for failure in &self.failures {
+            ui.colored_label(ui.visuals().error_fg_color, failure);
+        }
+        rescan
+    }
+}
@@ -0,0 +1,423 @@
+#[cfg(feature = "gui")]
+use std::f32::consts::TAU;
+
+#[cfg(feature = "gui")]
+use egui::{Painter, Stroke, Ui};
+#[cfg(feature = "gui")]
+use rand::Rng;
+
+use crate::{
+    game::TICKS_PER_SECOND,
+    math::{Pos2, Vec2},
+};
+
+#[cfg(feature = "gui")]
+use crate::{
+    boid_store::{BoidRef, BoidStore},
+    camera::Camera,
+    math::{Color32, Rect},
+};
+
+#[cfg(feature = "gui")]
+const PREDATOR_COLOR: Color32 = Color32::from_rgb(200, 30, 30);
+#[cfg(feature = "gui")]
+const TIRED_COLOR: Color32 = Color32::from_rgb(120, 70, 70);
+#[cfg(feature = "gui")]
+const STAMINA_COLOR: Color32 = Color32::from_rgb(240, 200, 60);
+/// Largest change of heading per tick when patrolling, in radians.
+#[cfg(feature = "gui")]
+const PATROL_TURN: f32 = 0.08;
+/// How far to either side of the flock's escape direction flankers head, in radians.
+#[cfg(feature = "gui")]
+const FLANK_ANGLE: f32 = 0.8;
+#[cfg(feature = "gui")]
+const GOAL_COLOR: Color32 = Color32::from_rgba_premultiplied(120, 20, 20, 120);
+
+/// What a predator hunting on its own goes after.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum PreyChoice {
+    /// The closest boid in sight
+    #[default]
+    NearestBoid,
+    /// The middle of the boids clustered around the closest one, so predators dive into the
+    /// thick of a group rather than picking off whichever boid is on its edge
+    NearestCluster,
+}
+
+/// Computer controlled predators that hunt the flock on their own.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct PredatorParameters {
+    pub count: usize,
+    /// Top speed while chasing a boid
+    pub sprint_speed: f32,
+    /// Speed while wandering around looking for prey or catching breath
+    pub patrol_speed: f32,
+    pub max_force: f32,
+    /// Predators spot boids this close
+    pub sight_radius: f32,
+    pub prey_choice: PreyChoice,
+    /// Boids this close to the nearest one count as its cluster
+    pub cluster_radius: f32,
+    /// Stamina used per tick of sprinting, stamina goes from 0 to 1
+    pub stamina_drain: f32,
+    /// Stamina regained per tick when not sprinting
+    pub stamina_recovery: f32,
+    /// An exhausted predator patrols until it has this much stamina again
+    pub rested_stamina: f32,
+    /// Predators work together: one drives the flock while the rest cut off its escape
+    pub pack_hunting: bool,
+    /// How far ahead of the flock flankers wait for it
+    pub flank_distance: f32,
+}
+
+impl Default for PredatorParameters {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            sprint_speed: 6.5,
+            patrol_speed: 2.0,
+            max_force: 0.3,
+            sight_radius: 250.0,
+            prey_choice: PreyChoice::default(),
+            cluster_radius: 100.0,
+            stamina_drain: 1.0 / 300.0,
+            stamina_recovery: 1.0 / 600.0,
+            rested_stamina: 0.8,
+            pack_hunting: false,
+            flank_distance: 150.0,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl PredatorParameters {
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.label("Number of Predators");
+        ui.add(egui::Slider::new(&mut self.count, 0..=10));
+        ui.label("Sprint Speed");
+        ui.add(egui::DragValue::new(&mut self.sprint_speed).speed(0.1));
+        ui.label("Patrol Speed");
+        ui.add(egui::DragValue::new(&mut self.patrol_speed).speed(0.1));
+        ui.label("Max Force");
+        ui.add(egui::DragValue::new(&mut self.max_force).speed(0.05));
+        ui.label("Sight Radius");
+        ui.add(egui::DragValue::new(&mut self.sight_radius).range(0.0..=f32::MAX));
+        ui.label("Hunt");
+        ui.horizontal(|ui| {
+            ui.radio_value(
+                &mut self.prey_choice,
+                PreyChoice::NearestBoid,
+                "Nearest Boid",
+            );
+            ui.radio_value(
+                &mut self.prey_choice,
+                PreyChoice::NearestCluster,
+                "Nearest Cluster",
+            );
+        });
+        if self.prey_choice == PreyChoice::NearestCluster {
+            ui.label("Cluster Radius");
+            ui.add(egui::DragValue::new(&mut self.cluster_radius).range(0.0..=f32::MAX));
+        }
+        ui.label("Sprint Time (seconds)");
+        seconds_drag(ui, &mut self.stamina_drain);
+        ui.label("Recovery Time (seconds)")
+            .on_hover_text("From exhausted to full stamina");
+        seconds_drag(ui, &mut self.stamina_recovery);
+        ui.label("Rested Stamina");
+        ui.add(egui::Slider::new(&mut self.rested_stamina, 0.0..=1.0))
+            .on_hover_text("How rested an exhausted predator has to be before it hunts again");
+
+        ui.checkbox(&mut self.pack_hunting, "Pack Hunting")
+            .on_hover_text(
+            "The predator nearest the flock drives it while the others flank where it will run to",
+        );
+        if self.pack_hunting {
+            ui.label("Flank Distance");
+            ui.add(egui::DragValue::new(&mut self.flank_distance).range(0.0..=f32::MAX));
+        }
+    }
+}
+
+/// Edit a per tick rate of the 0 to 1 stamina range as the seconds it takes to use up or fill.
+#[cfg(feature = "gui")]
+fn seconds_drag(ui: &mut Ui, per_tick: &mut f32) {
+    let mut seconds = 1.0 / (per_tick.max(f32::EPSILON) * TICKS_PER_SECOND);
+    if ui
+        .add(
+            egui::DragValue::new(&mut seconds)
+                .speed(0.1)
+                .range(0.1..=600.0),
+        )
+        .changed()
+    {
+        *per_tick = 1.0 / (seconds * TICKS_PER_SECOND);
+    }
+}
+
+/// Predators, the mouse one included, taking boids out of the flock when they get close enough.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct CatchParameters {
+    pub enabled: bool,
+    /// A predator this close to a boid catches it
+    pub kill_radius: f32,
+    /// Caught boids come back after a while, otherwise the flock shrinks for good
+    pub respawn: bool,
+    pub respawn_seconds: f32,
+}
+
+impl Default for CatchParameters {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kill_radius: 10.0,
+            respawn: true,
+            respawn_seconds: 5.0,
+        }
+    }
+}
+
+impl CatchParameters {
+    /// Ticks a caught boid waits before it comes back.
+    pub fn respawn_ticks(&self) -> u32 {
+        (self.respawn_seconds.max(0.0) * TICKS_PER_SECOND).round() as u32
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Predators Catch Boids");
+        if !self.enabled {
+            return;
+        }
+        ui.label("Kill Radius");
+        ui.add(
+            egui::DragValue::new(&mut self.kill_radius)
+                .speed(0.5)
+                .range(0.0..=200.0),
+        );
+        ui.checkbox(&mut self.respawn, "Respawn")
+            .on_hover_text("Bring caught boids back, keeping the flock size");
+        if self.respawn {
+            ui.label("Respawn Delay (seconds)");
+            ui.add(
+                egui::DragValue::new(&mut self.respawn_seconds)
+                    .speed(0.1)
+                    .range(0.0..=600.0),
+            );
+        }
+    }
+}
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct Predator {
+    pub position: Pos2,
+    pub velocity: Vec2,
+    /// Between 0 (exhausted) and 1 (fully rested)
+    pub stamina: f32,
+    /// Whether the predator ran out of stamina and is patrolling until it has rested
+    pub exhausted: bool,
+    /// Where the predator was heading last tick, `None` while patrolling
+    #[serde(default)]
+    pub goal: Option<Pos2>,
+}
+
+impl Predator {
+    #[cfg(feature = "gui")]
+    pub fn new(position: Pos2, rng: &mut impl Rng) -> Self {
+        Self {
+            position,
+            velocity: Vec2::angled(rng.gen_range(0.0..TAU)),
+            stamina: 1.0,
+            exhausted: false,
+            goal: None,
+        }
+    }
+
+    /// Whether the predator has the energy to hunt.
+    #[cfg(feature = "gui")]
+    fn hunting(&self) -> bool {
+        !self.exhausted
+    }
+
+    /// Flying boids this predator can see.
+    #[cfg(feature = "gui")]
+    fn visible_prey<'a>(
+        &'a self,
+        boids: &'a BoidStore,
+        params: &'a PredatorParameters,
+    ) -> impl Iterator<Item = BoidRef<'a>> {
+        boids.iter().filter(move |boid| {
+            boid.resting.is_none() && boid.position.distance(self.position) < params.sight_radius
+        })
+    }
+
+    /// Where to chase among the boids in sight, if the predator has the energy to chase anything.
+    #[cfg(feature = "gui")]
+    fn prey(&self, boids: &BoidStore, params: &PredatorParameters) -> Option<Pos2> {
+        if !self.hunting() {
+            return None;
+        }
+        let nearest = self
+            .visible_prey(boids, params)
+            .map(|boid| boid.position)
+            .min_by(|a, b| {
+                a.distance(self.position)
+                    .total_cmp(&b.distance(self.position))
+            })?;
+        match params.prey_choice {
+            PreyChoice::NearestBoid => Some(nearest),
+            PreyChoice::NearestCluster => {
+                // Always counts the nearest boid itself, so never empty
+                let (sum, count) = self
+                    .visible_prey(boids, params)
+                    .filter(|boid| boid.position.distance(nearest) <= params.cluster_radius)
+                    .fold((Vec2::ZERO, 0), |(sum, count), boid| {
+                        (sum + boid.position.to_vec2(), count + 1)
+                    });
+                Some(Pos2::ZERO + sum / count.max(1) as f32)
+            }
+        }
+    }
+
+    /// Sprint towards `goal`, or patrol when there's none.
+    #[cfg(feature = "gui")]
+    pub fn update(&mut self, goal: Option<Pos2>, params: &PredatorParameters, rng: &mut impl Rng) {
+        self.goal = goal;
+        let (desired_velocity, sprinting) = match goal {
+            Some(goal) if goal != self.position => (
+                (goal - self.position).normalized() * params.sprint_speed,
+                true,
+            ),
+            // Made it, wait there
+            Some(_) => (Vec2::ZERO, true),
+            None => {
+                let heading = self.velocity.angle() + rng.gen_range(-PATROL_TURN..PATROL_TURN);
+                (Vec2::angled(heading) * params.patrol_speed, false)
+            }
+        };
+
+        if sprinting {
+            self.stamina = (self.stamina - params.stamina_drain).max(0.0);
+            if self.stamina == 0.0 {
+                self.exhausted = true;
+            }
+        } else {
+            self.stamina = (self.stamina + params.stamina_recovery).min(1.0);
+            if self.stamina >= params.rested_stamina {
+                self.exhausted = false;
+            }
+        }
+
+        let mut steer = desired_velocity - self.velocity;
+        if steer.length() > params.max_force {
+            steer = steer.normalized() * params.max_force;
+        }
+        self.velocity += steer;
+        self.position += self.velocity;
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera, show_goal: bool) {
+        let screen_pos = camera.world_to_screen(rect, self.position);
+        if let Some(goal) = self.goal.filter(|_| show_goal) {
+            painter.line_segment(
+                [screen_pos, camera.world_to_screen(rect, goal)],
+                Stroke::new(1.0, GOAL_COLOR),
+            );
+        }
+        let color = if self.exhausted {
+            TIRED_COLOR
+        } else {
+            PREDATOR_COLOR
+        };
+        painter.circle_filled(screen_pos, 7.0, color);
+        if self.velocity != Vec2::ZERO {
+            painter.arrow(
+                screen_pos,
+                self.velocity.normalized() * 14.0,
+                Stroke::new(2.0, color),
+            );
+        }
+
+        // Stamina bar underneath
+        let bar = Rect::from_min_size(screen_pos + Vec2::new(-10.0, 10.0), Vec2::new(20.0, 3.0));
+        painter.rect_filled(bar, 0.0, Color32::from_gray(50));
+        painter.rect_filled(
+            Rect::from_min_size(bar.min, Vec2::new(bar.width() * self.stamina, bar.height())),
+            0.0,
+            STAMINA_COLOR,
+        );
+    }
+}
+
+/// Where every predator heads this tick, by index, `None` for those patrolling.
+///
+/// Alone, each predator chases the nearest boid it sees. In a pack the rested predators share one
+/// target, the middle of the boids any of them can see: the nearest one drives straight at it, which
+/// sends the flock fleeing directly away, and the others fan out ahead to either side of that path.
+#[cfg(feature = "gui")]
+pub fn hunt_goals(
+    predators: &[Predator],
+    boids: &BoidStore,
+    params: &PredatorParameters,
+) -> Vec<Option<Pos2>> {
+    let solo = || {
+        predators
+            .iter()
+            .map(|predator| predator.prey(boids, params))
+            .collect()
+    };
+    let hunters: Vec<usize> = (0..predators.len())
+        .filter(|index| predators[*index].hunting())
+        .collect();
+    if !params.pack_hunting || hunters.len() < 2 {
+        return solo();
+    }
+
+    // The shared target, boids seen by several hunters count once per hunter
+    let (sum, count) = hunters
+        .iter()
+        .flat_map(|index| predators[*index].visible_prey(boids, params))
+        .fold((Vec2::ZERO, 0), |(sum, count), boid| {
+            (sum + boid.position.to_vec2(), count + 1)
+        });
+    if count == 0 {
+        return solo();
+    }
+    let target = Pos2::ZERO + sum / count as f32;
+
+    let Some(driver) = hunters.iter().copied().min_by(|a, b| {
+        predators[*a]
+            .position
+            .distance(target)
+            .total_cmp(&predators[*b].position.distance(target))
+    }) else {
+        return solo();
+    };
+    let escape = (target - predators[driver].position).normalized();
+
+    let mut goals = vec![None; predators.len()];
+    goals[driver] = Some(target);
+    for (flank, index) in hunters
+        .into_iter()
+        .filter(|index| *index != driver)
+        .enumerate()
+    {
+        // Alternate sides, fanning out wider with every pair of flankers
+        let side = if flank % 2 == 0 { 1.0 } else { -1.0 };
+        let angle = side * FLANK_ANGLE * (1 + flank / 2) as f32;
+        goals[index] = Some(target + rotate(escape, angle) * params.flank_distance);
+    }
+    goals
+}
+
+#[cfg(feature = "gui")]
+fn rotate(vector: Vec2, angle: f32) -> Vec2 {
+    let (sin, cos) = angle.sin_cos();
+    Vec2::new(
+        vector.x * cos - vector.y * sin,
+        vector.x * sin + vector.y * cos,
+    )
+}
@@ -0,0 +1,203 @@
+use egui::DroppedFile;
+use ruzstd::{
+    decoding::StreamingDecoder,
+    encoding::{compress_to_vec, CompressionLevel},
+};
+
+use crate::{
+    error::{BoidsError, Result},
+    replay::{InputRecording, ReplayKeyframe, TimedInputEvent},
+};
+
+pub const RECORDING_EXTENSION: &str = "boidrec";
+/// Recording files start with this, followed by the header's length and the header itself.
+const MAGIC: &[u8; 8] = b"BOIDREC\0";
+/// Bumped only when files already saved with a released build can't be read as they are, along
+/// with a migration from the old version. Keyframe fields added before then go into this one.
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct RecordingHeader {
+    version: u32,
+    length_ticks: u64,
+    /// One per keyframe, in order
+    blocks: Vec<BlockIndex>,
+}
+
+/// Where a block is, so reading from a keyframe only decompresses the blocks from there on.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct BlockIndex {
+    /// Tick of the keyframe the block starts with
+    tick: u64,
+    /// Offset of the compressed block from the end of the header
+    offset: u64,
+    length: u64,
+}
+
+/// A keyframe with the events and checksums recorded until the next one, zstd compressed on its
+/// own.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct RecordingBlock {
+    keyframe: ReplayKeyframe,
+    events: Vec<TimedInputEvent>,
+    checksums: Vec<u64>,
+}
+
+/// Encode a recording to save or share it.
+///
+/// Only inputs are recorded between keyframes, so the keyframes' boids make up most of the data.
+/// They're kept at full precision, since replays need the exact state to reproduce the run, and
+/// compressed one block per keyframe instead.
+pub fn encode_recording(recording: &InputRecording) -> Vec<u8> {
+    let mut blocks = Vec::new();
+    let mut index = Vec::new();
+    for (i, keyframe) in recording.keyframes.iter().enumerate() {
+        let end = recording
+            .keyframes
+            .get(i + 1)
+            .map_or(recording.length_ticks, |next| next.tick);
+        let first_event = recording
+            .events
+            .partition_point(|event| event.tick < keyframe.tick);
+        let events = recording.events[first_event..]
+            .iter()
+            .take_while(|event| event.tick < end)
+            .cloned()
+            .collect();
+        // Recordings made before checksums existed have none
+        let checksums = recording.checksums.len();
+        let block = compress(&RecordingBlock {
+            keyframe: keyframe.clone(),
+            events,
+            checksums: recording.checksums
+                [(keyframe.tick as usize).min(checksums)..(end as usize).min(checksums)]
+                .to_vec(),
+        });
+        index.push(BlockIndex {
+            tick: keyframe.tick,
+            offset: blocks.len() as u64,
+            length: block.len() as u64,
+        });
+        blocks.extend(block);
+    }
+
+    // Serializing our own plain data types into a Vec can't fail
+    let header = bincode::serialize(&RecordingHeader {
+        version: FORMAT_VERSION,
+        length_ticks: recording.length_ticks,
+        blocks: index,
+    })
+    .unwrap_or_default();
+    let mut bytes = Vec::with_capacity(MAGIC.len() + 4 + header.len() + blocks.len());
+    bytes.extend(MAGIC);
+    bytes.extend((header.len() as u32).to_le_bytes());
+    bytes.extend(header);
+    bytes.extend(blocks);
+    bytes
+}
+
+pub fn decode_recording(bytes: &[u8]) -> Result<InputRecording> {
+    decode_recording_from(bytes, 0)
+}
+
+pub fn is_recording_file(name: &str) -> bool {
+    name.rsplit_once('.')
+        .is_some_and(|(_, extension)| extension.eq_ignore_ascii_case(RECORDING_EXTENSION))
+}
+
+/// Load a recording dropped onto the window.
+///
+/// Natively we get a path to read, on the web the browser hands us the bytes directly.
+pub fn recording_from_dropped_file(file: &DroppedFile) -> Result<InputRecording> {
+    if let Some(bytes) = &file.bytes {
+        return decode_recording(bytes);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = &file.path {
+        return decode_recording(&std::fs::read(path)?);
+    }
+
+    Err(BoidsError::Invalid(format!(
+        "could not read dropped file {}",
+        file.name
+    )))
+}
+
+/// Decode the part of a recording from the last keyframe at or before `tick`, which becomes tick 0.
+pub fn decode_recording_from(bytes: &[u8], tick: u64) -> Result<InputRecording> {
+    let rest = bytes
+        .strip_prefix(MAGIC)
+        .ok_or_else(|| BoidsError::Invalid("not a boids recording".to_owned()))?;
+    let length = rest
+        .get(..4)
+        .and_then(|length| length.try_into().ok())
+        .map(|length| u32::from_le_bytes(length) as usize + 4)
+        .ok_or_else(truncated)?;
+    let header = rest.get(4..length).ok_or_else(truncated)?;
+    let blocks = &rest[length..];
+    let header: RecordingHeader = bincode::deserialize(header).map_err(parse_error)?;
+    if header.version > FORMAT_VERSION {
+        return Err(BoidsError::Invalid(format!(
+            "recording format version {} is newer than this build reads",
+            header.version
+        )));
+    }
+
+    let first = header
+        .blocks
+        .partition_point(|block| block.tick <= tick)
+        .saturating_sub(1);
+    let cut = header.blocks.get(first).map_or(0, |block| block.tick);
+    let mut recording = InputRecording {
+        keyframes: Vec::new(),
+        events: Vec::new(),
+        length_ticks: header.length_ticks.saturating_sub(cut),
+        checksums: Vec::new(),
+    };
+    for index in &header.blocks[first..] {
+        let end = index.offset.checked_add(index.length).ok_or_else(|| {
+            BoidsError::Invalid("recording has a block past the end of any file".to_owned())
+        })?;
+        let block = usize::try_from(index.offset)
+            .ok()
+            .zip(usize::try_from(end).ok())
+            .and_then(|(start, end)| blocks.get(start..end))
+            .ok_or_else(truncated)?;
+        let block = StreamingDecoder::new(block).map_err(|err| BoidsError::Parse {
+            format: "recording",
+            message: err.to_string(),
+        })?;
+        let mut block: RecordingBlock = bincode::deserialize_from(block).map_err(parse_error)?;
+
+        block.keyframe.tick = block.keyframe.tick.saturating_sub(cut);
+        for event in &mut block.events {
+            event.tick = event.tick.saturating_sub(cut);
+        }
+        recording.keyframes.push(block.keyframe);
+        recording.events.extend(block.events);
+        recording.checksums.extend(block.checksums);
+    }
+
+    if recording.keyframes.is_empty() {
+        return Err(BoidsError::Invalid("recording has no keyframes".to_owned()));
+    }
+    Ok(recording)
+}
+
+fn compress<T: serde::Serialize>(value: &T) -> Vec<u8> {
+    // Serializing our own plain data types into a Vec can't fail
+    let bytes = bincode::serialize(value).unwrap_or_default();
+    compress_to_vec(bytes.as_slice(), CompressionLevel::Fastest)
+}
+
+fn truncated() -> BoidsError {
+    BoidsError::Invalid("recording is cut short".to_owned())
+}
+
+fn parse_error(err: bincode::Error) -> BoidsError {
+    BoidsError::Parse {
+        format: "recording",
+        message: err.to_string(),
+    }
+}
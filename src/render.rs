@@ -0,0 +1,392 @@
+#[cfg(feature = "gui")]
+use std::collections::{HashMap, VecDeque};
+
+#[cfg(feature = "gui")]
+use egui::{epaint::Mesh, Shape, Stroke, Ui};
+
+#[cfg(feature = "gui")]
+use crate::{
+    boid_store::{BoidRef, BoidStore},
+    boids_simulation::BoidsSimulationParameters,
+    camera::Camera,
+    coloring::ColorTheme,
+    group::GroupParameters,
+    leader::LEADER_COLOR,
+    math::{Color32, Pos2, Rect, Vec2},
+    particles::DEFAULT_BUDGET,
+};
+
+/// Boids just off screen can still reach into view with their arrow or be lifted into view by
+/// their altitude, so keep those too (in screen pixels).
+#[cfg(feature = "gui")]
+const CULL_MARGIN: f32 = 50.0;
+/// Side length of a boid drawn as a point, in screen pixels.
+#[cfg(feature = "gui")]
+const POINT_SIZE: f32 = 2.0;
+/// Length of a boid's arrow when its size isn't scaled, in screen pixels.
+#[cfg(feature = "gui")]
+const DEFAULT_SIZE: f32 = 10.0;
+/// Neighbor count at which a boid reaches the maximum size.
+#[cfg(feature = "gui")]
+const FULL_NEIGHBORS: f32 = 12.0;
+/// Newly hatched boids grow to full size over this many ticks.
+#[cfg(feature = "gui")]
+const HATCH_TICKS: f32 = 30.0;
+/// How many past positions a trail keeps, one per tick.
+#[cfg(feature = "gui")]
+const TRAIL_LENGTH: usize = 30;
+/// A trail is broken where its boid moved further than this in a tick, like wrapping around.
+#[cfg(feature = "gui")]
+const TRAIL_BREAK_DISTANCE: f32 = 50.0;
+
+/// What a boid is drawn as when zoomed in far enough to draw it as more than a point.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum BoidShape {
+    #[default]
+    Arrow,
+    Triangle,
+    Circle,
+}
+
+impl BoidShape {
+    pub fn label(self) -> &'static str {
+        match self {
+            BoidShape::Arrow => "Arrow",
+            BoidShape::Triangle => "Triangle",
+            BoidShape::Circle => "Circle",
+        }
+    }
+}
+
+/// Where a group's boid colors come from.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum GroupPalette {
+    /// Whatever the boid colors setting gives every boid
+    #[default]
+    Theme,
+    /// The group's color, whatever the boid colors setting
+    Solid,
+    /// The group's color, darker the slower the boid flies
+    Speed,
+}
+
+impl GroupPalette {
+    #[cfg(feature = "gui")]
+    fn label(self) -> &'static str {
+        match self {
+            GroupPalette::Theme => "Boid Colors Setting",
+            GroupPalette::Solid => "Group Color",
+            GroupPalette::Speed => "Group Color by Speed",
+        }
+    }
+}
+
+/// How a group's boids are drawn, so groups in a mixed flock can be told apart at a glance.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct GroupStyle {
+    pub shape: BoidShape,
+    /// Multiplies the size the boid would be drawn at otherwise
+    pub size: f32,
+    pub trail: bool,
+    pub palette: GroupPalette,
+}
+
+impl Default for GroupStyle {
+    fn default() -> Self {
+        Self {
+            shape: BoidShape::default(),
+            size: 1.0,
+            trail: false,
+            palette: GroupPalette::default(),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl GroupStyle {
+    pub fn draw_panel(&mut self, ui: &mut Ui, id: u32) {
+        egui::Grid::new(("group_style", id)).show(ui, |ui| {
+            ui.label("Shape");
+            egui::ComboBox::from_id_salt(("group_shape", id))
+                .selected_text(self.shape.label())
+                .show_ui(ui, |ui| {
+                    for shape in [BoidShape::Arrow, BoidShape::Triangle, BoidShape::Circle] {
+                        ui.selectable_value(&mut self.shape, shape, shape.label());
+                    }
+                });
+            ui.end_row();
+            ui.label("Size");
+            ui.add(egui::Slider::new(&mut self.size, 0.25..=4.0).suffix("×"));
+            ui.end_row();
+            ui.label("Colors");
+            egui::ComboBox::from_id_salt(("group_palette", id))
+                .selected_text(self.palette.label())
+                .show_ui(ui, |ui| {
+                    for palette in [
+                        GroupPalette::Theme,
+                        GroupPalette::Solid,
+                        GroupPalette::Speed,
+                    ] {
+                        ui.selectable_value(&mut self.palette, palette, palette.label());
+                    }
+                });
+            ui.end_row();
+        });
+        ui.checkbox(&mut self.trail, "Trail")
+            .on_hover_text("Draw where the group's boids have just been");
+    }
+}
+
+/// Recent positions of the boids in groups that draw trails, by boid id.
+#[cfg(feature = "gui")]
+#[derive(Default)]
+pub struct BoidTrails {
+    trails: HashMap<u64, VecDeque<Pos2>>,
+}
+
+#[cfg(feature = "gui")]
+impl BoidTrails {
+    /// Add where every trailed boid is after a tick, forgetting boids that are gone or stopped
+    /// trailing.
+    pub fn record(&mut self, boids: &BoidStore, groups: &GroupParameters) {
+        let mut trails = std::mem::take(&mut self.trails);
+        for boid in boids {
+            if !groups.style(boid.group).is_some_and(|style| style.trail) {
+                continue;
+            }
+            let mut trail = trails.remove(&boid.id).unwrap_or_default();
+            if trail.len() == TRAIL_LENGTH {
+                trail.pop_front();
+            }
+            trail.push_back(boid.position);
+            self.trails.insert(boid.id, trail);
+        }
+    }
+
+    /// Draw the trail of `boid` up to where it's drawn, fading out towards its oldest end.
+    #[cfg(feature = "gui")]
+    fn draw(
+        &self,
+        painter: &egui::Painter,
+        rect: &Rect,
+        camera: &Camera,
+        boid: BoidRef<'_>,
+        color: Color32,
+    ) {
+        let Some(trail) = self.trails.get(&boid.id) else {
+            return;
+        };
+        let points: Vec<Pos2> = trail.iter().copied().chain([boid.position]).collect();
+        for (index, pair) in points.windows(2).enumerate() {
+            if pair[0].distance(pair[1]) > TRAIL_BREAK_DISTANCE {
+                continue;
+            }
+            let fade = (index + 1) as f32 / points.len() as f32;
+            painter.line_segment(
+                [
+                    camera.world_to_screen(rect, pair[0]),
+                    camera.world_to_screen(rect, pair[1]),
+                ],
+                Stroke::new(1.5, color.gamma_multiply(0.6 * fade)),
+            );
+        }
+    }
+}
+
+/// What a boid's size on screen shows.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum SizeMapping {
+    /// Every boid is the same size
+    #[default]
+    Constant,
+    Speed,
+    NeighborCount,
+    Energy,
+}
+
+/// How the flock gets drawn, which doesn't change the simulation at all.
+#[cfg(feature = "gui")]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct RenderSettings {
+    /// Skip boids that are off screen
+    pub culling: bool,
+    /// Below this zoom boids are drawn as points in a single batch instead of arrows
+    pub point_zoom: f32,
+    pub colors: ColorTheme,
+    pub size: SizeMapping,
+    /// Size of the smallest and largest boids when the size is scaled, in screen pixels
+    pub min_size: f32,
+    pub max_size: f32,
+    /// Particle effects, and new boids growing in rather than popping into existence
+    pub effects: bool,
+    /// Most particles on screen at once, to keep effects from slowing down busy scenes
+    pub particle_budget: usize,
+    /// Show a boid's stats when hovering near it
+    pub tooltips: bool,
+    /// Draw boids part way between ticks rather than only where the last tick left them
+    pub interpolate: bool,
+    #[serde(skip)]
+    pub trails: BoidTrails,
+}
+
+#[cfg(feature = "gui")]
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            culling: true,
+            point_zoom: 0.25,
+            colors: ColorTheme::default(),
+            size: SizeMapping::default(),
+            min_size: 5.0,
+            max_size: 20.0,
+            effects: true,
+            particle_budget: DEFAULT_BUDGET,
+            tooltips: true,
+            interpolate: true,
+            trails: BoidTrails::default(),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl RenderSettings {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.culling, "Skip Off-Screen Boids");
+        ui.checkbox(&mut self.interpolate, "Smooth Motion")
+            .on_hover_text(
+                "Draw boids between ticks when the frame rate doesn't match the tick rate",
+            );
+        ui.checkbox(&mut self.effects, "Effects").on_hover_text(
+            "Sparkles when boids appear and disappear, bursts for catches, dust off walls",
+        );
+        if self.effects {
+            ui.label("Particle Budget");
+            ui.add(
+                egui::DragValue::new(&mut self.particle_budget)
+                    .speed(10.0)
+                    .range(0..=20_000),
+            );
+        }
+        ui.checkbox(&mut self.tooltips, "Boid Tooltips")
+            .on_hover_text("Hovering near a boid shows its speed, neighbors and strongest force");
+        ui.label("Draw As Points Below Zoom");
+        ui.add(egui::Slider::new(&mut self.point_zoom, 0.0..=1.0))
+            .on_hover_text("Zoomed out far enough, an arrow is just a dot anyway");
+        ui.separator();
+        self.colors.draw_panel(ui);
+        ui.separator();
+
+        ui.label("Boid Size");
+        egui::ComboBox::from_id_salt("boid_size")
+            .selected_text(match self.size {
+                SizeMapping::Constant => "Constant",
+                SizeMapping::Speed => "Speed",
+                SizeMapping::NeighborCount => "Neighbor Count",
+                SizeMapping::Energy => "Energy",
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.size, SizeMapping::Constant, "Constant");
+                ui.selectable_value(&mut self.size, SizeMapping::Speed, "Speed");
+                ui.selectable_value(&mut self.size, SizeMapping::NeighborCount, "Neighbor Count");
+                ui.selectable_value(&mut self.size, SizeMapping::Energy, "Energy");
+            });
+        if self.size != SizeMapping::Constant {
+            ui.horizontal(|ui| {
+                ui.label("Min");
+                ui.add(egui::DragValue::new(&mut self.min_size).range(1.0..=self.max_size));
+                ui.label("Max");
+                ui.add(egui::DragValue::new(&mut self.max_size).range(self.min_size..=100.0));
+            });
+        }
+    }
+
+    /// Length of `boid`'s arrow on screen, before any zoom or altitude adjustments.
+    fn boid_size(&self, boid: BoidRef<'_>, params: &BoidsSimulationParameters) -> f32 {
+        let hatching = if self.effects {
+            0.3 + 0.7 * (boid.age as f32 / HATCH_TICKS).min(1.0)
+        } else {
+            1.0
+        };
+        let style = params
+            .groups
+            .style(boid.group)
+            .map_or(1.0, |style| style.size);
+        hatching * style * self.mapped_size(boid, params)
+    }
+
+    fn mapped_size(&self, boid: BoidRef<'_>, params: &BoidsSimulationParameters) -> f32 {
+        let t = match self.size {
+            SizeMapping::Constant => return DEFAULT_SIZE,
+            SizeMapping::Speed => boid.velocity.length() / boid.max_speed(params).max(f32::EPSILON),
+            SizeMapping::NeighborCount => boid.neighbor_count as f32 / FULL_NEIGHBORS,
+            SizeMapping::Energy => boid.energy,
+        };
+        self.min_size + (self.max_size - self.min_size) * t.clamp(0.0, 1.0)
+    }
+
+    /// Draw every boid that can be seen in `rect`.
+    #[cfg(feature = "gui")]
+    pub fn draw_boids(
+        &self,
+        ui: &mut Ui,
+        rect: &Rect,
+        camera: &Camera,
+        boids: &BoidStore,
+        params: &BoidsSimulationParameters,
+    ) {
+        let visible = rect.expand(CULL_MARGIN);
+        let leader_cutoff = params.leaders.cutoff(boids);
+        let is_leader = |boid: BoidRef<'_>| leader_cutoff.is_some_and(|cutoff| boid.id <= cutoff);
+        let boids = boids.iter().filter(|boid| {
+            !self.culling || visible.contains(camera.world_to_screen(rect, boid.position))
+        });
+
+        if camera.zoom >= self.point_zoom {
+            let painter = ui.painter_at(*rect);
+            for boid in boids {
+                let (color, size) = if is_leader(boid) {
+                    (
+                        LEADER_COLOR,
+                        self.boid_size(boid, params) * params.leaders.leader_size,
+                    )
+                } else {
+                    (
+                        params
+                            .dominance
+                            .shade(boid, self.colors.boid_color(boid, params)),
+                        self.boid_size(boid, params),
+                    )
+                };
+                self.trails.draw(&painter, rect, camera, boid, color);
+                boid.draw(ui, rect, camera, params, color, size);
+            }
+            return;
+        }
+
+        let mut mesh = Mesh::default();
+        for boid in boids {
+            let mut size = self.boid_size(boid, params);
+            let color = if boid.resting.is_some() {
+                Color32::LIGHT_GRAY
+            } else if is_leader(boid) {
+                size *= params.leaders.leader_size;
+                LEADER_COLOR
+            } else {
+                params
+                    .dominance
+                    .shade(boid, self.colors.boid_color(boid, params))
+            };
+            mesh.add_colored_rect(
+                Rect::from_center_size(
+                    camera.world_to_screen(rect, boid.position),
+                    Vec2::splat(POINT_SIZE * size / DEFAULT_SIZE),
+                ),
+                color,
+            );
+        }
+        ui.painter_at(*rect).add(Shape::mesh(mesh));
+    }
+}
@@ -0,0 +1,682 @@
+use std::mem::size_of;
+
+use egui::{Pos2, Ui, Vec2};
+use tracing::debug;
+
+use crate::{
+    annotation::Annotation,
+    boid::Boid,
+    boid_store::BoidStore,
+    boids_simulation::BoidsSimulationParameters,
+    current_zone::CurrentZone,
+    danger::DangerMap,
+    energy::Thermal,
+    game::TICKS_PER_SECOND,
+    gate::Gate,
+    goal_point::{GoalPoint, WaypointPath},
+    group::BoidGroup,
+    impulse::Impulse,
+    lfo::Lfo,
+    migration::MigrationTarget,
+    moving_obstacle::{MovingObstacle, ObstaclePath},
+    obstacle::Obstacle,
+    perch::PerchSite,
+    predator::Predator,
+    split_merge::ExperimentForces,
+    temporary_obstacle::TemporaryObstacle,
+    tween::ParameterTween,
+};
+
+/// Everything the user controls that feeds into a simulation tick.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SimulationInputs {
+    pub predator: Option<Pos2>,
+    /// How far the predator moved since the last tick
+    pub predator_velocity: Vec2,
+    /// New boids spawn around this point, which follows the camera
+    pub spawn_center: Pos2,
+    /// Kept apart from `params` since the flock size isn't part of saved parameters
+    pub flock_size: usize,
+    /// As set by hand, without the tween or LFOs swinging them, which playback runs again
+    pub params: BoidsSimulationParameters,
+    /// See [`crate::lfo::LfoBank`]
+    #[serde(default)]
+    pub lfos: Vec<Lfo>,
+    /// Blending into a loaded preset, which sets the parameters itself until it's done
+    #[serde(default)]
+    pub tween: Option<ParameterTween>,
+    pub perches: Vec<PerchSite>,
+    #[serde(default)]
+    pub goal_points: Vec<GoalPoint>,
+    /// Moves on to the next waypoint by itself, so a change is recorded at every waypoint reached
+    #[serde(default)]
+    pub waypoint_path: WaypointPath,
+    pub current_zones: Vec<CurrentZone>,
+    pub thermals: Vec<Thermal>,
+    pub annotations: Vec<Annotation>,
+    #[serde(default)]
+    pub gates: Vec<Gate>,
+    /// Pushes for the coming tick only
+    #[serde(default)]
+    pub impulses: Vec<Impulse>,
+    #[serde(default)]
+    pub obstacles: Vec<Obstacle>,
+    /// Worn down by the simulation itself, so a change is recorded every time a boid hits one
+    #[serde(default)]
+    pub temporary_obstacles: Vec<TemporaryObstacle>,
+    #[serde(default)]
+    pub moving_obstacles: Vec<MovingObstacle>,
+    /// The split and merge tools
+    #[serde(default)]
+    pub experiment: ExperimentForces,
+}
+
+impl SimulationInputs {
+    /// Rough size of what the inputs keep on the heap.
+    fn heap_bytes(&self) -> usize {
+        self.perches.len() * size_of::<PerchSite>()
+            + self.goal_points.len() * size_of::<GoalPoint>()
+            + self.waypoint_path.waypoints.len() * size_of::<Pos2>()
+            + self.current_zones.len() * size_of::<CurrentZone>()
+            + self.thermals.len() * size_of::<Thermal>()
+            + self.gates.len() * size_of::<Gate>()
+            + self.impulses.len() * size_of::<Impulse>()
+            + self.obstacles.len() * size_of::<Obstacle>()
+            + self.temporary_obstacles.len() * size_of::<TemporaryObstacle>()
+            + moving_obstacles_bytes(&self.moving_obstacles)
+            + self.params.groups.groups.len() * size_of::<BoidGroup>()
+            + lfos_bytes(&self.lfos)
+            + self.tween.as_ref().map_or(0, tween_bytes)
+            + annotations_bytes(&self.annotations)
+    }
+}
+
+fn moving_obstacles_bytes(obstacles: &[MovingObstacle]) -> usize {
+    obstacles
+        .iter()
+        .map(|obstacle| {
+            size_of::<MovingObstacle>()
+                + match &obstacle.path {
+                    ObstaclePath::Patrol { waypoints, .. } => waypoints.len() * size_of::<Pos2>(),
+                    ObstaclePath::Orbit { .. } => 0,
+                }
+        })
+        .sum()
+}
+
+fn lfos_bytes(lfos: &[Lfo]) -> usize {
+    lfos.iter()
+        .map(|lfo| size_of::<Lfo>() + lfo.parameter.len())
+        .sum()
+}
+
+fn tween_bytes(tween: &ParameterTween) -> usize {
+    // Where it blends from usually has as many groups as where it's going
+    size_of::<ParameterTween>() + 2 * tween.target().groups.groups.len() * size_of::<BoidGroup>()
+}
+
+fn annotations_bytes(annotations: &[Annotation]) -> usize {
+    annotations
+        .iter()
+        .map(|annotation| size_of::<Annotation>() + annotation.text.len())
+        .sum()
+}
+
+/// A single change to the inputs, e.g. the predator moving or a thermal being placed.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub enum InputEvent {
+    Predator(Option<Pos2>),
+    PredatorVelocity(Vec2),
+    SpawnCenter(Pos2),
+    FlockSize(usize),
+    /// Boxed since the parameters dwarf every other event
+    Params(Box<BoidsSimulationParameters>),
+    Lfos(Vec<Lfo>),
+    /// Boxed for the same reason as [`Self::Params`]
+    Tween(Option<Box<ParameterTween>>),
+    Perches(Vec<PerchSite>),
+    GoalPoints(Vec<GoalPoint>),
+    WaypointPath(WaypointPath),
+    CurrentZones(Vec<CurrentZone>),
+    Thermals(Vec<Thermal>),
+    Annotations(Vec<Annotation>),
+    Gates(Vec<Gate>),
+    Impulses(Vec<Impulse>),
+    Obstacles(Vec<Obstacle>),
+    TemporaryObstacles(Vec<TemporaryObstacle>),
+    MovingObstacles(Vec<MovingObstacle>),
+    Experiment(ExperimentForces),
+}
+
+impl InputEvent {
+    /// Rough size of what the event keeps on the heap.
+    fn heap_bytes(&self) -> usize {
+        match self {
+            InputEvent::Params(params) => params.groups.groups.len() * size_of::<BoidGroup>(),
+            InputEvent::Lfos(lfos) => lfos_bytes(lfos),
+            InputEvent::Tween(tween) => tween.as_deref().map_or(0, tween_bytes),
+            InputEvent::Perches(perches) => perches.len() * size_of::<PerchSite>(),
+            InputEvent::GoalPoints(goals) => goals.len() * size_of::<GoalPoint>(),
+            InputEvent::WaypointPath(path) => path.waypoints.len() * size_of::<Pos2>(),
+            InputEvent::CurrentZones(zones) => zones.len() * size_of::<CurrentZone>(),
+            InputEvent::Thermals(thermals) => thermals.len() * size_of::<Thermal>(),
+            InputEvent::Annotations(annotations) => annotations_bytes(annotations),
+            InputEvent::Gates(gates) => gates.len() * size_of::<Gate>(),
+            InputEvent::Impulses(impulses) => impulses.len() * size_of::<Impulse>(),
+            InputEvent::Obstacles(obstacles) => obstacles.len() * size_of::<Obstacle>(),
+            InputEvent::TemporaryObstacles(obstacles) => {
+                obstacles.len() * size_of::<TemporaryObstacle>()
+            }
+            InputEvent::MovingObstacles(obstacles) => moving_obstacles_bytes(obstacles),
+            InputEvent::Predator(_)
+            | InputEvent::PredatorVelocity(_)
+            | InputEvent::SpawnCenter(_)
+            | InputEvent::FlockSize(_)
+            | InputEvent::Experiment(_) => 0,
+        }
+    }
+
+    pub fn apply(self, inputs: &mut SimulationInputs) {
+        match self {
+            InputEvent::Predator(predator) => inputs.predator = predator,
+            InputEvent::PredatorVelocity(velocity) => inputs.predator_velocity = velocity,
+            InputEvent::SpawnCenter(center) => inputs.spawn_center = center,
+            InputEvent::FlockSize(size) => inputs.flock_size = size,
+            InputEvent::Params(params) => inputs.params = *params,
+            InputEvent::Lfos(lfos) => inputs.lfos = lfos,
+            InputEvent::Tween(tween) => inputs.tween = tween.map(|tween| *tween),
+            InputEvent::Perches(perches) => inputs.perches = perches,
+            InputEvent::GoalPoints(goals) => inputs.goal_points = goals,
+            InputEvent::WaypointPath(path) => inputs.waypoint_path = path,
+            InputEvent::CurrentZones(zones) => inputs.current_zones = zones,
+            InputEvent::Thermals(thermals) => inputs.thermals = thermals,
+            InputEvent::Annotations(annotations) => inputs.annotations = annotations,
+            InputEvent::Gates(gates) => inputs.gates = gates,
+            InputEvent::Impulses(impulses) => inputs.impulses = impulses,
+            InputEvent::Obstacles(obstacles) => inputs.obstacles = obstacles,
+            InputEvent::TemporaryObstacles(obstacles) => inputs.temporary_obstacles = obstacles,
+            InputEvent::MovingObstacles(obstacles) => inputs.moving_obstacles = obstacles,
+            InputEvent::Experiment(experiment) => inputs.experiment = experiment,
+        }
+    }
+}
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct TimedInputEvent {
+    /// Ticks since the recording started
+    pub tick: u64,
+    pub event: InputEvent,
+}
+
+/// A full snapshot is kept this often (in ticks), old data is evicted a keyframe at a time.
+const KEYFRAME_INTERVAL: u64 = 600;
+const BYTES_PER_MB: f32 = 1024.0 * 1024.0;
+
+/// Limits on how much the replay system may keep around.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ReplaySettings {
+    /// Once a recording grows past this the oldest part is dropped
+    pub max_buffer_mb: f32,
+    /// Record a wrapping flock contained instead, so replays don't show boids jumping across edges
+    pub contain_while_recording: bool,
+}
+
+impl Default for ReplaySettings {
+    fn default() -> Self {
+        Self {
+            max_buffer_mb: 64.0,
+            contain_while_recording: true,
+        }
+    }
+}
+
+impl ReplaySettings {
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.label("Buffer Limit (MB)");
+        ui.add(egui::DragValue::new(&mut self.max_buffer_mb).range(1.0..=4096.0))
+            .on_hover_text("Long recordings drop their oldest part to stay under this");
+        ui.checkbox(&mut self.contain_while_recording, "Contain While Recording")
+            .on_hover_text(
+                "Switch a wrapping world to containment while recording, and back afterwards",
+            );
+    }
+
+    fn max_buffer_bytes(&self) -> usize {
+        (self.max_buffer_mb * BYTES_PER_MB) as usize
+    }
+}
+
+/// Everything needed to start replaying from a given tick.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct ReplayKeyframe {
+    /// Ticks since the recording started
+    pub tick: u64,
+    /// The RNG is reseeded with this at every keyframe, so playback can start at any of them
+    pub seed: u64,
+    pub boids: BoidStore,
+    /// Id of the next boid to hatch, so boids respawned during playback get the same ids
+    #[serde(default)]
+    pub next_boid_id: u64,
+    pub migration_target: MigrationTarget,
+    #[serde(default)]
+    pub predators: Vec<Predator>,
+    /// Ticks left until each caught boid respawns
+    #[serde(default)]
+    pub respawns: Vec<u32>,
+    /// See [`crate::moving_obstacle::MovingObstacle::at`]
+    #[serde(default)]
+    pub obstacle_clock: u64,
+    #[serde(default)]
+    pub danger_map: DangerMap,
+    pub inputs: SimulationInputs,
+}
+
+impl ReplayKeyframe {
+    fn approximate_bytes(&self) -> usize {
+        size_of::<Self>()
+            + self.boids.len() * size_of::<Boid>()
+            + self.predators.len() * size_of::<Predator>()
+            + self.respawns.len() * size_of::<u32>()
+            + self.inputs.heap_bytes()
+    }
+}
+
+/// An interactive session: keyframes of the state and every input change between them.
+///
+/// Replaying the events from the first keyframe with the same seeds reproduces the session exactly.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct InputRecording {
+    /// Never empty, the first one is where playback starts
+    pub keyframes: Vec<ReplayKeyframe>,
+    pub events: Vec<TimedInputEvent>,
+    pub length_ticks: u64,
+    /// State checksum after every recorded tick, see [`state_checksum`]
+    #[serde(default)]
+    pub checksums: Vec<u64>,
+}
+
+impl InputRecording {
+    pub fn start(&self) -> &ReplayKeyframe {
+        &self.keyframes[0]
+    }
+
+    pub fn length_seconds(&self) -> f32 {
+        self.length_ticks as f32 / TICKS_PER_SECOND
+    }
+
+    /// Roughly how much memory the recording takes up.
+    pub fn approximate_bytes(&self) -> usize {
+        size_of::<Self>()
+            + self
+                .keyframes
+                .iter()
+                .map(ReplayKeyframe::approximate_bytes)
+                .sum::<usize>()
+            + self
+                .events
+                .iter()
+                .map(|event| size_of::<TimedInputEvent>() + event.event.heap_bytes())
+                .sum::<usize>()
+            + self.checksums.len() * size_of::<u64>()
+    }
+
+    /// The part of the recording from the last keyframe at or before `tick`, which becomes tick 0.
+    pub fn starting_at(&self, tick: u64) -> InputRecording {
+        let mut recording = self.clone();
+        let mut remaining = tick;
+        while let Some(next) = recording
+            .keyframes
+            .get(1)
+            .map(|keyframe| keyframe.tick)
+            .filter(|next| *next <= remaining)
+        {
+            recording.evict_oldest();
+            remaining -= next;
+        }
+        recording
+    }
+
+    /// Drop everything before the second keyframe, which becomes the new start.
+    ///
+    /// Returns false if there's only one keyframe left, since a recording can't start without one.
+    fn evict_oldest(&mut self) -> bool {
+        if self.keyframes.len() < 2 {
+            return false;
+        }
+
+        self.keyframes.remove(0);
+        let cut = self.keyframes[0].tick;
+        let evicted = self.events.partition_point(|event| event.tick < cut);
+        self.events.drain(..evicted);
+        self.checksums
+            .drain(..(cut as usize).min(self.checksums.len()));
+        self.length_ticks -= cut;
+
+        for keyframe in &mut self.keyframes {
+            keyframe.tick -= cut;
+        }
+        for event in &mut self.events {
+            event.tick -= cut;
+        }
+        true
+    }
+}
+
+/// Builds a recording by comparing the inputs every tick with the ones seen before.
+pub struct InputRecorder {
+    recording: InputRecording,
+    last_inputs: SimulationInputs,
+    /// Running total of [`InputRecording::approximate_bytes`], so the budget is cheap to check
+    bytes: usize,
+}
+
+impl InputRecorder {
+    /// Start recording from `start`, whose tick is ignored.
+    pub fn new(mut start: ReplayKeyframe) -> Self {
+        start.tick = 0;
+        let last_inputs = start.inputs.clone();
+        let recording = InputRecording {
+            keyframes: vec![start],
+            events: Vec::new(),
+            length_ticks: 0,
+            checksums: Vec::new(),
+        };
+        Self {
+            bytes: recording.approximate_bytes(),
+            recording,
+            last_inputs,
+        }
+    }
+
+    /// Whether the next tick should start with a keyframe.
+    pub fn keyframe_due(&self) -> bool {
+        let tick = self.recording.length_ticks;
+        tick > 0 && tick % KEYFRAME_INTERVAL == 0
+    }
+
+    /// Snapshot the state before the next tick, the caller reseeds its RNG with the keyframe's
+    /// seed. The keyframe's tick is filled in here.
+    pub fn add_keyframe(&mut self, mut keyframe: ReplayKeyframe) {
+        keyframe.tick = self.recording.length_ticks;
+        self.bytes += keyframe.approximate_bytes();
+        self.recording.keyframes.push(keyframe);
+    }
+
+    /// Record whatever changed since the last tick.
+    pub fn record(&mut self, inputs: &SimulationInputs) {
+        let tick = self.recording.length_ticks;
+        let last = &mut self.last_inputs;
+        let mut changes = Vec::new();
+
+        if inputs.predator != last.predator {
+            changes.push(InputEvent::Predator(inputs.predator));
+        }
+        if inputs.predator_velocity != last.predator_velocity {
+            changes.push(InputEvent::PredatorVelocity(inputs.predator_velocity));
+        }
+        if inputs.spawn_center != last.spawn_center {
+            changes.push(InputEvent::SpawnCenter(inputs.spawn_center));
+        }
+        if inputs.flock_size != last.flock_size {
+            changes.push(InputEvent::FlockSize(inputs.flock_size));
+        }
+        if inputs.params != last.params {
+            changes.push(InputEvent::Params(Box::new(inputs.params.clone())));
+        }
+        if inputs.lfos != last.lfos {
+            changes.push(InputEvent::Lfos(inputs.lfos.clone()));
+        }
+        if inputs.tween != last.tween {
+            changes.push(InputEvent::Tween(inputs.tween.clone().map(Box::new)));
+        }
+        if inputs.perches != last.perches {
+            changes.push(InputEvent::Perches(inputs.perches.clone()));
+        }
+        if inputs.goal_points != last.goal_points {
+            changes.push(InputEvent::GoalPoints(inputs.goal_points.clone()));
+        }
+        if inputs.waypoint_path != last.waypoint_path {
+            changes.push(InputEvent::WaypointPath(inputs.waypoint_path.clone()));
+        }
+        if inputs.current_zones != last.current_zones {
+            changes.push(InputEvent::CurrentZones(inputs.current_zones.clone()));
+        }
+        if inputs.thermals != last.thermals {
+            changes.push(InputEvent::Thermals(inputs.thermals.clone()));
+        }
+        if inputs.annotations != last.annotations {
+            changes.push(InputEvent::Annotations(inputs.annotations.clone()));
+        }
+        if inputs.gates != last.gates {
+            changes.push(InputEvent::Gates(inputs.gates.clone()));
+        }
+        if inputs.impulses != last.impulses {
+            changes.push(InputEvent::Impulses(inputs.impulses.clone()));
+        }
+        if inputs.obstacles != last.obstacles {
+            changes.push(InputEvent::Obstacles(inputs.obstacles.clone()));
+        }
+        if inputs.temporary_obstacles != last.temporary_obstacles {
+            changes.push(InputEvent::TemporaryObstacles(
+                inputs.temporary_obstacles.clone(),
+            ));
+        }
+        if inputs.moving_obstacles != last.moving_obstacles {
+            changes.push(InputEvent::MovingObstacles(inputs.moving_obstacles.clone()));
+        }
+        if inputs.experiment != last.experiment {
+            changes.push(InputEvent::Experiment(inputs.experiment));
+        }
+
+        *last = inputs.clone();
+        self.bytes += changes
+            .iter()
+            .map(|event| size_of::<TimedInputEvent>() + event.heap_bytes())
+            .sum::<usize>();
+        self.recording.events.extend(
+            changes
+                .into_iter()
+                .map(|event| TimedInputEvent { tick, event }),
+        );
+        self.recording.length_ticks += 1;
+    }
+
+    /// Store the checksum of the state the last recorded tick produced.
+    pub fn record_checksum(&mut self, checksum: u64) {
+        self.recording.checksums.push(checksum);
+        self.bytes += size_of::<u64>();
+    }
+
+    /// Evict the oldest keyframes until the recording fits in the buffer limit.
+    pub fn enforce_budget(&mut self, settings: &ReplaySettings) {
+        let max_bytes = settings.max_buffer_bytes();
+        if self.bytes <= max_bytes {
+            return;
+        }
+
+        let before = self.recording.length_ticks;
+        while self.bytes > max_bytes && self.recording.evict_oldest() {
+            self.bytes = self.recording.approximate_bytes();
+        }
+        debug!(
+            evicted_ticks = before - self.recording.length_ticks,
+            bytes = self.bytes,
+            "replay buffer over budget"
+        );
+    }
+
+    pub fn approximate_bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn length_seconds(&self) -> f32 {
+        self.recording.length_seconds()
+    }
+
+    pub fn length_ticks(&self) -> u64 {
+        self.recording.length_ticks
+    }
+
+    pub fn finish(self) -> InputRecording {
+        self.recording
+    }
+}
+
+/// What the simulation needs from a recording for one tick.
+pub struct ReplayStep<'a> {
+    pub inputs: &'a SimulationInputs,
+    /// Set on keyframes, where the recorder reseeded the RNG
+    pub reseed: Option<u64>,
+}
+
+/// Feeds a recording back into the simulation one tick at a time.
+pub struct InputPlayer {
+    recording: InputRecording,
+    inputs: SimulationInputs,
+    tick: u64,
+    next_event: usize,
+    /// The first keyframe is where playback starts, so this starts at the second
+    next_keyframe: usize,
+    /// First tick whose state didn't match the recording
+    pub diverged_at: Option<u64>,
+}
+
+impl InputPlayer {
+    pub fn new(recording: InputRecording) -> Self {
+        Self {
+            inputs: recording.start().inputs.clone(),
+            recording,
+            tick: 0,
+            next_event: 0,
+            next_keyframe: 1,
+            diverged_at: None,
+        }
+    }
+
+    pub fn recording(&self) -> &InputRecording {
+        &self.recording
+    }
+
+    /// The inputs for the next tick, or `None` once the recording is over.
+    pub fn next_step(&mut self) -> Option<ReplayStep<'_>> {
+        if self.tick >= self.recording.length_ticks {
+            return None;
+        }
+
+        let reseed = self
+            .recording
+            .keyframes
+            .get(self.next_keyframe)
+            .filter(|keyframe| keyframe.tick == self.tick)
+            .map(|keyframe| keyframe.seed);
+        if reseed.is_some() {
+            self.next_keyframe += 1;
+        }
+
+        let events = &self.recording.events[self.next_event..];
+        let due = events
+            .iter()
+            .take_while(|event| event.tick <= self.tick)
+            .count();
+        for event in &events[..due] {
+            event.event.clone().apply(&mut self.inputs);
+        }
+        self.next_event += due;
+        self.tick += 1;
+
+        Some(ReplayStep {
+            inputs: &self.inputs,
+            reseed,
+        })
+    }
+
+    /// Compare the state the last tick produced with the recording, returning true on the first mismatch.
+    ///
+    /// Recordings made without checksums can't be checked and never diverge.
+    pub fn check(&mut self, checksum: u64) -> bool {
+        if self.diverged_at.is_some() {
+            return false;
+        }
+
+        let tick = self.tick.saturating_sub(1);
+        let expected = self.recording.checksums.get(tick as usize);
+        if expected.is_some_and(|expected| *expected != checksum) {
+            self.diverged_at = Some(tick);
+            return true;
+        }
+        false
+    }
+
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.tick as f32 / TICKS_PER_SECOND
+    }
+}
+
+/// Hash of the simulation state, used to find the exact tick where a replay diverges.
+///
+/// Hashes the bit patterns of every float so even the smallest difference between platforms or builds shows up.
+pub fn state_checksum(
+    boids: &BoidStore,
+    migration_target: &MigrationTarget,
+    predators: &[Predator],
+) -> u64 {
+    // 64 bit FNV-1a, which is simple and stable across platforms unlike the std hasher
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut hash = OFFSET_BASIS;
+    let mut write = |value: u32| {
+        for byte in value.to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(PRIME);
+        }
+    };
+
+    write(boids.len() as u32);
+    for boid in boids {
+        write(boid.position.x.to_bits());
+        write(boid.position.y.to_bits());
+        write(boid.velocity.x.to_bits());
+        write(boid.velocity.y.to_bits());
+        write(boid.altitude.to_bits());
+        write(boid.energy.to_bits());
+        write(boid.resting.map_or(u32::MAX, |ticks| ticks));
+        write(boid.rank.to_bits());
+        let genome = &boid.genome;
+        for gene in [
+            genome.separation,
+            genome.alignment,
+            genome.cohesion,
+            genome.avoidance,
+            genome.speed,
+        ] {
+            write(gene.to_bits());
+        }
+    }
+    write(migration_target.position.x.to_bits());
+    write(migration_target.position.y.to_bits());
+    write(predators.len() as u32);
+    for predator in predators {
+        write(predator.position.x.to_bits());
+        write(predator.position.y.to_bits());
+        write(predator.stamina.to_bits());
+    }
+
+    hash
+}
+
+/// Whether we are recording the user's inputs, replaying a recording or neither.
+#[derive(Default)]
+pub enum ReplayState {
+    #[default]
+    Idle,
+    Recording(InputRecorder),
+    Playing(InputPlayer),
+}
+
+impl ReplayState {
+    pub fn is_playing(&self) -> bool {
+        matches!(self, ReplayState::Playing(_))
+    }
+}
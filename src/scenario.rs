@@ -0,0 +1,164 @@
+use egui::{DroppedFile, Pos2, Vec2};
+
+use crate::{
+    annotation::Annotation,
+    boid::Boid,
+    boids_simulation::BoidsSimulationParameters,
+    error::{BoidsError, Result},
+};
+
+/// Version of the scenario format this build writes.
+///
+/// Fields added with a default load from older files on their own. Bump this (and add a migration)
+/// when a change would make older files load wrong, like a field being renamed or its meaning
+/// changing.
+pub const SCENARIO_VERSION: u32 = 1;
+
+/// `MIGRATIONS[n]` brings a version `n` scenario up to version `n + 1`.
+const MIGRATIONS: [fn(&mut Scenario); SCENARIO_VERSION as usize] = [
+    // Files from before scenarios had a version only lack the version itself
+    |_| {},
+];
+
+/// A saved simulation setup.
+///
+/// A scenario always carries the parameters and optionally the boids themselves.
+/// A scenario without boids is what we call a preset: loading it only changes the parameters.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Scenario {
+    /// Files without a version predate versioning, which makes them version 0
+    #[serde(default)]
+    pub version: u32,
+    pub params: BoidsSimulationParameters,
+    pub boids: Vec<BoidState>,
+    /// Boid annotations refer to boids by their index in `boids`
+    pub annotations: Vec<Annotation>,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self {
+            version: SCENARIO_VERSION,
+            params: BoidsSimulationParameters::default(),
+            boids: Vec::new(),
+            annotations: Vec::new(),
+        }
+    }
+}
+
+/// Just the version of a scenario, read before the rest so files from newer builds get a clear
+/// error rather than whatever their new fields fail to parse as.
+#[derive(serde::Deserialize)]
+#[serde(rename = "Scenario")]
+struct ScenarioVersion {
+    #[serde(default)]
+    version: u32,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct BoidState {
+    pub position: Pos2,
+    pub velocity: Vec2,
+}
+
+impl BoidState {
+    pub fn to_boid(&self, id: u64) -> Boid {
+        Boid::with_id(id, self.position, self.velocity)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ScenarioFormat {
+    Ron,
+    Json,
+}
+
+impl ScenarioFormat {
+    fn from_file_name(name: &str) -> Result<Self> {
+        let extension = name
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("ron") => Ok(ScenarioFormat::Ron),
+            Some("json") => Ok(ScenarioFormat::Json),
+            _ => Err(BoidsError::Invalid(format!(
+                "{name} is not a .ron or .json scenario file"
+            ))),
+        }
+    }
+}
+
+impl Scenario {
+    /// Decode a scenario, using the file name's extension to pick the format.
+    pub fn from_bytes(name: &str, bytes: &[u8]) -> Result<Self> {
+        let text = std::str::from_utf8(bytes)
+            .map_err(|err| BoidsError::Invalid(format!("{name} is not valid UTF-8: {err}")))?;
+
+        let format = ScenarioFormat::from_file_name(name)?;
+        let ScenarioVersion { version } = match format {
+            ScenarioFormat::Ron => ron::from_str(text)?,
+            ScenarioFormat::Json => serde_json::from_str(text)?,
+        };
+        if version > SCENARIO_VERSION {
+            return Err(BoidsError::Invalid(format!(
+                "{name} is scenario version {version}, this build only reads up to version {SCENARIO_VERSION}"
+            )));
+        }
+
+        let mut scenario: Scenario = match format {
+            ScenarioFormat::Ron => ron::from_str(text)?,
+            ScenarioFormat::Json => serde_json::from_str(text)?,
+        };
+        scenario.migrate();
+        scenario.validate()?;
+
+        Ok(scenario)
+    }
+
+    /// Bring a scenario saved by an older build up to the current version.
+    fn migrate(&mut self) {
+        for migration in &MIGRATIONS[self.version as usize..] {
+            migration(self);
+            self.version += 1;
+        }
+    }
+
+    /// Load a file dropped onto the window.
+    ///
+    /// Natively we get a path to read, on the web the browser hands us the bytes directly.
+    pub fn from_dropped_file(file: &DroppedFile) -> Result<Self> {
+        if let Some(bytes) = &file.bytes {
+            return Self::from_bytes(&file.name, bytes);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = &file.path {
+            let bytes = std::fs::read(path)?;
+            return Self::from_bytes(&path.to_string_lossy(), &bytes);
+        }
+
+        Err(BoidsError::Invalid(format!(
+            "could not read dropped file {}",
+            file.name
+        )))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.params.validate()?;
+
+        let all_finite = self.boids.iter().all(|boid| {
+            boid.position.x.is_finite()
+                && boid.position.y.is_finite()
+                && boid.velocity.x.is_finite()
+                && boid.velocity.y.is_finite()
+        });
+        if !all_finite {
+            return Err(BoidsError::Invalid(
+                "boid positions and velocities must be numbers".to_owned(),
+            ));
+        }
+
+        Ok(())
+    }
+}
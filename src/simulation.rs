@@ -0,0 +1,695 @@
+use std::{
+    borrow::Cow,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    boid_store::{BoidRef, BoidStore},
+    boids_simulation::BoidsSimulationParameters,
+    chunk::ActiveChunks,
+    current_zone::{current_force, CurrentZone},
+    danger::DangerMap,
+    energy::{nearest_thermal, thermal_containing, Thermal},
+    game::{GameSettings, Shepherd},
+    goal_point::{GoalPoint, WaypointPath},
+    group::group_params,
+    impulse::Impulse,
+    integrator::Integrator,
+    interaction::Reaction,
+    leader::Leader,
+    math::{Pos2, Rect, Vec2},
+    moving_obstacle::{obstacles_at, MovingObstacle},
+    obstacle::Obstacle,
+    perch::{nearest_perch_point, PerchSite},
+    spatial_grid::NeighborIndex,
+    split_merge::ExperimentForces,
+    steering::{dominant_color, dominant_rule, SteeringContext, SteeringRule},
+    temporary_obstacle::TemporaryObstacle,
+    threat::{Threat, ThreatIndex},
+};
+
+pub const SIMULATION_AREA_WIDTH: f32 = 1700.0;
+pub const SIMULATION_AREA_HEIGHT: f32 = 950.0;
+/// The simulation area in world coordinates, which the arena is fitted into
+pub const WORLD_BOUNDS: Rect = Rect::from_min_max(
+    Pos2::new(-SIMULATION_AREA_WIDTH / 2.0, -SIMULATION_AREA_HEIGHT / 2.0),
+    Pos2::new(SIMULATION_AREA_WIDTH / 2.0, SIMULATION_AREA_HEIGHT / 2.0),
+);
+
+/// Everything a tick of a flock depends on besides the flock itself.
+///
+/// The app, its ghost and integrator comparison and [`crate::HeadlessSimulation`] all step their
+/// flocks through here, so they move exactly alike.
+pub struct World<'a> {
+    pub params: &'a BoidsSimulationParameters,
+    pub rules: &'a [Box<dyn SteeringRule>],
+    /// Every predator, mouse driven or not
+    pub threats: Vec<Threat>,
+    pub migration_target: Pos2,
+    pub goal_points: &'a [GoalPoint],
+    pub waypoint_path: &'a WaypointPath,
+    pub danger_map: &'a DangerMap,
+    pub perches: &'a [PerchSite],
+    pub thermals: &'a [Thermal],
+    /// The shepherd when a game is running
+    pub shepherd: Option<&'a Shepherd>,
+    pub game_settings: &'a GameSettings,
+    pub obstacles: &'a [Obstacle],
+    pub temporary_obstacles: &'a [TemporaryObstacle],
+    pub moving_obstacles: &'a [MovingObstacle],
+    /// Ticks the moving obstacles and the wind have run for
+    pub obstacle_clock: u64,
+    pub current_zones: &'a [CurrentZone],
+    /// Pushes that only last this tick
+    pub impulses: &'a [Impulse],
+    /// The dividing wall and merge attractor, when either is on
+    pub experiment: &'a ExperimentForces,
+    /// `None` updates every boid every tick
+    pub active_chunks: Option<ActiveChunks>,
+    pub tick: u64,
+    pub bounds: Rect,
+    /// Work the forces out on every core, see [`ForceInputs::compute`]
+    pub parallel: bool,
+}
+
+/// What a step ran into and how long it took, for the app to show.
+pub struct StepOutcome {
+    #[cfg(feature = "gui")]
+    /// Where boids bounced off a wall, with their velocity after
+    pub bounces: Vec<(Pos2, Vec2)>,
+    #[cfg(feature = "gui")]
+    /// The index of the temporary obstacle each boid that hit one flew into
+    pub hits: Vec<usize>,
+    #[cfg(feature = "gui")]
+    /// Time working out the forces took, not counting RK2's second go at them
+    pub force_time: Duration,
+    #[cfg(feature = "gui")]
+    /// Time visual crowding took, `None` while it's off
+    pub crowding_time: Option<Duration>,
+}
+
+impl World<'_> {
+    /// Step `boids` by one tick: work out every boid's forces and move it with them.
+    #[cfg_attr(not(feature = "gui"), allow(unused_variables))]
+    pub fn step(&self, boids: &mut BoidStore) -> StepOutcome {
+        let (force_time, crowding_time) = self.apply_forces(boids);
+        let midpoints = self.midpoint_accelerations(boids);
+        let (bounces, hits) = self.integrate(boids, &midpoints);
+        StepOutcome {
+            #[cfg(feature = "gui")]
+            bounces,
+            #[cfg(feature = "gui")]
+            hits,
+            #[cfg(feature = "gui")]
+            force_time,
+            #[cfg(feature = "gui")]
+            crowding_time,
+        }
+    }
+
+    /// The total force on each of `probes` from `flock` and the world, without the flock feeling
+    /// the probes.
+    #[cfg(feature = "gui")]
+    pub fn probe_forces(&self, flock: &BoidStore, probes: &BoidStore) -> Vec<Vec2> {
+        let forces = self.with_inputs(flock, |inputs, scratch| {
+            inputs.compute(probes, scratch, false)
+        });
+        probes
+            .iter()
+            .zip(&forces)
+            .map(|(probe, forces)| {
+                forces.total() + self.environment_force(probe.position, probe.velocity)
+            })
+            .collect()
+    }
+
+    /// Every obstacle where boids steer around it, which for moving ones is where they're about
+    /// to be.
+    pub fn obstacles_ahead(&self) -> Cow<'_, [Obstacle]> {
+        obstacles_at(
+            self.obstacles,
+            self.temporary_obstacles,
+            self.moving_obstacles,
+            self.obstacle_clock as f32 + self.params.obstacle_avoidance.prediction,
+        )
+    }
+
+    /// The push of the currents and the wind on a boid at `position` flying with `velocity`.
+    #[cfg(feature = "gui")]
+    fn environment_force(&self, position: Pos2, velocity: Vec2) -> Vec2 {
+        current_force(self.current_zones, position, velocity)
+            + self.params.wind.force_at(position, self.obstacle_clock)
+    }
+
+    /// Hand `f` what the forces on `flock` are worked out from.
+    fn with_inputs<R>(
+        &self,
+        flock: &BoidStore,
+        f: impl FnOnce(&ForceInputs<'_>, ForceScratch<'_>) -> R,
+    ) -> R {
+        let threat_index = ThreatIndex::new(&self.threats, self.params.max_avoidance_radius());
+        let resolved_groups = self.params.resolve_groups();
+        // Boids across an edge the flock wraps over are neighbors too, by way of copies of them
+        let mut wrapped = self.params.arena.wrapped_copies(
+            flock,
+            &self.bounds,
+            self.params.boundary_mode,
+            self.params.max_neighbor_radius(),
+        );
+        let with_copies: Cow<'_, BoidStore> = if wrapped.is_empty() {
+            Cow::Borrowed(flock)
+        } else {
+            let mut with_copies = flock.clone();
+            with_copies.append(&mut wrapped);
+            Cow::Owned(with_copies)
+        };
+        let neighbor_index = NeighborIndex::new(
+            &with_copies,
+            self.params.max_neighbor_radius(),
+            self.params.neighbor_search,
+        );
+        let leaders = self.params.leaders.leaders(flock);
+        let obstacles = self.obstacles_ahead();
+        let inputs = ForceInputs {
+            params: self.params,
+            resolved_groups: &resolved_groups,
+            rules: self.rules,
+            silenced: &self.params.mixer.silenced(self.rules),
+            migration_target: self.migration_target,
+            goal_points: self.goal_points,
+            waypoint_path: self.waypoint_path,
+            leaders: &leaders,
+            danger_map: self.danger_map,
+            perches: self.perches,
+            thermals: self.thermals,
+            shepherd: self.shepherd,
+            game_settings: self.game_settings,
+            obstacles: &obstacles,
+            experiment: self.experiment,
+            active_chunks: self.active_chunks.as_ref(),
+            tick: self.tick,
+            bounds: self.bounds,
+        };
+        f(&inputs, ForceScratch::new(neighbor_index, threat_index))
+    }
+
+    /// Add every boid's forces to its acceleration, returning the time the forces and visual
+    /// crowding took.
+    fn apply_forces(&self, boids: &mut BoidStore) -> (Duration, Option<Duration>) {
+        if self.params.adaptive_separation.enabled {
+            self.params
+                .adaptive_separation
+                .update(boids, self.params.separation_radius());
+        }
+
+        // Work out every boid's forces from the flock as it was, then apply them all
+        let (forces, force_time) = self.with_inputs(boids, |inputs, scratch| {
+            let started = Instant::now();
+            let forces = inputs.compute(boids, scratch, self.parallel);
+            (forces, started.elapsed())
+        });
+        let crowding_time = self
+            .params
+            .crowding
+            .enabled
+            .then(|| forces.iter().map(|forces| forces.crowding_time).sum());
+
+        for (mut boid, forces) in boids.iter_mut().zip(&forces) {
+            if let Some(count) = forces.neighbor_count {
+                boid.neighbor_count = count;
+            }
+            if let Some(centroid) = forces.centroid {
+                boid.perceived_centroid = centroid;
+            }
+            // Perched boids sit still until they take off again
+            if boid.resting.is_some() {
+                boid.dominant_force = None;
+                continue;
+            }
+
+            boid.dominant_force = dominant_rule(self.rules, &forces.rules);
+            self.accelerate(boid.acceleration, *boid.position, *boid.velocity, forces);
+
+            if let Some(color) = dominant_color(self.rules, &forces.rules) {
+                *boid.color = color;
+            }
+        }
+        (force_time, crowding_time)
+    }
+
+    /// Add `forces` on a boid at `position` flying with `velocity` to `acceleration`, along with
+    /// the currents and the wind.
+    fn accelerate(
+        &self,
+        acceleration: &mut Vec2,
+        position: Pos2,
+        velocity: Vec2,
+        forces: &BoidForces,
+    ) {
+        for force in &forces.rules {
+            *acceleration += *force;
+        }
+        *acceleration += forces.migration;
+        *acceleration += forces.goal_point;
+        *acceleration += forces.waypoint;
+        *acceleration += forces.leader;
+        *acceleration += forces.danger;
+        *acceleration += forces.perch;
+        *acceleration += forces.thermal;
+        *acceleration += forces.shepherd;
+        *acceleration += forces.goal;
+        *acceleration += forces.experiment;
+        *acceleration += current_force(self.current_zones, position, velocity);
+        *acceleration += self.params.wind.force_at(position, self.obstacle_clock);
+    }
+
+    /// The acceleration of every boid halfway through this tick's step, empty unless RK2 needs it.
+    ///
+    /// Works the forces out again with every boid moved half a step along, then puts them back.
+    /// Unlike [`Self::apply_forces`] this leaves everything else about the boids alone, so RK2
+    /// adapts separation and smooths centroids once a tick like the other integrators.
+    fn midpoint_accelerations(&self, boids: &mut BoidStore) -> Vec<Vec2> {
+        if self.params.integration.integrator != Integrator::Rk2 {
+            return Vec::new();
+        }
+        let half_step = self.params.integration.time_step / 2.0;
+        let start: Vec<(Pos2, Vec2)> = boids
+            .iter()
+            .map(|boid| (boid.position, boid.velocity))
+            .collect();
+        for boid in &mut *boids {
+            if boid.resting.is_none() {
+                *boid.position += *boid.velocity * half_step;
+                *boid.velocity += *boid.acceleration * half_step;
+            }
+        }
+        let forces = self.with_inputs(boids, |inputs, scratch| {
+            inputs.compute(boids, scratch, self.parallel)
+        });
+        boids
+            .iter_mut()
+            .zip(start)
+            .zip(&forces)
+            .map(|((boid, (position, velocity)), forces)| {
+                let mut midpoint = Vec2::ZERO;
+                // Perched boids sit still until they take off again
+                if boid.resting.is_none() {
+                    self.accelerate(&mut midpoint, *boid.position, *boid.velocity, forces);
+                }
+                *boid.position = position;
+                *boid.velocity = velocity;
+                midpoint
+            })
+            .collect()
+    }
+
+    /// Move every boid with its acceleration, returning where boids bounced off a wall and their
+    /// velocity after, and the index of the temporary obstacle each boid that hit one flew into.
+    ///
+    /// `midpoints` are the accelerations halfway through the step when RK2 needs them.
+    fn integrate(
+        &self,
+        boids: &mut BoidStore,
+        midpoints: &[Vec2],
+    ) -> (Vec<(Pos2, Vec2)>, Vec<usize>) {
+        let resolved_groups = self.params.resolve_groups();
+        let dt = self.params.integration.time_step;
+        let mut bounces = Vec::new();
+        let mut hits = Vec::new();
+        // Where the temporary obstacles are among all of them
+        let temporary = self.obstacles.len()..self.obstacles.len() + self.temporary_obstacles.len();
+        let obstacles = obstacles_at(
+            self.obstacles,
+            self.temporary_obstacles,
+            self.moving_obstacles,
+            self.obstacle_clock as f32,
+        );
+
+        // Update positions from velocity/acceleration
+        for (i, mut boid) in boids.iter_mut().enumerate() {
+            boid.age += 1;
+            if boid.resting.is_some() {
+                continue;
+            }
+
+            let offset = boid.apply_forces(
+                group_params(self.params, &resolved_groups, boid.group),
+                midpoints.get(i).copied(),
+            );
+            for impulse in self.impulses {
+                impulse.push(&mut boid);
+            }
+            for zone in self.current_zones {
+                zone.constrain(*boid.position, boid.velocity);
+            }
+            *boid.position += offset;
+            // The arena moves boids by a tick's worth of velocity
+            let mut step = *boid.velocity * dt;
+            let before = step;
+            self.params.arena.advance(
+                boid.position,
+                &mut step,
+                &self.bounds,
+                self.params.boundary_mode,
+            );
+            // Only bouncing off a wall changes the velocity here
+            if step != before {
+                *boid.velocity = step / dt;
+                bounces.push((*boid.position, *boid.velocity));
+            }
+            // Anything that still flew into an obstacle is put back on its surface
+            for (index, obstacle) in obstacles.iter().enumerate() {
+                if obstacle.push_out(boid.position, boid.velocity) && temporary.contains(&index) {
+                    hits.push(index - temporary.start);
+                }
+            }
+        }
+        (bounces, hits)
+    }
+}
+
+/// Everything the forces on a boid depend on besides the boid itself, shared by the whole flock
+/// for a tick.
+///
+/// Working out the forces only reads the flock, so every boid can be done at once.
+struct ForceInputs<'a> {
+    pub params: &'a BoidsSimulationParameters,
+    /// See [`BoidsSimulationParameters::resolve_groups`]
+    pub resolved_groups: &'a [(u32, BoidsSimulationParameters)],
+    pub rules: &'a [Box<dyn SteeringRule>],
+    /// See [`crate::mixer::BehaviorMixer::silenced`], silenced rules pull with no force
+    pub silenced: &'a [bool],
+    pub migration_target: Pos2,
+    pub goal_points: &'a [GoalPoint],
+    pub waypoint_path: &'a WaypointPath,
+    /// See [`crate::leader::LeaderParameters::leaders`]
+    pub leaders: &'a [Leader],
+    pub danger_map: &'a DangerMap,
+    pub perches: &'a [PerchSite],
+    pub thermals: &'a [Thermal],
+    /// The shepherd when a game is running
+    pub shepherd: Option<&'a Shepherd>,
+    pub game_settings: &'a GameSettings,
+    pub obstacles: &'a [Obstacle],
+    /// The dividing wall and merge attractor, when either is on
+    pub experiment: &'a ExperimentForces,
+    /// `None` updates every boid every tick
+    pub active_chunks: Option<&'a ActiveChunks>,
+    pub tick: u64,
+    pub bounds: Rect,
+}
+
+/// What working out the forces needs to look up and keep around between boids.
+///
+/// Clones share the lookups but not the buffers, so each thread gets its own.
+#[derive(Clone)]
+struct ForceScratch<'a> {
+    neighbor_index: NeighborIndex<'a>,
+    threat_index: ThreatIndex<'a>,
+    neighbors: Vec<BoidRef<'a>>,
+    same_group: Vec<BoidRef<'a>>,
+    nearest: Vec<BoidRef<'a>>,
+    nearest_flockmates: Vec<BoidRef<'a>>,
+    nearby_threats: Vec<&'a Threat>,
+}
+
+impl<'a> ForceScratch<'a> {
+    fn new(neighbor_index: NeighborIndex<'a>, threat_index: ThreatIndex<'a>) -> Self {
+        Self {
+            neighbor_index,
+            threat_index,
+            neighbors: Vec::new(),
+            same_group: Vec::new(),
+            nearest: Vec::new(),
+            nearest_flockmates: Vec::new(),
+            nearby_threats: Vec::new(),
+        }
+    }
+}
+
+/// The forces on one boid, kept apart so they can be added to its acceleration in a fixed order.
+#[derive(Default)]
+struct BoidForces {
+    /// One for each steering rule, in the order they're registered
+    pub rules: Vec<Vec2>,
+    pub migration: Vec2,
+    /// Towards the nearest goal point
+    pub goal_point: Vec2,
+    /// Towards the waypoint the path is heading for
+    pub waypoint: Vec2,
+    /// Towards the spot behind the nearest leader
+    pub leader: Vec2,
+    /// Away from where the flock remembers predators getting close
+    pub danger: Vec2,
+    pub perch: Vec2,
+    pub thermal: Vec2,
+    pub shepherd: Vec2,
+    pub goal: Vec2,
+    pub experiment: Vec2,
+    /// `None` for boids coasting until their next update, which keep their count from then
+    pub neighbor_count: Option<usize>,
+    /// See [`Self::neighbor_count`]
+    pub centroid: Option<Option<Pos2>>,
+    /// Time visual crowding took for this boid
+    pub crowding_time: Duration,
+}
+
+impl BoidForces {
+    /// Every force together, the same ones a boid adds to its acceleration.
+    #[cfg(feature = "gui")]
+    fn total(&self) -> Vec2 {
+        self.rules
+            .iter()
+            .fold(Vec2::ZERO, |sum, force| sum + *force)
+            + self.migration
+            + self.goal_point
+            + self.waypoint
+            + self.leader
+            + self.danger
+            + self.perch
+            + self.thermal
+            + self.shepherd
+            + self.goal
+            + self.experiment
+    }
+}
+
+impl ForceInputs<'_> {
+    /// The forces on every boid in `boids`, spread over all cores with `parallel`.
+    ///
+    /// Either way the forces come out exactly the same, only the time it takes differs.
+    #[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+    fn compute(
+        &self,
+        boids: &BoidStore,
+        scratch: ForceScratch<'_>,
+        parallel: bool,
+    ) -> Vec<BoidForces> {
+        #[cfg(feature = "parallel")]
+        if parallel {
+            use rayon::prelude::*;
+
+            return (0..boids.len())
+                .into_par_iter()
+                .map_init(
+                    || scratch.clone(),
+                    |scratch, index| self.boid_forces(boids.boid(index), scratch),
+                )
+                .collect();
+        }
+
+        let mut scratch = scratch;
+        boids
+            .iter()
+            .map(|boid| self.boid_forces(boid, &mut scratch))
+            .collect()
+    }
+
+    fn boid_forces(&self, boid: BoidRef<'_>, scratch: &mut ForceScratch<'_>) -> BoidForces {
+        if let Some(active) = self.active_chunks {
+            if !self.params.chunks.should_update(boid, self.tick, active) {
+                // Distant boids coast along until their next update
+                return BoidForces {
+                    rules: vec![Vec2::ZERO; self.rules.len()],
+                    ..BoidForces::default()
+                };
+            }
+        }
+
+        // Groups can override some of the global parameters
+        let params = group_params(self.params, self.resolved_groups, boid.group);
+        let ForceScratch {
+            neighbor_index,
+            threat_index,
+            neighbors,
+            same_group,
+            nearest,
+            nearest_flockmates,
+            nearby_threats,
+        } = scratch;
+        neighbor_index.candidates(boid.position, neighbors);
+        let interactions = &self.params.groups.interactions;
+        if !interactions.is_empty() {
+            neighbors
+                .retain(|other| interactions.reaction(boid.group, other.group) != Reaction::Ignore);
+        }
+        let mut crowding_time = Duration::ZERO;
+        if self.params.crowding.enabled {
+            let started = Instant::now();
+            self.params.crowding.occlude(boid.position, neighbors);
+            crowding_time = started.elapsed();
+        }
+
+        let neighbor_count = neighbors
+            .iter()
+            .filter(|other| {
+                let distance = boid.position.distance(other.position);
+                distance > 0.0 && distance < params.neighbor_radius
+            })
+            .count();
+        // Boids chasing or running from others don't flock with them
+        let flocks_with = |other: &BoidRef<'_>| {
+            (!self.params.groups.flock_apart || other.group == boid.group)
+                && interactions.reaction(boid.group, other.group) == Reaction::Flock
+        };
+        let flockmates: &[BoidRef<'_>] =
+            if self.params.groups.flock_apart || !interactions.is_empty() {
+                same_group.clear();
+                same_group.extend(neighbors.iter().copied().filter(flocks_with));
+                same_group
+            } else {
+                neighbors
+            };
+
+        let topology = &params.topology;
+        nearest.clear();
+        nearest_flockmates.clear();
+        if topology.uses_nearest() {
+            let visible = |other: &BoidRef<'_>| {
+                other.position != boid.position
+                    && params.perception.can_see(boid, other.position)
+                    && interactions.reaction(boid.group, other.group) != Reaction::Ignore
+            };
+            neighbor_index.nearest(
+                boid.position,
+                topology.nearest_count,
+                |other| visible(&other),
+                nearest,
+            );
+            neighbor_index.nearest(
+                boid.position,
+                topology.nearest_count,
+                |other| visible(&other) && flocks_with(&other),
+                nearest_flockmates,
+            );
+        }
+        let centroid = boid.perceived_centroid(
+            topology.cohesion.pick(flockmates, nearest_flockmates),
+            params,
+        );
+        if self.params.altitude.within_predator_reach(boid) {
+            threat_index.nearby(boid.position, nearby_threats);
+        } else {
+            nearby_threats.clear();
+        }
+
+        let context = SteeringContext {
+            params,
+            neighbors,
+            flockmates,
+            nearest,
+            nearest_flockmates,
+            centroid,
+            threats: nearby_threats,
+            bounds: self.bounds,
+            obstacles: self.obstacles,
+            tick: self.tick,
+        };
+        let rules = self
+            .rules
+            .iter()
+            .zip(self.silenced)
+            .map(|(rule, silenced)| {
+                if *silenced {
+                    Vec2::ZERO
+                } else {
+                    rule.force(boid, &context)
+                }
+            })
+            .collect();
+
+        let migration = if self.params.migration.enabled {
+            boid.calculate_seek_force(self.migration_target, self.params.migration.weight, params)
+        } else {
+            Vec2::ZERO
+        };
+
+        let perching = &self.params.perching;
+        let perch = nearest_perch_point(self.perches, boid.position, perching.attraction_radius)
+            .map_or(Vec2::ZERO, |point| {
+                boid.calculate_seek_force(point, perching.attraction_weight, params)
+            });
+
+        let shepherd = self
+            .shepherd
+            .filter(|shepherd| shepherd.protects(boid.position, self.game_settings))
+            .map_or(Vec2::ZERO, |shepherd| {
+                boid.calculate_seek_force(
+                    shepherd.position,
+                    self.game_settings.shepherd_weight,
+                    params,
+                )
+            });
+
+        let goal = self
+            .params
+            .groups
+            .goal(boid.group)
+            .map_or(Vec2::ZERO, |goal| {
+                boid.calculate_heading_force(goal.heading(), goal.weight, params)
+            });
+
+        BoidForces {
+            rules,
+            migration,
+            goal_point: self
+                .params
+                .goal_seeking
+                .force(boid, self.goal_points, params),
+            waypoint: self
+                .waypoint_path
+                .force(boid, &self.params.goal_seeking, params),
+            leader: self.params.leaders.force(boid, self.leaders, params),
+            danger: self.danger_map.force(boid, &self.params.danger, params),
+            perch,
+            thermal: self.thermal_force(boid),
+            shepherd,
+            goal,
+            experiment: self.experiment.force(boid, params),
+            neighbor_count: Some(neighbor_count),
+            centroid: Some(centroid),
+            crowding_time,
+        }
+    }
+
+    /// Boids low on energy head for the nearest thermal and circle in it once they get there.
+    fn thermal_force(&self, boid: BoidRef<'_>) -> Vec2 {
+        let energy = &self.params.energy;
+        if !energy.enabled || !boid.seeking_thermal {
+            return Vec2::ZERO;
+        }
+
+        if let Some(thermal) = thermal_containing(self.thermals, boid.position) {
+            boid.calculate_orbit_force(
+                thermal.center,
+                thermal.orbit_radius(),
+                energy.thermal_weight,
+                self.params,
+            )
+        } else if let Some(thermal) = nearest_thermal(self.thermals, boid.position) {
+            boid.calculate_seek_force(thermal.center, energy.thermal_weight, self.params)
+        } else {
+            Vec2::ZERO
+        }
+    }
+}
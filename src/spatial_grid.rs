@@ -0,0 +1,348 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    boid_store::{BoidRef, BoidStore},
+    math::Pos2,
+};
+
+/// How boids find the neighbors that go into separation, alignment and cohesion.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum NeighborSearch {
+    /// Every boid looks at every other boid
+    BruteForce,
+    /// Boids are bucketed into grid cells the size of the neighbor radius and only nearby cells are checked
+    #[default]
+    Grid,
+}
+
+/// Boid indices bucketed by the grid cell their position falls in.
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    /// The lowest and highest cell coordinates anything is in, `None` while the grid is empty
+    extent: Option<((i32, i32), (i32, i32))>,
+}
+
+impl SpatialGrid {
+    pub fn new(boids: &BoidStore, cell_size: f32) -> Self {
+        Self::from_positions(boids.positions().iter().copied(), cell_size)
+    }
+
+    /// Bucket anything with a position, indices refer to the order of `positions`.
+    pub fn from_positions(positions: impl Iterator<Item = Pos2>, cell_size: f32) -> Self {
+        let mut grid = Self {
+            cell_size: cell_size.max(1.0),
+            cells: HashMap::new(),
+            extent: None,
+        };
+        for (index, position) in positions.enumerate() {
+            let cell = grid.cell(position);
+            grid.cells.entry(cell).or_default().push(index);
+            grid.extent = Some(match grid.extent {
+                None => (cell, cell),
+                Some((min, max)) => (
+                    (min.0.min(cell.0), min.1.min(cell.1)),
+                    (max.0.max(cell.0), max.1.max(cell.1)),
+                ),
+            });
+        }
+        grid
+    }
+
+    fn cell(&self, position: Pos2) -> (i32, i32) {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Indices of every boid in a cell that could be within `radius` of `position`, in ascending order.
+    pub fn candidates(&self, position: Pos2, radius: f32, out: &mut Vec<usize>) {
+        out.clear();
+        let reach = (radius / self.cell_size).ceil() as i32;
+        let (x, y) = self.cell(position);
+        for cell_x in x - reach..=x + reach {
+            for cell_y in y - reach..=y + reach {
+                if let Some(indices) = self.cells.get(&(cell_x, cell_y)) {
+                    out.extend_from_slice(indices);
+                }
+            }
+        }
+        // Keep the same order as a brute force search so both give bit for bit the same forces
+        out.sort_unstable();
+    }
+
+    /// Index of the closest of `positions` within `radius` of `position`, only looking in the
+    /// cells it could be in. `positions` has to be what the grid was built from.
+    #[cfg(feature = "gui")]
+    pub fn nearest(&self, positions: &[Pos2], position: Pos2, radius: f32) -> Option<usize> {
+        let mut candidates = Vec::new();
+        self.candidates(position, radius, &mut candidates);
+        candidates
+            .into_iter()
+            .map(|index| (index, positions[index].distance(position)))
+            .filter(|(_, distance)| *distance < radius)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+    }
+
+    /// Indices of the `k` closest of `positions` to `position` that `keep` lets through, nearest
+    /// first, searching the cells in rings around `position` until nothing further out could be
+    /// closer. `positions` has to be what the grid was built from.
+    pub fn k_nearest(
+        &self,
+        positions: &[Pos2],
+        position: Pos2,
+        k: usize,
+        keep: impl Fn(usize) -> bool,
+        out: &mut Vec<usize>,
+    ) {
+        out.clear();
+        let Some((min, max)) = self.extent else {
+            return;
+        };
+        if k == 0 {
+            return;
+        }
+        let (x, y) = self.cell(position);
+        let last_ring = [x - min.0, max.0 - x, y - min.1, max.1 - y]
+            .into_iter()
+            .max()
+            .unwrap_or(0)
+            .max(0);
+        let mut found = Vec::new();
+        for ring in 0..=last_ring {
+            for cell_x in x - ring..=x + ring {
+                for cell_y in y - ring..=y + ring {
+                    if (cell_x - x).abs() != ring && (cell_y - y).abs() != ring {
+                        continue;
+                    }
+                    let Some(indices) = self.cells.get(&(cell_x, cell_y)) else {
+                        continue;
+                    };
+                    found.extend(
+                        indices
+                            .iter()
+                            .filter(|index| keep(**index))
+                            .map(|index| (positions[*index].distance(position), *index)),
+                    );
+                }
+            }
+            // Anything in the rings further out is at least this far away
+            let searched = ring as f32 * self.cell_size;
+            if found.len() >= k {
+                sort_by_distance(&mut found);
+                if found[k - 1].0 <= searched {
+                    break;
+                }
+            }
+        }
+        sort_by_distance(&mut found);
+        out.extend(found.iter().take(k).map(|(_, index)| *index));
+    }
+}
+
+/// Closest first, ties broken by index so every search orders them the same way.
+fn sort_by_distance(found: &mut [(f32, usize)]) {
+    found.sort_unstable_by(|(a, a_index), (b, b_index)| a.total_cmp(b).then(a_index.cmp(b_index)));
+}
+
+/// Answers "who might be my neighbor?" with whichever [`NeighborSearch`] is selected.
+///
+/// Clones share the grid, so every thread searching it can have its own.
+#[derive(Clone)]
+pub struct NeighborIndex<'a> {
+    boids: &'a BoidStore,
+    grid: Option<Arc<SpatialGrid>>,
+    radius: f32,
+    scratch: Vec<usize>,
+}
+
+impl<'a> NeighborIndex<'a> {
+    /// `radius` has to cover the largest neighbor radius any boid uses.
+    pub fn new(boids: &'a BoidStore, radius: f32, search: NeighborSearch) -> Self {
+        let grid = match search {
+            NeighborSearch::BruteForce => None,
+            NeighborSearch::Grid => Some(Arc::new(SpatialGrid::new(boids, radius))),
+        };
+        Self {
+            boids,
+            grid,
+            radius,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Fill `out` with the boids that could be neighbors of `position`, a superset of the real neighbors.
+    pub fn candidates(&mut self, position: Pos2, out: &mut Vec<BoidRef<'a>>) {
+        out.clear();
+        match &self.grid {
+            None => out.extend(self.boids),
+            Some(grid) => {
+                grid.candidates(position, self.radius, &mut self.scratch);
+                out.extend(self.scratch.iter().map(|index| self.boids.boid(*index)));
+            }
+        }
+    }
+
+    /// Fill `out` with the `k` boids closest to `position` that `keep` lets through, nearest
+    /// first, however far away they are.
+    pub fn nearest(
+        &mut self,
+        position: Pos2,
+        k: usize,
+        keep: impl Fn(BoidRef<'a>) -> bool,
+        out: &mut Vec<BoidRef<'a>>,
+    ) {
+        let boids = self.boids;
+        match &self.grid {
+            None => {
+                let mut found: Vec<(f32, usize)> = boids
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, boid)| keep(*boid))
+                    .map(|(index, boid)| (boid.position.distance(position), index))
+                    .collect();
+                sort_by_distance(&mut found);
+                self.scratch.clear();
+                self.scratch
+                    .extend(found.iter().take(k).map(|(_, index)| *index));
+            }
+            Some(grid) => grid.k_nearest(
+                boids.positions(),
+                position,
+                k,
+                |index| keep(boids.boid(index)),
+                &mut self.scratch,
+            ),
+        }
+        out.clear();
+        out.extend(self.scratch.iter().map(|index| boids.boid(*index)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::{NeighborIndex, NeighborSearch};
+    use crate::{
+        boid::Boid,
+        boid_store::{BoidRef, BoidStore},
+        boids_simulation::BoidsSimulationParameters,
+        math::{Pos2, Vec2},
+        HeadlessSimulation,
+    };
+
+    const FLOCK_SIZE: usize = 2000;
+    /// The grid has to beat brute force by at least this much, it's usually well over 10x.
+    const REQUIRED_SPEEDUP: f64 = 4.0;
+    const TICKS: usize = 3;
+
+    fn flock(params: &BoidsSimulationParameters) -> BoidStore {
+        let mut rng = StdRng::seed_from_u64(221);
+        (0..FLOCK_SIZE)
+            .map(|_| {
+                let position =
+                    Pos2::new(rng.gen_range(-850.0..850.0), rng.gen_range(-475.0..475.0));
+                let velocity = Vec2::new(
+                    rng.gen_range(-params.max_speed..params.max_speed),
+                    rng.gen_range(-params.max_speed..params.max_speed),
+                );
+                Boid::new(position, velocity)
+            })
+            .collect()
+    }
+
+    /// A flock stepped through the same [`crate::simulation::World`] as the app, looking for
+    /// neighbors with `search`.
+    fn simulation(search: NeighborSearch) -> HeadlessSimulation {
+        let mut simulation = HeadlessSimulation::new(FLOCK_SIZE, 221);
+        simulation.params.neighbor_search = search;
+        simulation
+    }
+
+    fn state(simulation: &mut HeadlessSimulation) -> Vec<(Pos2, Vec2)> {
+        simulation
+            .flock()
+            .boids()
+            .map(|boid| (boid.position(), boid.velocity()))
+            .collect()
+    }
+
+    fn time(search: NeighborSearch) -> Duration {
+        let mut simulation = simulation(search);
+        let start = Instant::now();
+        for _ in 0..TICKS {
+            simulation.step();
+        }
+        start.elapsed()
+    }
+
+    #[test]
+    fn grid_matches_brute_force() {
+        let mut grid = simulation(NeighborSearch::Grid);
+        let mut brute_force = simulation(NeighborSearch::BruteForce);
+        for _ in 0..TICKS {
+            grid.step();
+            brute_force.step();
+        }
+        assert!(state(&mut grid) == state(&mut brute_force));
+    }
+
+    #[test]
+    #[cfg(feature = "gui")]
+    fn nearest_matches_brute_force() {
+        let params = BoidsSimulationParameters::default();
+        let positions: Vec<Pos2> = flock(&params).iter().map(|boid| boid.position).collect();
+        let radius = 15.0;
+        let grid = super::SpatialGrid::from_positions(positions.iter().copied(), radius);
+        let mut rng = StdRng::seed_from_u64(259);
+        for _ in 0..500 {
+            let position = Pos2::new(rng.gen_range(-850.0..850.0), rng.gen_range(-475.0..475.0));
+            let expected = positions
+                .iter()
+                .enumerate()
+                .map(|(index, other)| (index, other.distance(position)))
+                .filter(|(_, distance)| *distance < radius)
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(index, _)| index);
+            assert_eq!(grid.nearest(&positions, position, radius), expected);
+        }
+    }
+
+    #[test]
+    fn nearest_neighbors_match_brute_force() {
+        let params = BoidsSimulationParameters::default();
+        let boids = flock(&params);
+        let mut grid = NeighborIndex::new(&boids, params.neighbor_radius, NeighborSearch::Grid);
+        let mut brute_force =
+            NeighborIndex::new(&boids, params.neighbor_radius, NeighborSearch::BruteForce);
+        let (mut expected, mut found) = (Vec::new(), Vec::new());
+        for boid in boids.iter().take(200) {
+            // Leaving out every other boid makes the grid search further out than one ring
+            let keep = |other: BoidRef<'_>| other.id % 2 == 0 && other.id != boid.id;
+            brute_force.nearest(boid.position, 7, keep, &mut expected);
+            grid.nearest(boid.position, 7, keep, &mut found);
+            let ids = |boids: &[BoidRef<'_>]| boids.iter().map(|boid| boid.id).collect::<Vec<_>>();
+            assert_eq!(ids(&found), ids(&expected));
+        }
+    }
+
+    /// Timing depends on the machine and whatever else it's doing, so this only runs on request,
+    /// and in its own CI job, with `cargo test --release --lib -- --ignored grid_is_faster`.
+    #[test]
+    #[ignore]
+    fn grid_is_faster_than_brute_force() {
+        let brute_force = time(NeighborSearch::BruteForce);
+        let grid = time(NeighborSearch::Grid);
+        let speedup = brute_force.as_secs_f64() / grid.as_secs_f64().max(f64::EPSILON);
+        assert!(
+            speedup >= REQUIRED_SPEEDUP,
+            "grid search is only {speedup:.1}x faster than brute force at {FLOCK_SIZE} boids \
+             ({grid:?} vs {brute_force:?}), expected at least {REQUIRED_SPEEDUP}x"
+        );
+    }
+}
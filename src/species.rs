@@ -0,0 +1,177 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use egui::Color32;
+
+use crate::{
+    error::{BoidsError, Result},
+    group::{GroupGoal, GroupParameters, ParameterOverrides},
+    interaction::Reaction,
+    render::GroupStyle,
+};
+
+/// Where species definitions are read from, relative to the working directory.
+const SPECIES_DIR: &str = "species";
+/// How often the species directory is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A group defined in a TOML file rather than in the UI, which becomes the group with its name.
+///
+/// ```toml
+/// name = "Starlings"
+/// color = [230, 90, 80, 255]
+///
+/// [overrides]
+/// max_speed = 6.0
+/// neighbor_radius = 40.0
+///
+/// [style]
+/// shape = "Triangle"
+/// size = 0.8
+///
+/// [reactions]
+/// Hawks = "Flee"
+/// ```
+#[derive(Clone, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpeciesDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub color: Option<Color32>,
+    #[serde(default)]
+    pub overrides: ParameterOverrides,
+    #[serde(default)]
+    pub style: GroupStyle,
+    #[serde(default)]
+    pub goal: Option<GroupGoal>,
+    /// How this species reacts to others by name, the ones left out are flocked with
+    #[serde(default)]
+    pub reactions: BTreeMap<String, Reaction>,
+}
+
+impl SpeciesDefinition {
+    fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|err| BoidsError::Parse {
+            format: "species TOML",
+            message: format!("{}: {err}", path.display()),
+        })
+    }
+}
+
+/// Species definitions in the `species/` directory, read again whenever the files change.
+pub struct SpeciesLibrary {
+    dir: PathBuf,
+    /// Every definition file with when it was last modified, as of the last load
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    last_poll: Option<Instant>,
+}
+
+impl Default for SpeciesLibrary {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from(SPECIES_DIR),
+            files: Vec::new(),
+            last_poll: None,
+        }
+    }
+}
+
+impl SpeciesLibrary {
+    /// The definitions, if this is the first call or any file has been added, removed or
+    /// modified since the last one that returned them.
+    pub fn poll(&mut self) -> Option<Result<Vec<SpeciesDefinition>>> {
+        let now = Instant::now();
+        if self
+            .last_poll
+            .is_some_and(|last| now.duration_since(last) < POLL_INTERVAL)
+        {
+            return None;
+        }
+        let first = self.last_poll.is_none();
+        self.last_poll = Some(now);
+
+        let files = match self.scan() {
+            Ok(files) => files,
+            // No directory just means no species
+            Err(BoidsError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Some(Err(err)),
+        };
+        if files == self.files && !first {
+            return None;
+        }
+        self.files = files;
+        if self.files.is_empty() {
+            return None;
+        }
+        Some(
+            self.files
+                .iter()
+                .map(|(path, _)| SpeciesDefinition::load(path))
+                .collect(),
+        )
+    }
+
+    fn scan(&self) -> Result<Vec<(PathBuf, Option<SystemTime>)>> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "toml")
+            {
+                let modified = std::fs::metadata(&path)?.modified().ok();
+                files.push((path, modified));
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+impl GroupParameters {
+    /// Make every species a group, or update the group already named after it.
+    ///
+    /// A species' reactions replace its whole row of the interaction matrix.
+    pub fn apply_species(&mut self, species: &[SpeciesDefinition]) -> Result<()> {
+        for definition in species {
+            let group = match self
+                .groups
+                .iter()
+                .position(|group| group.name == definition.name)
+            {
+                Some(index) => &mut self.groups[index],
+                None => self.add(definition.name.clone()),
+            };
+            group.overrides = definition.overrides.clone();
+            group.color = definition.color.or(group.color);
+            group.style = definition.style.clone();
+            group.goal = definition.goal;
+        }
+
+        for definition in species {
+            let id = self.id_named(&definition.name).expect("added above");
+            self.interactions.clear_row(id);
+            for (name, reaction) in &definition.reactions {
+                let Some(to) = self.id_named(name) else {
+                    return Err(BoidsError::Invalid(format!(
+                        "species {} reacts to {name}, which isn't a group",
+                        definition.name
+                    )));
+                };
+                self.interactions.set(id, to, *reaction);
+            }
+        }
+        Ok(())
+    }
+
+    fn id_named(&self, name: &str) -> Option<u32> {
+        self.groups
+            .iter()
+            .find(|group| group.name == name)
+            .map(|group| group.id)
+    }
+}
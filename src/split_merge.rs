@@ -0,0 +1,340 @@
+#[cfg(feature = "gui")]
+use egui::{Painter, Stroke, Ui};
+#[cfg(feature = "gui")]
+use tracing::info;
+
+use crate::{
+    boid_store::BoidRef,
+    boids_simulation::BoidsSimulationParameters,
+    math::{Pos2, Vec2},
+};
+
+#[cfg(feature = "gui")]
+use crate::{
+    boid_store::BoidStore,
+    camera::Camera,
+    game::TICKS_PER_SECOND,
+    math::{Color32, Rect},
+    metrics::flock_sizes,
+};
+
+#[cfg(feature = "gui")]
+const WALL_COLOR: Color32 = Color32::from_rgb(230, 80, 80);
+#[cfg(feature = "gui")]
+const ATTRACTOR_COLOR: Color32 = Color32::from_rgb(90, 200, 255);
+/// Far enough in world units for the wall to cross the screen at any zoom worth using.
+#[cfg(feature = "gui")]
+const WALL_DRAW_LENGTH: f32 = 100_000.0;
+/// Finding the flocks means visiting every boid's neighbors, so the re-merge is only checked every few ticks.
+#[cfg(feature = "gui")]
+const SAMPLE_TICKS: u64 = 3;
+/// Oldest trials are dropped past this.
+#[cfg(feature = "gui")]
+const MAX_TRIALS: usize = 100;
+
+/// A line across the world that pushes boids near it further to their own side, cutting the
+/// flock in two.
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct DividingWall {
+    pub point: Pos2,
+    /// Unit length, across the line
+    pub normal: Vec2,
+    /// How far from the line boids feel it
+    pub reach: f32,
+    pub weight: f32,
+}
+
+impl DividingWall {
+    fn force(&self, boid: BoidRef<'_>, params: &BoidsSimulationParameters) -> Vec2 {
+        let distance = (boid.position - self.point).dot(self.normal);
+        if distance.abs() >= self.reach {
+            return Vec2::ZERO;
+        }
+        let away = if distance < 0.0 {
+            -self.normal
+        } else {
+            self.normal
+        };
+        // Strongest on the line itself, fading out to nothing at its reach
+        let closeness = 1.0 - distance.abs() / self.reach;
+        boid.calculate_heading_force(away, self.weight * closeness, params)
+    }
+
+    #[cfg(feature = "gui")]
+    fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        let along = self.normal.rot90() * WALL_DRAW_LENGTH;
+        let segment = |offset: f32| {
+            let point = self.point + self.normal * offset;
+            [
+                camera.world_to_screen(rect, point - along),
+                camera.world_to_screen(rect, point + along),
+            ]
+        };
+        painter.line_segment(segment(0.0), Stroke::new(3.0, WALL_COLOR));
+        let edge = Stroke::new(1.0, WALL_COLOR.gamma_multiply(0.4));
+        painter.line_segment(segment(self.reach), edge);
+        painter.line_segment(segment(-self.reach), edge);
+    }
+}
+
+/// A point every boid is pulled towards, however far away, to bring a scattered flock together.
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct MergeAttractor {
+    pub position: Pos2,
+    pub weight: f32,
+}
+
+/// The split and merge forces in effect, kept with the inputs so replays repeat them.
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ExperimentForces {
+    pub wall: Option<DividingWall>,
+    pub attractor: Option<MergeAttractor>,
+}
+
+impl ExperimentForces {
+    pub fn force(&self, boid: BoidRef<'_>, params: &BoidsSimulationParameters) -> Vec2 {
+        let wall = self
+            .wall
+            .map_or(Vec2::ZERO, |wall| wall.force(boid, params));
+        let attractor = self.attractor.map_or(Vec2::ZERO, |attractor| {
+            boid.calculate_seek_force(attractor.position, attractor.weight, params)
+        });
+        wall + attractor
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera) {
+        if let Some(wall) = &self.wall {
+            wall.draw(painter, rect, camera);
+        }
+        if let Some(attractor) = &self.attractor {
+            let screen_pos = camera.world_to_screen(rect, attractor.position);
+            painter.circle_stroke(screen_pos, 14.0, Stroke::new(2.0, ATTRACTOR_COLOR));
+            painter.circle_stroke(screen_pos, 7.0, Stroke::new(1.0, ATTRACTOR_COLOR));
+        }
+    }
+}
+
+/// A split the flock came back together from.
+#[cfg(feature = "gui")]
+#[derive(Clone, Copy)]
+pub struct MergeTrial {
+    /// Tick the wall came down
+    pub released: u64,
+    /// How long the wall kept the flock apart, in ticks
+    pub split_ticks: u64,
+    /// Ticks from the wall coming down to the flock being whole again
+    pub merge_ticks: u64,
+    /// Whether the merge attractor was on at any point while the flock came back together
+    pub assisted: bool,
+}
+
+/// Which of the tools the user toggled from the panel.
+#[cfg(feature = "gui")]
+pub enum ExperimentToggle {
+    Split,
+    Merge,
+}
+
+/// Tools to cut the flock in two with a wall and pull it back together with an attractor, timing
+/// how long it takes to re-merge so the same experiment can be run again and compared.
+#[cfg(feature = "gui")]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct SplitMergeExperiment {
+    /// Direction the wall runs in, in degrees from the x axis
+    pub wall_angle: f32,
+    pub wall_reach: f32,
+    pub wall_weight: f32,
+    pub attractor_weight: f32,
+    /// Share of the flying boids the largest flock needs to count as merged again
+    pub merged_share: f32,
+    #[serde(skip)]
+    pub forces: ExperimentForces,
+    /// Tick the wall went up
+    #[serde(skip)]
+    split_at: Option<u64>,
+    /// The trial under way since the wall came down, waiting for the flock to merge
+    #[serde(skip)]
+    timing: Option<MergeTrial>,
+    /// Oldest first
+    #[serde(skip)]
+    trials: Vec<MergeTrial>,
+}
+
+#[cfg(feature = "gui")]
+impl Default for SplitMergeExperiment {
+    fn default() -> Self {
+        Self {
+            wall_angle: 90.0,
+            wall_reach: 150.0,
+            wall_weight: 3.0,
+            attractor_weight: 1.0,
+            merged_share: 0.9,
+            forces: ExperimentForces::default(),
+            split_at: None,
+            timing: None,
+            trials: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl SplitMergeExperiment {
+    /// Put the wall up through `center`, or take it down and start timing the re-merge.
+    ///
+    /// Returns whether the wall is up now.
+    pub fn toggle_split(&mut self, center: Pos2, tick: u64) -> bool {
+        if self.forces.wall.take().is_some() {
+            self.timing = self.split_at.take().map(|split_at| MergeTrial {
+                released: tick,
+                split_ticks: tick - split_at,
+                merge_ticks: 0,
+                assisted: self.forces.attractor.is_some(),
+            });
+            return false;
+        }
+        self.forces.wall = Some(DividingWall {
+            point: center,
+            normal: Vec2::angled(self.wall_angle.to_radians()).rot90(),
+            reach: self.wall_reach.max(1.0),
+            weight: self.wall_weight,
+        });
+        self.split_at = Some(tick);
+        // Splitting again abandons a re-merge that was still being timed
+        self.timing = None;
+        true
+    }
+
+    /// Switch the attractor on at `center`, or off. Returns whether it's on now.
+    pub fn toggle_merge(&mut self, center: Pos2) -> bool {
+        if self.forces.attractor.take().is_some() {
+            return false;
+        }
+        self.forces.attractor = Some(MergeAttractor {
+            position: center,
+            weight: self.attractor_weight,
+        });
+        true
+    }
+
+    /// Check on the re-merge being timed after `tick`, returning the trial once the flock is
+    /// whole again.
+    pub fn update(&mut self, boids: &BoidStore, radius: f32, tick: u64) -> Option<MergeTrial> {
+        let trial = self.timing.as_mut()?;
+        trial.assisted |= self.forces.attractor.is_some();
+        if tick % SAMPLE_TICKS != 0 {
+            return None;
+        }
+
+        let sizes = flock_sizes(boids, radius);
+        let flying: usize = sizes.iter().sum();
+        let largest = sizes.iter().copied().max().unwrap_or(0);
+        if flying == 0 || (largest as f32) < self.merged_share * flying as f32 {
+            return None;
+        }
+        let mut trial = self.timing.take()?;
+        trial.merge_ticks = tick - trial.released;
+        info!(
+            split_ticks = trial.split_ticks,
+            merge_ticks = trial.merge_ticks,
+            assisted = trial.assisted,
+            "flock re-merged"
+        );
+        if self.trials.len() == MAX_TRIALS {
+            self.trials.remove(0);
+        }
+        self.trials.push(trial);
+        Some(trial)
+    }
+
+    /// Stop timing the re-merge, after the flock jumped to another state (like the start of a
+    /// replay) that the time would mean nothing for.
+    pub fn interrupt(&mut self) {
+        self.split_at = None;
+        self.timing = None;
+    }
+
+    /// `split_hint` and `merge_hint` describe the keys that toggle the tools.
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(
+        &mut self,
+        ui: &mut Ui,
+        split_hint: &str,
+        merge_hint: &str,
+    ) -> Option<ExperimentToggle> {
+        let mut toggle = None;
+        ui.horizontal(|ui| {
+            let split = if self.forces.wall.is_some() {
+                "Release Split"
+            } else {
+                "Split Flock"
+            };
+            if ui.button(split).on_hover_text(split_hint).clicked() {
+                toggle = Some(ExperimentToggle::Split);
+            }
+            let merge = if self.forces.attractor.is_some() {
+                "Release Merge"
+            } else {
+                "Force Merge"
+            };
+            if ui.button(merge).on_hover_text(merge_hint).clicked() {
+                toggle = Some(ExperimentToggle::Merge);
+            }
+        });
+
+        ui.label("Wall Angle");
+        ui.add(
+            egui::DragValue::new(&mut self.wall_angle)
+                .range(0.0..=180.0)
+                .suffix("°"),
+        );
+        ui.label("Wall Reach");
+        ui.add(egui::DragValue::new(&mut self.wall_reach).range(1.0..=f32::MAX));
+        ui.label("Wall Weight");
+        ui.add(egui::DragValue::new(&mut self.wall_weight).speed(0.05));
+        ui.label("Attractor Weight");
+        ui.add(egui::DragValue::new(&mut self.attractor_weight).speed(0.05));
+        ui.add(egui::Slider::new(&mut self.merged_share, 0.5..=1.0).text("Merged Share"))
+            .on_hover_text(
+                "The share of the flying boids the biggest flock needs to count as merged",
+            );
+        ui.weak("Changes apply the next time a tool is switched on");
+
+        ui.separator();
+        if self.timing.is_some() {
+            ui.label("Timing the re-merge...");
+        }
+        if self.trials.is_empty() {
+            ui.label("No trials yet");
+            return toggle;
+        }
+        let mean = self
+            .trials
+            .iter()
+            .map(|trial| trial.merge_ticks as f32)
+            .sum::<f32>()
+            / self.trials.len() as f32;
+        ui.label(format!(
+            "Mean re-merge {:.1}s over {} trials",
+            mean / TICKS_PER_SECOND,
+            self.trials.len()
+        ));
+        egui::ScrollArea::vertical()
+            .max_height(150.0)
+            .show(ui, |ui| {
+                for trial in self.trials.iter().rev() {
+                    ui.label(format!(
+                        "Split {:.1}s, re-merged in {:.1}s{}",
+                        trial.split_ticks as f32 / TICKS_PER_SECOND,
+                        trial.merge_ticks as f32 / TICKS_PER_SECOND,
+                        if trial.assisted { " (assisted)" } else { "" }
+                    ));
+                }
+            });
+        if ui.button("Clear Trials").clicked() {
+            self.trials.clear();
+        }
+        toggle
+    }
+}
@@ -0,0 +1,276 @@
+use crate::{
+    boid_store::BoidRef,
+    boids_simulation::{BoidsSimulationParameters, BoundaryMode},
+    math::{Color32, Pos2, Rect, Vec2},
+    obstacle::Obstacle,
+    threat::Threat,
+};
+
+/// What a boid knows about its surroundings when working out where to steer.
+pub struct SteeringContext<'a> {
+    /// The flock's parameters with the boid's group overrides applied
+    pub params: &'a BoidsSimulationParameters,
+    /// Boids that might be within the neighbor radius, the rules check the distance themselves
+    pub neighbors: &'a [BoidRef<'a>],
+    /// The neighbors the boid aligns with, which leaves out other groups when they flock apart
+    pub flockmates: &'a [BoidRef<'a>],
+    /// The closest of [`Self::neighbors`] the boid can see, for rules that look at a fixed number
+    /// of neighbors, empty when none do
+    pub nearest: &'a [BoidRef<'a>],
+    /// The closest of [`Self::flockmates`] the boid can see, see [`Self::nearest`]
+    pub nearest_flockmates: &'a [BoidRef<'a>],
+    /// See [`Boid::perceived_centroid`]
+    pub centroid: Option<Pos2>,
+    /// Threats close enough to matter, empty when the boid is out of their reach
+    pub threats: &'a [&'a Threat],
+    /// The simulation area the arena is fitted into
+    pub bounds: Rect,
+    /// Every obstacle in the world, the rule ignores the ones out of reach
+    pub obstacles: &'a [Obstacle],
+    /// Ticks simulated so far
+    pub tick: u64,
+}
+
+/// One of the rules a boid steers by, which the simulation adds up into its acceleration.
+///
+/// New behaviours go in as another rule registered in [`flocking_rules`], without touching the
+/// force loop. Rules are shared between the threads working out the forces.
+pub trait SteeringRule: Send + Sync {
+    /// What the rule is called in the UI.
+    fn name(&self) -> &'static str;
+
+    fn force(&self, boid: BoidRef<'_>, context: &SteeringContext<'_>) -> Vec2;
+
+    /// Boids this rule pulls on the hardest are drawn in this color, if any.
+    fn color(&self) -> Option<Color32> {
+        None
+    }
+
+    /// The weight the rule's fader on the mixer sets, if it has one.
+    fn weight_mut<'a>(&self, _params: &'a mut BoidsSimulationParameters) -> Option<&'a mut f32> {
+        None
+    }
+}
+
+pub struct Separation;
+
+impl SteeringRule for Separation {
+    fn name(&self) -> &'static str {
+        "Separation"
+    }
+
+    fn force(&self, boid: BoidRef<'_>, context: &SteeringContext<'_>) -> Vec2 {
+        let topology = &context.params.topology;
+        boid.calculate_separation_force(
+            topology.separation.pick(context.neighbors, context.nearest),
+            context.params,
+        )
+    }
+
+    fn color(&self) -> Option<Color32> {
+        Some(Color32::YELLOW)
+    }
+
+    fn weight_mut<'a>(&self, params: &'a mut BoidsSimulationParameters) -> Option<&'a mut f32> {
+        Some(&mut params.separation_weight)
+    }
+}
+
+pub struct Alignment;
+
+impl SteeringRule for Alignment {
+    fn name(&self) -> &'static str {
+        "Alignment"
+    }
+
+    fn force(&self, boid: BoidRef<'_>, context: &SteeringContext<'_>) -> Vec2 {
+        let topology = &context.params.topology;
+        boid.calculate_alignment_force(
+            topology
+                .alignment
+                .pick(context.flockmates, context.nearest_flockmates),
+            context.params,
+        )
+    }
+
+    fn color(&self) -> Option<Color32> {
+        Some(Color32::GREEN)
+    }
+
+    fn weight_mut<'a>(&self, params: &'a mut BoidsSimulationParameters) -> Option<&'a mut f32> {
+        Some(&mut params.alignment_weight)
+    }
+}
+
+pub struct Cohesion;
+
+impl SteeringRule for Cohesion {
+    fn name(&self) -> &'static str {
+        "Cohesion"
+    }
+
+    fn force(&self, boid: BoidRef<'_>, context: &SteeringContext<'_>) -> Vec2 {
+        boid.calculate_cohesion_force(context.centroid, context.params)
+    }
+
+    fn color(&self) -> Option<Color32> {
+        Some(Color32::BLUE)
+    }
+
+    fn weight_mut<'a>(&self, params: &'a mut BoidsSimulationParameters) -> Option<&'a mut f32> {
+        Some(&mut params.cohesion_weight)
+    }
+}
+
+pub struct Avoidance;
+
+impl SteeringRule for Avoidance {
+    fn name(&self) -> &'static str {
+        "Avoidance"
+    }
+
+    fn force(&self, boid: BoidRef<'_>, context: &SteeringContext<'_>) -> Vec2 {
+        boid.calculate_avoidance_force(context.threats, context.params)
+    }
+
+    fn color(&self) -> Option<Color32> {
+        Some(Color32::RED)
+    }
+
+    fn weight_mut<'a>(&self, params: &'a mut BoidsSimulationParameters) -> Option<&'a mut f32> {
+        Some(&mut params.avoidance_weight)
+    }
+}
+
+/// Drifting off course along smooth noise, see [`crate::wander::WanderParameters`].
+pub struct Wander;
+
+impl SteeringRule for Wander {
+    fn name(&self) -> &'static str {
+        "Wander"
+    }
+
+    fn force(&self, boid: BoidRef<'_>, context: &SteeringContext<'_>) -> Vec2 {
+        context
+            .params
+            .wander
+            .force(boid, context.tick, context.params)
+    }
+
+    fn weight_mut<'a>(&self, params: &'a mut BoidsSimulationParameters) -> Option<&'a mut f32> {
+        Some(&mut params.wander.weight)
+    }
+}
+
+pub struct ObstacleAvoidance;
+
+impl SteeringRule for ObstacleAvoidance {
+    fn name(&self) -> &'static str {
+        "Obstacle Avoidance"
+    }
+
+    fn force(&self, boid: BoidRef<'_>, context: &SteeringContext<'_>) -> Vec2 {
+        boid.calculate_obstacle_avoidance_force(context.obstacles, context.params)
+    }
+
+    fn color(&self) -> Option<Color32> {
+        Some(Color32::from_rgb(255, 150, 40))
+    }
+
+    fn weight_mut<'a>(&self, params: &'a mut BoidsSimulationParameters) -> Option<&'a mut f32> {
+        Some(&mut params.obstacle_avoidance.weight)
+    }
+}
+
+/// Turning back from the edges before reaching them, only in [`BoundaryMode::Contain`].
+pub struct Containment;
+
+impl SteeringRule for Containment {
+    fn name(&self) -> &'static str {
+        "Containment"
+    }
+
+    fn force(&self, boid: BoidRef<'_>, context: &SteeringContext<'_>) -> Vec2 {
+        let params = context.params;
+        if params.boundary_mode != BoundaryMode::Contain {
+            return Vec2::ZERO;
+        }
+        let containment = &params.containment;
+        match params.arena.nearest_edge(&context.bounds, boid.position) {
+            Some((distance, inward)) if distance < containment.margin => {
+                // Turn harder the closer the edge is
+                let urgency = 1.0 - distance.max(0.0) / containment.margin.max(f32::EPSILON);
+                boid.calculate_heading_force(inward, containment.strength * urgency, params)
+            }
+            _ => Vec2::ZERO,
+        }
+    }
+
+    fn weight_mut<'a>(&self, params: &'a mut BoidsSimulationParameters) -> Option<&'a mut f32> {
+        Some(&mut params.containment.strength)
+    }
+}
+
+/// Chasing and running from boids in other groups, see [`crate::interaction::InteractionMatrix`].
+pub struct Interactions;
+
+impl SteeringRule for Interactions {
+    fn name(&self) -> &'static str {
+        "Interactions"
+    }
+
+    fn force(&self, boid: BoidRef<'_>, context: &SteeringContext<'_>) -> Vec2 {
+        context
+            .params
+            .groups
+            .interactions
+            .force(boid, context.neighbors, context.params)
+    }
+
+    fn color(&self) -> Option<Color32> {
+        Some(Color32::from_rgb(230, 80, 230))
+    }
+
+    fn weight_mut<'a>(&self, params: &'a mut BoidsSimulationParameters) -> Option<&'a mut f32> {
+        Some(&mut params.groups.interactions.weight)
+    }
+}
+
+/// The rules every boid steers by, in the order their forces are added up.
+pub fn flocking_rules() -> Vec<Box<dyn SteeringRule>> {
+    vec![
+        Box::new(Separation),
+        Box::new(Alignment),
+        Box::new(Cohesion),
+        Box::new(Avoidance),
+        Box::new(Wander),
+        Box::new(Interactions),
+        Box::new(Containment),
+        Box::new(ObstacleAvoidance),
+    ]
+}
+
+/// The name of the rule pulling on a boid the hardest, `None` when none of them pull at all.
+pub fn dominant_rule(rules: &[Box<dyn SteeringRule>], forces: &[Vec2]) -> Option<&'static str> {
+    rules
+        .iter()
+        .zip(forces)
+        .filter(|(_, force)| **force != Vec2::ZERO)
+        .max_by(|(_, a), (_, b)| a.length_sq().total_cmp(&b.length_sq()))
+        .map(|(rule, _)| rule.name())
+}
+
+/// The color of the rule pulling on a boid the hardest, from each rule's force on it.
+///
+/// Rules without a color don't count, and a tie leaves the boid's color alone.
+pub fn dominant_color(rules: &[Box<dyn SteeringRule>], forces: &[Vec2]) -> Option<Color32> {
+    let colored = || {
+        rules
+            .iter()
+            .zip(forces)
+            .filter_map(|(rule, force)| Some((rule.color()?, force.length_sq())))
+    };
+    let (color, strongest) = colored().max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+    let ties = colored().filter(|(_, force)| *force == strongest).count();
+    (ties == 1).then_some(color)
+}
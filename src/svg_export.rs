@@ -0,0 +1,249 @@
+use std::fmt::Write;
+
+use egui::{
+    epaint::{ClippedShape, ColorMode, Mesh, PathStroke, TextShape},
+    Color32, Pos2, Rect, Shape, Stroke,
+};
+
+/// A frame of the world view as SVG, so it stays crisp at any size in a paper or on a slide.
+///
+/// Built from the shapes the view painted for the frame, so everything drawn on screen (boids,
+/// trails, the perimeter and every overlay) ends up in the picture the same way.
+pub struct SvgFrame {
+    /// The view on screen, which becomes the picture's origin and size
+    rect: Rect,
+    body: String,
+}
+
+impl SvgFrame {
+    pub fn new(rect: Rect, background: Color32) -> Self {
+        let mut frame = Self {
+            rect,
+            body: String::new(),
+        };
+        frame.add(&Shape::rect_filled(rect, 0.0, background));
+        frame
+    }
+
+    /// Add shapes the view painted, in the order they were painted.
+    pub fn extend<'a>(&mut self, shapes: impl IntoIterator<Item = &'a ClippedShape>) {
+        for shape in shapes {
+            self.add(&shape.shape);
+        }
+    }
+
+    fn add(&mut self, shape: &Shape) {
+        let body = &mut self.body;
+        let origin = self.rect.min;
+        let point = |pos: Pos2| {
+            let pos = pos - origin;
+            format!("{:.2},{:.2}", pos.x, pos.y)
+        };
+        // Writing to a string can't fail
+        let _ = match shape {
+            Shape::Noop | Shape::Callback(_) => Ok(()),
+            Shape::Vec(shapes) => {
+                for shape in shapes {
+                    self.add(shape);
+                }
+                Ok(())
+            }
+            Shape::Circle(circle) => {
+                let center = circle.center - origin;
+                writeln!(
+                    body,
+                    r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" {} {}/>"#,
+                    center.x,
+                    center.y,
+                    circle.radius,
+                    fill(circle.fill),
+                    stroke(circle.stroke),
+                )
+            }
+            Shape::Ellipse(ellipse) => {
+                let center = ellipse.center - origin;
+                writeln!(
+                    body,
+                    r#"<ellipse cx="{:.2}" cy="{:.2}" rx="{:.2}" ry="{:.2}" {} {}/>"#,
+                    center.x,
+                    center.y,
+                    ellipse.radius.x,
+                    ellipse.radius.y,
+                    fill(ellipse.fill),
+                    stroke(ellipse.stroke),
+                )
+            }
+            Shape::LineSegment { points, stroke } => writeln!(
+                body,
+                r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" {}/>"#,
+                points[0].x - origin.x,
+                points[0].y - origin.y,
+                points[1].x - origin.x,
+                points[1].y - origin.y,
+                path_stroke(stroke),
+            ),
+            Shape::Path(path) => {
+                let points: Vec<String> = path.points.iter().map(|&pos| point(pos)).collect();
+                writeln!(
+                    body,
+                    r#"<{} points="{}" {} {}/>"#,
+                    if path.closed { "polygon" } else { "polyline" },
+                    points.join(" "),
+                    fill(path.fill),
+                    path_stroke(&path.stroke),
+                )
+            }
+            Shape::Rect(rect) => {
+                let min = rect.rect.min - origin;
+                writeln!(
+                    body,
+                    r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" rx="{:.2}" {} {}/>"#,
+                    min.x,
+                    min.y,
+                    rect.rect.width(),
+                    rect.rect.height(),
+                    rect.rounding.nw,
+                    fill(rect.fill),
+                    stroke(rect.stroke),
+                )
+            }
+            Shape::Text(text) => write_text(body, text, origin),
+            Shape::Mesh(mesh) => write_mesh(body, mesh, origin),
+            Shape::QuadraticBezier(curve) => writeln!(
+                body,
+                r#"<path d="M{} Q{} {}{}" {} {}/>"#,
+                point(curve.points[0]),
+                point(curve.points[1]),
+                point(curve.points[2]),
+                if curve.closed { " Z" } else { "" },
+                fill(curve.fill),
+                path_stroke(&curve.stroke),
+            ),
+            Shape::CubicBezier(curve) => writeln!(
+                body,
+                r#"<path d="M{} C{} {} {}{}" {} {}/>"#,
+                point(curve.points[0]),
+                point(curve.points[1]),
+                point(curve.points[2]),
+                point(curve.points[3]),
+                if curve.closed { " Z" } else { "" },
+                fill(curve.fill),
+                path_stroke(&curve.stroke),
+            ),
+        };
+    }
+
+    pub fn to_svg(&self) -> String {
+        let size = self.rect.size();
+        format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w:.0}" height="{h:.0}" viewBox="0 0 {w:.2} {h:.2}">"#,
+                "\n",
+                r#"<clipPath id="view"><rect width="{w:.2}" height="{h:.2}"/></clipPath>"#,
+                "\n",
+                r#"<g clip-path="url(#view)" stroke-linecap="round" stroke-linejoin="round">"#,
+                "\n{body}</g>\n</svg>\n"
+            ),
+            w = size.x,
+            h = size.y,
+            body = self.body,
+        )
+    }
+}
+
+/// SVG's colors aren't premultiplied, so the opacity goes in its own attribute.
+fn color(name: &str, color: Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    if a == u8::MAX {
+        format!(r#"{name}="rgb({r},{g},{b})""#)
+    } else {
+        format!(
+            r#"{name}="rgb({r},{g},{b})" {name}-opacity="{:.3}""#,
+            f32::from(a) / 255.0
+        )
+    }
+}
+
+fn fill(fill: Color32) -> String {
+    if fill == Color32::TRANSPARENT {
+        r#"fill="none""#.to_owned()
+    } else {
+        color("fill", fill)
+    }
+}
+
+fn stroke(stroke: Stroke) -> String {
+    if stroke.is_empty() {
+        return r#"stroke="none""#.to_owned();
+    }
+    format!(
+        r#"{} stroke-width="{:.2}""#,
+        color("stroke", stroke.color),
+        stroke.width
+    )
+}
+
+fn path_stroke(path_stroke: &PathStroke) -> String {
+    match &path_stroke.color {
+        ColorMode::Solid(solid) => stroke(Stroke::new(path_stroke.width, *solid)),
+        // Gradients along a path have no SVG equivalent, so they're left out
+        ColorMode::UV(_) => r#"stroke="none""#.to_owned(),
+    }
+}
+
+/// Each triangle as its own polygon in its first corner's color, which is the color of the whole
+/// triangle for everything the view draws as a mesh.
+fn write_mesh(body: &mut String, mesh: &Mesh, origin: Pos2) -> std::fmt::Result {
+    for triangle in mesh.indices.chunks_exact(3) {
+        let corners: Vec<_> = triangle
+            .iter()
+            .map(|&index| &mesh.vertices[index as usize])
+            .collect();
+        writeln!(
+            body,
+            r#"<polygon points="{:.2},{:.2} {:.2},{:.2} {:.2},{:.2}" {}/>"#,
+            corners[0].pos.x - origin.x,
+            corners[0].pos.y - origin.y,
+            corners[1].pos.x - origin.x,
+            corners[1].pos.y - origin.y,
+            corners[2].pos.x - origin.x,
+            corners[2].pos.y - origin.y,
+            fill(corners[0].color),
+        )?;
+    }
+    Ok(())
+}
+
+/// Text in a font of the same size and color, a line per row, rather than the glyphs egui drew.
+fn write_text(body: &mut String, text: &TextShape, origin: Pos2) -> std::fmt::Result {
+    let Some(section) = text.galley.job.sections.first() else {
+        return Ok(());
+    };
+    let mut text_color = text.override_text_color.unwrap_or(section.format.color);
+    if text_color == Color32::PLACEHOLDER {
+        text_color = text.fallback_color;
+    }
+    for row in &text.galley.rows {
+        let line: String = row.glyphs.iter().map(|glyph| glyph.chr).collect();
+        if line.trim().is_empty() {
+            continue;
+        }
+        let pos = text.pos + row.rect.min.to_vec2() - origin;
+        writeln!(
+            body,
+            r#"<text x="{:.2}" y="{:.2}" font-family="sans-serif" font-size="{:.1}" dominant-baseline="text-before-edge" {}>{}</text>"#,
+            pos.x,
+            pos.y,
+            section.format.font_id.size,
+            fill(text_color.gamma_multiply(text.opacity_factor)),
+            escape(&line),
+        )?;
+    }
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,111 @@
+#[cfg(feature = "gui")]
+use egui::{Painter, Ui};
+
+use crate::obstacle::Obstacle;
+
+#[cfg(feature = "gui")]
+use crate::{camera::Camera, game::TICKS_PER_SECOND, math::Rect};
+
+/// An obstacle that doesn't last, either running out of time or breaking after enough boids
+/// fly into it, so the world can change over a long run.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TemporaryObstacle {
+    pub obstacle: Obstacle,
+    /// The obstacle clock when it was placed
+    pub placed_at: u64,
+    /// Ticks it lasts, `None` until it breaks
+    pub lifetime: Option<u64>,
+    /// Boid collisions it takes to break, `None` if nothing can break it
+    pub strength: Option<u32>,
+    /// Boid collisions so far
+    pub hits: u32,
+}
+
+impl TemporaryObstacle {
+    /// How close to gone the obstacle is at `clock`, from 0 when new to 1 when it's gone.
+    #[cfg(feature = "gui")]
+    pub fn wear(&self, clock: u64) -> f32 {
+        let aged = self.lifetime.map_or(0.0, |lifetime| {
+            clock.saturating_sub(self.placed_at) as f32 / lifetime.max(1) as f32
+        });
+        let battered = self
+            .strength
+            .map_or(0.0, |strength| self.hits as f32 / strength.max(1) as f32);
+        aged.max(battered).min(1.0)
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera, clock: u64) {
+        // Fade out as it wears, but never so far that it can't be seen
+        let opacity = 1.0 - 0.7 * self.wear(clock);
+        self.obstacle.draw_faded(painter, rect, camera, opacity);
+    }
+}
+
+/// Count boid collisions against `obstacles` and take out the ones that are gone at `clock`,
+/// returning the obstacles that broke.
+///
+/// `hits` are indices into `obstacles`, one for each boid that flew into one.
+#[cfg(feature = "gui")]
+pub fn wear_down(
+    obstacles: &mut Vec<TemporaryObstacle>,
+    hits: impl IntoIterator<Item = usize>,
+    clock: u64,
+) -> Vec<Obstacle> {
+    for index in hits {
+        if let Some(obstacle) = obstacles.get_mut(index) {
+            obstacle.hits += 1;
+        }
+    }
+    let mut broken = Vec::new();
+    obstacles.retain(|obstacle| {
+        let gone = obstacle.wear(clock) >= 1.0;
+        if gone {
+            broken.push(obstacle.obstacle);
+        }
+        !gone
+    });
+    broken
+}
+
+/// How long the obstacle tool's next obstacle lasts.
+#[derive(Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct DurabilityBrush {
+    /// Seconds, 0 lasts until it breaks
+    pub lifetime: f32,
+    /// Boid collisions, 0 can't be broken
+    pub strength: u32,
+}
+
+impl DurabilityBrush {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.label("Lifetime");
+        ui.add(
+            egui::DragValue::new(&mut self.lifetime)
+                .speed(0.5)
+                .range(0.0..=3600.0)
+                .suffix("s"),
+        )
+        .on_hover_text("How long new obstacles last, 0 for as long as they hold out");
+        ui.label("Strength");
+        ui.add(egui::DragValue::new(&mut self.strength).range(0..=10_000))
+            .on_hover_text("How many boids flying into new obstacles breaks them, 0 for never");
+    }
+
+    /// `obstacle` placed at `clock` with this durability, `None` if it would last forever.
+    #[cfg(feature = "gui")]
+    pub fn apply(&self, obstacle: Obstacle, clock: u64) -> Option<TemporaryObstacle> {
+        let lifetime = (self.lifetime > 0.0)
+            .then(|| ((self.lifetime * TICKS_PER_SECOND).round() as u64).max(1));
+        let strength = (self.strength > 0).then_some(self.strength);
+        (lifetime.is_some() || strength.is_some()).then_some(TemporaryObstacle {
+            obstacle,
+            placed_at: clock,
+            lifetime,
+            strength,
+            hits: 0,
+        })
+    }
+}
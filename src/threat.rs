@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+#[cfg(feature = "gui")]
+use egui::Ui;
+
+use crate::{
+    math::{Pos2, Vec2},
+    spatial_grid::SpatialGrid,
+};
+
+/// Something boids flee from, like a predator.
+#[derive(Clone, Copy)]
+pub struct Threat {
+    pub position: Pos2,
+    /// World units per tick
+    pub velocity: Vec2,
+}
+
+/// How much harder than usual boids may steer in a flash expansion.
+const FLASH_BURST: f32 = 3.0;
+/// How strongly a fountaining boid heads back past the threat, relative to stepping aside.
+const FOUNTAIN_AWAY: f32 = 0.3;
+
+/// How prey escapes a predator, after strategies seen in real schools and flocks.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum EscapeStrategy {
+    /// Steer away from the predator
+    #[default]
+    Flee,
+    /// Every boid bolts straight away from the predator at once, so the flock bursts outwards
+    FlashExpansion,
+    /// Boids split to either side of the predator, stream past it and close up again behind it
+    Fountain,
+}
+
+impl EscapeStrategy {
+    #[cfg(feature = "gui")]
+    const ALL: [EscapeStrategy; 3] = [
+        EscapeStrategy::Flee,
+        EscapeStrategy::FlashExpansion,
+        EscapeStrategy::Fountain,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            EscapeStrategy::Flee => "Flee",
+            EscapeStrategy::FlashExpansion => "Flash Expansion",
+            EscapeStrategy::Fountain => "Fountain",
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn draw_selector(&mut self, ui: &mut Ui, id: impl std::hash::Hash) {
+        egui::ComboBox::from_id_salt(id)
+            .selected_text(self.label())
+            .show_ui(ui, |ui| {
+                for strategy in Self::ALL {
+                    ui.selectable_value(self, strategy, strategy.label());
+                }
+            });
+    }
+
+    /// Multiplier on the max force while escaping.
+    pub fn force_multiplier(self) -> f32 {
+        match self {
+            EscapeStrategy::FlashExpansion => FLASH_BURST,
+            EscapeStrategy::Flee | EscapeStrategy::Fountain => 1.0,
+        }
+    }
+
+    /// Which way to escape a threat `away` from it (a unit vector), moving at `velocity`.
+    pub fn direction(self, away: Vec2, threat: &Threat, velocity: Vec2) -> Vec2 {
+        match self {
+            EscapeStrategy::Flee | EscapeStrategy::FlashExpansion => away,
+            EscapeStrategy::Fountain => {
+                // Which way the threat is moving relative to the boid
+                let heading = if threat.velocity != Vec2::ZERO {
+                    threat.velocity.normalized()
+                } else {
+                    -velocity.normalized()
+                };
+                if !heading.is_finite() || away.dot(heading) < 0.0 {
+                    // Already behind the threat, so ease off and let cohesion close the gap
+                    return away * FOUNTAIN_AWAY;
+                }
+                // Step out of the threat's path on whichever side the boid is already on, then
+                // stream back past it
+                let lateral = away - heading * away.dot(heading);
+                let lateral = if lateral.length_sq() > f32::EPSILON {
+                    lateral.normalized()
+                } else {
+                    heading.rot90()
+                };
+                (lateral - heading * FOUNTAIN_AWAY).normalized()
+            }
+        }
+    }
+}
+
+/// Finds the threats close enough to a boid to scare it, however many predators there are.
+#[derive(Clone)]
+pub struct ThreatIndex<'a> {
+    threats: &'a [Threat],
+    grid: Arc<SpatialGrid>,
+    radius: f32,
+    scratch: Vec<usize>,
+}
+
+impl<'a> ThreatIndex<'a> {
+    /// `radius` has to cover the largest avoidance radius any boid uses.
+    pub fn new(threats: &'a [Threat], radius: f32) -> Self {
+        Self {
+            threats,
+            grid: Arc::new(SpatialGrid::from_positions(
+                threats.iter().map(|threat| threat.position),
+                radius,
+            )),
+            radius,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Fill `out` with the threats within the radius of `position`.
+    pub fn nearby(&mut self, position: Pos2, out: &mut Vec<&'a Threat>) {
+        out.clear();
+        if self.threats.is_empty() {
+            return;
+        }
+        self.grid
+            .candidates(position, self.radius, &mut self.scratch);
+        out.extend(
+            self.scratch
+                .iter()
+                .map(|index| &self.threats[*index])
+                .filter(|threat| threat.position.distance(position) < self.radius),
+        );
+    }
+}
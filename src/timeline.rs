@@ -0,0 +1,145 @@
+use std::ops::RangeInclusive;
+
+use egui::{Color32, Pos2, Rect, Sense, Stroke, Ui, Vec2};
+
+use crate::game::TICKS_PER_SECOND;
+
+const STRIP_HEIGHT: f32 = 24.0;
+/// How close (in screen pixels) the pointer needs to be to a marker to pick it.
+const MARKER_PICK_DISTANCE: f32 = 4.0;
+/// Parameter changes this close together are one marker, so dragging a slider doesn't leave a
+/// marker every frame.
+const MERGE_TICKS: u64 = TICKS_PER_SECOND as u64;
+/// Oldest markers are dropped past this, long sessions would otherwise pile them up forever.
+const MAX_MARKERS: usize = 10_000;
+const REPLAYABLE_COLOR: Color32 = Color32::from_rgba_premultiplied(40, 70, 40, 120);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MarkerKind {
+    /// The user changed the parameters by hand
+    Parameters,
+    /// A preset or scenario was loaded, or the playlist moved on to its next entry
+    Preset,
+    /// Something the simulation noticed on its own, like the flock changing regime
+    Event,
+}
+
+impl MarkerKind {
+    fn color(self) -> Color32 {
+        match self {
+            MarkerKind::Parameters => Color32::from_rgb(100, 160, 255),
+            MarkerKind::Preset => Color32::from_rgb(255, 200, 60),
+            MarkerKind::Event => Color32::from_rgb(230, 90, 90),
+        }
+    }
+}
+
+pub struct TimelineMarker {
+    pub tick: u64,
+    pub kind: MarkerKind,
+    pub label: String,
+}
+
+/// A strip along the bottom of the window showing how long the session has run, with a marker
+/// for everything that changed along the way.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct SessionTimeline {
+    pub open: bool,
+    /// Oldest first
+    #[serde(skip)]
+    markers: Vec<TimelineMarker>,
+}
+
+impl SessionTimeline {
+    pub fn mark(&mut self, tick: u64, kind: MarkerKind, label: impl Into<String>) {
+        let label = label.into();
+        if let Some(last) = self.markers.last_mut() {
+            if kind == MarkerKind::Parameters
+                && last.kind == kind
+                && tick.saturating_sub(last.tick) < MERGE_TICKS
+            {
+                last.tick = tick;
+                return;
+            }
+        }
+        if self.markers.len() == MAX_MARKERS {
+            self.markers.remove(0);
+        }
+        self.markers.push(TimelineMarker { tick, kind, label });
+    }
+
+    /// Draw the strip up to tick `now`, shading the ticks in `replayable`.
+    ///
+    /// Returns the tick of a marker the user clicked inside `replayable`, to jump there.
+    pub fn draw(
+        &self,
+        ui: &mut Ui,
+        now: u64,
+        replayable: Option<RangeInclusive<u64>>,
+    ) -> Option<u64> {
+        let mut jump = None;
+        ui.horizontal(|ui| {
+            ui.label(format_ticks(0));
+            let width = ui.available_width() - 60.0;
+            let (rect, response) =
+                ui.allocate_exact_size(Vec2::new(width.max(0.0), STRIP_HEIGHT), Sense::click());
+            ui.label(format_ticks(now));
+
+            let painter = ui.painter_at(rect);
+            let x = |tick: u64| rect.left() + rect.width() * tick as f32 / now.max(1) as f32;
+            painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+            if let Some(range) = &replayable {
+                painter.rect_filled(
+                    Rect::from_x_y_ranges(x(*range.start())..=x(*range.end()), rect.y_range()),
+                    0.0,
+                    REPLAYABLE_COLOR,
+                );
+            }
+            for marker in &self.markers {
+                let top = Pos2::new(x(marker.tick), rect.top() + 3.0);
+                painter.line_segment(
+                    [top, Pos2::new(top.x, rect.bottom() - 3.0)],
+                    Stroke::new(2.0, marker.kind.color()),
+                );
+            }
+
+            let Some(pointer) = response.hover_pos() else {
+                return;
+            };
+            let hovered = self
+                .markers
+                .iter()
+                .map(|marker| (marker, (x(marker.tick) - pointer.x).abs()))
+                .filter(|(_, distance)| *distance <= MARKER_PICK_DISTANCE)
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(marker, _)| marker);
+            let Some(marker) = hovered else {
+                return;
+            };
+            let replayable = replayable
+                .as_ref()
+                .is_some_and(|range| range.contains(&marker.tick));
+            let hint = if replayable {
+                "Click to replay from here"
+            } else {
+                "Only moments still in the replay buffer can be jumped to"
+            };
+            let clicked = response.clicked();
+            response.on_hover_text(format!(
+                "{} at {}\n{hint}",
+                marker.label,
+                format_ticks(marker.tick)
+            ));
+            if clicked && replayable {
+                jump = Some(marker.tick);
+            }
+        });
+        jump
+    }
+}
+
+pub fn format_ticks(ticks: u64) -> String {
+    let seconds = (ticks as f32 / TICKS_PER_SECOND) as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
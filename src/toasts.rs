@@ -0,0 +1,116 @@
+use egui::{Align2, Color32, RichText};
+use tracing::{error, info, warn};
+
+use crate::error::BoidsError;
+
+/// How long a toast stays on screen, in seconds.
+const TOAST_DURATION: f64 = 5.0;
+const TOAST_WIDTH: f32 = 320.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ToastKind {
+    Info,
+    Warning,
+    Error,
+}
+
+impl ToastKind {
+    fn color(self) -> Color32 {
+        match self {
+            ToastKind::Info => Color32::LIGHT_BLUE,
+            ToastKind::Warning => Color32::YELLOW,
+            ToastKind::Error => Color32::RED,
+        }
+    }
+}
+
+struct Toast {
+    kind: ToastKind,
+    text: String,
+    // Set the first time the toast is drawn, since that's when we know the egui time
+    expires_at: Option<f64>,
+}
+
+/// Short-lived notifications stacked in the bottom right corner of the window.
+///
+/// Every toast is also sent to the log so it ends up in the log window.
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+}
+
+impl Toasts {
+    pub fn info(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        info!("{text}");
+        self.push(ToastKind::Info, text);
+    }
+
+    pub fn warning(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        warn!("{text}");
+        self.push(ToastKind::Warning, text);
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        error!("{text}");
+        self.push(ToastKind::Error, text);
+    }
+
+    /// Show `err` as an error toast, prefixed with what we were trying to do.
+    pub fn report(&mut self, context: &str, err: &BoidsError) {
+        self.error(format!("{context}: {err}"));
+    }
+
+    fn push(&mut self, kind: ToastKind, text: String) {
+        self.toasts.push(Toast {
+            kind,
+            text,
+            expires_at: None,
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let now = ctx.input(|i| i.time);
+        self.toasts
+            .retain(|toast| toast.expires_at.map_or(true, |expires_at| expires_at > now));
+        if self.toasts.is_empty() {
+            return;
+        }
+
+        let mut dismissed = None;
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(Align2::RIGHT_BOTTOM, [-8.0, -8.0])
+            .order(egui::Order::Foreground)
+            .interactable(true)
+            .show(ctx, |ui| {
+                ui.set_max_width(TOAST_WIDTH);
+                for (index, toast) in self.toasts.iter_mut().enumerate() {
+                    let expires_at = *toast.expires_at.get_or_insert(now + TOAST_DURATION);
+
+                    let response = egui::Frame::popup(ui.style())
+                        .stroke(egui::Stroke::new(1.0, toast.kind.color()))
+                        .show(ui, |ui| {
+                            ui.set_width(TOAST_WIDTH);
+                            ui.label(RichText::new(&toast.text).color(toast.kind.color()));
+                        })
+                        .response
+                        .interact(egui::Sense::click())
+                        .on_hover_text("Click to dismiss");
+                    if response.clicked() {
+                        dismissed = Some(index);
+                    }
+
+                    // Keep repainting so the toast disappears on time even if nothing else is happening
+                    ctx.request_repaint_after(std::time::Duration::from_secs_f64(
+                        (expires_at - now).max(0.0),
+                    ));
+                }
+            });
+
+        if let Some(index) = dismissed {
+            self.toasts.remove(index);
+        }
+    }
+}
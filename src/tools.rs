@@ -0,0 +1,98 @@
+use egui::{Pos2, Ui};
+
+/// What the mouse does inside the simulation area.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum Tool {
+    /// The cursor is a predator the boids flee from
+    #[default]
+    Predator,
+    /// Click to place a goal the boids seek, shift click to add another, right click to remove one
+    Goal,
+    /// Click or drag to place a perch, right click to remove one
+    Perch,
+    /// Drag to place a current zone, right click to remove one
+    Current,
+    /// Drag from the center to place a thermal, right click to remove one
+    Thermal,
+    /// Drag to place a region that emits or removes boids, right click to remove one
+    Gate,
+    /// Drag to place an obstacle or move one, right click to remove one
+    Obstacle,
+    /// Drag to push the boids around where the drag started
+    Gust,
+    /// Click a boid to inspect and edit its genome
+    Inspect,
+    /// Click a boid or a point, or drag a region, to annotate it, right click to remove
+    Annotate,
+}
+
+/// A primary button drag in the simulation area, as start and end positions in world coordinates.
+pub enum ToolDrag {
+    InProgress(Pos2, Pos2),
+    Finished(Pos2, Pos2),
+}
+
+impl Tool {
+    pub const ALL: [Tool; 10] = [
+        Tool::Predator,
+        Tool::Goal,
+        Tool::Perch,
+        Tool::Current,
+        Tool::Thermal,
+        Tool::Gate,
+        Tool::Obstacle,
+        Tool::Gust,
+        Tool::Inspect,
+        Tool::Annotate,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Tool::Predator => "Predator",
+            Tool::Goal => "Goal",
+            Tool::Perch => "Perch",
+            Tool::Current => "Current",
+            Tool::Thermal => "Thermal",
+            Tool::Gate => "Source/Sink",
+            Tool::Obstacle => "Obstacle",
+            Tool::Gust => "Gust",
+            Tool::Inspect => "Inspect",
+            Tool::Annotate => "Annotate",
+        }
+    }
+
+    fn hint(self) -> &'static str {
+        match self {
+            Tool::Predator => {
+                "The cursor scares the boids away, long press to leave the predator in place"
+            }
+            Tool::Goal => {
+                "Click to move the goal, shift click to add another goal, right click to remove"
+            }
+            Tool::Perch => {
+                "Click to place a perch point, drag to place a perch line, right click to remove"
+            }
+            Tool::Current => "Drag to place a current zone, right click to remove",
+            Tool::Thermal => "Drag out from the center to place a thermal, right click to remove",
+            Tool::Gate => "Drag to place a boid source or sink, right click to remove",
+            Tool::Obstacle => {
+                "Drag to place an obstacle, drag an obstacle to move it, right click to remove"
+            }
+            Tool::Gust => "Drag to blow the boids near where you started the way you dragged",
+            Tool::Inspect => "Click a boid to see and edit its genome",
+            Tool::Annotate => {
+                "Click a boid or a point, or drag out a region, to label it, right click to remove"
+            }
+        }
+    }
+
+    pub fn draw_selector(&mut self, ui: &mut Ui) {
+        ui.label("Tool");
+        ui.horizontal_wrapped(|ui| {
+            for tool in Tool::ALL {
+                ui.selectable_value(self, tool, tool.label())
+                    .on_hover_text(tool.hint());
+            }
+        });
+    }
+}
@@ -0,0 +1,80 @@
+#[cfg(feature = "gui")]
+use egui::Ui;
+
+/// Which of the boids around it a flocking rule listens to.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum NeighborSelection {
+    /// Every boid within the neighbor radius, however many there are
+    #[default]
+    Radius,
+    /// The closest few boids however far away they are, like starlings keeping track of their
+    /// six or seven nearest neighbors
+    Nearest,
+}
+
+impl NeighborSelection {
+    /// How far away a neighbor can be, given the boid's neighbor radius.
+    pub fn reach(self, neighbor_radius: f32) -> f32 {
+        match self {
+            NeighborSelection::Radius => neighbor_radius,
+            // The nearest neighbors have already been picked out, wherever they are
+            NeighborSelection::Nearest => f32::INFINITY,
+        }
+    }
+
+    /// `within_radius` or `nearest`, whichever this selects.
+    pub fn pick<'a, T>(self, within_radius: &'a [T], nearest: &'a [T]) -> &'a [T] {
+        match self {
+            NeighborSelection::Radius => within_radius,
+            NeighborSelection::Nearest => nearest,
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    fn draw_selector(&mut self, ui: &mut Ui, label: &str) {
+        ui.horizontal(|ui| {
+            ui.label(label);
+            ui.radio_value(self, NeighborSelection::Radius, "Radius");
+            ui.radio_value(self, NeighborSelection::Nearest, "Nearest");
+        });
+    }
+}
+
+/// Whether separation, alignment and cohesion look at metric or topological neighbors.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct TopologyParameters {
+    /// How many neighbors the rules set to [`NeighborSelection::Nearest`] look at
+    pub nearest_count: usize,
+    pub separation: NeighborSelection,
+    pub alignment: NeighborSelection,
+    pub cohesion: NeighborSelection,
+}
+
+impl Default for TopologyParameters {
+    fn default() -> Self {
+        Self {
+            nearest_count: 7,
+            separation: NeighborSelection::Radius,
+            alignment: NeighborSelection::Radius,
+            cohesion: NeighborSelection::Radius,
+        }
+    }
+}
+
+impl TopologyParameters {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        self.separation.draw_selector(ui, "Separation");
+        self.alignment.draw_selector(ui, "Alignment");
+        self.cohesion.draw_selector(ui, "Cohesion");
+        ui.label("Nearest Neighbors");
+        ui.add(egui::Slider::new(&mut self.nearest_count, 1..=20))
+            .on_hover_text("How many of the closest boids the rules set to Nearest look at");
+    }
+
+    /// Whether any rule needs the nearest neighbors looked up.
+    pub fn uses_nearest(&self) -> bool {
+        [self.separation, self.alignment, self.cohesion].contains(&NeighborSelection::Nearest)
+    }
+}
@@ -0,0 +1,113 @@
+use egui::Ui;
+
+use crate::{boids_simulation::BoidsSimulationParameters, game::TICKS_PER_SECOND};
+
+/// Whether loaded presets and scenarios blend in, rather than replacing the parameters at once.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct TransitionSettings {
+    pub enabled: bool,
+    pub seconds: f32,
+}
+
+impl Default for TransitionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            seconds: 3.0,
+        }
+    }
+}
+
+impl TransitionSettings {
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Blend In Loaded Parameters")
+            .on_hover_text("Ease from the current parameters to loaded ones instead of snapping, for live demos");
+        if self.enabled {
+            ui.label("Blend Time (seconds)");
+            ui.add(
+                egui::DragValue::new(&mut self.seconds)
+                    .speed(0.1)
+                    .range(0.1..=60.0),
+            );
+        }
+    }
+
+    /// Start easing towards `target` from tick `start`, or `None` when transitions are off.
+    pub fn tween(
+        &self,
+        from: &BoidsSimulationParameters,
+        target: BoidsSimulationParameters,
+        start: u64,
+    ) -> Option<ParameterTween> {
+        self.enabled
+            .then(|| ParameterTween::new(from.clone(), target, self.seconds, start))
+    }
+}
+
+/// Eases the simulation parameters from one set to another, a tick at a time.
+///
+/// Only numbers blend, everything else (the boundary mode, groups and so on) switches to the
+/// target's straight away.
+///
+/// How far along it is only depends on the tick, so a replay recording the tween once blends the
+/// same way.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct ParameterTween {
+    from: BoidsSimulationParameters,
+    to: BoidsSimulationParameters,
+    /// Tick of the flock's clock the first step is taken on, the one LFOs go by too
+    start: u64,
+    ticks: u32,
+    /// What the tween set the parameters to last, to notice someone else changing them
+    #[serde(skip)]
+    last: Option<BoidsSimulationParameters>,
+}
+
+/// Two tweens are the same blend whatever they last set the parameters to.
+impl PartialEq for ParameterTween {
+    fn eq(&self, other: &Self) -> bool {
+        self.from == other.from
+            && self.to == other.to
+            && self.start == other.start
+            && self.ticks == other.ticks
+    }
+}
+
+impl ParameterTween {
+    pub fn new(
+        from: BoidsSimulationParameters,
+        to: BoidsSimulationParameters,
+        seconds: f32,
+        start: u64,
+    ) -> Self {
+        Self {
+            from,
+            to,
+            start,
+            ticks: (seconds * TICKS_PER_SECOND).max(1.0) as u32,
+            last: None,
+        }
+    }
+
+    /// What the parameters end up as.
+    pub fn target(&self) -> &BoidsSimulationParameters {
+        &self.to
+    }
+
+    /// Whether anyone else changed the parameters since the tween last set them, which takes over
+    /// from the tween.
+    pub fn overridden(&self, params: &BoidsSimulationParameters) -> bool {
+        self.last.as_ref().is_some_and(|last| last != params)
+    }
+
+    /// Set `params` to where the blend is on `tick`, returning false once the tween is done.
+    pub fn step(&mut self, params: &mut BoidsSimulationParameters, tick: u64) -> bool {
+        let step = tick.saturating_sub(self.start) + 1;
+        let t = (step as f32 / self.ticks as f32).min(1.0);
+        // Ease in and out, so the flock doesn't lurch at either end
+        *params = self.from.interpolate(&self.to, t * t * (3.0 - 2.0 * t));
+        self.last = Some(params.clone());
+        step < u64::from(self.ticks)
+    }
+}
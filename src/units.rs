@@ -0,0 +1,101 @@
+#[cfg(feature = "gui")]
+use egui::Ui;
+
+use crate::game::TICKS_PER_SECOND;
+
+/// What kind of measurement a parameter is, so the panel can show it in real-world units.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+    /// Weights, ratios and anything else without a unit
+    Plain,
+    /// World units, shown in meters
+    Distance,
+    /// World units per tick, shown in m/s
+    Speed,
+    /// World units per tick per tick, shown in m/s²
+    Acceleration,
+}
+
+/// How big the world is in real life, to compare parameters against measurements of real flocks.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct UnitParameters {
+    /// How many meters one world unit stands for
+    pub meters_per_unit: f32,
+    /// Show distances, speeds and accelerations in the panel in meters and seconds
+    pub real_units: bool,
+}
+
+impl Default for UnitParameters {
+    fn default() -> Self {
+        Self {
+            // Puts the default flock at a starling's 15 m/s with neighbors a couple of meters away
+            meters_per_unit: 0.05,
+            real_units: false,
+        }
+    }
+}
+
+impl UnitParameters {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui, world_width: f32, world_height: f32) {
+        ui.checkbox(&mut self.real_units, "Show Real-World Units")
+            .on_hover_text(
+                "Show distances in meters and speeds in m/s instead of world units and ticks",
+            );
+        ui.label("World Scale");
+        ui.add(
+            egui::DragValue::new(&mut self.meters_per_unit)
+                .speed(0.001)
+                .range(0.0001..=100.0)
+                .suffix(" m/unit"),
+        );
+        ui.weak(format!(
+            "The world is {:.0} × {:.0} m",
+            world_width * self.meters_per_unit,
+            world_height * self.meters_per_unit
+        ));
+    }
+
+    /// How many of `quantity`'s real-world units one of its simulation units is.
+    pub fn scale(&self, quantity: Quantity) -> f32 {
+        if !self.real_units {
+            return 1.0;
+        }
+        match quantity {
+            Quantity::Plain => 1.0,
+            Quantity::Distance => self.meters_per_unit,
+            Quantity::Speed => self.meters_per_unit * TICKS_PER_SECOND,
+            Quantity::Acceleration => self.meters_per_unit * TICKS_PER_SECOND * TICKS_PER_SECOND,
+        }
+    }
+
+    /// `slider` showing and taking its value in `quantity`'s real-world units, when they're on.
+    #[cfg(feature = "gui")]
+    pub fn slider<'a>(&self, slider: egui::Slider<'a>, quantity: Quantity) -> egui::Slider<'a> {
+        let scale = self.scale(quantity) as f64;
+        if scale == 1.0 {
+            return slider;
+        }
+        slider
+            .custom_formatter(move |value, decimals| {
+                let digits = (*decimals.end()).max(2);
+                format!("{:.*}", digits, value * scale)
+            })
+            .custom_parser(move |text| text.trim().parse::<f64>().ok().map(|value| value / scale))
+            .suffix(self.suffix(quantity))
+    }
+
+    /// What to put after a value of `quantity` shown in the panel.
+    pub fn suffix(&self, quantity: Quantity) -> &'static str {
+        if !self.real_units {
+            return "";
+        }
+        match quantity {
+            Quantity::Plain => "",
+            Quantity::Distance => " m",
+            Quantity::Speed => " m/s",
+            Quantity::Acceleration => " m/s²",
+        }
+    }
+}
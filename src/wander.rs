@@ -0,0 +1,84 @@
+use std::f32::consts::FRAC_PI_2;
+
+#[cfg(feature = "gui")]
+use egui::Ui;
+
+use crate::{
+    boid_store::BoidRef,
+    boids_simulation::BoidsSimulationParameters,
+    game::TICKS_PER_SECOND,
+    math::{Rot2, Vec2},
+};
+
+/// Boids drifting off their heading a little at a time, so ones on their own don't fly in
+/// perfectly straight lines.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct WanderParameters {
+    /// 0 turns wandering off
+    pub weight: f32,
+    /// How many times a second, roughly, a boid changes which way it's drifting
+    pub frequency: f32,
+}
+
+impl Default for WanderParameters {
+    fn default() -> Self {
+        Self {
+            weight: 0.0,
+            frequency: 0.5,
+        }
+    }
+}
+
+impl WanderParameters {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.label("Wander Weight");
+        ui.add(egui::Slider::new(&mut self.weight, 0.0..=2.0))
+            .on_hover_text("How hard boids drift off their heading, 0 keeps them flying straight");
+        ui.label("Wander Frequency");
+        ui.add(
+            egui::Slider::new(&mut self.frequency, 0.01..=5.0)
+                .logarithmic(true)
+                .suffix(" Hz"),
+        )
+        .on_hover_text("Low frequencies make long lazy curves, high ones a restless flutter");
+    }
+
+    /// The pull off `boid`'s heading at `tick`, which every boid follows its own smooth noise for.
+    pub fn force(&self, boid: BoidRef<'_>, tick: u64, params: &BoidsSimulationParameters) -> Vec2 {
+        if self.weight == 0.0 || boid.velocity == Vec2::ZERO {
+            return Vec2::ZERO;
+        }
+        let time = tick as f32 / TICKS_PER_SECOND * self.frequency;
+        // Up to a right angle either way off where the boid is heading now
+        let turn = gradient_noise(boid.id, time) * FRAC_PI_2;
+        let heading = Rot2::from_angle(turn) * boid.velocity.normalized();
+        boid.calculate_heading_force(heading, self.weight, params)
+    }
+}
+
+/// One dimensional gradient noise between -1 and 1 along `x`, a different curve for each `seed`.
+fn gradient_noise(seed: u64, x: f32) -> f32 {
+    let cell = x.floor();
+    let t = x - cell;
+    let cell = cell as i64;
+    let left = lattice_gradient(seed, cell) * t;
+    let right = lattice_gradient(seed, cell + 1) * (t - 1.0);
+    // Quintic fade so the curve's slope is smooth across lattice points
+    let fade = t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+    // A gradient noise in one dimension peaks at half the gradient
+    (2.0 * (left + (right - left) * fade)).clamp(-1.0, 1.0)
+}
+
+/// The slope of the noise at a lattice point, between -1 and 1.
+pub fn lattice_gradient(seed: u64, cell: i64) -> f32 {
+    // SplitMix64 finalizer, plenty random for picking slopes
+    let mut hash = seed
+        .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        .wrapping_add(cell as u64);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    hash ^= hash >> 31;
+    (hash >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+}
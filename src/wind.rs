@@ -0,0 +1,175 @@
+use std::f32::consts::TAU;
+
+#[cfg(feature = "gui")]
+use egui::{Painter, Stroke, Ui};
+
+use crate::{
+    game::TICKS_PER_SECOND,
+    math::{Pos2, Vec2},
+    wander::lattice_gradient,
+};
+
+#[cfg(feature = "gui")]
+use crate::{
+    camera::Camera,
+    math::{Color32, Rect},
+};
+
+#[cfg(feature = "gui")]
+const ARROW_COLOR: Color32 = Color32::from_rgb(150, 190, 230);
+/// Gap between the arrows showing the wind, in screen points so there are as many at any zoom.
+#[cfg(feature = "gui")]
+const ARROW_SPACING: f32 = 50.0;
+/// Inside this distance from its center a vortex dies down, rather than spinning infinitely fast.
+const VORTEX_CORE: f32 = 60.0;
+
+/// The shape of the wind blowing over the whole world.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum WindKind {
+    #[default]
+    Off,
+    /// The same everywhere
+    Constant,
+    /// Round and round a point
+    Vortex,
+    /// Gusts that swirl about and drift over time
+    Turbulence,
+}
+
+/// An environmental force on every boid, like [`crate::current_zone::CurrentZone`] but
+/// everywhere at once.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct WindParameters {
+    pub kind: WindKind,
+    /// World units per tick squared
+    pub strength: f32,
+    /// Direction of constant wind in degrees, clockwise from the positive x axis
+    pub direction: f32,
+    pub vortex_center: Pos2,
+    /// Width of a gust in world units
+    pub turbulence_scale: f32,
+    /// How quickly the gusts drift, in gust widths a second
+    pub turbulence_speed: f32,
+    pub show_arrows: bool,
+}
+
+impl Default for WindParameters {
+    fn default() -> Self {
+        Self {
+            kind: WindKind::Off,
+            strength: 0.1,
+            direction: 0.0,
+            vortex_center: Pos2::ZERO,
+            turbulence_scale: 300.0,
+            turbulence_speed: 0.1,
+            show_arrows: false,
+        }
+    }
+}
+
+impl WindParameters {
+    #[cfg(feature = "gui")]
+    pub fn draw_panel(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.kind, WindKind::Off, "Off");
+            ui.radio_value(&mut self.kind, WindKind::Constant, "Constant");
+            ui.radio_value(&mut self.kind, WindKind::Vortex, "Vortex");
+            ui.radio_value(&mut self.kind, WindKind::Turbulence, "Turbulence");
+        });
+        if self.kind == WindKind::Off {
+            return;
+        }
+        ui.label("Wind Strength");
+        ui.add(egui::DragValue::new(&mut self.strength).speed(0.005))
+            .on_hover_text("Negative strengths turn vortices the other way");
+        match self.kind {
+            WindKind::Off => {}
+            WindKind::Constant => {
+                ui.label("Wind Direction");
+                ui.add(egui::Slider::new(&mut self.direction, 0.0..=360.0).suffix("°"));
+            }
+            WindKind::Vortex => {
+                ui.label("Vortex Center");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut self.vortex_center.x).prefix("x: "));
+                    ui.add(egui::DragValue::new(&mut self.vortex_center.y).prefix("y: "));
+                });
+            }
+            WindKind::Turbulence => {
+                ui.label("Gust Size");
+                ui.add(egui::DragValue::new(&mut self.turbulence_scale).range(10.0..=5000.0));
+                ui.label("Gust Drift");
+                ui.add(
+                    egui::Slider::new(&mut self.turbulence_speed, 0.0..=2.0)
+                        .logarithmic(true)
+                        .suffix("/s"),
+                )
+                .on_hover_text("How many gust widths the pattern drifts a second");
+            }
+        }
+        ui.checkbox(&mut self.show_arrows, "Show Wind");
+    }
+
+    /// The wind's force on a boid at `position` at `tick`.
+    pub fn force_at(&self, position: Pos2, tick: u64) -> Vec2 {
+        match self.kind {
+            WindKind::Off => Vec2::ZERO,
+            WindKind::Constant => Vec2::angled(self.direction.to_radians()) * self.strength,
+            WindKind::Vortex => {
+                let offset = position - self.vortex_center;
+                let distance = offset.length();
+                if distance == 0.0 {
+                    return Vec2::ZERO;
+                }
+                let core = (distance / VORTEX_CORE).min(1.0);
+                offset.rot90() / distance * self.strength * core
+            }
+            WindKind::Turbulence => {
+                let drift = tick as f32 / TICKS_PER_SECOND * self.turbulence_speed;
+                let scale = self.turbulence_scale.max(1.0);
+                // Value noise is mostly near the middle, going around twice spreads the gusts
+                // out over every direction rather than most blowing the same way
+                let angle = value_noise(position.x / scale + drift, position.y / scale) * 2.0 * TAU;
+                Vec2::angled(angle) * self.strength
+            }
+        }
+    }
+
+    /// Arrows across the visible world showing which way the wind blows and how hard.
+    #[cfg(feature = "gui")]
+    pub fn draw(&self, painter: &Painter, rect: &Rect, camera: &Camera, tick: u64) {
+        if self.kind == WindKind::Off || !self.show_arrows || self.strength == 0.0 {
+            return;
+        }
+        let stroke = Stroke::new(1.0, ARROW_COLOR);
+        let mut y = rect.min.y + ARROW_SPACING / 2.0;
+        while y < rect.max.y {
+            let mut x = rect.min.x + ARROW_SPACING / 2.0;
+            while x < rect.max.x {
+                let screen_pos = Pos2::new(x, y);
+                let force = self.force_at(camera.screen_to_world(rect, screen_pos), tick);
+                // Full length arrows are as strong as the wind gets
+                let arrow = force / self.strength.abs() * ARROW_SPACING * 0.4;
+                painter.arrow(screen_pos - arrow / 2.0, arrow, stroke);
+                x += ARROW_SPACING;
+            }
+            y += ARROW_SPACING;
+        }
+    }
+}
+
+/// Smooth two dimensional noise between 0 and 1, with random values at whole numbers eased
+/// between.
+fn value_noise(x: f32, y: f32) -> f32 {
+    let (cell_x, cell_y) = (x.floor(), y.floor());
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (tx, ty) = (smooth(x - cell_x), smooth(y - cell_y));
+    let (cell_x, cell_y) = (cell_x as i64, cell_y as i64);
+    // The lattice is between -1 and 1, the noise between 0 and 1
+    let corner =
+        |dx: i64, dy: i64| (lattice_gradient((cell_y + dy) as u64, cell_x + dx) + 1.0) / 2.0;
+    let top = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * tx;
+    let bottom = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * tx;
+    top + (bottom - top) * ty
+}